@@ -1,10 +1,29 @@
 //! AI configuration.
 
 use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default token budget for the context section of a prompt.
+const DEFAULT_MAX_CONTEXT_TOKENS: usize = 2048;
+
+/// Built-in AI backend, selecting the engine's provider in
+/// [`AIEngine::new`](crate::AIEngine::new).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AIBackend {
+    /// Use local Ollama server
+    Ollama,
+    /// Use local llama.cpp server (self-managed)
+    #[default]
+    LocalAI,
+    /// Generate deterministic stubs without calling a model
+    Mock,
+}
 
 /// Configuration for the AI engine.
 #[derive(Debug, Clone)]
 pub struct AIConfig {
+    /// Built-in backend to use
+    pub backend: AIBackend,
     /// Cache directory
     pub cache_dir: PathBuf,
     /// Whether to use cache
@@ -15,17 +34,27 @@ pub struct AIConfig {
     pub ollama_model: Option<String>,
     /// Local AI model filename (for Local AI backend)
     pub local_model: Option<String>,
+    /// Timeout for a single backend request
+    pub timeout: Duration,
+    /// How many times to retry a timed-out or unreachable backend
+    pub max_retries: u32,
+    /// Approximate token budget for the context section of a prompt
+    pub max_context_tokens: usize,
 }
 
 impl Default for AIConfig {
     fn default() -> Self {
         Self {
+            backend: AIBackend::default(),
             cache_dir: PathBuf::from(".haira-cache/ai"),
             // Disabled by default - HIF caching is used at the build level instead
             use_cache: false,
             min_confidence: 0.5,
             ollama_model: None,
             local_model: None,
+            timeout: Duration::from_secs(120),
+            max_retries: 2,
+            max_context_tokens: DEFAULT_MAX_CONTEXT_TOKENS,
         }
     }
 }
@@ -33,6 +62,12 @@ impl Default for AIConfig {
 impl AIConfig {
     /// Create config from environment variables.
     pub fn from_env() -> Self {
+        let backend = match std::env::var("HAIRA_AI_BACKEND").as_deref() {
+            Ok("ollama") => AIBackend::Ollama,
+            Ok("mock") => AIBackend::Mock,
+            _ => AIBackend::LocalAI,
+        };
+
         let cache_dir = std::env::var("HAIRA_CACHE_DIR")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from(".haira-cache/ai"));
@@ -50,12 +85,32 @@ impl AIConfig {
         let ollama_model = std::env::var("HAIRA_OLLAMA_MODEL").ok();
         let local_model = std::env::var("HAIRA_LOCAL_MODEL").ok();
 
+        let timeout = std::env::var("HAIRA_AI_TIMEOUT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(120));
+
+        let max_retries = std::env::var("HAIRA_AI_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+
+        let max_context_tokens = std::env::var("HAIRA_AI_MAX_CONTEXT_TOKENS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_CONTEXT_TOKENS);
+
         Self {
+            backend,
             cache_dir,
             use_cache,
             min_confidence,
             ollama_model,
             local_model,
+            timeout,
+            max_retries,
+            max_context_tokens,
         }
     }
 
@@ -72,6 +127,11 @@ pub struct AIConfigBuilder {
 }
 
 impl AIConfigBuilder {
+    pub fn backend(mut self, backend: AIBackend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
     pub fn cache_dir(mut self, path: impl Into<PathBuf>) -> Self {
         self.config.cache_dir = path.into();
         self
@@ -97,6 +157,21 @@ impl AIConfigBuilder {
         self
     }
 
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.config.max_retries = retries;
+        self
+    }
+
+    pub fn max_context_tokens(mut self, tokens: usize) -> Self {
+        self.config.max_context_tokens = tokens;
+        self
+    }
+
     pub fn build(self) -> AIConfig {
         self.config
     }
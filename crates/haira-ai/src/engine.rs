@@ -1,34 +1,31 @@
 //! AI Engine - the main entry point for intent interpretation.
 //!
-//! Supports two backends:
-//! - **Local AI** (primary) - Uses llama.cpp with local models
-//! - **Ollama** (fallback) - Uses Ollama server
+//! The engine answers simple name patterns itself, caches results, and
+//! picks a confident, valid interpretation from what its [`AiProvider`]
+//! returns. See [`crate::provider`] for the built-in providers.
+//!
+//! Each model request is bounded by `AIConfig::timeout` and retried with
+//! exponential backoff; if the local server keeps failing, the local
+//! provider falls back to Ollama.
+
+use std::future::Future;
+use std::time::Duration;
 
 use thiserror::Error;
 use tracing::{debug, info, warn};
 
 use crate::cache::AICache;
-use crate::config::AIConfig;
+use crate::config::{AIBackend, AIConfig};
 use crate::ollama::{OllamaClient, OllamaError};
-use crate::prompt::{self, SYSTEM_PROMPT};
-use haira_cir::{AIResponse, CIRFunction, InterpretationContext};
-use haira_local_ai::{LlamaCppServer, LocalAIError};
-
-/// AI backend type.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum AIBackend {
-    /// Use local Ollama server
-    Ollama,
-    /// Use local llama.cpp server (self-managed)
-    LocalAI,
-}
+use crate::prompt;
+use crate::provider::{AiProvider, LocalAIProvider, MockProvider, OllamaProvider};
+use haira_cir::{AIRequest, AIResponse, CIRFunction, InterpretationContext, RequestType};
+use haira_local_ai::LocalAIError;
 
 /// AI Engine for interpreting developer intent.
 pub struct AIEngine {
     config: AIConfig,
-    ollama_client: Option<OllamaClient>,
-    local_ai_server: Option<LlamaCppServer>,
-    backend: AIBackend,
+    provider: Box<dyn AiProvider>,
     cache: AICache,
 }
 
@@ -51,14 +48,127 @@ pub enum AIError {
     InterpretationFailed(String),
     #[error("no AI backend available")]
     NoBackend,
+    #[error("AI backend timed out after {attempts} attempt(s) of {timeout:?} each")]
+    Timeout { timeout: Duration, attempts: u32 },
+    #[error("AI backend failed after {attempts} attempt(s): {last_error}")]
+    ExhaustedRetries {
+        attempts: u32,
+        last_error: Box<AIError>,
+    },
+}
+
+impl AIError {
+    /// Whether retrying the same request might succeed.
+    pub(crate) fn is_transient(&self) -> bool {
+        match self {
+            AIError::Ollama(OllamaError::ServerNotRunning(_)) => true,
+            AIError::Ollama(OllamaError::Http(e)) => e.is_connect() || e.is_timeout(),
+            AIError::LocalAI(LocalAIError::ServerNotRunning(_)) => true,
+            AIError::LocalAI(LocalAIError::ServerNotReady { .. }) => true,
+            AIError::LocalAI(LocalAIError::Http(e)) => e.is_connect() || e.is_timeout(),
+            AIError::Timeout { .. } | AIError::ExhaustedRetries { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Base delay between retries; doubled after each failed attempt.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// How long to wait for llama-server to load its model.
+pub(crate) const LOCAL_SERVER_READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Timeout and retry settings for a single backend request.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    timeout: Duration,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub(crate) fn from_config(config: &AIConfig) -> Self {
+        Self {
+            timeout: config.timeout,
+            max_retries: config.max_retries,
+            backoff: RETRY_BACKOFF,
+        }
+    }
+
+    /// Run `request` until it succeeds, fails with a non-transient error, or
+    /// runs out of retries.
+    pub(crate) async fn run<F, Fut>(&self, mut request: F) -> Result<String, AIError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<String, AIError>>,
+    {
+        let attempts = self.max_retries + 1;
+        let mut last_error = None;
+
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                let delay = self.backoff * 2u32.saturating_pow(attempt - 1);
+                debug!(
+                    "Retrying AI request in {:?} (attempt {})",
+                    delay,
+                    attempt + 1
+                );
+                tokio::time::sleep(delay).await;
+            }
+
+            let error = match tokio::time::timeout(self.timeout, request()).await {
+                Ok(Ok(text)) => return Ok(text),
+                Ok(Err(e)) if !e.is_transient() => return Err(e),
+                Ok(Err(e)) => e,
+                Err(_) => AIError::Timeout {
+                    timeout: self.timeout,
+                    attempts: attempt + 1,
+                },
+            };
+
+            warn!("AI request attempt {} failed: {}", attempt + 1, error);
+            last_error = Some(error);
+        }
+
+        match last_error {
+            Some(AIError::Timeout { timeout, .. }) => Err(AIError::Timeout { timeout, attempts }),
+            Some(e) => Err(AIError::ExhaustedRetries {
+                attempts,
+                last_error: Box::new(e),
+            }),
+            None => Err(AIError::NoBackend),
+        }
+    }
 }
 
 impl AIEngine {
-    /// Create a new AI engine with Local AI backend (default).
+    /// Create a new AI engine with the built-in provider selected by
+    /// `config.backend` (Local AI by default).
     ///
-    /// The model filename should be the name of a GGUF file in ~/.haira/models/
+    /// The Local AI model is `config.local_model`, the name of a GGUF file in
+    /// ~/.haira/models/, and the Ollama model `config.ollama_model`.
     pub fn new(config: AIConfig) -> Self {
-        Self::with_local_ai(config, None)
+        match config.backend {
+            AIBackend::LocalAI => {
+                let model = config.local_model.clone();
+                Self::with_local_ai(config, model.as_deref())
+            }
+            AIBackend::Ollama => {
+                let model = config.ollama_model.clone();
+                Self::with_ollama(config, model.as_deref())
+            }
+            AIBackend::Mock => Self::mock(config),
+        }
+    }
+
+    /// Create a new AI engine answering requests with `provider`.
+    pub fn with_provider(config: AIConfig, provider: Box<dyn AiProvider>) -> Self {
+        let cache = AICache::new(config.cache_dir.clone());
+        Self {
+            config,
+            provider,
+            cache,
+        }
     }
 
     /// Create a new AI engine with Ollama backend.
@@ -69,126 +179,59 @@ impl AIEngine {
             OllamaClient::new()
         };
 
-        let cache = AICache::new(config.cache_dir.clone());
-
-        Self {
-            config,
-            ollama_client: Some(ollama_client),
-            local_ai_server: None,
-            backend: AIBackend::Ollama,
-            cache,
-        }
+        let provider = OllamaProvider::new(ollama_client, &config);
+        Self::with_provider(config, Box::new(provider))
     }
 
     /// Create a new AI engine with local llama.cpp backend.
     ///
     /// The model filename should be the name of a GGUF file in ~/.haira/models/
     pub fn with_local_ai(config: AIConfig, model_filename: Option<&str>) -> Self {
-        let filename = model_filename
-            .unwrap_or(haira_local_ai::DEFAULT_MODEL_FILENAME)
-            .to_string();
+        let filename = model_filename.unwrap_or(haira_local_ai::DEFAULT_MODEL_FILENAME);
 
-        let server = LlamaCppServer::new(filename);
-        let cache = AICache::new(config.cache_dir.clone());
+        // Ollama is kept as a fallback when the local server stops responding
+        let fallback = match &config.ollama_model {
+            Some(model) => OllamaClient::new().with_model(model),
+            None => OllamaClient::new(),
+        };
 
-        Self {
-            config,
-            ollama_client: None,
-            local_ai_server: Some(server),
-            backend: AIBackend::LocalAI,
-            cache,
-        }
+        let provider = LocalAIProvider::new(filename, &config)
+            .with_fallback(OllamaProvider::new(fallback, &config));
+        Self::with_provider(config, Box::new(provider))
     }
 
-    /// Set the AI backend to use.
-    pub fn set_backend(&mut self, backend: AIBackend) {
-        self.backend = backend;
+    /// Create a new AI engine with the mock backend.
+    ///
+    /// Interpretations are deterministic stubs built from the function
+    /// signature; no server or model is needed.
+    pub fn mock(config: AIConfig) -> Self {
+        Self::with_provider(config, Box::new(MockProvider))
     }
 
-    /// Get the current backend.
-    pub fn backend(&self) -> AIBackend {
-        self.backend
+    /// The provider answering requests.
+    pub fn provider(&self) -> &dyn AiProvider {
+        self.provider.as_ref()
     }
 
-    /// Start the local AI server (only for LocalAI backend).
-    ///
-    /// This starts the llama-server process and waits for it to become ready.
+    /// Start the provider's server, if it manages one (like Local AI's
+    /// llama-server), and wait for it to become ready.
     pub async fn start_local_server(&mut self) -> Result<(), AIError> {
-        if self.backend != AIBackend::LocalAI {
-            return Ok(()); // No-op for other backends
-        }
-
-        let server = self.local_ai_server.as_mut().ok_or(AIError::NoBackend)?;
-
-        // Start the server process
-        server.start()?;
-
-        // Wait for it to become ready (up to 60 seconds for model loading)
-        server
-            .wait_ready(std::time::Duration::from_secs(60))
-            .await?;
-
-        Ok(())
+        self.provider.start().await
     }
 
-    /// Stop the local AI server (only for LocalAI backend).
+    /// Stop the provider's server, if it manages one.
     pub fn stop_local_server(&mut self) -> Result<(), AIError> {
-        if let Some(ref mut server) = self.local_ai_server {
-            server.stop()?;
-        }
-        Ok(())
+        self.provider.stop()
     }
 
-    /// Check if the local AI server is running.
+    /// Check if the provider's server is running.
     pub fn is_local_server_running(&mut self) -> bool {
-        if let Some(ref mut server) = self.local_ai_server {
-            server.is_running()
-        } else {
-            false
-        }
+        self.provider.is_running()
     }
 
-    /// Check if the current backend is available.
+    /// Check if the provider is available.
     pub async fn check_availability(&self) -> Result<(), AIError> {
-        match self.backend {
-            AIBackend::Ollama => {
-                let client = self.ollama_client.as_ref().ok_or(AIError::NoBackend)?;
-                client.check_availability().await?;
-                Ok(())
-            }
-            AIBackend::LocalAI => {
-                let server = self.local_ai_server.as_ref().ok_or(AIError::NoBackend)?;
-                // Check that the server binary and model exist
-                if !server.binary_exists() {
-                    return Err(AIError::LocalAI(LocalAIError::ServerBinaryNotFound(
-                        haira_local_ai::paths::llama_server_path()
-                            .display()
-                            .to_string(),
-                    )));
-                }
-                if !server.model_exists() {
-                    return Err(AIError::LocalAI(LocalAIError::ModelNotFound(
-                        "Model not found. Run: haira model pull".to_string(),
-                    )));
-                }
-                Ok(())
-            }
-        }
-    }
-
-    /// Complete a prompt using the configured backend.
-    async fn complete(&self, system: &str, user_message: &str) -> Result<String, AIError> {
-        match self.backend {
-            AIBackend::Ollama => {
-                let client = self.ollama_client.as_ref().ok_or(AIError::NoBackend)?;
-                Ok(client.complete(system, user_message).await?)
-            }
-            AIBackend::LocalAI => {
-                let server = self.local_ai_server.as_ref().ok_or(AIError::NoBackend)?;
-                let client = server.client();
-                Ok(client.complete(system, user_message).await?)
-            }
-        }
+        self.provider.check_availability().await
     }
 
     /// Interpret a function call and generate CIR.
@@ -216,25 +259,41 @@ impl AIEngine {
             }
         }
 
+        let params: Vec<(String, String)> = context
+            .call_site
+            .arguments
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                let name = arg.name.clone().unwrap_or_else(|| format!("arg{}", i));
+                (name, arg.ty.clone())
+            })
+            .collect();
+        let use_cache = self.config.use_cache && self.provider.caches_results();
+
         // 2. Check cache
         let context_json = serde_json::to_string(&context)?;
         let cache_key = AICache::cache_key(function_name, &context_json);
 
-        if self.config.use_cache {
+        if use_cache {
             if let Some(func) = self.cache.get(&cache_key) {
                 info!("Cache hit for: {}", function_name);
                 return Ok(func);
             }
         }
 
-        // 3. Call AI backend
-        let user_prompt = prompt::build_user_prompt(function_name, &context);
-
-        debug!("Calling {:?} backend...", self.backend);
-        let response_text = self.complete(SYSTEM_PROMPT, &user_prompt).await?;
+        // 3. Ask the provider
+        let request = AIRequest {
+            request_type: RequestType::InferIntent,
+            function_name: function_name.to_string(),
+            intent: None,
+            params,
+            return_type: context.call_site.expected_return.clone(),
+            context,
+        };
 
-        // 4. Parse response
-        let response: AIResponse = self.parse_response(&response_text)?;
+        debug!("Calling {} provider...", self.provider.name());
+        let response = self.provider.interpret(&request).await?;
 
         if !response.success {
             return Err(AIError::InterpretationFailed(
@@ -244,41 +303,22 @@ impl AIEngine {
             ));
         }
 
-        // 5. Check confidence
-        if response.confidence < self.config.min_confidence {
-            warn!(
-                "Low confidence for {}: {} (minimum: {})",
-                function_name, response.confidence, self.config.min_confidence
-            );
-            return Err(AIError::LowConfidence {
-                confidence: response.confidence,
-                minimum: self.config.min_confidence,
-            });
-        }
-
-        let func = response.interpretation.ok_or_else(|| {
-            AIError::InterpretationFailed("No interpretation returned".to_string())
-        })?;
-
-        // 6. Validate CIR
-        if let Err(errors) = haira_cir::validate(&func) {
-            let error_msg = errors
-                .iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<_>>()
-                .join(", ");
-            return Err(AIError::Validation(error_msg));
-        }
+        // 5-6. Pick a confident, valid interpretation
+        let (func, confidence) = Self::select_interpretation(response, self.config.min_confidence)
+            .map_err(|e| {
+                warn!("No acceptable interpretation for {}: {}", function_name, e);
+                e
+            })?;
 
         // 7. Cache result
-        if self.config.use_cache {
+        if use_cache {
             self.cache.set(&cache_key, &func)?;
             info!("Cached result for: {}", function_name);
         }
 
         info!(
             "Successfully interpreted {} with confidence {}",
-            function_name, response.confidence
+            function_name, confidence
         );
 
         Ok(func)
@@ -307,26 +347,33 @@ impl AIEngine {
         info!("Interpreting explicit intent for: {}", name_for_log);
         debug!("Intent: {}", intent);
 
+        let use_cache = self.config.use_cache && self.provider.caches_results();
+
         // 1. Build cache key from intent + signature + context
         let cache_key = self.intent_cache_key(function_name, intent, params, return_type, &context);
 
         // 2. Check cache
-        if self.config.use_cache {
+        if use_cache {
             if let Some(func) = self.cache.get(&cache_key) {
                 info!("Cache hit for intent: {}", name_for_log);
                 return Ok(func);
             }
         }
 
-        // 3. Call AI backend
-        let user_prompt =
-            prompt::build_intent_prompt(function_name, intent, params, return_type, &context);
-
-        debug!("Calling {:?} backend for intent...", self.backend);
-        let response_text = self.complete(SYSTEM_PROMPT, &user_prompt).await?;
+        // 3. Ask the provider
+        let request = AIRequest {
+            request_type: RequestType::ImplementIntent,
+            function_name: function_name
+                .unwrap_or(prompt::ANONYMOUS_FUNCTION_NAME)
+                .to_string(),
+            intent: Some(intent.to_string()),
+            params: params.to_vec(),
+            return_type: return_type.map(str::to_string),
+            context,
+        };
 
-        // 4. Parse response
-        let response: AIResponse = self.parse_response(&response_text)?;
+        debug!("Calling {} provider for intent...", self.provider.name());
+        let mut response = self.provider.interpret(&request).await?;
 
         if !response.success {
             return Err(AIError::InterpretationFailed(
@@ -336,50 +383,41 @@ impl AIEngine {
             ));
         }
 
-        // 5. Check confidence (can be more lenient for explicit intents)
-        let min_confidence = self.config.min_confidence * 0.8; // 20% more lenient for explicit
-        if response.confidence < min_confidence {
-            warn!(
-                "Low confidence for intent {}: {} (minimum: {})",
-                name_for_log, response.confidence, min_confidence
-            );
-            return Err(AIError::LowConfidence {
-                confidence: response.confidence,
-                minimum: min_confidence,
-            });
-        }
-
-        let mut func = response.interpretation.ok_or_else(|| {
-            AIError::InterpretationFailed("No interpretation returned".to_string())
-        })?;
-
-        // 6. Override function name if provided
-        if let Some(name) = function_name {
-            func.name = name.to_string();
+        // 5. Override function name if provided, and fix recursive calls -
+        // LLMs often confuse variable names with function names
+        let candidates = response.interpretation.iter_mut().chain(
+            response
+                .alternatives
+                .iter_mut()
+                .map(|a| &mut a.interpretation),
+        );
+        for func in candidates {
+            if let Some(name) = function_name {
+                func.name = name.to_string();
+            }
+            Self::fix_recursive_calls(func);
         }
 
-        // 6.5. Fix recursive calls - LLMs often confuse variable names with function names
-        Self::fix_recursive_calls(&mut func);
-
-        // 7. Validate CIR
-        if let Err(errors) = haira_cir::validate(&func) {
-            let error_msg = errors
-                .iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<_>>()
-                .join(", ");
-            return Err(AIError::Validation(error_msg));
-        }
+        // 6-7. Pick a confident, valid interpretation (more lenient for explicit intents)
+        let min_confidence = self.config.min_confidence * 0.8; // 20% more lenient for explicit
+        let (func, confidence) =
+            Self::select_interpretation(response, min_confidence).map_err(|e| {
+                warn!(
+                    "No acceptable interpretation for intent {}: {}",
+                    name_for_log, e
+                );
+                e
+            })?;
 
         // 8. Cache result
-        if self.config.use_cache {
+        if use_cache {
             self.cache.set(&cache_key, &func)?;
             info!("Cached intent result for: {}", name_for_log);
         }
 
         info!(
             "Successfully interpreted intent {} with confidence {}",
-            name_for_log, response.confidence
+            name_for_log, confidence
         );
 
         Ok(func)
@@ -422,8 +460,64 @@ impl AIEngine {
         format!("intent_{:x}", hasher.finish())
     }
 
+    /// Pick the interpretation to use from a successful response.
+    ///
+    /// The primary interpretation wins if it meets `min_confidence` and
+    /// passes CIR validation; otherwise the most confident alternative that
+    /// does. Fails with [`AIError::Validation`] if a confident candidate
+    /// existed but none was valid, and [`AIError::LowConfidence`] otherwise.
+    fn select_interpretation(
+        response: AIResponse,
+        min_confidence: f64,
+    ) -> Result<(CIRFunction, f64), AIError> {
+        let mut alternatives: Vec<_> = response
+            .alternatives
+            .into_iter()
+            .map(|a| (a.interpretation, a.confidence))
+            .collect();
+        alternatives.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let candidates = response
+            .interpretation
+            .map(|func| (func, response.confidence))
+            .into_iter()
+            .chain(alternatives);
+
+        let mut best_confidence = None;
+        let mut validation_error = None;
+        for (func, confidence) in candidates {
+            if confidence < min_confidence {
+                best_confidence =
+                    Some(best_confidence.map_or(confidence, |best: f64| best.max(confidence)));
+                continue;
+            }
+            match haira_cir::validate(&func) {
+                Ok(()) => return Ok((func, confidence)),
+                Err(errors) => {
+                    let error_msg = errors
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    debug!("Discarding invalid interpretation: {}", error_msg);
+                    validation_error.get_or_insert(error_msg);
+                }
+            }
+        }
+
+        match (validation_error, best_confidence) {
+            (Some(error_msg), _) => Err(AIError::Validation(error_msg)),
+            (None, Some(confidence)) => Err(AIError::LowConfidence {
+                confidence,
+                minimum: min_confidence,
+            }),
+            (None, None) => Err(AIError::InterpretationFailed(
+                "No interpretation returned".to_string(),
+            )),
+        }
+    }
+
     /// Parse AI response, handling potential JSON issues.
-    fn parse_response(&self, text: &str) -> Result<AIResponse, serde_json::Error> {
+    pub(crate) fn parse_response(text: &str) -> Result<AIResponse, serde_json::Error> {
         debug!("Raw AI response ({} chars):\n{}", text.len(), text);
 
         // Clean up common LLM artifacts
@@ -436,7 +530,7 @@ impl AIEngine {
         debug!("Extracted JSON:\n{}", json_text);
 
         // Normalize the JSON to handle common model variations
-        let normalized = self.normalize_cir_json(&json_text);
+        let normalized = Self::normalize_cir_json(&json_text);
         debug!("Normalized JSON:\n{}", normalized);
 
         let result = serde_json::from_str(&normalized);
@@ -640,7 +734,7 @@ impl AIEngine {
     }
 
     /// Normalize CIR JSON to handle common variations from different models.
-    fn normalize_cir_json(&self, json: &str) -> String {
+    fn normalize_cir_json(json: &str) -> String {
         // Parse as generic JSON value for manipulation
         let Ok(mut value) = serde_json::from_str::<serde_json::Value>(json) else {
             return json.to_string();
@@ -1656,7 +1750,7 @@ Output only the JSON object, nothing else:"#
 
         let system = "You are a type inference assistant. Given field names, infer their types. Output only valid JSON.";
 
-        let response = self.complete(system, &prompt).await?;
+        let response = self.provider.complete(system, &prompt).await?;
 
         // Parse the response as JSON
         let cleaned = Self::clean_llm_output(&response);
@@ -1757,6 +1851,114 @@ mod tests {
         assert_eq!(func.name, "get_users");
     }
 
+    /// A valid function returning `value`.
+    fn returning_int(name: &str, value: i64) -> CIRFunction {
+        CIRFunction::new(name)
+            .returning("int")
+            .with_op(haira_cir::CIROperation::Literal {
+                value: haira_cir::CIRValue::Int(value),
+                result: "_lit".to_string(),
+            })
+            .with_op(haira_cir::CIROperation::Return {
+                value: haira_cir::CIRValue::var("_lit"),
+            })
+    }
+
+    fn ai_response(
+        primary: CIRFunction,
+        confidence: f64,
+        alternatives: Vec<(CIRFunction, f64)>,
+    ) -> AIResponse {
+        AIResponse {
+            success: true,
+            interpretation: Some(primary),
+            confidence,
+            alternatives: alternatives
+                .into_iter()
+                .map(|(interpretation, confidence)| haira_cir::Alternative {
+                    interpretation,
+                    confidence,
+                })
+                .collect(),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_low_confidence_primary_falls_back_to_alternative() {
+        let response = ai_response(
+            returning_int("pick", 1),
+            0.2,
+            vec![
+                (returning_int("pick", 2), 0.6),
+                (returning_int("pick", 3), 0.9),
+            ],
+        );
+
+        let (func, confidence) = AIEngine::select_interpretation(response, 0.5).unwrap();
+        assert_eq!(confidence, 0.9);
+        assert!(matches!(
+            func.body[0],
+            haira_cir::CIROperation::Literal {
+                value: haira_cir::CIRValue::Int(3),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_invalid_candidates_are_discarded() {
+        let invalid =
+            CIRFunction::new("pick")
+                .returning("int")
+                .with_op(haira_cir::CIROperation::Return {
+                    value: haira_cir::CIRValue::var("undefined"),
+                });
+        let response = ai_response(
+            invalid.clone(),
+            0.95,
+            vec![(invalid.clone(), 0.9), (returning_int("pick", 2), 0.7)],
+        );
+        let (func, confidence) = AIEngine::select_interpretation(response, 0.5).unwrap();
+        assert_eq!(confidence, 0.7);
+        assert_eq!(func.body.len(), 2);
+
+        let all_invalid = ai_response(invalid, 0.95, vec![]);
+        assert!(matches!(
+            AIEngine::select_interpretation(all_invalid, 0.5),
+            Err(AIError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_confident_candidate_is_low_confidence() {
+        let response = ai_response(
+            returning_int("pick", 1),
+            0.2,
+            vec![(returning_int("pick", 2), 0.4)],
+        );
+        match AIEngine::select_interpretation(response, 0.5) {
+            Err(AIError::LowConfidence {
+                confidence,
+                minimum,
+            }) => {
+                assert_eq!(confidence, 0.4);
+                assert_eq!(minimum, 0.5);
+            }
+            other => panic!("expected low confidence, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plain_alternative_parses_without_confidence() {
+        let json = r#"{"success": true, "confidence": 0.9, "alternatives": [
+            {"name": "f", "params": [], "returns": "int", "body": []}
+        ]}"#;
+        let response: AIResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.alternatives[0].interpretation.name, "f");
+        assert_eq!(response.alternatives[0].confidence, 0.0);
+    }
+
     #[test]
     fn test_confidence_levels() {
         assert_eq!(AIEngine::confidence_level(0.95), "high");
@@ -1765,10 +1967,181 @@ mod tests {
         assert_eq!(AIEngine::confidence_level(0.3), "failed");
     }
 
+    #[tokio::test]
+    async fn test_mock_backend_interpret_intent() {
+        let mut engine = AIEngine::mock(AIConfig::default());
+        assert_eq!(engine.provider().name(), "mock");
+
+        let params = vec![("id".to_string(), "int".to_string())];
+        let func = engine
+            .interpret_intent(
+                Some("load_user"),
+                "Load a user by id",
+                &params,
+                Some("User"),
+                test_context(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(func.name, "load_user");
+        assert!(haira_cir::validate(&func).is_ok());
+        assert!(matches!(
+            func.body[0],
+            haira_cir::CIROperation::Construct { .. }
+        ));
+    }
+
     #[test]
     fn test_ollama_backend() {
         let config = AIConfig::default();
         let engine = AIEngine::with_ollama(config, Some("codellama:7b"));
-        assert_eq!(engine.backend(), AIBackend::Ollama);
+        assert_eq!(engine.provider().name(), "ollama");
+    }
+
+    /// Provider answering every request with a canned response, recording
+    /// the requests it gets.
+    struct CannedProvider {
+        response: AIResponse,
+        requests: std::sync::Mutex<Vec<AIRequest>>,
+    }
+
+    #[async_trait::async_trait]
+    impl AiProvider for CannedProvider {
+        fn name(&self) -> &str {
+            "canned"
+        }
+
+        async fn interpret(&self, request: &AIRequest) -> Result<AIResponse, AIError> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(self.response.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_provider() {
+        let provider = std::sync::Arc::new(CannedProvider {
+            response: ai_response(returning_int("do_something_complex", 7), 0.9, vec![]),
+            requests: Default::default(),
+        });
+
+        /// Share the provider with the test, to inspect its requests.
+        struct Shared(std::sync::Arc<CannedProvider>);
+
+        #[async_trait::async_trait]
+        impl AiProvider for Shared {
+            fn name(&self) -> &str {
+                self.0.name()
+            }
+
+            async fn interpret(&self, request: &AIRequest) -> Result<AIResponse, AIError> {
+                self.0.interpret(request).await
+            }
+        }
+
+        let mut engine =
+            AIEngine::with_provider(AIConfig::default(), Box::new(Shared(provider.clone())));
+        assert_eq!(engine.provider().name(), "canned");
+
+        let func = engine
+            .interpret("do_something_complex", test_context())
+            .await
+            .unwrap();
+        assert_eq!(func.name, "do_something_complex");
+        assert_eq!(func.body.len(), 2);
+
+        let params = vec![("n".to_string(), "int".to_string())];
+        let func = engine
+            .interpret_intent(
+                Some("count_things"),
+                "Count the things",
+                &params,
+                Some("int"),
+                test_context(),
+            )
+            .await
+            .unwrap();
+        // The intent's name wins over the one the provider returned
+        assert_eq!(func.name, "count_things");
+
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].request_type, RequestType::InferIntent);
+        assert_eq!(requests[0].function_name, "do_something_complex");
+        assert_eq!(requests[1].request_type, RequestType::ImplementIntent);
+        assert_eq!(requests[1].intent.as_deref(), Some("Count the things"));
+        assert_eq!(requests[1].params, params);
+        assert_eq!(requests[1].return_type.as_deref(), Some("int"));
+    }
+
+    fn fast_policy(timeout: Duration, max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            timeout,
+            max_retries,
+            backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_succeeds_after_transient_failures() {
+        let policy = fast_policy(Duration::from_secs(5), 3);
+        let mut calls = 0;
+
+        let result = policy
+            .run(|| {
+                calls += 1;
+                let attempt = calls;
+                async move {
+                    if attempt <= 2 {
+                        Err(AIError::Ollama(OllamaError::ServerNotRunning(
+                            "http://localhost:11434".to_string(),
+                        )))
+                    } else {
+                        Ok("done".to_string())
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_times_out() {
+        let policy = fast_policy(Duration::from_millis(20), 2);
+        let mut calls = 0;
+
+        let result = policy
+            .run(|| {
+                calls += 1;
+                async {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                    Ok(String::new())
+                }
+            })
+            .await;
+
+        match result {
+            Err(AIError::Timeout { attempts, .. }) => assert_eq!(attempts, 3),
+            other => panic!("expected timeout, got {:?}", other),
+        }
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_permanent_error() {
+        let policy = fast_policy(Duration::from_secs(5), 3);
+        let mut calls = 0;
+
+        let result = policy
+            .run(|| {
+                calls += 1;
+                async { Err(AIError::Validation("bad CIR".to_string())) }
+            })
+            .await;
+
+        assert!(matches!(result, Err(AIError::Validation(_))));
+        assert_eq!(calls, 1);
     }
 }
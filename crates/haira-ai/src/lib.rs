@@ -16,10 +16,14 @@
 //!                         └───────────┘
 //! ```
 //!
-//! ## AI Backends
+//! ## AI Providers
 //!
 //! - **Local AI** (primary) - Uses llama.cpp with local models
 //! - **Ollama** (fallback) - Uses Ollama server
+//! - **Mock** - Deterministic stubs, no model (for tests and CI)
+//!
+//! Other providers plug in by implementing [`AiProvider`] and passing it to
+//! [`AIEngine::with_provider`].
 //!
 //! ## Usage
 //!
@@ -36,13 +40,17 @@ mod cache;
 mod config;
 mod engine;
 pub mod hif;
+mod mock;
 mod ollama;
 mod prompt;
+pub mod provider;
 
 pub use cache::AICache;
-pub use config::AIConfig;
-pub use engine::{AIBackend, AIEngine, AIError};
+pub use config::{AIBackend, AIConfig};
+pub use engine::{AIEngine, AIError};
 pub use ollama::{OllamaClient, OllamaError, DEFAULT_OLLAMA_MODEL, DEFAULT_OLLAMA_URL};
+pub use prompt::{estimate_tokens, PromptBuilder};
+pub use provider::AiProvider;
 
 // Re-export local AI types
 pub use haira_local_ai::{
@@ -52,6 +60,6 @@ pub use haira_local_ai::{
 
 // Re-export CIR types for convenience
 pub use haira_cir::{
-    AIRequest, AIResponse, CIRFunction, CIROperation, CIRType, CIRValue, InterpretationContext,
-    TypeDefinition,
+    AIRequest, AIResponse, Alternative, CIRFunction, CIROperation, CIRType, CIRValue,
+    InterpretationContext, RequestType, TypeDefinition,
 };
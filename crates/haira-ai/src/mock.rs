@@ -0,0 +1,165 @@
+//! Mock backend - deterministic CIR stubs without a model.
+//!
+//! Used by `haira build --mock-ai` and in tests. The generated body only
+//! depends on the function signature and the types in scope, so the same
+//! input always produces the same CIR.
+
+use haira_cir::{CIRFunction, CIROperation, CIRType, CIRValue, TypeDefinition};
+
+/// Name of the variable holding the stub's return value.
+const RESULT_VAR: &str = "_result";
+
+/// Build a stub function that returns a default value of its return type.
+///
+/// Structs known from `types` are constructed with default field values,
+/// lists are empty, and everything else falls back to a primitive default.
+pub(crate) fn mock_function(
+    name: &str,
+    params: &[(String, String)],
+    return_type: Option<&str>,
+    types: &[TypeDefinition],
+) -> CIRFunction {
+    let mut func = CIRFunction::new(name);
+
+    for (param_name, param_type) in params {
+        func = func.with_param(param_name, parse_type_string(param_type));
+    }
+
+    let ret_type = return_type.unwrap_or("none");
+    func = func.returning(parse_type_string(ret_type));
+
+    match default_operation(ret_type, types) {
+        Some(op) => func.with_op(op).with_op(CIROperation::Return {
+            value: CIRValue::var(RESULT_VAR),
+        }),
+        None => func.with_op(CIROperation::Return {
+            value: CIRValue::None,
+        }),
+    }
+}
+
+/// The operation producing a default value of `ty` into [`RESULT_VAR`].
+fn default_operation(ty: &str, types: &[TypeDefinition]) -> Option<CIROperation> {
+    if ty.starts_with('[') {
+        return Some(CIROperation::CreateList {
+            elements: Vec::new(),
+            result: RESULT_VAR.to_string(),
+        });
+    }
+
+    if let Some(def) = types.iter().find(|t| t.name == ty) {
+        let fields = def
+            .fields
+            .iter()
+            .map(|f| (f.name.clone(), default_value(&f.ty)))
+            .collect();
+        return Some(CIROperation::Construct {
+            ty: def.name.clone(),
+            fields,
+            result: RESULT_VAR.to_string(),
+        });
+    }
+
+    match default_value(ty) {
+        CIRValue::None => None,
+        value => Some(CIROperation::Literal {
+            value,
+            result: RESULT_VAR.to_string(),
+        }),
+    }
+}
+
+/// Default literal for a primitive type; `None` for anything else.
+fn default_value(ty: &str) -> CIRValue {
+    match ty {
+        "int" => CIRValue::Int(0),
+        "float" => CIRValue::Float(0.0),
+        "string" => CIRValue::string(""),
+        "bool" => CIRValue::Bool(false),
+        _ => CIRValue::None,
+    }
+}
+
+/// Parse a type string (`int`, `[User]`, `Option<int>`) into a CIR type.
+fn parse_type_string(s: &str) -> CIRType {
+    if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        CIRType::list(parse_type_string(inner))
+    } else if let Some(inner) = s.strip_prefix("Option<").and_then(|s| s.strip_suffix('>')) {
+        CIRType::option(parse_type_string(inner))
+    } else {
+        CIRType::simple(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use haira_cir::FieldDefinition;
+
+    fn user_type() -> TypeDefinition {
+        TypeDefinition {
+            name: "User".to_string(),
+            fields: vec![
+                FieldDefinition {
+                    name: "name".to_string(),
+                    ty: "string".to_string(),
+                    optional: false,
+                    default: None,
+                },
+                FieldDefinition {
+                    name: "age".to_string(),
+                    ty: "int".to_string(),
+                    optional: false,
+                    default: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_mock_int_is_valid() {
+        let params = vec![("a".to_string(), "int".to_string())];
+        let func = mock_function("double", &params, Some("int"), &[]);
+
+        assert!(haira_cir::validate(&func).is_ok());
+        assert!(matches!(
+            func.body[0],
+            CIROperation::Literal {
+                value: CIRValue::Int(0),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_mock_struct_is_valid() {
+        let func = mock_function("new_user", &[], Some("User"), &[user_type()]);
+
+        assert!(haira_cir::validate(&func).is_ok());
+        match &func.body[0] {
+            CIROperation::Construct { ty, fields, .. } => {
+                assert_eq!(ty, "User");
+                assert_eq!(fields.len(), 2);
+            }
+            other => panic!("expected construct, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_mock_other_types_are_valid() {
+        for ret in [
+            None,
+            Some("float"),
+            Some("string"),
+            Some("bool"),
+            Some("[User]"),
+        ] {
+            let func = mock_function("f", &[], ret, &[user_type()]);
+            assert!(
+                haira_cir::validate(&func).is_ok(),
+                "invalid mock for {:?}",
+                ret
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
 //! Ollama API client for local LLM inference.
 
+use futures_util::future::Either;
+use futures_util::{stream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -25,6 +27,8 @@ pub enum OllamaError {
     Api(String),
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("Malformed stream chunk: {0}")]
+    Stream(String),
     #[error("Ollama server not running at {0}. Start it with: ollama serve")]
     ServerNotRunning(String),
     #[error("Model '{0}' not found. Pull it with: ollama pull {0}")]
@@ -49,10 +53,13 @@ struct OllamaOptions {
 }
 
 /// Response from Ollama generate API.
+///
+/// With `stream: true` the server sends one of these per line.
 #[derive(Debug, Deserialize)]
 struct OllamaResponse {
+    #[serde(default)]
     response: String,
-    #[allow(dead_code)]
+    #[serde(default)]
     done: bool,
     #[serde(default)]
     error: Option<String>,
@@ -132,24 +139,62 @@ impl OllamaClient {
     }
 
     /// Send a prompt to Ollama and get a response.
+    ///
+    /// The response is streamed from the server and buffered into a single string.
     pub async fn complete(&self, system: &str, user_message: &str) -> Result<String, OllamaError> {
+        let fragments = self.stream_generate(Some(system), user_message).await;
+        futures_util::pin_mut!(fragments);
+
+        let mut output = String::new();
+        while let Some(fragment) = fragments.next().await {
+            output.push_str(&fragment?);
+        }
+
+        Ok(output)
+    }
+
+    /// Send a prompt to Ollama and stream back token fragments as they arrive.
+    ///
+    /// Connection and API errors are yielded as the first (and only) item.
+    pub async fn generate_stream(
+        &self,
+        prompt: &str,
+    ) -> impl Stream<Item = Result<String, OllamaError>> {
+        self.stream_generate(None, prompt).await
+    }
+
+    async fn stream_generate(
+        &self,
+        system: Option<&str>,
+        prompt: &str,
+    ) -> impl Stream<Item = Result<String, OllamaError>> {
         let request = OllamaRequest {
             model: self.model.clone(),
-            prompt: user_message.to_string(),
-            system: Some(system.to_string()),
-            stream: false,
+            prompt: prompt.to_string(),
+            system: system.map(str::to_string),
+            stream: true,
             options: Some(OllamaOptions {
                 temperature: 0.1, // Low temperature for deterministic code generation
                 num_predict: 4096,
             }),
         };
 
+        match self.send_generate(&request).await {
+            Ok(response) => Either::Left(ndjson_fragments(response.bytes_stream())),
+            Err(e) => Either::Right(stream::once(async move { Err(e) })),
+        }
+    }
+
+    async fn send_generate(
+        &self,
+        request: &OllamaRequest,
+    ) -> Result<reqwest::Response, OllamaError> {
         let url = format!("{}/api/generate", self.base_url);
 
         let response = self
             .client
             .post(&url)
-            .json(&request)
+            .json(request)
             .send()
             .await
             .map_err(|e| {
@@ -166,13 +211,7 @@ impl OllamaClient {
             return Err(OllamaError::Api(format!("{}: {}", status, text)));
         }
 
-        let response: OllamaResponse = response.json().await?;
-
-        if let Some(error) = response.error {
-            return Err(OllamaError::Api(error));
-        }
-
-        Ok(response.response)
+        Ok(response)
     }
 
     /// Get the current model name.
@@ -192,9 +231,145 @@ impl Default for OllamaClient {
     }
 }
 
+/// Turn a newline-delimited JSON body into a stream of response fragments.
+///
+/// Stops after the chunk marked `done`, or after the first error.
+fn ndjson_fragments<S, B>(body: S) -> impl Stream<Item = Result<String, OllamaError>>
+where
+    S: Stream<Item = Result<B, reqwest::Error>>,
+    B: AsRef<[u8]>,
+{
+    let state = (Box::pin(body), Vec::new(), false);
+
+    stream::unfold(state, |(mut body, mut buffer, mut finished)| async move {
+        loop {
+            if finished {
+                return None;
+            }
+
+            let line = if let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                buffer.drain(..=pos).collect::<Vec<u8>>()
+            } else {
+                match body.next().await {
+                    Some(Ok(bytes)) => {
+                        buffer.extend_from_slice(bytes.as_ref());
+                        continue;
+                    }
+                    Some(Err(e)) => {
+                        return Some((Err(OllamaError::Http(e)), (body, buffer, true)));
+                    }
+                    // Flush a trailing line that wasn't newline-terminated
+                    None => {
+                        finished = true;
+                        std::mem::take(&mut buffer)
+                    }
+                }
+            };
+
+            match parse_chunk(&line) {
+                None => continue,
+                Some(Ok((fragment, done))) => {
+                    finished |= done;
+                    if fragment.is_empty() {
+                        continue;
+                    }
+                    return Some((Ok(fragment), (body, buffer, finished)));
+                }
+                Some(Err(e)) => return Some((Err(e), (body, buffer, true))),
+            }
+        }
+    })
+}
+
+/// Parse one streamed line into `(fragment, done)`. Blank lines yield `None`;
+/// lines that aren't UTF-8 are an error rather than skipped.
+fn parse_chunk(line: &[u8]) -> Option<Result<(String, bool), OllamaError>> {
+    let line = match std::str::from_utf8(line) {
+        Ok(line) => line.trim(),
+        Err(e) => return Some(Err(OllamaError::Stream(e.to_string()))),
+    };
+    if line.is_empty() {
+        return None;
+    }
+
+    let chunk: OllamaResponse = match serde_json::from_str(line) {
+        Ok(chunk) => chunk,
+        Err(e) => return Some(Err(OllamaError::Json(e))),
+    };
+
+    if let Some(error) = chunk.error {
+        return Some(Err(OllamaError::Api(error)));
+    }
+
+    Some(Ok((chunk.response, chunk.done)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve a single `/api/generate` request, writing each chunk as its own NDJSON line.
+    async fn mock_generate_server(chunks: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            // Drain the request headers and body
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|l| {
+                            let (name, value) = l.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+
+            socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+
+            for (i, chunk) in chunks.iter().enumerate() {
+                let line = serde_json::json!({ "response": chunk, "done": false });
+                socket
+                    .write_all(format!("{}\n", line).as_bytes())
+                    .await
+                    .unwrap();
+                socket.flush().await.unwrap();
+                if i + 1 < chunks.len() {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+            }
+            socket
+                .write_all(b"{\"response\":\"\",\"done\":true}\n")
+                .await
+                .unwrap();
+            socket.shutdown().await.unwrap();
+        });
+
+        format!("http://{}", addr)
+    }
 
     #[test]
     fn test_default_client() {
@@ -218,4 +393,50 @@ mod tests {
         assert_eq!(client.base_url(), "http://myserver:11434");
         assert_eq!(client.model(), "qwen2.5-coder:7b");
     }
+
+    #[tokio::test]
+    async fn test_generate_stream_yields_chunks() {
+        let url = mock_generate_server(vec!["fn ", "main", "() {}"]).await;
+        let client = OllamaClient::with_config(url, "test-model");
+
+        let fragments: Vec<String> = client
+            .generate_stream("write main")
+            .await
+            .map(|f| f.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(fragments, vec!["fn ", "main", "() {}"]);
+        assert_eq!(fragments.concat(), "fn main() {}");
+    }
+
+    #[tokio::test]
+    async fn test_complete_buffers_stream() {
+        let url = mock_generate_server(vec!["{\"ok\":", " true", "}"]).await;
+        let client = OllamaClient::with_config(url, "test-model");
+
+        let response = client.complete("system", "user").await.unwrap();
+        assert_eq!(response, "{\"ok\": true}");
+    }
+
+    #[test]
+    fn test_parse_chunk_error() {
+        let result = parse_chunk(br#"{"error":"model not loaded"}"#);
+        assert!(matches!(result, Some(Err(OllamaError::Api(_)))));
+        assert!(parse_chunk(b"  \n").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_chunk_ends_stream_with_error() {
+        let body = stream::iter(vec![
+            Ok::<_, reqwest::Error>(b"{\"response\":\"fn \"}\n".to_vec()),
+            Ok(b"{\"response\":\"\xff\"}\n".to_vec()),
+            Ok(b"{\"response\":\"main\"}\n".to_vec()),
+        ]);
+
+        let results: Vec<_> = ndjson_fragments(body).collect().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap(), "fn ");
+        assert!(matches!(results[1], Err(OllamaError::Stream(_))));
+    }
 }
@@ -1,6 +1,8 @@
 //! Prompt engineering for AI intent interpretation.
 
-use haira_cir::InterpretationContext;
+use std::collections::{HashSet, VecDeque};
+
+use haira_cir::{CallSiteInfo, InterpretationContext, ProjectSchema, TypeDefinition};
 
 /// System prompt for intent interpretation.
 pub const SYSTEM_PROMPT: &str = r#"You are a code generation assistant for the Haira programming language. Your task is to interpret function names and generate Canonical IR (CIR) implementations.
@@ -245,9 +247,158 @@ If you cannot interpret the function, return:
 ```
 "#;
 
+/// Approximate number of bytes per token, for budgeting prompts.
+const BYTES_PER_TOKEN: usize = 4;
+
+/// Name given to an `ai` block without one.
+pub(crate) const ANONYMOUS_FUNCTION_NAME: &str = "anonymous_ai_function";
+
+/// Estimate how many tokens `text` takes up.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(BYTES_PER_TOKEN)
+}
+
+/// Builds the JSON context section of a prompt within a token budget.
+///
+/// The call site and project schema are always included. Types in scope are
+/// added most relevant first: those named by the call site's argument types
+/// and expected return, or passed to [`PromptBuilder::reference`], then the
+/// types their fields use, then the rest in declaration order. A type that
+/// would push the context over the budget is left out.
+#[derive(Debug, Clone, Default)]
+pub struct PromptBuilder {
+    call_site: Option<CallSiteInfo>,
+    types: Vec<TypeDefinition>,
+    project_schema: ProjectSchema,
+    references: Vec<String>,
+    max_tokens: Option<usize>,
+}
+
+impl PromptBuilder {
+    /// Create an empty builder with no budget.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from everything in `context`.
+    pub fn from_context(context: &InterpretationContext) -> Self {
+        Self::new()
+            .call_site(context.call_site.clone())
+            .types(context.types_in_scope.iter().cloned())
+            .project_schema(context.project_schema.clone())
+    }
+
+    /// Set the call site being interpreted.
+    pub fn call_site(mut self, call_site: CallSiteInfo) -> Self {
+        self.call_site = Some(call_site);
+        self
+    }
+
+    /// Add types in scope.
+    pub fn types(mut self, types: impl IntoIterator<Item = TypeDefinition>) -> Self {
+        self.types.extend(types);
+        self
+    }
+
+    /// Set the project schema.
+    pub fn project_schema(mut self, schema: ProjectSchema) -> Self {
+        self.project_schema = schema;
+        self
+    }
+
+    /// Mark the types named in the type string `ty` (like `[User]`) as relevant.
+    pub fn reference(mut self, ty: &str) -> Self {
+        self.references.push(ty.to_string());
+        self
+    }
+
+    /// Limit the context to about `max_tokens` tokens.
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// The context to send, with the types that fit the budget.
+    pub fn build_context(&self) -> InterpretationContext {
+        let mut context = InterpretationContext {
+            types_in_scope: Vec::new(),
+            call_site: self.call_site.clone().unwrap_or_else(|| CallSiteInfo {
+                file: String::new(),
+                line: 0,
+                arguments: Vec::new(),
+                expected_return: None,
+            }),
+            project_schema: self.project_schema.clone(),
+        };
+
+        for ty in self.types_by_relevance() {
+            context.types_in_scope.push(ty.clone());
+            if let Some(max_tokens) = self.max_tokens {
+                if estimate_tokens(&render(&context)) > max_tokens {
+                    context.types_in_scope.pop();
+                }
+            }
+        }
+        context
+    }
+
+    /// The context as pretty-printed JSON.
+    pub fn build(&self) -> String {
+        render(&self.build_context())
+    }
+
+    fn types_by_relevance(&self) -> Vec<&TypeDefinition> {
+        let call_site = self.call_site.iter().flat_map(|site| {
+            let arguments = site.arguments.iter().map(|arg| arg.ty.as_str());
+            arguments.chain(site.expected_return.as_deref())
+        });
+        let mut pending: VecDeque<&str> = call_site
+            .chain(self.references.iter().map(String::as_str))
+            .flat_map(type_names)
+            .collect();
+
+        // Breadth-first, so direct references come before the types they use
+        let mut seen = HashSet::new();
+        let mut ordered = Vec::new();
+        while let Some(name) = pending.pop_front() {
+            if let Some(ty) = self.types.iter().find(|t| t.name == name) {
+                if seen.insert(name) {
+                    ordered.push(ty);
+                    pending.extend(ty.fields.iter().flat_map(|f| type_names(&f.ty)));
+                }
+            }
+        }
+
+        ordered.extend(
+            self.types
+                .iter()
+                .filter(|t| !seen.contains(t.name.as_str())),
+        );
+        ordered
+    }
+}
+
+/// The identifiers in a type string: `{string:[User]}` names `string` and `User`.
+fn type_names(ty: &str) -> impl Iterator<Item = &str> {
+    ty.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|name| !name.is_empty())
+}
+
+fn render(context: &InterpretationContext) -> String {
+    serde_json::to_string_pretty(context).unwrap_or_default()
+}
+
 /// Build the user prompt for a specific request.
-pub fn build_user_prompt(function_name: &str, context: &InterpretationContext) -> String {
-    let context_json = serde_json::to_string_pretty(context).unwrap_or_default();
+///
+/// The context is limited to about `max_context_tokens` tokens.
+pub fn build_user_prompt(
+    function_name: &str,
+    context: &InterpretationContext,
+    max_context_tokens: usize,
+) -> String {
+    let context_json = PromptBuilder::from_context(context)
+        .max_tokens(max_context_tokens)
+        .build();
 
     format!(
         r#"Interpret the function `{function_name}` and generate a CIR implementation.
@@ -272,17 +423,27 @@ Generate the CIR now:"#
 /// Build the user prompt for an explicit AI intent block.
 ///
 /// This is used when the user explicitly specifies what they want via
-/// the `ai` block syntax with natural language description.
+/// the `ai` block syntax with natural language description. The context
+/// is limited to about `max_context_tokens` tokens, favoring the types the
+/// signature uses.
 pub fn build_intent_prompt(
     function_name: Option<&str>,
     intent: &str,
     params: &[(String, String)], // (name, type) pairs
     return_type: Option<&str>,
     context: &InterpretationContext,
+    max_context_tokens: usize,
 ) -> String {
-    let context_json = serde_json::to_string_pretty(context).unwrap_or_default();
+    let mut builder = PromptBuilder::from_context(context).max_tokens(max_context_tokens);
+    for (_, ty) in params {
+        builder = builder.reference(ty);
+    }
+    if let Some(ty) = return_type {
+        builder = builder.reference(ty);
+    }
+    let context_json = builder.build();
 
-    let name = function_name.unwrap_or("anonymous_ai_function");
+    let name = function_name.unwrap_or(ANONYMOUS_FUNCTION_NAME);
 
     let params_desc = if params.is_empty() {
         "No parameters".to_string()
@@ -582,6 +743,90 @@ fn capitalize(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use haira_cir::{ArgumentInfo, FieldDefinition};
+
+    fn type_def(name: &str, fields: &[(&str, &str)]) -> TypeDefinition {
+        TypeDefinition {
+            name: name.to_string(),
+            fields: fields
+                .iter()
+                .map(|(name, ty)| FieldDefinition {
+                    name: name.to_string(),
+                    ty: ty.to_string(),
+                    optional: false,
+                    default: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn call_site(arguments: &[&str], expected_return: Option<&str>) -> CallSiteInfo {
+        CallSiteInfo {
+            file: "main.haira".to_string(),
+            line: 1,
+            arguments: arguments
+                .iter()
+                .map(|ty| ArgumentInfo {
+                    name: None,
+                    ty: ty.to_string(),
+                })
+                .collect(),
+            expected_return: expected_return.map(str::to_string),
+        }
+    }
+
+    /// Many unrelated types, declared before `Report` and `Entry`.
+    fn large_scope() -> Vec<TypeDefinition> {
+        let mut types: Vec<_> = (0..200)
+            .map(|i| {
+                type_def(
+                    &format!("Unrelated{}", i),
+                    &[("id", "int"), ("name", "string")],
+                )
+            })
+            .collect();
+        types.push(type_def("Report", &[("entries", "[Entry]")]));
+        types.push(type_def("Entry", &[("total", "float")]));
+        types
+    }
+
+    #[test]
+    fn test_budget_keeps_expected_return_type() {
+        let builder = PromptBuilder::new()
+            .call_site(call_site(&["int"], Some("Option<Report>")))
+            .types(large_scope())
+            .max_tokens(300);
+        let context = builder.build_context();
+        let names: Vec<&str> = context
+            .types_in_scope
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+
+        assert_eq!(&names[..2], ["Report", "Entry"]);
+        assert!(names.len() < 202);
+        assert!(estimate_tokens(&builder.build()) <= 300);
+    }
+
+    #[test]
+    fn test_intent_prompt_respects_budget() {
+        let context = InterpretationContext {
+            types_in_scope: large_scope(),
+            call_site: call_site(&[], None),
+            project_schema: ProjectSchema::default(),
+        };
+        let full = build_intent_prompt(None, "Sum it up", &[], Some("Report"), &context, 100_000);
+        let small = build_intent_prompt(None, "Sum it up", &[], Some("Report"), &context, 200);
+
+        assert!(full.contains("Unrelated199"));
+        assert!(small.contains("\"Report\""));
+        assert!(!small.contains("Unrelated199"));
+        let context_json = PromptBuilder::from_context(&context)
+            .reference("Report")
+            .max_tokens(200)
+            .build();
+        assert!(estimate_tokens(&context_json) <= 200);
+    }
 
     #[test]
     fn test_parse_get_all() {
@@ -0,0 +1,262 @@
+//! AI providers - where the engine's interpretations come from.
+//!
+//! [`AiProvider`] turns an [`AIRequest`] into an [`AIResponse`]. The engine
+//! handles everything around it (name patterns, caching, confidence and
+//! validation), so a provider only has to answer requests. The built-in
+//! providers are:
+//! - [`LocalAIProvider`] - llama.cpp with local models, falling back to Ollama
+//! - [`OllamaProvider`] - an Ollama server
+//! - [`MockProvider`] - deterministic stubs, no model (for tests and CI)
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::config::AIConfig;
+use crate::engine::{AIEngine, AIError, RetryPolicy, LOCAL_SERVER_READY_TIMEOUT};
+use crate::mock;
+use crate::ollama::OllamaClient;
+use crate::prompt::{self, SYSTEM_PROMPT};
+use haira_cir::{AIRequest, AIResponse, RequestType};
+use haira_local_ai::{LlamaCppServer, LocalAIError};
+
+/// A source of interpretations for [`AIEngine`](crate::AIEngine).
+///
+/// Implement this to plug in another model provider, such as an
+/// OpenAI-compatible endpoint, and pass it to
+/// [`AIEngine::with_provider`](crate::AIEngine::with_provider).
+#[async_trait]
+pub trait AiProvider: Send + Sync {
+    /// Short name for logs, like `"ollama"`.
+    fn name(&self) -> &str;
+
+    /// Interpret a request.
+    async fn interpret(&self, request: &AIRequest) -> Result<AIResponse, AIError>;
+
+    /// Complete a free-form prompt, for requests that don't produce CIR
+    /// (like inferring struct field types). Unsupported by default.
+    async fn complete(&self, _system: &str, _prompt: &str) -> Result<String, AIError> {
+        Err(AIError::NoBackend)
+    }
+
+    /// Check that the provider can serve requests.
+    async fn check_availability(&self) -> Result<(), AIError> {
+        Ok(())
+    }
+
+    /// Start any server the provider manages.
+    async fn start(&mut self) -> Result<(), AIError> {
+        Ok(())
+    }
+
+    /// Stop any server the provider manages.
+    fn stop(&mut self) -> Result<(), AIError> {
+        Ok(())
+    }
+
+    /// Whether a server managed by the provider is running.
+    fn is_running(&mut self) -> bool {
+        false
+    }
+
+    /// Whether the engine should cache this provider's interpretations.
+    fn caches_results(&self) -> bool {
+        true
+    }
+}
+
+/// The user prompt for a request to a model.
+fn user_prompt(request: &AIRequest, max_context_tokens: usize) -> String {
+    match request.request_type {
+        RequestType::ImplementIntent => prompt::build_intent_prompt(
+            Some(&request.function_name),
+            request.intent.as_deref().unwrap_or_default(),
+            &request.params,
+            request.return_type.as_deref(),
+            &request.context,
+            max_context_tokens,
+        ),
+        _ => {
+            prompt::build_user_prompt(&request.function_name, &request.context, max_context_tokens)
+        }
+    }
+}
+
+/// Provider backed by an Ollama server.
+pub struct OllamaProvider {
+    client: OllamaClient,
+    policy: RetryPolicy,
+    max_context_tokens: usize,
+}
+
+impl OllamaProvider {
+    /// Use `client`, with the timeouts and prompt budget of `config`.
+    pub fn new(client: OllamaClient, config: &AIConfig) -> Self {
+        Self {
+            client,
+            policy: RetryPolicy::from_config(config),
+            max_context_tokens: config.max_context_tokens,
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    async fn interpret(&self, request: &AIRequest) -> Result<AIResponse, AIError> {
+        let prompt = user_prompt(request, self.max_context_tokens);
+        let text = self.complete(SYSTEM_PROMPT, &prompt).await?;
+        Ok(AIEngine::parse_response(&text)?)
+    }
+
+    async fn complete(&self, system: &str, prompt: &str) -> Result<String, AIError> {
+        self.policy
+            .run(|| async { Ok(self.client.complete(system, prompt).await?) })
+            .await
+    }
+
+    async fn check_availability(&self) -> Result<(), AIError> {
+        self.client.check_availability().await?;
+        Ok(())
+    }
+}
+
+/// Provider backed by a self-managed llama.cpp server.
+///
+/// Falls back to Ollama when the local server times out or stays
+/// unreachable after all retries.
+pub struct LocalAIProvider {
+    server: LlamaCppServer,
+    fallback: Option<OllamaProvider>,
+    policy: RetryPolicy,
+    max_context_tokens: usize,
+}
+
+impl LocalAIProvider {
+    /// Serve the GGUF model `model_filename` from ~/.haira/models/, with
+    /// the timeouts and prompt budget of `config`.
+    pub fn new(model_filename: &str, config: &AIConfig) -> Self {
+        Self {
+            server: LlamaCppServer::new(model_filename.to_string()),
+            fallback: None,
+            policy: RetryPolicy::from_config(config),
+            max_context_tokens: config.max_context_tokens,
+        }
+    }
+
+    /// Fall back to `fallback` when the local server keeps failing.
+    pub fn with_fallback(mut self, fallback: OllamaProvider) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+}
+
+#[async_trait]
+impl AiProvider for LocalAIProvider {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn interpret(&self, request: &AIRequest) -> Result<AIResponse, AIError> {
+        let prompt = user_prompt(request, self.max_context_tokens);
+        let text = self.complete(SYSTEM_PROMPT, &prompt).await?;
+        Ok(AIEngine::parse_response(&text)?)
+    }
+
+    async fn complete(&self, system: &str, prompt: &str) -> Result<String, AIError> {
+        let client = self.server.client();
+        let result = match self
+            .server
+            .wait_until_ready(LOCAL_SERVER_READY_TIMEOUT)
+            .await
+        {
+            Ok(()) => {
+                self.policy
+                    .run(|| async { Ok(client.complete(system, prompt).await?) })
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        match (result, &self.fallback) {
+            (Err(e), Some(fallback)) if e.is_transient() => {
+                warn!("Local AI failed ({}), falling back to Ollama", e);
+                fallback.complete(system, prompt).await
+            }
+            (result, _) => result,
+        }
+    }
+
+    async fn check_availability(&self) -> Result<(), AIError> {
+        // Check that the server binary and model exist
+        if !self.server.binary_exists() {
+            return Err(AIError::LocalAI(LocalAIError::ServerBinaryNotFound(
+                haira_local_ai::paths::llama_server_path()
+                    .display()
+                    .to_string(),
+            )));
+        }
+        if !self.server.model_exists() {
+            return Err(AIError::LocalAI(LocalAIError::ModelNotFound(
+                "Model not found. Run: haira model pull".to_string(),
+            )));
+        }
+        Ok(())
+    }
+
+    async fn start(&mut self) -> Result<(), AIError> {
+        self.server.start()?;
+        // Wait for it to become ready (up to 60 seconds for model loading)
+        self.server
+            .wait_until_ready(LOCAL_SERVER_READY_TIMEOUT)
+            .await?;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), AIError> {
+        self.server.stop()?;
+        Ok(())
+    }
+
+    fn is_running(&mut self) -> bool {
+        self.server.is_running()
+    }
+}
+
+/// Provider generating deterministic stubs from the request's signature;
+/// no server or model is needed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockProvider;
+
+#[async_trait]
+impl AiProvider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn interpret(&self, request: &AIRequest) -> Result<AIResponse, AIError> {
+        let mut func = mock::mock_function(
+            &request.function_name,
+            &request.params,
+            request.return_type.as_deref(),
+            &request.context.types_in_scope,
+        );
+        if let Some(intent) = &request.intent {
+            func = func.with_description(format!("Mock implementation: {}", intent));
+        }
+        Ok(AIResponse {
+            success: true,
+            interpretation: Some(func),
+            confidence: 1.0,
+            alternatives: vec![],
+            error: None,
+        })
+    }
+
+    fn caches_results(&self) -> bool {
+        // Stubs are cheaper to regenerate than to cache
+        false
+    }
+}
@@ -1,6 +1,6 @@
 //! AST node definitions for Haira.
 
-use crate::{Span, Spanned};
+use crate::{Span, Spanned, Structural};
 use smol_str::SmolStr;
 
 /// A complete Haira source file.
@@ -21,6 +21,8 @@ pub type Item = Spanned<ItemKind>;
 pub enum ItemKind {
     /// Type definition: `User { name, age, email }`
     TypeDef(TypeDef),
+    /// Enum definition: `Shape { Circle(float), Square(float), Empty }`
+    EnumDef(EnumDef),
     /// Function definition: `greet(name) { ... }`
     FunctionDef(FunctionDef),
     /// Method definition: `User.greet() { ... }`
@@ -31,6 +33,10 @@ pub enum ItemKind {
     AiFunctionDef(AiBlock),
     /// A statement at module level
     Statement(Statement),
+    /// Import of another file: `import "shapes"`
+    Import(Import),
+    /// Constant: `const MAX_USERS = 100`
+    Const(ConstDef),
 }
 
 // ============================================================================
@@ -47,6 +53,37 @@ pub struct TypeDef {
     pub name: Spanned<SmolStr>,
     /// Fields
     pub fields: Vec<Field>,
+    /// Leading `///` doc comment
+    pub doc: Option<String>,
+}
+
+/// An enum definition: `Shape { Circle(float), Square(float), Empty }`
+///
+/// Enums share the braced syntax of type definitions and are told apart
+/// by their capitalized variant names.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumDef {
+    /// Whether this enum is public
+    pub is_public: bool,
+    /// Enum name
+    pub name: Spanned<SmolStr>,
+    /// Variants
+    pub variants: Vec<Variant>,
+    /// Leading `///` doc comment
+    pub doc: Option<String>,
+}
+
+/// An enum variant: `Circle(float)` or `Empty`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variant {
+    /// Variant name
+    pub name: Spanned<SmolStr>,
+    /// Payload types, empty for a variant without a payload
+    pub fields: Vec<Spanned<Type>>,
+    /// Span of the entire variant
+    pub span: Span,
 }
 
 /// A field in a type definition.
@@ -73,6 +110,31 @@ pub struct TypeAlias {
     pub ty: Spanned<Type>,
 }
 
+/// A constant: `const MAX_USERS = 100`
+///
+/// The value must be a literal or operators over literals and other
+/// constants, and is substituted at every use.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConstDef {
+    /// Whether this constant is public
+    pub is_public: bool,
+    /// Constant name
+    pub name: Spanned<SmolStr>,
+    /// Value
+    pub value: Expr,
+    /// Leading `///` doc comment
+    pub doc: Option<String>,
+}
+
+/// An import of another file: `import "shapes"`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    /// The imported path, relative to the importing file
+    pub path: Spanned<SmolStr>,
+}
+
 // ============================================================================
 // Type Expressions
 // ============================================================================
@@ -83,15 +145,17 @@ pub struct TypeAlias {
 pub enum Type {
     /// Simple named type: `int`, `string`, `User`
     Named(SmolStr),
-    /// List type: `[int]`, `[User]`
+    /// List type: `[int]`, `List<User>`
     List(Box<Spanned<Type>>),
-    /// Map type: `{string: int}`
+    /// Map type: `{string: int}`, `Map<string, int>`
     Map {
         key: Box<Spanned<Type>>,
         value: Box<Spanned<Type>>,
     },
-    /// Option type: `Option<User>`
+    /// Option type: `User?`, `Option<User>`
     Option(Box<Spanned<Type>>),
+    /// Tuple type: `(int, string)`
+    Tuple(Vec<Spanned<Type>>),
     /// Function type: `(int, int) -> int`
     Function {
         params: Vec<Spanned<Type>>,
@@ -124,6 +188,8 @@ pub struct FunctionDef {
     pub return_ty: Option<Spanned<Type>>,
     /// Function body
     pub body: Block,
+    /// Leading `///` doc comment
+    pub doc: Option<String>,
 }
 
 /// A method definition: `User.greet() { ... }`
@@ -140,6 +206,8 @@ pub struct MethodDef {
     pub return_ty: Option<Spanned<Type>>,
     /// Method body
     pub body: Block,
+    /// Leading `///` doc comment
+    pub doc: Option<String>,
 }
 
 /// A function parameter.
@@ -190,7 +258,19 @@ pub enum StatementKind {
     Expr(Expr),
 }
 
-/// An assignment: `x = 42` or `x, y = get_pair()`
+impl StatementKind {
+    /// Whether this statement has a value when it ends a block: a bare
+    /// expression, an `if` whose every branch has an `else`, or a `match`.
+    pub fn has_value(&self) -> bool {
+        match self {
+            StatementKind::Expr(_) | StatementKind::Match(_) => true,
+            StatementKind::If(if_stmt) => if_stmt.has_else(),
+            _ => false,
+        }
+    }
+}
+
+/// An assignment: `x = 42`, `x, y = get_pair()` or `x += 1`
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Assignment {
@@ -198,6 +278,9 @@ pub struct Assignment {
     pub targets: Vec<AssignTarget>,
     /// Value being assigned
     pub value: Expr,
+    /// Operator of a compound assignment, combining the single target's
+    /// current value with `value`: `Add` for `x += 1`
+    pub op: Option<Spanned<BinaryOp>>,
 }
 
 /// An assignment target.
@@ -240,6 +323,17 @@ pub struct IfStatement {
     pub else_branch: Option<ElseBranch>,
 }
 
+impl IfStatement {
+    /// Whether the chain ends in a plain `else`, so some branch always runs.
+    pub fn has_else(&self) -> bool {
+        match &self.else_branch {
+            Some(ElseBranch::Block(_)) => true,
+            Some(ElseBranch::ElseIf(else_if)) => else_if.node.has_else(),
+            None => false,
+        }
+    }
+}
+
 /// An else branch - either a block or another if.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -338,6 +432,8 @@ pub enum ExprKind {
     Block(Block),
     /// List literal: `[1, 2, 3]`
     List(Vec<Expr>),
+    /// Tuple literal: `(1, "x")`
+    Tuple(Vec<Expr>),
     /// Map literal: `{ "a": 1, "b": 2 }`
     Map(Vec<(Expr, Expr)>),
     /// Type instantiation: `User { name = "Alice", age = 30 }`
@@ -370,6 +466,8 @@ pub enum Literal {
     Int(i64),
     /// Float: `3.14`
     Float(f64),
+    /// Character: `'a'`
+    Char(char),
     /// String: `"hello"`
     String(SmolStr),
     /// Interpolated string parts
@@ -417,6 +515,14 @@ pub enum BinaryOp {
     // Logical
     And, // and
     Or,  // or
+    // Bitwise
+    BitAnd, // &
+    BitOr,  // |
+    BitXor, // ^
+    Shl,    // <<
+    Shr,    // >>
+    // Optional
+    Coalesce, // ??
 }
 
 /// A unary expression: `-x`, `not x`
@@ -555,11 +661,14 @@ pub enum Pattern {
     Literal(Literal),
     /// Identifier binding: `x`
     Identifier(SmolStr),
-    /// Constructor pattern: `Some { value }` or `User { name, age }`
+    /// Constructor pattern: `Some { value }`, `User { name, age }`, or
+    /// `Circle(radius)`
     Constructor {
         name: SmolStr,
         fields: Vec<Spanned<SmolStr>>,
     },
+    /// Tuple pattern: `(x, 0, _)`
+    Tuple(Vec<Spanned<Pattern>>),
 }
 
 /// Type instantiation: `User { name = "Alice" }`
@@ -650,6 +759,20 @@ pub struct AiBlock {
     pub span: Span,
 }
 
+impl AiBlock {
+    /// Name of the function generated for this block.
+    ///
+    /// Anonymous blocks are named after a structural hash of their
+    /// signature and intent, so the name stays stable between interpretation
+    /// and code generation, and when code around the block moves.
+    pub fn function_name(&self) -> SmolStr {
+        match &self.name {
+            Some(name) => name.node.clone(),
+            None => SmolStr::new(format!("__ai_anon_{:016x}", self.structural_hash())),
+        }
+    }
+}
+
 // ============================================================================
 // Blocks
 // ============================================================================
@@ -672,4 +795,11 @@ impl Block {
             span,
         }
     }
+
+    /// The statement giving the block its value: the last one, if it has a
+    /// value (see [`StatementKind::has_value`]). A block ending in any other
+    /// statement has no value.
+    pub fn value(&self) -> Option<&Statement> {
+        self.statements.last().filter(|stmt| stmt.node.has_value())
+    }
 }
@@ -0,0 +1,115 @@
+//! Compile-time evaluation of constant expressions.
+
+use crate::{BinaryOp, Expr, ExprKind, ItemKind, Literal, SourceFile, UnaryOp};
+use smol_str::SmolStr;
+
+/// The value of a constant expression: a literal, a constant, or operators
+/// over constant expressions. `constant` gives the values of the constants
+/// in scope.
+pub fn const_value(expr: &Expr, constant: &dyn Fn(&str) -> Option<Literal>) -> Option<Literal> {
+    match &expr.node {
+        ExprKind::Literal(Literal::InterpolatedString(_)) => None,
+        ExprKind::Literal(lit) => Some(lit.clone()),
+        ExprKind::Identifier(name) => constant(name),
+        ExprKind::Paren(inner) => const_value(inner, constant),
+        ExprKind::Binary(bin) => fold_binary(
+            bin.op.node,
+            &const_value(&bin.left, constant)?,
+            &const_value(&bin.right, constant)?,
+        ),
+        ExprKind::Unary(unary) => {
+            fold_unary(unary.op.node, &const_value(&unary.operand, constant)?)
+        }
+        _ => None,
+    }
+}
+
+/// The values of the `const` items of `ast`, in order. Each constant can use
+/// the ones before it; those whose value isn't constant are left out.
+pub fn const_values(ast: &SourceFile) -> Vec<(SmolStr, Literal)> {
+    let mut values: Vec<(SmolStr, Literal)> = Vec::new();
+    for item in &ast.items {
+        let ItemKind::Const(const_def) = &item.node else {
+            continue;
+        };
+        let lookup = |name: &str| {
+            values
+                .iter()
+                .rev()
+                .find(|(defined, _)| defined == name)
+                .map(|(_, value)| value.clone())
+        };
+        if let Some(value) = const_value(&const_def.value, &lookup) {
+            values.push((const_def.name.node.clone(), value));
+        }
+    }
+    values
+}
+
+/// The result of a binary operation over literals, if it can be computed at
+/// compile time with the same result as at runtime.
+///
+/// Integer operations that would overflow or divide by zero are left alone
+/// so they keep their runtime behavior. Comparisons are not folded, since
+/// codegen produces them as integers.
+pub fn fold_binary(op: BinaryOp, left: &Literal, right: &Literal) -> Option<Literal> {
+    match (left, right) {
+        (Literal::Int(a), Literal::Int(b)) => {
+            let (a, b) = (*a, *b);
+            let value = match op {
+                BinaryOp::Add => a.checked_add(b)?,
+                BinaryOp::Sub => a.checked_sub(b)?,
+                BinaryOp::Mul => a.checked_mul(b)?,
+                // `checked_*` returns None for zero divisors and `MIN / -1`
+                BinaryOp::Div => a.checked_div(b)?,
+                BinaryOp::Mod => a.checked_rem(b)?,
+                BinaryOp::BitAnd => a & b,
+                BinaryOp::BitOr => a | b,
+                BinaryOp::BitXor => a ^ b,
+                // Shift amounts past the word are left for codegen to mask
+                BinaryOp::Shl => a.checked_shl(u32::try_from(b).ok()?)?,
+                BinaryOp::Shr => (a as u64).checked_shr(u32::try_from(b).ok()?)? as i64,
+                _ => return None,
+            };
+            Some(Literal::Int(value))
+        }
+        // Mixed operands are promoted to float, as in codegen
+        (Literal::Float(_) | Literal::Int(_), Literal::Float(_) | Literal::Int(_)) => {
+            let a = as_float(left)?;
+            let b = as_float(right)?;
+            let value = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                // Matches codegen's `a - floor(a / b) * b`
+                BinaryOp::Mod => a - (a / b).floor() * b,
+                _ => return None,
+            };
+            Some(Literal::Float(value))
+        }
+        (Literal::String(a), Literal::String(b)) if op == BinaryOp::Add => {
+            Some(Literal::String(format!("{}{}", a, b).into()))
+        }
+        _ => None,
+    }
+}
+
+/// The result of a unary operation over a literal, if it can be computed at
+/// compile time.
+pub fn fold_unary(op: UnaryOp, operand: &Literal) -> Option<Literal> {
+    match (op, operand) {
+        (UnaryOp::Neg, Literal::Int(n)) => n.checked_neg().map(Literal::Int),
+        (UnaryOp::Neg, Literal::Float(n)) => Some(Literal::Float(-n)),
+        (UnaryOp::Not, Literal::Bool(b)) => Some(Literal::Bool(!b)),
+        _ => None,
+    }
+}
+
+fn as_float(lit: &Literal) -> Option<f64> {
+    match lit {
+        Literal::Int(n) => Some(*n as f64),
+        Literal::Float(n) => Some(*n),
+        _ => None,
+    }
+}
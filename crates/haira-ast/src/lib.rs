@@ -7,7 +7,15 @@
 //! name resolution and type checking.
 
 mod ast;
+mod consts;
+mod line_index;
 mod span;
+mod structural;
+pub mod visit;
 
 pub use ast::*;
+pub use consts::{const_value, const_values, fold_binary, fold_unary};
+pub use line_index::LineIndex;
 pub use span::{Span, Spanned};
+pub use structural::{Encoder, Structural};
+pub use visit::Visitor;
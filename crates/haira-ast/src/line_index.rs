@@ -0,0 +1,128 @@
+//! Conversion between byte offsets and line/column positions.
+
+use std::ops::Range;
+
+/// The lines of a source file, for converting byte offsets to
+/// positions and back in logarithmic time.
+///
+/// Lines and columns count from zero, and columns count bytes. Both `\n` and
+/// `\r\n` end a line, and the last line need not end with either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// The text of each line, without its line ending
+    lines: Vec<Range<usize>>,
+}
+
+impl LineIndex {
+    /// Index the lines of `source`.
+    pub fn new(source: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for (i, _) in source.match_indices('\n') {
+            let end = if source[..i].ends_with('\r') {
+                i - 1
+            } else {
+                i
+            };
+            lines.push(start..end);
+            start = i + 1;
+        }
+        lines.push(start..source.len());
+        Self { lines }
+    }
+
+    /// The number of lines, counting an empty last line after a final line
+    /// ending.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The line and column of a byte offset. Offsets past the end of the
+    /// source fall on its end.
+    pub fn line_col(&self, offset: usize) -> (u32, u32) {
+        let last = self.lines.len() - 1;
+        let offset = offset.min(self.lines[last].end);
+        let line = self
+            .lines
+            .partition_point(|line| line.start <= offset)
+            .saturating_sub(1);
+        (line as u32, (offset - self.lines[line].start) as u32)
+    }
+
+    /// The byte offset of a line and column. Columns past the end of a line
+    /// fall on its end, and lines past the last on the end of the source.
+    pub fn offset(&self, line: u32, col: u32) -> usize {
+        match self.lines.get(line as usize) {
+            Some(text) => (text.start + col as usize).min(text.end),
+            None => self.lines[self.lines.len() - 1].end,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_with_a_blank_line() {
+        let source = "x = 1\n\ny = 22\n";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_col(0), (0, 0));
+        assert_eq!(index.line_col(4), (0, 4));
+        // The newline itself ends its line
+        assert_eq!(index.line_col(5), (0, 5));
+        assert_eq!(index.line_col(6), (1, 0));
+        assert_eq!(index.line_col(7), (2, 0));
+        assert_eq!(index.line_col(12), (2, 5));
+        assert_eq!(index.line_col(14), (3, 0));
+        assert_eq!(index.line_col(100), (3, 0));
+
+        assert_eq!(index.offset(2, 4), source.find("22").unwrap());
+        assert_eq!(index.offset(1, 0), 6);
+        assert_eq!(index.offset(1, 3), 6);
+        assert_eq!(index.offset(9, 0), source.len());
+    }
+
+    #[test]
+    fn test_crlf_lines() {
+        let source = "a = 1\r\nbb = 2\r\n\r\nc";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_col(source.find("bb").unwrap()), (1, 0));
+        assert_eq!(index.line_col(source.find('c').unwrap()), (3, 0));
+        // Columns stop short of the `\r`
+        assert_eq!(index.offset(0, 10), 5);
+        assert_eq!(index.offset(2, 0), source.find("\r\n\r\n").unwrap() + 2);
+    }
+
+    #[test]
+    fn test_no_trailing_newline() {
+        let source = "first\nlast";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(index.line_col(source.len()), (1, 4));
+        assert_eq!(index.offset(1, 4), source.len());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "f(a) {\r\n    a\n\n}\nprint(f(1))";
+        let index = LineIndex::new(source);
+        for offset in source.char_indices().map(|(i, _)| i) {
+            let (line, col) = index.line_col(offset);
+            if source[offset..].starts_with(['\r', '\n']) {
+                continue;
+            }
+            assert_eq!(index.offset(line, col), offset, "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn test_empty_source() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.line_col(0), (0, 0));
+        assert_eq!(index.offset(0, 5), 0);
+    }
+}
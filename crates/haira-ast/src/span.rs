@@ -92,3 +92,54 @@ impl<T> std::ops::DerefMut for Spanned<T> {
         &mut self.node
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryExpr, BinaryOp, ExprKind, Literal};
+    use smol_str::SmolStr;
+
+    fn add(left: i64, right: i64, offset: u32) -> Spanned<ExprKind> {
+        let lit = |n, start| {
+            Spanned::new(
+                ExprKind::Literal(Literal::Int(n)),
+                Span::new(start, start + 1),
+            )
+        };
+        Spanned::new(
+            ExprKind::Binary(BinaryExpr {
+                left: Box::new(lit(left, offset)),
+                op: Spanned::new(BinaryOp::Add, Span::new(offset + 2, offset + 3)),
+                right: Box::new(lit(right, offset + 4)),
+            }),
+            Span::new(offset, offset + 5),
+        )
+    }
+
+    #[test]
+    fn test_map_keeps_the_span() {
+        let name = Spanned::new(SmolStr::new("x"), Span::new(3, 4));
+        let expr = name.map(ExprKind::Identifier);
+        assert_eq!(expr.span, Span::new(3, 4));
+        assert_eq!(expr.node, ExprKind::Identifier(SmolStr::new("x")));
+    }
+
+    #[test]
+    fn test_as_ref_borrows_the_node() {
+        let expr = add(1, 2, 7);
+        let borrowed = expr.as_ref();
+        assert!(std::ptr::eq(borrowed.node, &expr.node));
+        assert_eq!(borrowed.span, Span::new(7, 12));
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = Span::new(2, 5);
+        let b = Span::new(8, 10);
+        assert_eq!(a.merge(b), Span::new(2, 10));
+        // Order doesn't matter, and overlapping spans merge too
+        assert_eq!(b.merge(a), Span::new(2, 10));
+        assert_eq!(Span::new(0, 9).merge(Span::new(3, 4)), Span::new(0, 9));
+        assert_eq!(a.merge(Span::empty(12)), Span::new(2, 12));
+    }
+}
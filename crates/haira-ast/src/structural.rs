@@ -0,0 +1,921 @@
+//! Span-insensitive hashing and equality of AST nodes.
+//!
+//! [`Structural`] walks a node and its children, writing node kinds, names
+//! and literal values into an [`Encoder`] and skipping every [`Span`]. Two
+//! structurally identical nodes at different source positions therefore
+//! encode the same, which makes [`Structural::structural_hash`] usable as a
+//! cache key (e.g. for generated AI code) and for deduplication.
+//!
+//! The hash is 64-bit FNV-1a over a fixed little-endian encoding, so it is
+//! stable across Rust releases, platforms and runs.
+//!
+//! [`Span`]: crate::Span
+
+use crate::{
+    AiBlock, Argument, AssignPath, AssignTarget, Assignment, BinaryExpr, BinaryOp, Block, CallExpr,
+    ConstDef, ElseBranch, EnumDef, ExprKind, Field, FieldExpr, ForPattern, ForStatement,
+    FunctionDef, IfStatement, Import, IndexExpr, InstanceExpr, InstanceField, ItemKind, LambdaBody,
+    LambdaExpr, Literal, MatchArm, MatchArmBody, MatchExpr, MethodCallExpr, MethodDef, Param,
+    Pattern, PipeExpr, RangeExpr, ReturnStatement, SelectArm, SelectExpr, SourceFile, Spanned,
+    StatementKind, StringPart, TryStatement, Type, TypeAlias, TypeDef, UnaryExpr, UnaryOp, Variant,
+    WhileStatement,
+};
+use smol_str::SmolStr;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Sink for the span-free encoding of a node.
+///
+/// Every variable-length value is length-prefixed and every enum variant is
+/// tagged, so the encoding is unambiguous: equal encodings mean equal
+/// structure.
+pub struct Encoder {
+    out: Output,
+}
+
+enum Output {
+    Hash(u64),
+    Bytes(Vec<u8>),
+}
+
+impl Encoder {
+    fn hasher() -> Self {
+        Self {
+            out: Output::Hash(FNV_OFFSET_BASIS),
+        }
+    }
+
+    fn recorder() -> Self {
+        Self {
+            out: Output::Bytes(Vec::new()),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        match &mut self.out {
+            Output::Hash(hash) => {
+                for &byte in bytes {
+                    *hash ^= u64::from(byte);
+                    *hash = hash.wrapping_mul(FNV_PRIME);
+                }
+            }
+            Output::Bytes(buf) => buf.extend_from_slice(bytes),
+        }
+    }
+
+    /// Write a variant tag.
+    pub fn tag(&mut self, tag: u8) {
+        self.write(&[tag]);
+    }
+
+    /// Write a length or count.
+    pub fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    /// Write an integer.
+    pub fn u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Write a string.
+    pub fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.write(s.as_bytes());
+    }
+}
+
+/// A node that can be hashed and compared ignoring source positions.
+pub trait Structural {
+    /// Write the node's kind and children, skipping spans.
+    fn encode(&self, enc: &mut Encoder);
+
+    /// Hash the node and its children, ignoring source positions.
+    fn structural_hash(&self) -> u64 {
+        let mut enc = Encoder::hasher();
+        self.encode(&mut enc);
+        match enc.out {
+            Output::Hash(hash) => hash,
+            Output::Bytes(_) => unreachable!(),
+        }
+    }
+
+    /// Compare two nodes and their children, ignoring source positions.
+    fn structurally_eq(&self, other: &Self) -> bool {
+        let encode = |node: &Self| {
+            let mut enc = Encoder::recorder();
+            node.encode(&mut enc);
+            match enc.out {
+                Output::Bytes(bytes) => bytes,
+                Output::Hash(_) => unreachable!(),
+            }
+        };
+        encode(self) == encode(other)
+    }
+}
+
+// ============================================================================
+// Containers and leaves
+// ============================================================================
+
+impl<T: Structural> Structural for Spanned<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.node.encode(enc);
+    }
+}
+
+impl<T: Structural> Structural for Box<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        (**self).encode(enc);
+    }
+}
+
+impl<T: Structural> Structural for Vec<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.len(self.len());
+        for item in self {
+            item.encode(enc);
+        }
+    }
+}
+
+impl<T: Structural> Structural for Option<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Some(value) => {
+                enc.tag(1);
+                value.encode(enc);
+            }
+            None => enc.tag(0),
+        }
+    }
+}
+
+impl<A: Structural, B: Structural> Structural for (A, B) {
+    fn encode(&self, enc: &mut Encoder) {
+        self.0.encode(enc);
+        self.1.encode(enc);
+    }
+}
+
+impl Structural for SmolStr {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.str(self);
+    }
+}
+
+impl Structural for String {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.str(self);
+    }
+}
+
+impl Structural for bool {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.tag(*self as u8);
+    }
+}
+
+impl Structural for BinaryOp {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.tag(*self as u8);
+    }
+}
+
+impl Structural for UnaryOp {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.tag(*self as u8);
+    }
+}
+
+// ============================================================================
+// Items
+// ============================================================================
+
+impl Structural for SourceFile {
+    fn encode(&self, enc: &mut Encoder) {
+        self.items.encode(enc);
+    }
+}
+
+impl Structural for ItemKind {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ItemKind::TypeDef(def) => {
+                enc.tag(0);
+                def.encode(enc);
+            }
+            ItemKind::EnumDef(def) => {
+                enc.tag(1);
+                def.encode(enc);
+            }
+            ItemKind::FunctionDef(def) => {
+                enc.tag(2);
+                def.encode(enc);
+            }
+            ItemKind::MethodDef(def) => {
+                enc.tag(3);
+                def.encode(enc);
+            }
+            ItemKind::TypeAlias(alias) => {
+                enc.tag(4);
+                alias.encode(enc);
+            }
+            ItemKind::AiFunctionDef(block) => {
+                enc.tag(5);
+                block.encode(enc);
+            }
+            ItemKind::Statement(stmt) => {
+                enc.tag(6);
+                stmt.encode(enc);
+            }
+            ItemKind::Import(import) => {
+                enc.tag(7);
+                import.encode(enc);
+            }
+            ItemKind::Const(def) => {
+                enc.tag(8);
+                def.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for TypeDef {
+    fn encode(&self, enc: &mut Encoder) {
+        self.is_public.encode(enc);
+        self.name.encode(enc);
+        self.fields.encode(enc);
+        self.doc.encode(enc);
+    }
+}
+
+impl Structural for EnumDef {
+    fn encode(&self, enc: &mut Encoder) {
+        self.is_public.encode(enc);
+        self.name.encode(enc);
+        self.variants.encode(enc);
+        self.doc.encode(enc);
+    }
+}
+
+impl Structural for Variant {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.fields.encode(enc);
+    }
+}
+
+impl Structural for Field {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.ty.encode(enc);
+        self.default.encode(enc);
+    }
+}
+
+impl Structural for TypeAlias {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.ty.encode(enc);
+    }
+}
+
+impl Structural for ConstDef {
+    fn encode(&self, enc: &mut Encoder) {
+        self.is_public.encode(enc);
+        self.name.encode(enc);
+        self.value.encode(enc);
+        self.doc.encode(enc);
+    }
+}
+
+impl Structural for Import {
+    fn encode(&self, enc: &mut Encoder) {
+        self.path.encode(enc);
+    }
+}
+
+impl Structural for Type {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Type::Named(name) => {
+                enc.tag(0);
+                name.encode(enc);
+            }
+            Type::List(elem) => {
+                enc.tag(1);
+                elem.encode(enc);
+            }
+            Type::Map { key, value } => {
+                enc.tag(2);
+                key.encode(enc);
+                value.encode(enc);
+            }
+            Type::Option(inner) => {
+                enc.tag(3);
+                inner.encode(enc);
+            }
+            Type::Tuple(elems) => {
+                enc.tag(4);
+                elems.encode(enc);
+            }
+            Type::Function { params, ret } => {
+                enc.tag(5);
+                params.encode(enc);
+                ret.encode(enc);
+            }
+            Type::Union(members) => {
+                enc.tag(6);
+                members.encode(enc);
+            }
+            Type::Generic { name, args } => {
+                enc.tag(7);
+                name.encode(enc);
+                args.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for FunctionDef {
+    fn encode(&self, enc: &mut Encoder) {
+        self.is_public.encode(enc);
+        self.name.encode(enc);
+        self.params.encode(enc);
+        self.return_ty.encode(enc);
+        self.body.encode(enc);
+        self.doc.encode(enc);
+    }
+}
+
+impl Structural for MethodDef {
+    fn encode(&self, enc: &mut Encoder) {
+        self.type_name.encode(enc);
+        self.name.encode(enc);
+        self.params.encode(enc);
+        self.return_ty.encode(enc);
+        self.body.encode(enc);
+        self.doc.encode(enc);
+    }
+}
+
+impl Structural for Param {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.ty.encode(enc);
+        self.default.encode(enc);
+        self.is_rest.encode(enc);
+    }
+}
+
+// ============================================================================
+// Statements
+// ============================================================================
+
+impl Structural for Block {
+    fn encode(&self, enc: &mut Encoder) {
+        self.statements.encode(enc);
+    }
+}
+
+impl Structural for StatementKind {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            StatementKind::Assignment(assign) => {
+                enc.tag(0);
+                assign.encode(enc);
+            }
+            StatementKind::If(if_stmt) => {
+                enc.tag(1);
+                if_stmt.encode(enc);
+            }
+            StatementKind::For(for_stmt) => {
+                enc.tag(2);
+                for_stmt.encode(enc);
+            }
+            StatementKind::While(while_stmt) => {
+                enc.tag(3);
+                while_stmt.encode(enc);
+            }
+            StatementKind::Match(match_expr) => {
+                enc.tag(4);
+                match_expr.encode(enc);
+            }
+            StatementKind::Return(ret) => {
+                enc.tag(5);
+                ret.encode(enc);
+            }
+            StatementKind::Try(try_stmt) => {
+                enc.tag(6);
+                try_stmt.encode(enc);
+            }
+            StatementKind::Break => enc.tag(7),
+            StatementKind::Continue => enc.tag(8),
+            StatementKind::Expr(expr) => {
+                enc.tag(9);
+                expr.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for Assignment {
+    fn encode(&self, enc: &mut Encoder) {
+        self.targets.encode(enc);
+        self.value.encode(enc);
+        self.op.encode(enc);
+    }
+}
+
+impl Structural for AssignTarget {
+    fn encode(&self, enc: &mut Encoder) {
+        self.path.encode(enc);
+        self.ty.encode(enc);
+    }
+}
+
+impl Structural for AssignPath {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            AssignPath::Identifier(name) => {
+                enc.tag(0);
+                name.encode(enc);
+            }
+            AssignPath::Field { object, field } => {
+                enc.tag(1);
+                object.encode(enc);
+                field.encode(enc);
+            }
+            AssignPath::Index { object, index } => {
+                enc.tag(2);
+                object.encode(enc);
+                index.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for IfStatement {
+    fn encode(&self, enc: &mut Encoder) {
+        self.condition.encode(enc);
+        self.then_branch.encode(enc);
+        self.else_branch.encode(enc);
+    }
+}
+
+impl Structural for ElseBranch {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ElseBranch::Block(block) => {
+                enc.tag(0);
+                block.encode(enc);
+            }
+            ElseBranch::ElseIf(else_if) => {
+                enc.tag(1);
+                else_if.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for ForStatement {
+    fn encode(&self, enc: &mut Encoder) {
+        self.pattern.encode(enc);
+        self.iterator.encode(enc);
+        self.body.encode(enc);
+    }
+}
+
+impl Structural for ForPattern {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ForPattern::Single(name) => {
+                enc.tag(0);
+                name.encode(enc);
+            }
+            ForPattern::Pair(first, second) => {
+                enc.tag(1);
+                first.encode(enc);
+                second.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for WhileStatement {
+    fn encode(&self, enc: &mut Encoder) {
+        self.condition.encode(enc);
+        self.body.encode(enc);
+    }
+}
+
+impl Structural for ReturnStatement {
+    fn encode(&self, enc: &mut Encoder) {
+        self.values.encode(enc);
+    }
+}
+
+impl Structural for TryStatement {
+    fn encode(&self, enc: &mut Encoder) {
+        self.body.encode(enc);
+        self.error_name.encode(enc);
+        self.catch_body.encode(enc);
+    }
+}
+
+// ============================================================================
+// Expressions
+// ============================================================================
+
+impl Structural for ExprKind {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            ExprKind::Literal(lit) => {
+                enc.tag(0);
+                lit.encode(enc);
+            }
+            ExprKind::Identifier(name) => {
+                enc.tag(1);
+                name.encode(enc);
+            }
+            ExprKind::Binary(binary) => {
+                enc.tag(2);
+                binary.encode(enc);
+            }
+            ExprKind::Unary(unary) => {
+                enc.tag(3);
+                unary.encode(enc);
+            }
+            ExprKind::Call(call) => {
+                enc.tag(4);
+                call.encode(enc);
+            }
+            ExprKind::MethodCall(call) => {
+                enc.tag(5);
+                call.encode(enc);
+            }
+            ExprKind::Field(field) => {
+                enc.tag(6);
+                field.encode(enc);
+            }
+            ExprKind::Index(index) => {
+                enc.tag(7);
+                index.encode(enc);
+            }
+            ExprKind::Pipe(pipe) => {
+                enc.tag(8);
+                pipe.encode(enc);
+            }
+            ExprKind::Lambda(lambda) => {
+                enc.tag(9);
+                lambda.encode(enc);
+            }
+            ExprKind::Match(match_expr) => {
+                enc.tag(10);
+                match_expr.encode(enc);
+            }
+            ExprKind::If(if_stmt) => {
+                enc.tag(11);
+                if_stmt.encode(enc);
+            }
+            ExprKind::Block(block) => {
+                enc.tag(12);
+                block.encode(enc);
+            }
+            ExprKind::List(elems) => {
+                enc.tag(13);
+                elems.encode(enc);
+            }
+            ExprKind::Tuple(elems) => {
+                enc.tag(14);
+                elems.encode(enc);
+            }
+            ExprKind::Map(entries) => {
+                enc.tag(15);
+                entries.encode(enc);
+            }
+            ExprKind::Instance(instance) => {
+                enc.tag(16);
+                instance.encode(enc);
+            }
+            ExprKind::Range(range) => {
+                enc.tag(17);
+                range.encode(enc);
+            }
+            ExprKind::Propagate(inner) => {
+                enc.tag(18);
+                inner.encode(enc);
+            }
+            ExprKind::Some(inner) => {
+                enc.tag(19);
+                inner.encode(enc);
+            }
+            ExprKind::None => enc.tag(20),
+            ExprKind::Async(block) => {
+                enc.tag(21);
+                block.encode(enc);
+            }
+            ExprKind::Spawn(block) => {
+                enc.tag(22);
+                block.encode(enc);
+            }
+            ExprKind::Select(select) => {
+                enc.tag(23);
+                select.encode(enc);
+            }
+            ExprKind::Paren(inner) => {
+                enc.tag(24);
+                inner.encode(enc);
+            }
+            ExprKind::Ai(block) => {
+                enc.tag(25);
+                block.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for Literal {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Literal::Int(n) => {
+                enc.tag(0);
+                enc.u64(*n as u64);
+            }
+            Literal::Float(n) => {
+                enc.tag(1);
+                enc.u64(n.to_bits());
+            }
+            Literal::Char(c) => {
+                enc.tag(2);
+                enc.u64(u64::from(*c));
+            }
+            Literal::String(s) => {
+                enc.tag(3);
+                s.encode(enc);
+            }
+            Literal::InterpolatedString(parts) => {
+                enc.tag(4);
+                parts.encode(enc);
+            }
+            Literal::Bool(b) => {
+                enc.tag(5);
+                b.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for StringPart {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            StringPart::Literal(text) => {
+                enc.tag(0);
+                text.encode(enc);
+            }
+            StringPart::Expr(expr) => {
+                enc.tag(1);
+                expr.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for BinaryExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.left.encode(enc);
+        self.op.encode(enc);
+        self.right.encode(enc);
+    }
+}
+
+impl Structural for UnaryExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.op.encode(enc);
+        self.operand.encode(enc);
+    }
+}
+
+impl Structural for CallExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.callee.encode(enc);
+        self.args.encode(enc);
+    }
+}
+
+impl Structural for MethodCallExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.receiver.encode(enc);
+        self.method.encode(enc);
+        self.args.encode(enc);
+    }
+}
+
+impl Structural for Argument {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.value.encode(enc);
+    }
+}
+
+impl Structural for FieldExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.object.encode(enc);
+        self.field.encode(enc);
+    }
+}
+
+impl Structural for IndexExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.object.encode(enc);
+        self.index.encode(enc);
+    }
+}
+
+impl Structural for PipeExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.left.encode(enc);
+        self.right.encode(enc);
+    }
+}
+
+impl Structural for LambdaExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.params.encode(enc);
+        self.body.encode(enc);
+    }
+}
+
+impl Structural for LambdaBody {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            LambdaBody::Expr(expr) => {
+                enc.tag(0);
+                expr.encode(enc);
+            }
+            LambdaBody::Block(block) => {
+                enc.tag(1);
+                block.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for MatchExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.subject.encode(enc);
+        self.arms.encode(enc);
+    }
+}
+
+impl Structural for MatchArm {
+    fn encode(&self, enc: &mut Encoder) {
+        self.pattern.encode(enc);
+        self.guard.encode(enc);
+        self.body.encode(enc);
+    }
+}
+
+impl Structural for MatchArmBody {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            MatchArmBody::Expr(expr) => {
+                enc.tag(0);
+                expr.encode(enc);
+            }
+            MatchArmBody::Block(block) => {
+                enc.tag(1);
+                block.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for Pattern {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Pattern::Wildcard => enc.tag(0),
+            Pattern::Literal(lit) => {
+                enc.tag(1);
+                lit.encode(enc);
+            }
+            Pattern::Identifier(name) => {
+                enc.tag(2);
+                name.encode(enc);
+            }
+            Pattern::Constructor { name, fields } => {
+                enc.tag(3);
+                name.encode(enc);
+                fields.encode(enc);
+            }
+            Pattern::Tuple(elems) => {
+                enc.tag(4);
+                elems.encode(enc);
+            }
+        }
+    }
+}
+
+impl Structural for InstanceExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.type_name.encode(enc);
+        self.fields.encode(enc);
+    }
+}
+
+impl Structural for InstanceField {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.value.encode(enc);
+    }
+}
+
+impl Structural for RangeExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.start.encode(enc);
+        self.end.encode(enc);
+        self.inclusive.encode(enc);
+    }
+}
+
+impl Structural for SelectExpr {
+    fn encode(&self, enc: &mut Encoder) {
+        self.arms.encode(enc);
+        self.default.encode(enc);
+    }
+}
+
+impl Structural for SelectArm {
+    fn encode(&self, enc: &mut Encoder) {
+        self.binding.encode(enc);
+        self.channel.encode(enc);
+        self.body.encode(enc);
+    }
+}
+
+impl Structural for AiBlock {
+    fn encode(&self, enc: &mut Encoder) {
+        self.name.encode(enc);
+        self.params.encode(enc);
+        self.return_ty.encode(enc);
+        self.intent.encode(enc);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Expr, Span};
+
+    fn lit(n: i64, start: u32) -> Expr {
+        Spanned::new(
+            ExprKind::Literal(Literal::Int(n)),
+            Span::new(start, start + 1),
+        )
+    }
+
+    fn add(left: i64, right: i64, offset: u32) -> Expr {
+        Spanned::new(
+            ExprKind::Binary(BinaryExpr {
+                left: Box::new(lit(left, offset)),
+                op: Spanned::new(BinaryOp::Add, Span::new(offset + 2, offset + 3)),
+                right: Box::new(lit(right, offset + 4)),
+            }),
+            Span::new(offset, offset + 5),
+        )
+    }
+
+    #[test]
+    fn test_structural_hash_ignores_spans() {
+        assert_eq!(
+            add(1, 2, 0).structural_hash(),
+            add(1, 2, 40).structural_hash()
+        );
+    }
+
+    #[test]
+    fn test_structural_hash_sees_changed_literal() {
+        assert_ne!(
+            add(1, 2, 0).structural_hash(),
+            add(1, 3, 0).structural_hash()
+        );
+    }
+
+    #[test]
+    fn test_structurally_eq_ignores_spans() {
+        let (a, b) = (add(1, 2, 0), add(1, 2, 40));
+        // Plain equality still sees the spans
+        assert_ne!(a, b);
+        assert!(a.structurally_eq(&b));
+        assert!(!a.structurally_eq(&add(2, 1, 0)));
+    }
+
+    #[test]
+    fn test_structural_hash_is_stable() {
+        // Pinned so a change to the encoding or hasher shows up here
+        // rather than as silently invalidated cache keys.
+        assert_eq!(lit(7, 0).structural_hash(), 0x0ce2_b2b1_0029_112a);
+    }
+}
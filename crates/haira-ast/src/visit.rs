@@ -0,0 +1,370 @@
+//! Read-only traversal of the AST.
+//!
+//! Implement [`Visitor`] and override the `visit_*` methods for the nodes of
+//! interest. Each default implementation calls the matching `walk_*`
+//! function, which visits the node's children; call it from an override to
+//! keep descending.
+
+use crate::{
+    AssignPath, Block, ElseBranch, Expr, ExprKind, IfStatement, Item, ItemKind, LambdaBody,
+    Literal, MatchArm, MatchArmBody, Param, SourceFile, Statement, StatementKind, StringPart,
+};
+
+/// A visitor over AST nodes.
+pub trait Visitor {
+    fn visit_item(&mut self, item: &Item) {
+        walk_item(self, item);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Statement) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_param(&mut self, param: &Param) {
+        walk_param(self, param);
+    }
+
+    fn visit_match_arm(&mut self, arm: &MatchArm) {
+        walk_match_arm(self, arm);
+    }
+}
+
+/// Visit every item in a source file.
+pub fn walk_source_file<V: Visitor + ?Sized>(visitor: &mut V, ast: &SourceFile) {
+    for item in &ast.items {
+        visitor.visit_item(item);
+    }
+}
+
+pub fn walk_item<V: Visitor + ?Sized>(visitor: &mut V, item: &Item) {
+    match &item.node {
+        ItemKind::TypeDef(type_def) => {
+            for field in &type_def.fields {
+                if let Some(default) = &field.default {
+                    visitor.visit_expr(default);
+                }
+            }
+        }
+        ItemKind::FunctionDef(func) => {
+            for param in &func.params {
+                visitor.visit_param(param);
+            }
+            visitor.visit_block(&func.body);
+        }
+        ItemKind::MethodDef(method) => {
+            for param in &method.params {
+                visitor.visit_param(param);
+            }
+            visitor.visit_block(&method.body);
+        }
+        ItemKind::AiFunctionDef(ai_block) => {
+            for param in &ai_block.params {
+                visitor.visit_param(param);
+            }
+        }
+        ItemKind::Statement(stmt) => visitor.visit_stmt(stmt),
+        ItemKind::Const(const_def) => visitor.visit_expr(&const_def.value),
+        ItemKind::EnumDef(_) | ItemKind::TypeAlias(_) | ItemKind::Import(_) => {}
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    for stmt in &block.statements {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Statement) {
+    match &stmt.node {
+        StatementKind::Assignment(assign) => {
+            for target in &assign.targets {
+                walk_assign_path(visitor, &target.path);
+            }
+            visitor.visit_expr(&assign.value);
+        }
+        StatementKind::If(if_stmt) => walk_if(visitor, if_stmt),
+        StatementKind::For(for_stmt) => {
+            visitor.visit_expr(&for_stmt.iterator);
+            visitor.visit_block(&for_stmt.body);
+        }
+        StatementKind::While(while_stmt) => {
+            visitor.visit_expr(&while_stmt.condition);
+            visitor.visit_block(&while_stmt.body);
+        }
+        StatementKind::Match(match_expr) => {
+            visitor.visit_expr(&match_expr.subject);
+            for arm in &match_expr.arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        StatementKind::Return(ret) => {
+            for value in &ret.values {
+                visitor.visit_expr(value);
+            }
+        }
+        StatementKind::Try(try_stmt) => {
+            visitor.visit_block(&try_stmt.body);
+            visitor.visit_block(&try_stmt.catch_body);
+        }
+        StatementKind::Expr(expr) => visitor.visit_expr(expr),
+        StatementKind::Break | StatementKind::Continue => {}
+    }
+}
+
+/// Visit the expressions inside an assignment target, such as index operands.
+pub fn walk_assign_path<V: Visitor + ?Sized>(visitor: &mut V, path: &AssignPath) {
+    match path {
+        AssignPath::Identifier(_) => {}
+        AssignPath::Field { object, .. } => walk_assign_path(visitor, object),
+        AssignPath::Index { object, index } => {
+            walk_assign_path(visitor, object);
+            visitor.visit_expr(index);
+        }
+    }
+}
+
+/// Visit an if statement or expression, including its `else if` chain.
+pub fn walk_if<V: Visitor + ?Sized>(visitor: &mut V, if_stmt: &IfStatement) {
+    visitor.visit_expr(&if_stmt.condition);
+    visitor.visit_block(&if_stmt.then_branch);
+    match &if_stmt.else_branch {
+        Some(ElseBranch::Block(block)) => visitor.visit_block(block),
+        Some(ElseBranch::ElseIf(else_if)) => walk_if(visitor, &else_if.node),
+        None => {}
+    }
+}
+
+pub fn walk_param<V: Visitor + ?Sized>(visitor: &mut V, param: &Param) {
+    if let Some(default) = &param.default {
+        visitor.visit_expr(default);
+    }
+}
+
+pub fn walk_match_arm<V: Visitor + ?Sized>(visitor: &mut V, arm: &MatchArm) {
+    if let Some(guard) = &arm.guard {
+        visitor.visit_expr(guard);
+    }
+    walk_match_arm_body(visitor, &arm.body);
+}
+
+fn walk_match_arm_body<V: Visitor + ?Sized>(visitor: &mut V, body: &MatchArmBody) {
+    match body {
+        MatchArmBody::Expr(expr) => visitor.visit_expr(expr),
+        MatchArmBody::Block(block) => visitor.visit_block(block),
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match &expr.node {
+        ExprKind::Literal(Literal::InterpolatedString(parts)) => {
+            for part in parts {
+                if let StringPart::Expr(inner) = part {
+                    visitor.visit_expr(inner);
+                }
+            }
+        }
+        ExprKind::Literal(_) | ExprKind::Identifier(_) | ExprKind::None => {}
+        ExprKind::Binary(bin) => {
+            visitor.visit_expr(&bin.left);
+            visitor.visit_expr(&bin.right);
+        }
+        ExprKind::Unary(unary) => visitor.visit_expr(&unary.operand),
+        ExprKind::Call(call) => {
+            visitor.visit_expr(&call.callee);
+            for arg in &call.args {
+                visitor.visit_expr(&arg.value);
+            }
+        }
+        ExprKind::MethodCall(call) => {
+            visitor.visit_expr(&call.receiver);
+            for arg in &call.args {
+                visitor.visit_expr(&arg.value);
+            }
+        }
+        ExprKind::Field(field) => visitor.visit_expr(&field.object),
+        ExprKind::Index(index) => {
+            visitor.visit_expr(&index.object);
+            visitor.visit_expr(&index.index);
+        }
+        ExprKind::Pipe(pipe) => {
+            visitor.visit_expr(&pipe.left);
+            visitor.visit_expr(&pipe.right);
+        }
+        ExprKind::Lambda(lambda) => {
+            for param in &lambda.params {
+                visitor.visit_param(param);
+            }
+            match &lambda.body {
+                LambdaBody::Expr(body) => visitor.visit_expr(body),
+                LambdaBody::Block(block) => visitor.visit_block(block),
+            }
+        }
+        ExprKind::Match(match_expr) => {
+            visitor.visit_expr(&match_expr.subject);
+            for arm in &match_expr.arms {
+                visitor.visit_match_arm(arm);
+            }
+        }
+        ExprKind::If(if_stmt) => walk_if(visitor, if_stmt),
+        ExprKind::Block(block) | ExprKind::Async(block) | ExprKind::Spawn(block) => {
+            visitor.visit_block(block)
+        }
+        ExprKind::List(elements) | ExprKind::Tuple(elements) => {
+            for element in elements {
+                visitor.visit_expr(element);
+            }
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                visitor.visit_expr(key);
+                visitor.visit_expr(value);
+            }
+        }
+        ExprKind::Instance(instance) => {
+            for field in &instance.fields {
+                visitor.visit_expr(&field.value);
+            }
+        }
+        ExprKind::Range(range) => {
+            visitor.visit_expr(&range.start);
+            visitor.visit_expr(&range.end);
+        }
+        ExprKind::Propagate(inner) | ExprKind::Some(inner) | ExprKind::Paren(inner) => {
+            visitor.visit_expr(inner)
+        }
+        ExprKind::Select(select) => {
+            for arm in &select.arms {
+                visitor.visit_expr(&arm.channel);
+                walk_match_arm_body(visitor, &arm.body);
+            }
+            if let Some(default) = &select.default {
+                visitor.visit_block(default);
+            }
+        }
+        ExprKind::Ai(ai_block) => {
+            for param in &ai_block.params {
+                visitor.visit_param(param);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Argument, CallExpr, FunctionDef, LambdaExpr, Span, Spanned};
+
+    /// Counts every call expression.
+    #[derive(Default)]
+    struct CallCounter {
+        calls: usize,
+    }
+
+    impl Visitor for CallCounter {
+        fn visit_expr(&mut self, expr: &Expr) {
+            if matches!(expr.node, ExprKind::Call(_)) {
+                self.calls += 1;
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    fn expr(node: ExprKind) -> Expr {
+        Spanned::new(node, Span::new(0, 0))
+    }
+
+    fn call(name: &str, args: Vec<Expr>) -> Expr {
+        expr(ExprKind::Call(CallExpr {
+            callee: Box::new(expr(ExprKind::Identifier(name.into()))),
+            args: args
+                .into_iter()
+                .map(|value| Argument {
+                    name: None,
+                    value,
+                    span: Span::new(0, 0),
+                })
+                .collect(),
+        }))
+    }
+
+    fn stmt(expr: Expr) -> Statement {
+        Spanned::new(StatementKind::Expr(expr), Span::new(0, 0))
+    }
+
+    #[test]
+    fn test_counting_visitor_finds_nested_calls() {
+        // f() { print(g(1)) }
+        // [h(), () => k(x)]
+        let body = Block {
+            statements: vec![stmt(call(
+                "print",
+                vec![call("g", vec![expr(ExprKind::Literal(Literal::Int(1)))])],
+            ))],
+            span: Span::new(0, 0),
+        };
+        let lambda = expr(ExprKind::Lambda(LambdaExpr {
+            params: Vec::new(),
+            body: LambdaBody::Expr(Box::new(call(
+                "k",
+                vec![expr(ExprKind::Identifier("x".into()))],
+            ))),
+        }));
+        let ast = SourceFile {
+            items: vec![
+                Spanned::new(
+                    ItemKind::FunctionDef(FunctionDef {
+                        is_public: false,
+                        name: Spanned::new("f".into(), Span::new(0, 0)),
+                        params: Vec::new(),
+                        return_ty: None,
+                        body,
+                        doc: None,
+                    }),
+                    Span::new(0, 0),
+                ),
+                Spanned::new(
+                    ItemKind::Statement(stmt(expr(ExprKind::List(vec![
+                        call("h", Vec::new()),
+                        lambda,
+                    ])))),
+                    Span::new(0, 0),
+                ),
+            ],
+            span: Span::new(0, 0),
+        };
+
+        let mut counter = CallCounter::default();
+        walk_source_file(&mut counter, &ast);
+        assert_eq!(counter.calls, 4);
+    }
+
+    #[test]
+    fn test_override_can_skip_children() {
+        /// Counts only calls that are not nested in another call.
+        #[derive(Default)]
+        struct OuterCalls {
+            calls: usize,
+        }
+
+        impl Visitor for OuterCalls {
+            fn visit_expr(&mut self, expr: &Expr) {
+                match expr.node {
+                    ExprKind::Call(_) => self.calls += 1,
+                    _ => walk_expr(self, expr),
+                }
+            }
+        }
+
+        let mut counter = OuterCalls::default();
+        counter.visit_stmt(&stmt(call("print", vec![call("g", Vec::new())])));
+        assert_eq!(counter.calls, 1);
+    }
+}
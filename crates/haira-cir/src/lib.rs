@@ -50,12 +50,26 @@ pub struct AIResponse {
     pub confidence: f64,
     /// Alternative interpretations (if ambiguous)
     #[serde(default)]
-    pub alternatives: Vec<CIRFunction>,
+    pub alternatives: Vec<Alternative>,
     /// Error message (if failed)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
+/// An alternative interpretation, with its own confidence.
+///
+/// Serialized as the function's fields plus `confidence`, so a plain
+/// function still parses (with a confidence of zero).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alternative {
+    /// The interpreted function
+    #[serde(flatten)]
+    pub interpretation: CIRFunction,
+    /// Confidence score (0.0 - 1.0)
+    #[serde(default)]
+    pub confidence: f64,
+}
+
 /// Request for AI interpretation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIRequest {
@@ -63,16 +77,27 @@ pub struct AIRequest {
     pub request_type: RequestType,
     /// Function name to interpret
     pub function_name: String,
+    /// What the developer described, for an explicit `ai` block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+    /// Parameters as (name, type) pairs, declared or seen at the call site
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<(String, String)>,
+    /// Return type, declared or expected at the call site
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<String>,
     /// Context for interpretation
     pub context: InterpretationContext,
 }
 
 /// Type of AI request.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RequestType {
     /// Infer intent and generate implementation
     InferIntent,
+    /// Implement the intent described in an explicit `ai` block
+    ImplementIntent,
     /// Generate type definition
     GenerateType,
     /// Suggest completion
@@ -2,44 +2,61 @@
 
 use haira_ai::hif::{
     cir_function_to_hif_intent, hif_intent_to_cir_function, parse_hif, write_hif, HIFFile,
+    HIF_FILENAME,
 };
 use haira_ai::{AIConfig, AIEngine, AIError};
 use haira_ast::{Item, ItemKind, SourceFile, Spanned, Type};
-use haira_cir::{
-    CIRFunction, CIROperation, CIRType, CIRValue, CallSiteInfo, FieldDefinition,
-    InterpretationContext, TypeDefinition,
-};
-use haira_codegen::{cir_to_function_def, compile_to_executable, CodegenOptions};
-use haira_parser::parse;
+use haira_codegen::{cir_to_function_def, link, write_object, CodegenOptions};
+use haira_driver::{interpret_ai_blocks, interpretation_context, type_to_string};
+use haira_parser::fold_constants;
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How AI blocks are interpreted.
+pub(crate) struct AiOptions<'a> {
+    /// Use a local Ollama server.
+    pub ollama: bool,
+    /// The Ollama model to use.
+    pub ollama_model: &'a str,
+    /// Use the local llama.cpp server.
+    pub local_ai: bool,
+    /// Generate deterministic stubs instead of calling a model.
+    pub mock: bool,
+    /// Interpret every AI block, inline ones included, before codegen.
+    pub interpret: bool,
+}
 
 pub(crate) fn run(
     file: &Path,
     output: Option<&Path>,
-    use_ollama: bool,
-    ollama_model: &str,
-    use_local_ai: bool,
-    mock_ai: bool,
+    ai: AiOptions,
+    options: CodegenOptions,
+    print_timings: bool,
 ) -> miette::Result<()> {
+    let AiOptions {
+        ollama: use_ollama,
+        ollama_model,
+        local_ai: use_local_ai,
+        mock: mock_ai,
+        interpret: interpret_ai,
+    } = ai;
     let source =
         fs::read_to_string(file).map_err(|e| miette::miette!("Failed to read file: {}", e))?;
 
     eprintln!("Compiling: {}", file.display());
 
-    let result = parse(&source);
+    let mut timings = Timings::default();
+    timings.start("parse");
+    let mut ast = super::load_program(file, &source)?;
 
-    // Report parse errors
-    if !result.errors.is_empty() {
-        for err in &result.errors {
-            eprintln!("Parse error: {}", err);
-        }
-        return Err(miette::miette!("{} parse error(s)", result.errors.len()));
+    timings.start("interpret");
+    if interpret_ai {
+        interpret_all_ai_blocks(&mut ast, file, use_ollama, ollama_model, mock_ai)?;
     }
 
     // Check if there are AI blocks that need interpretation
-    let ai_block_indices: Vec<usize> = result
-        .ast
+    let ai_block_indices: Vec<usize> = ast
         .items
         .iter()
         .enumerate()
@@ -52,8 +69,6 @@ pub(crate) fn run(
         })
         .collect();
 
-    let mut ast = result.ast;
-
     // Load HIF cache file if it exists
     let hif_path = file.with_extension("hif");
     let mut hif_file = load_hif_file(&hif_path);
@@ -67,17 +82,18 @@ pub(crate) fn run(
                 ai_block_indices.len()
             );
 
+            let context = interpretation_context(&ast, file);
+            let mut engine = AIEngine::mock(AIConfig::default());
+            let runtime = tokio::runtime::Runtime::new()
+                .map_err(|e| miette::miette!("Failed to create async runtime: {}", e))?;
+
             for &idx in &ai_block_indices {
                 let ai_block = match &ast.items[idx].node {
                     ItemKind::AiFunctionDef(block) => block.clone(),
                     _ => continue,
                 };
 
-                let name = ai_block
-                    .name
-                    .as_ref()
-                    .map(|n| n.node.to_string())
-                    .unwrap_or_else(|| format!("__ai_anon_{}", idx));
+                let name = ai_block.function_name().to_string();
 
                 eprintln!("  Generating mock for: {}", name);
 
@@ -98,8 +114,21 @@ pub(crate) fn run(
                 let return_type = ai_block.return_ty.as_ref().map(|t| type_to_string(&t.node));
 
                 // Generate mock CIR
-                let cir_func =
-                    generate_mock_cir(&name, &params, return_type.as_deref(), &ai_block.intent);
+                let cir_func = runtime
+                    .block_on(engine.interpret_intent(
+                        Some(&name),
+                        &ai_block.intent,
+                        &params,
+                        return_type.as_deref(),
+                        context.clone(),
+                    ))
+                    .map_err(|e| {
+                        miette::miette!(
+                            "Mock interpretation failed for '{}': {}",
+                            name,
+                            format_ai_error(&e)
+                        )
+                    })?;
 
                 // Convert CIR to AST FunctionDef
                 match cir_to_function_def(&cir_func) {
@@ -131,7 +160,7 @@ pub(crate) fn run(
             );
 
             // Build interpretation context from the AST
-            let context = build_interpretation_context(&ast, file);
+            let context = interpretation_context(&ast, file);
 
             // Initialize AI engine with Ollama backend
             let config = AIConfig::default();
@@ -165,11 +194,7 @@ pub(crate) fn run(
                     _ => continue,
                 };
 
-                let name = ai_block
-                    .name
-                    .as_ref()
-                    .map(|n| n.node.to_string())
-                    .unwrap_or_else(|| format!("__ai_anon_{}", idx));
+                let name = ai_block.function_name().to_string();
 
                 // Compute hash for cache lookup
                 let intent_hash = compute_intent_hash(&name, &ai_block.intent);
@@ -278,7 +303,7 @@ pub(crate) fn run(
             );
 
             // Build interpretation context from the AST
-            let context = build_interpretation_context(&ast, file);
+            let context = interpretation_context(&ast, file);
 
             // Initialize AI engine with local AI backend
             let config = AIConfig::default();
@@ -316,11 +341,7 @@ pub(crate) fn run(
                     _ => continue,
                 };
 
-                let name = ai_block
-                    .name
-                    .as_ref()
-                    .map(|n| n.node.to_string())
-                    .unwrap_or_else(|| format!("__ai_anon_{}", idx));
+                let name = ai_block.function_name().to_string();
 
                 // Compute hash for cache lookup
                 let intent_hash = compute_intent_hash(&name, &ai_block.intent);
@@ -442,7 +463,14 @@ pub(crate) fn run(
 
     // Infer types for struct fields that don't have explicit type annotations
     // This uses AI to determine types based on field names
-    let ast = infer_struct_field_types(ast, use_ollama, ollama_model, use_local_ai)?;
+    timings.start("infer-fields");
+    let mut ast = infer_struct_field_types(ast, use_ollama, ollama_model, use_local_ai)?;
+
+    // Evaluate constant expressions before they reach codegen
+    timings.start("fold");
+    fold_constants(&mut ast);
+
+    timings.start("codegen");
 
     // Determine output binary name
     let output_file = output.map(|p| p.to_path_buf()).unwrap_or_else(|| {
@@ -455,13 +483,128 @@ pub(crate) fn run(
         output_dir.join(stem)
     });
 
-    // Compile to native binary
-    let options = CodegenOptions::default();
-    compile_to_executable(&ast, &output_file, options)
+    // Compile to native binary (or a wasm module)
+    let output_file = if options.is_wasm() {
+        output_file.with_extension("wasm")
+    } else {
+        output_file
+    };
+    let emit_clif = options.emit_clif;
+    let emit_asm = options.emit_asm;
+    let emit_object = options.emit_object;
+    // Cross-compiled objects are left for the target's linker
+    let linked = !options.skip_link && !options.is_cross();
+    write_object(&ast, &output_file, &options)
         .map_err(|e| miette::miette!("Compilation error: {}", e))?;
 
-    eprintln!("Built: {}", output_file.display());
+    timings.start("link");
+    link(&output_file, &options).map_err(|e| miette::miette!("Compilation error: {}", e))?;
+    timings.finish();
+
+    if linked {
+        eprintln!("Built: {}", output_file.display());
+    }
+    if emit_object || !linked {
+        eprintln!("Object: {}", output_file.with_extension("o").display());
+    }
+    if emit_clif {
+        eprintln!("CLIF: {}", output_file.with_extension("clif").display());
+    }
+    if emit_asm {
+        eprintln!("Assembly: {}", output_file.with_extension("s").display());
+    }
+    if print_timings {
+        timings.print();
+    }
+
+    Ok(())
+}
+
+/// Wall-clock durations of the phases of a build, in the order they ran.
+///
+/// Phases are logged as they start and finish, which `--verbose` shows.
+#[derive(Default)]
+struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    current: Option<(&'static str, Instant)>,
+}
+
+impl Timings {
+    /// Finish the running phase, if any, and start `phase`.
+    fn start(&mut self, phase: &'static str) {
+        self.finish();
+        tracing::debug!("{} started", phase);
+        self.current = Some((phase, Instant::now()));
+    }
+
+    /// Finish the running phase.
+    fn finish(&mut self) {
+        if let Some((phase, start)) = self.current.take() {
+            let elapsed = start.elapsed();
+            tracing::debug!("{} finished in {:.2?}", phase, elapsed);
+            self.phases.push((phase, elapsed));
+        }
+    }
+
+    /// Print each phase's duration, and their total, in milliseconds.
+    fn print(&self) {
+        eprintln!("Timings:");
+        let total: Duration = self.phases.iter().map(|(_, elapsed)| *elapsed).sum();
+        for (phase, elapsed) in self.phases.iter().chain([&("total", total)]) {
+            eprintln!("  {:<12} {:>10.3}ms", phase, elapsed.as_secs_f64() * 1000.0);
+        }
+    }
+}
+
+/// Interpret every AI block in `ast` with the selected backend.
+///
+/// Local AI is used unless `--mock-ai` or `--ollama` is given. Results are
+/// cached in the project's `haira.hif`, next to `file`, and reused while
+/// the block is unchanged.
+fn interpret_all_ai_blocks(
+    ast: &mut SourceFile,
+    file: &Path,
+    use_ollama: bool,
+    ollama_model: &str,
+    mock_ai: bool,
+) -> miette::Result<()> {
+    let config = AIConfig::default();
+    let mut engine = if mock_ai {
+        AIEngine::mock(config)
+    } else if use_ollama {
+        AIEngine::with_ollama(config, Some(ollama_model))
+    } else {
+        AIEngine::with_local_ai(config, None)
+    };
+
+    let hif_path = file
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(HIF_FILENAME);
+    let mut hif_file = load_hif_file(&hif_path);
 
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| miette::miette!("Failed to create async runtime: {}", e))?;
+    let context = interpretation_context(ast, file);
+    let result = runtime.block_on(interpret_ai_blocks(
+        ast,
+        &mut engine,
+        &context,
+        &mut hif_file,
+    ));
+    let _ = engine.stop_local_server();
+
+    let stats = result.map_err(|e| miette::miette!("{}", e))?;
+    if stats.interpreted > 0 {
+        save_hif_file(&hif_path, &hif_file);
+    }
+    if stats.interpreted + stats.cached > 0 {
+        eprintln!(
+            "Interpreted {} AI block(s), {} from cache.\n",
+            stats.interpreted + stats.cached,
+            stats.cached
+        );
+    }
     Ok(())
 }
 
@@ -487,192 +630,14 @@ fn format_ai_error(e: &AIError) -> String {
         AIError::LocalAI(local_err) => {
             format!("Local AI error: {}", local_err)
         }
-        _ => e.to_string(),
-    }
-}
-
-/// Generate a mock CIR function for testing.
-fn generate_mock_cir(
-    name: &str,
-    params: &[(String, String)],
-    return_type: Option<&str>,
-    intent: &str,
-) -> CIRFunction {
-    let mut func =
-        CIRFunction::new(name).with_description(format!("Mock implementation: {}", intent));
-
-    // Add parameters
-    for (param_name, param_type) in params {
-        func = func.with_param(param_name, CIRType::simple(param_type));
-    }
-
-    // Set return type
-    let ret_type = return_type.unwrap_or("none");
-    func = func.returning(parse_type_string(ret_type));
-
-    // Generate appropriate mock body based on return type
-    // Note: The Haira compiler currently uses i64 for all values internally,
-    // so we need to return appropriate values that work with i64.
-    func = match ret_type {
-        "int" => func
-            .with_op(CIROperation::Literal {
-                value: CIRValue::Int(0),
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            }),
-        "float" => func
-            .with_op(CIROperation::Literal {
-                value: CIRValue::Float(0.0),
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            }),
-        "string" => {
-            // Return 0 as a placeholder - string handling in codegen returns pointer
-            // but function signatures use i64. Real AI would need proper string handling.
-            func.with_op(CIROperation::Literal {
-                value: CIRValue::Int(0),
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            })
-        }
-        "bool" => func
-            .with_op(CIROperation::Literal {
-                value: CIRValue::Int(1), // true as i64
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            }),
-        "none" | "" => func
-            .with_op(CIROperation::Literal {
-                value: CIRValue::Int(0),
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            }),
-        t if t.starts_with('[') => {
-            // List type - return 0 as placeholder (empty list pointer)
-            func.with_op(CIROperation::Literal {
-                value: CIRValue::Int(0),
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            })
-        }
-        _ => {
-            // For other types (structs, etc.), return 0 as placeholder
-            // Real AI implementation would construct proper objects
-            func.with_op(CIROperation::Literal {
-                value: CIRValue::Int(0),
-                result: "_result".to_string(),
-            })
-            .with_op(CIROperation::Return {
-                value: CIRValue::Ref("_result".to_string()),
-            })
-        }
-    };
-
-    func
-}
-
-/// Parse a type string into CIRType.
-fn parse_type_string(s: &str) -> CIRType {
-    if s.starts_with('[') && s.ends_with(']') {
-        let inner = &s[1..s.len() - 1];
-        CIRType::list(parse_type_string(inner))
-    } else if s.starts_with("Option<") && s.ends_with('>') {
-        let inner = &s[7..s.len() - 1];
-        CIRType::option(parse_type_string(inner))
-    } else {
-        CIRType::simple(s)
-    }
-}
-
-/// Build interpretation context from the parsed AST.
-fn build_interpretation_context(ast: &SourceFile, file: &Path) -> InterpretationContext {
-    let mut types_in_scope = Vec::new();
-
-    // Extract all type definitions from the AST
-    for item in &ast.items {
-        if let ItemKind::TypeDef(type_def) = &item.node {
-            let fields = type_def
-                .fields
-                .iter()
-                .map(|f| {
-                    let ty =
-                        f.ty.as_ref()
-                            .map(|t| type_to_string(&t.node))
-                            .unwrap_or_else(|| "any".to_string());
-                    FieldDefinition {
-                        name: f.name.node.to_string(),
-                        ty,
-                        optional: false, // TODO: detect optionality
-                        default: None,
-                    }
-                })
-                .collect();
-
-            types_in_scope.push(TypeDefinition {
-                name: type_def.name.node.to_string(),
-                fields,
-            });
-        }
-    }
-
-    InterpretationContext {
-        types_in_scope,
-        call_site: CallSiteInfo {
-            file: file.display().to_string(),
-            line: 1,
-            arguments: vec![],
-            expected_return: None,
-        },
-        project_schema: Default::default(),
-    }
-}
-
-/// Convert an AST Type to a string representation.
-fn type_to_string(ty: &Type) -> String {
-    match ty {
-        Type::Named(name) => name.to_string(),
-        Type::List(inner) => format!("[{}]", type_to_string(&inner.node)),
-        Type::Map { key, value } => {
+        AIError::Timeout { timeout, attempts } => {
             format!(
-                "{{{}:{}}}",
-                type_to_string(&key.node),
-                type_to_string(&value.node)
+                "AI backend timed out after {} attempt(s) of {}s each (set HAIRA_AI_TIMEOUT to raise the limit)",
+                attempts,
+                timeout.as_secs()
             )
         }
-        Type::Option(inner) => format!("Option<{}>", type_to_string(&inner.node)),
-        Type::Function { params, ret } => {
-            let params_str = params
-                .iter()
-                .map(|p| type_to_string(&p.node))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("({}) -> {}", params_str, type_to_string(&ret.node))
-        }
-        Type::Union(variants) => variants
-            .iter()
-            .map(|v| type_to_string(&v.node))
-            .collect::<Vec<_>>()
-            .join(" | "),
-        Type::Generic { name, args } => {
-            let args_str = args
-                .iter()
-                .map(|a| type_to_string(&a.node))
-                .collect::<Vec<_>>()
-                .join(", ");
-            format!("{}<{}>", name, args_str)
-        }
+        _ => e.to_string(),
     }
 }
 
@@ -883,7 +848,7 @@ fn string_to_type(s: &str) -> Type {
 }
 
 /// Load a HIF cache file if it exists.
-fn load_hif_file(path: &Path) -> HIFFile {
+pub(crate) fn load_hif_file(path: &Path) -> HIFFile {
     if path.exists() {
         match fs::read_to_string(path) {
             Ok(content) => match parse_hif(&content) {
@@ -904,7 +869,7 @@ fn load_hif_file(path: &Path) -> HIFFile {
 }
 
 /// Save a HIF cache file.
-fn save_hif_file(path: &Path, hif: &HIFFile) {
+pub(crate) fn save_hif_file(path: &Path, hif: &HIFFile) {
     let content = write_hif(hif);
     match fs::write(path, &content) {
         Ok(_) => {
@@ -1,21 +1,39 @@
 //! Check command - check files for errors without full compilation.
 
-use haira_parser::parse;
+use rayon::prelude::*;
+use std::fmt::Write;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// The outcome of checking a single file.
+struct FileReport {
+    /// The rendered diagnostics, printed once every file is checked.
+    output: String,
+    errors: usize,
+    warnings: usize,
+}
 
-pub(crate) fn run(files: &[std::path::PathBuf]) -> miette::Result<()> {
+pub(crate) fn run(files: &[PathBuf]) -> miette::Result<()> {
     if files.is_empty() {
         return Err(miette::miette!("No files specified"));
     }
 
+    let files = expand_directories(files)?;
+
+    // Files are checked independently, so they're checked in parallel;
+    // `collect` keeps the reports in argument order for stable output.
+    let reports = files
+        .par_iter()
+        .map(|file| check_file(file))
+        .collect::<miette::Result<Vec<_>>>()?;
+
     let mut total_errors = 0;
     let mut total_warnings = 0;
 
-    for file in files {
-        let (errors, warnings) = check_file(file)?;
-        total_errors += errors;
-        total_warnings += warnings;
+    for report in &reports {
+        print!("{}", report.output);
+        total_errors += report.errors;
+        total_warnings += report.warnings;
     }
 
     println!();
@@ -34,60 +52,76 @@ pub(crate) fn run(files: &[std::path::PathBuf]) -> miette::Result<()> {
     }
 }
 
-fn check_file(file: &Path) -> miette::Result<(usize, usize)> {
-    let source = fs::read_to_string(file)
-        .map_err(|e| miette::miette!("Failed to read {}: {}", file.display(), e))?;
-
-    println!("Checking: {}", file.display());
-
-    let result = parse(&source);
-
-    let mut errors = 0;
-    let warnings = 0;
-
-    // Report parse errors
-    for err in &result.errors {
-        let span = err.span();
-        let (line, col) = offset_to_line_col(&source, span.start);
-        println!("  error[E0001]: {}", err);
-        println!("   --> {}:{}:{}", file.display(), line, col);
-        println!("    |");
-        print_source_line(&source, line);
-        println!("    |");
-        errors += 1;
+/// Replace each directory in `paths` with the `.haira` files beneath it,
+/// sorted by path.
+fn expand_directories(paths: &[PathBuf]) -> miette::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found = Vec::new();
+            collect_sources(path, &mut found)?;
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
     }
+    Ok(files)
+}
 
-    // Basic semantic checks could be added here
-    // For now, we just do parsing validation
-
-    if errors == 0 {
-        println!("  ok");
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> miette::Result<()> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| miette::miette!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| miette::miette!("Failed to read {}: {}", dir.display(), e))?
+            .path();
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "haira") {
+            files.push(path);
+        }
     }
-
-    Ok((errors, warnings))
+    Ok(())
 }
 
-fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
-    let mut line = 1;
-    let mut col = 1;
+fn check_file(file: &Path) -> miette::Result<FileReport> {
+    let source = fs::read_to_string(file)
+        .map_err(|e| miette::miette!("Failed to read {}: {}", file.display(), e))?;
 
-    for (i, c) in source.char_indices() {
-        if i >= offset {
-            break;
+    let mut output = String::new();
+    writeln!(output, "Checking: {}", file.display()).unwrap();
+
+    // A file importing others is checked together with them
+    let result = if super::has_imports(&haira_parser::parse(&source).ast) {
+        let project = haira_driver::compile_project(file)?;
+        for err in &project.result.errors {
+            let source = super::source_of(&project, err.file.as_deref());
+            writeln!(output, "{:?}", err.to_diagnostic(source)).unwrap();
         }
-        if c == '\n' {
-            line += 1;
-            col = 1;
-        } else {
-            col += 1;
+        for warning in &project.result.warnings {
+            let source = super::source_of(&project, warning.file.as_deref());
+            writeln!(output, "{:?}", warning.to_diagnostic(source)).unwrap();
         }
-    }
-
-    (line, col)
-}
+        project.result
+    } else {
+        let result = haira_driver::check_source(&source, Some(file))?;
+        for err in &result.errors {
+            writeln!(output, "{:?}", err.to_diagnostic(&source)).unwrap();
+        }
+        for warning in &result.warnings {
+            writeln!(output, "{:?}", warning.to_diagnostic(&source)).unwrap();
+        }
+        result
+    };
 
-fn print_source_line(source: &str, line_num: usize) {
-    if let Some(line) = source.lines().nth(line_num - 1) {
-        println!("{:4} | {}", line_num, line);
+    if result.errors.is_empty() {
+        writeln!(output, "  ok").unwrap();
     }
+
+    Ok(FileReport {
+        output,
+        errors: result.errors.len(),
+        warnings: result.warnings.len(),
+    })
 }
@@ -1,5 +1,10 @@
 //! Info command - show information about Haira installation.
 
+use haira_codegen::struct_layouts;
+use haira_parser::parse;
+use std::fs;
+use std::path::Path;
+
 pub(crate) fn run() -> miette::Result<()> {
     println!("Haira Programming Language");
     println!("===========================");
@@ -34,3 +39,42 @@ pub(crate) fn run() -> miette::Result<()> {
 
     Ok(())
 }
+
+/// Print the layout of every struct in `file` as JSON.
+pub(crate) fn layout(file: &Path) -> miette::Result<()> {
+    let source =
+        fs::read_to_string(file).map_err(|e| miette::miette!("Failed to read file: {}", e))?;
+
+    let result = parse(&source);
+    if !result.errors.is_empty() {
+        return Err(miette::miette!("{} parse errors", result.errors.len()));
+    }
+
+    let layouts: Vec<_> = struct_layouts(&result.ast)
+        .into_iter()
+        .map(|layout| {
+            let fields: Vec<_> = layout
+                .fields
+                .into_iter()
+                .map(|field| {
+                    serde_json::json!({
+                        "name": field.name,
+                        "type": field.ty,
+                        "offset": field.offset,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "name": layout.name,
+                "fields": fields,
+                "size": layout.size,
+            })
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&layouts)
+        .map_err(|e| miette::miette!("Failed to serialize layouts: {}", e))?;
+    println!("{}", json);
+
+    Ok(())
+}
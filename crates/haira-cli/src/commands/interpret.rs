@@ -1,22 +1,41 @@
 //! Interpret command - test AI interpretation of function names.
 
+use super::build::{load_hif_file, save_hif_file};
+use haira_ai::hif::{cir_function_to_hif_intent, compute_context_hash, write_hif, HIFFile};
 use haira_ai::{AIConfig, AIEngine, InterpretationContext, TypeDefinition};
-use haira_cir::{CallSiteInfo, FieldDefinition};
+use haira_cir::{CIRFunction, CallSiteInfo, ProjectSchema};
 use std::path::Path;
 
-pub(crate) async fn run(name: &str, context_file: Option<&Path>) -> miette::Result<()> {
+pub(crate) async fn run(
+    name: &str,
+    context_file: Option<&Path>,
+    context_json: Option<&str>,
+    format: &str,
+    save: bool,
+    mock_ai: bool,
+) -> miette::Result<()> {
     println!("Interpreting function: {}\n", name);
 
     // Load context if provided
-    let context = if let Some(path) = context_file {
-        load_context(path)?
-    } else {
-        default_context()
+    let context = match (context_file, context_json) {
+        (Some(path), _) => load_context(path)?,
+        (None, Some(json)) => parse_context(json)?,
+        (None, None) => context_for_name(name),
     };
+    println!(
+        "Context: {} type(s) in scope, called from {}:{}\n",
+        context.types_in_scope.len(),
+        context.call_site.file,
+        context.call_site.line
+    );
 
     // Create AI engine
     let config = AIConfig::default();
-    let mut engine = AIEngine::new(config);
+    let mut engine = if mock_ai {
+        AIEngine::mock(config)
+    } else {
+        AIEngine::new(config)
+    };
 
     // Check if we can use pattern matching (no AI needed)
     println!("Checking pattern matching...");
@@ -32,8 +51,16 @@ pub(crate) async fn run(name: &str, context_file: Option<&Path>) -> miette::Resu
     match engine.interpret(name, context).await {
         Ok(func) => {
             println!("Interpretation successful!\n");
-            println!("Generated CIR:");
-            println!("{}", serde_json::to_string_pretty(&func).unwrap());
+            print_function(&func, format)?;
+            if save {
+                let path = Path::new(haira_ai::hif::HIF_FILENAME);
+                let mut hif_file = load_hif_file(path);
+                hif_file.add_intent(cir_function_to_hif_intent(
+                    &func,
+                    &compute_context_hash(name),
+                ));
+                save_hif_file(path, &hif_file);
+            }
         }
         Err(e) => {
             println!("AI interpretation failed: {}", e);
@@ -45,84 +72,88 @@ pub(crate) async fn run(name: &str, context_file: Option<&Path>) -> miette::Resu
     Ok(())
 }
 
+/// Print the interpreted function as CIR JSON, a HIF intent, or Haira source.
+fn print_function(func: &CIRFunction, format: &str) -> miette::Result<()> {
+    match format {
+        "hif" => {
+            let mut hif_file = HIFFile::new();
+            hif_file.add_intent(cir_function_to_hif_intent(
+                func,
+                &compute_context_hash(&func.name),
+            ));
+            println!("Generated HIF:");
+            print!("{}", write_hif(&hif_file));
+        }
+        "haira" => {
+            let def = haira_codegen::cir_to_function_def(func)
+                .map_err(|e| miette::miette!("Failed to convert CIR to Haira: {}", e))?;
+            println!("Generated Haira:");
+            print!("{}", haira_driver::function_to_source(&def));
+        }
+        _ => {
+            println!("Generated CIR:");
+            println!("{}", serde_json::to_string_pretty(func).unwrap());
+        }
+    }
+    Ok(())
+}
+
 fn load_context(path: &Path) -> miette::Result<InterpretationContext> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| miette::miette!("Failed to read context file: {}", e))?;
 
-    let context: InterpretationContext = serde_json::from_str(&content)
+    parse_context(&content)
+}
+
+/// Parse a context from JSON, naming the field that doesn't match the
+/// `InterpretationContext` schema.
+fn parse_context(json: &str) -> miette::Result<InterpretationContext> {
+    let value: serde_json::Value = serde_json::from_str(json)
         .map_err(|e| miette::miette!("Failed to parse context JSON: {}", e))?;
 
-    Ok(context)
+    serde_json::from_value(value.clone()).map_err(|e| match offending_field(&value) {
+        Some((field, e)) => miette::miette!("Invalid context JSON at `{}`: {}", field, e),
+        None => miette::miette!("Invalid context JSON: {}", e),
+    })
 }
 
-fn default_context() -> InterpretationContext {
-    // Create a default context with common types
+/// The first field of a context that fails to deserialize, with its error.
+fn offending_field(value: &serde_json::Value) -> Option<(String, serde_json::Error)> {
+    if let Some(types) = value.get("types_in_scope").and_then(|v| v.as_array()) {
+        for (i, ty) in types.iter().enumerate() {
+            if let Err(e) = serde_json::from_value::<TypeDefinition>(ty.clone()) {
+                return Some((format!("types_in_scope[{}]", i), e));
+            }
+        }
+    }
+    if let Some(call_site) = value.get("call_site") {
+        if let Err(e) = serde_json::from_value::<CallSiteInfo>(call_site.clone()) {
+            return Some(("call_site".to_string(), e));
+        }
+    }
+    if let Some(schema) = value.get("project_schema") {
+        if let Err(e) = serde_json::from_value::<ProjectSchema>(schema.clone()) {
+            return Some(("project_schema".to_string(), e));
+        }
+    }
+    None
+}
+
+/// A minimal context for trying out `name` without a context file: no types
+/// in scope, and a return type when the name's verb implies one.
+fn context_for_name(name: &str) -> InterpretationContext {
+    let expected_return = match name.split('_').next() {
+        Some("is" | "has" | "can") => Some("Bool".to_string()),
+        Some("count") => Some("Int".to_string()),
+        _ => None,
+    };
     InterpretationContext {
-        types_in_scope: vec![
-            TypeDefinition {
-                name: "User".to_string(),
-                fields: vec![
-                    FieldDefinition {
-                        name: "id".to_string(),
-                        ty: "Int".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                    FieldDefinition {
-                        name: "name".to_string(),
-                        ty: "String".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                    FieldDefinition {
-                        name: "email".to_string(),
-                        ty: "String".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                    FieldDefinition {
-                        name: "active".to_string(),
-                        ty: "Bool".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                ],
-            },
-            TypeDefinition {
-                name: "Post".to_string(),
-                fields: vec![
-                    FieldDefinition {
-                        name: "id".to_string(),
-                        ty: "Int".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                    FieldDefinition {
-                        name: "title".to_string(),
-                        ty: "String".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                    FieldDefinition {
-                        name: "content".to_string(),
-                        ty: "String".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                    FieldDefinition {
-                        name: "author_id".to_string(),
-                        ty: "Int".to_string(),
-                        optional: false,
-                        default: None,
-                    },
-                ],
-            },
-        ],
+        types_in_scope: vec![],
         call_site: CallSiteInfo {
-            file: "main.haira".to_string(),
+            file: "<interpret>".to_string(),
             line: 1,
             arguments: vec![],
-            expected_return: None,
+            expected_return,
         },
         project_schema: Default::default(),
     }
@@ -4,10 +4,14 @@ use haira_lexer::Lexer;
 use std::fs;
 use std::path::Path;
 
-pub(crate) fn run(file: &Path) -> miette::Result<()> {
+pub(crate) fn run(file: &Path, json: bool) -> miette::Result<()> {
     let source =
         fs::read_to_string(file).map_err(|e| miette::miette!("Failed to read file: {}", e))?;
 
+    if json {
+        return print_json(&source);
+    }
+
     println!("Tokenizing: {}\n", file.display());
 
     let lexer = Lexer::new(&source);
@@ -51,3 +55,35 @@ pub(crate) fn run(file: &Path) -> miette::Result<()> {
         Ok(())
     }
 }
+
+/// Print the tokens of `source` as a JSON array, with each lexer error in
+/// place of the text it failed on.
+fn print_json(source: &str) -> miette::Result<()> {
+    let mut error_count = 0;
+    let entries: Vec<_> = Lexer::new(source)
+        .map(|result| match result {
+            Ok(token) => serde_json::json!({
+                "kind": token.kind,
+                "span": token.span,
+                "text": &source[token.span.clone()],
+            }),
+            Err(err) => {
+                error_count += 1;
+                serde_json::json!({
+                    "error": err.to_string(),
+                    "span": err.span(),
+                })
+            }
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&entries)
+        .map_err(|e| miette::miette!("Failed to serialize tokens: {}", e))?;
+    println!("{}", json);
+
+    if error_count > 0 {
+        Err(miette::miette!("{} lexer errors", error_count))
+    } else {
+        Ok(())
+    }
+}
@@ -8,3 +8,69 @@ pub(crate) mod lex;
 pub(crate) mod model;
 pub(crate) mod parse;
 pub(crate) mod run;
+
+use haira_ast::{ItemKind, SourceFile};
+use haira_driver::{CompilationError, Project};
+use haira_parser::ParseError;
+use std::path::Path;
+
+/// Print each parse error with its source snippet, returning the error
+/// that ends the command.
+pub(crate) fn report_parse_errors(
+    file: &Path,
+    source: &str,
+    errors: &[ParseError],
+) -> miette::Report {
+    for err in errors {
+        let err = CompilationError {
+            message: err.to_string(),
+            file: Some(file.display().to_string()),
+            span: Some(err.span()),
+        };
+        eprintln!("{:?}", err.to_diagnostic(source));
+    }
+    miette::miette!("{} parse error(s)", errors.len())
+}
+
+/// Parse `file`, whose text is `source`, into the program to compile.
+///
+/// A file importing others is compiled as a project, merging in the files
+/// it imports. Parse errors, and any error in the project's files, are
+/// printed and end the command.
+pub(crate) fn load_program(file: &Path, source: &str) -> miette::Result<SourceFile> {
+    let result = haira_parser::parse(source);
+    if !result.errors.is_empty() {
+        return Err(report_parse_errors(file, source, &result.errors));
+    }
+    if !has_imports(&result.ast) {
+        return Ok(result.ast);
+    }
+
+    let project = haira_driver::compile_project(file)?;
+    if !project.result.success {
+        for err in &project.result.errors {
+            eprintln!(
+                "{:?}",
+                err.to_diagnostic(source_of(&project, err.file.as_deref()))
+            );
+        }
+        return Err(miette::miette!("{} error(s)", project.result.errors.len()));
+    }
+    Ok(project.ast)
+}
+
+/// Whether `ast` imports other files, and so is compiled as a project.
+pub(crate) fn has_imports(ast: &SourceFile) -> bool {
+    ast.items
+        .iter()
+        .any(|item| matches!(item.node, ItemKind::Import(_)))
+}
+
+/// The source of the project file a diagnostic names, to render it against.
+pub(crate) fn source_of<'a>(project: &'a Project, file: Option<&str>) -> &'a str {
+    project
+        .files
+        .iter()
+        .find(|f| Some(f.path.display().to_string().as_str()) == file)
+        .map_or("", |f| f.source.as_str())
+}
@@ -18,8 +18,17 @@ pub(crate) fn list() -> miette::Result<()> {
         return Ok(());
     }
 
+    // Only the registry model has a checksum or download record to verify against
+    let default_model = ModelManager::default_model();
+
     println!("Installed models:");
     for model in models {
+        if format!("{}.gguf", model) == default_model.filename {
+            if let Err(e) = manager.verify(&default_model) {
+                println!("  - {} (corrupted: {})", model, e);
+                continue;
+            }
+        }
         println!("  - {}", model);
     }
 
@@ -65,8 +74,13 @@ pub(crate) async fn pull(path: Option<&Path>) -> miette::Result<()> {
         let model = ModelManager::default_model();
 
         if manager.is_installed(&model.filename) {
-            println!("Model '{}' is already installed.", model.name);
-            return Ok(());
+            match manager.verify(&model) {
+                Ok(_) => {
+                    println!("Model '{}' is already installed.", model.name);
+                    return Ok(());
+                }
+                Err(e) => println!("Installed model is corrupted ({}), downloading again.", e),
+            }
         }
 
         println!("Downloading model: {}", model.name);
@@ -1,5 +1,6 @@
 //! Parse command - parse a file and show AST.
 
+use haira_ast::LineIndex;
 use haira_parser::parse;
 use std::fs;
 use std::path::Path;
@@ -15,10 +16,11 @@ pub(crate) fn run(file: &Path, json: bool) -> miette::Result<()> {
     // Report errors
     if !result.errors.is_empty() {
         println!("Errors:");
+        let lines = LineIndex::new(&source);
         for err in &result.errors {
             let span = err.span();
-            let (line, col) = offset_to_line_col(&source, span.start);
-            println!("  {}:{}:{}: {}", file.display(), line, col, err);
+            let (line, col) = lines.line_col(span.start);
+            println!("  {}:{}:{}: {}", file.display(), line + 1, col + 1, err);
         }
         println!();
     }
@@ -87,6 +89,17 @@ fn print_item(item: &haira_ast::Item, source: &str, indent: usize) {
                 def.params.len()
             );
         }
+        haira_ast::ItemKind::EnumDef(def) => {
+            println!(
+                "{}EnumDef: {} ({} variants)",
+                prefix,
+                def.name.node,
+                def.variants.len()
+            );
+            for variant in &def.variants {
+                println!("{}  - {}", prefix, variant.name.node);
+            }
+        }
         haira_ast::ItemKind::TypeAlias(alias) => {
             println!("{}TypeAlias: {}", prefix, alias.name.node);
         }
@@ -107,6 +120,12 @@ fn print_item(item: &haira_ast::Item, source: &str, indent: usize) {
         haira_ast::ItemKind::Statement(stmt) => {
             print_statement_kind(stmt, source, indent);
         }
+        haira_ast::ItemKind::Const(const_def) => {
+            println!("{}Const: {}", prefix, const_def.name.node);
+        }
+        haira_ast::ItemKind::Import(import) => {
+            println!("{}Import: {:?}", prefix, import.path.node);
+        }
     }
 }
 
@@ -120,7 +139,15 @@ fn print_statement_kind(stmt: &haira_ast::StatementKind, source: &str, indent: u
                 .iter()
                 .map(|t| format_assign_path(&t.path))
                 .collect();
-            println!("{}Assignment: {} = ...", prefix, targets.join(", "));
+            match &assign.op {
+                Some(op) => println!(
+                    "{}Compound assignment ({:?}): {} = ...",
+                    prefix,
+                    op.node,
+                    targets.join(", ")
+                ),
+                None => println!("{}Assignment: {} = ...", prefix, targets.join(", ")),
+            }
         }
         haira_ast::StatementKind::If(_) => {
             println!("{}If statement", prefix);
@@ -164,6 +191,7 @@ fn print_expr_kind(expr: &haira_ast::ExprKind, _source: &str, indent: usize) {
             let lit_str = match lit {
                 haira_ast::Literal::Int(n) => format!("Int({})", n),
                 haira_ast::Literal::Float(n) => format!("Float({})", n),
+                haira_ast::Literal::Char(c) => format!("Char({:?})", c),
                 haira_ast::Literal::String(s) => format!("String({:?})", s),
                 haira_ast::Literal::Bool(b) => format!("Bool({})", b),
                 haira_ast::Literal::InterpolatedString(_) => "InterpolatedString".to_string(),
@@ -196,25 +224,6 @@ fn print_expr_kind(expr: &haira_ast::ExprKind, _source: &str, indent: usize) {
     }
 }
 
-fn offset_to_line_col(source: &str, offset: usize) -> (usize, usize) {
-    let mut line = 1;
-    let mut col = 1;
-
-    for (i, c) in source.char_indices() {
-        if i >= offset {
-            break;
-        }
-        if c == '\n' {
-            line += 1;
-            col = 1;
-        } else {
-            col += 1;
-        }
-    }
-
-    (line, col)
-}
-
 fn format_assign_path(path: &haira_ast::AssignPath) -> String {
     match path {
         haira_ast::AssignPath::Identifier(name) => name.node.to_string(),
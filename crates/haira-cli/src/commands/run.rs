@@ -1,47 +1,56 @@
 //! Run command - compile and execute a Haira file.
 
 use haira_codegen::{compile_to_executable, CodegenOptions};
-use haira_parser::parse;
 use std::fs;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, ExitStatus};
 
 pub(crate) fn run(file: &Path) -> miette::Result<()> {
     let source =
         fs::read_to_string(file).map_err(|e| miette::miette!("Failed to read file: {}", e))?;
 
-    let result = parse(&source);
+    let ast = super::load_program(file, &source)?;
 
-    // Report parse errors
-    if !result.errors.is_empty() {
-        for err in &result.errors {
-            eprintln!("Parse error: {}", err);
-        }
-        return Err(miette::miette!("{} parse error(s)", result.errors.len()));
-    }
-
-    // Create temporary output path
+    // Create a temporary output path, unique per process so concurrent runs don't collide
     let tmp_dir = std::env::temp_dir();
-    let output_file = tmp_dir.join("haira_run_temp");
-
-    // Compile to native binary
-    let options = CodegenOptions::default();
-    compile_to_executable(&result.ast, &output_file, options)
+    let output_file = tmp_dir.join(format!("haira_run_{}", std::process::id()));
+
+    // Compile to native binary; nothing is run if this fails. Running is for
+    // development, so out-of-bounds indexing aborts cleanly
+    let options = CodegenOptions {
+        bounds_checks: true,
+        ..Default::default()
+    };
+    compile_to_executable(&ast, &output_file, options)
         .map_err(|e| miette::miette!("Compilation error: {}", e))?;
 
-    // Execute the binary
-    let status = Command::new(&output_file)
-        .status()
-        .map_err(|e| miette::miette!("Failed to execute: {}", e))?;
+    // Execute the binary with inherited stdio, so its output streams live
+    let status = Command::new(&output_file).status();
 
     // Clean up
     fs::remove_file(&output_file).ok();
 
+    let status = status.map_err(|e| miette::miette!("Failed to execute: {}", e))?;
     if !status.success() {
-        if let Some(code) = status.code() {
-            return Err(miette::miette!("Program exited with code {}", code));
-        }
+        std::process::exit(exit_code(status));
     }
 
     Ok(())
 }
+
+/// The exit code to report for the program's exit status.
+///
+/// A program killed by a signal reports `128 + signal`, as shells do.
+fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
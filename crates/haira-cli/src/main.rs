@@ -1,6 +1,7 @@
 //! Haira CLI - Command-line interface for the Haira programming language.
 
 use clap::{Parser, Subcommand};
+use haira_codegen::CodegenOptions;
 use std::path::PathBuf;
 
 mod commands;
@@ -54,6 +55,52 @@ enum Commands {
         /// Use mock AI interpretation for testing (generates stub implementations)
         #[arg(long)]
         mock_ai: bool,
+        /// Interpret every AI block, including inline `ai` expressions, before
+        /// code generation, using the selected backend
+        #[arg(long)]
+        interpret_ai: bool,
+        /// Target triple (`wasm32` is short for wasm32-unknown-unknown)
+        #[arg(long, value_name = "TRIPLE")]
+        target: Option<String>,
+        /// Artifacts to emit: `exe` (the default), `obj` (the object file,
+        /// skipping the link unless `exe` is also given), `asm`, or `clif`
+        #[arg(long, value_name = "KIND", value_parser = ["exe", "obj", "asm", "clif"])]
+        emit: Vec<String>,
+        /// Abort on integer overflow instead of wrapping
+        #[arg(long)]
+        overflow_checks: bool,
+        /// Abort with a "stack overflow" message when a thread uses more than
+        /// BYTES of stack (default 1 MiB), instead of crashing
+        #[arg(
+            long,
+            value_name = "BYTES",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "1048576"
+        )]
+        stack_guard: Option<u64>,
+        /// Abort on a list index out of bounds (always on at `-O0`)
+        #[arg(long)]
+        bounds_checks: bool,
+        /// Optimization level: 0 (none), 1-2 (speed), or 3 (speed and size)
+        #[arg(
+            short = 'O',
+            long = "opt-level",
+            default_value_t = 2,
+            value_parser = clap::value_parser!(u8).range(0..=3)
+        )]
+        opt_level: u8,
+        /// Linker to use instead of `$HAIRA_LINKER` or `cc`
+        #[arg(long, value_name = "PATH")]
+        linker: Option<String>,
+        /// Extra argument to pass to the linker (repeatable)
+        #[arg(long = "link-arg", value_name = "ARG", allow_hyphen_values = true)]
+        link_args: Vec<String>,
+        /// Print how long each phase of the build took: parse, interpret,
+        /// infer-fields, fold, codegen and link (`haira build` has no
+        /// resolve, typecheck, HIR or MIR stages)
+        #[arg(long)]
+        timings: bool,
     },
 
     /// Manage local AI models
@@ -87,10 +134,17 @@ enum Commands {
     Lex {
         /// Input file
         file: PathBuf,
+        /// Print the tokens and errors as a JSON array
+        #[arg(long)]
+        json: bool,
     },
 
     /// Show information about the Haira installation
-    Info,
+    Info {
+        /// Print the struct layouts of a file as JSON instead
+        #[arg(long, value_name = "FILE")]
+        layout: Option<PathBuf>,
+    },
 
     /// Interpret a function name (test AI interpretation)
     Interpret {
@@ -99,6 +153,18 @@ enum Commands {
         /// Type context (JSON file)
         #[arg(long)]
         context: Option<PathBuf>,
+        /// Type context as inline JSON, instead of a file
+        #[arg(long, value_name = "JSON", conflicts_with = "context")]
+        context_json: Option<String>,
+        /// How to print the result: `cir`, `hif`, or `haira` source
+        #[arg(long, value_parser = ["cir", "hif", "haira"], default_value = "cir")]
+        format: String,
+        /// Add the result to `haira.hif` in the current directory
+        #[arg(long)]
+        save: bool,
+        /// Use mock AI interpretation for testing (generates stub implementations)
+        #[arg(long)]
+        mock_ai: bool,
     },
 }
 
@@ -121,13 +187,48 @@ fn main() -> miette::Result<()> {
             ollama_model,
             local_ai,
             mock_ai,
+            interpret_ai,
+            target,
+            emit,
+            overflow_checks,
+            stack_guard,
+            bounds_checks,
+            opt_level,
+            linker,
+            link_args,
+            timings,
         } => commands::build::run(
             &file,
             output.as_deref(),
-            ollama,
-            &ollama_model,
-            local_ai,
-            mock_ai,
+            commands::build::AiOptions {
+                ollama,
+                ollama_model: &ollama_model,
+                local_ai,
+                mock: mock_ai,
+                interpret: interpret_ai,
+            },
+            CodegenOptions {
+                target: target.map(|t| {
+                    if t == "wasm32" {
+                        "wasm32-unknown-unknown".to_string()
+                    } else {
+                        t
+                    }
+                }),
+                emit_clif: emit.iter().any(|kind| kind == "clif"),
+                emit_asm: emit.iter().any(|kind| kind == "asm"),
+                emit_object: emit.iter().any(|kind| kind == "obj"),
+                skip_link: emit.iter().any(|kind| kind == "obj")
+                    && !emit.iter().any(|kind| kind == "exe"),
+                overflow_checks,
+                stack_guard,
+                bounds_checks: bounds_checks || opt_level == 0,
+                opt_level,
+                linker,
+                link_args,
+                ..Default::default()
+            },
+            timings,
         ),
         Commands::Model { action } => match action {
             ModelAction::Pull { path } => tokio::runtime::Runtime::new()
@@ -139,10 +240,27 @@ fn main() -> miette::Result<()> {
         Commands::Run { file } => commands::run::run(&file),
         Commands::Parse { file, json } => commands::parse::run(&file, json),
         Commands::Check { files } => commands::check::run(&files),
-        Commands::Lex { file } => commands::lex::run(&file),
-        Commands::Info => commands::info::run(),
-        Commands::Interpret { name, context } => tokio::runtime::Runtime::new()
+        Commands::Lex { file, json } => commands::lex::run(&file, json),
+        Commands::Info { layout } => match layout {
+            Some(file) => commands::info::layout(&file),
+            None => commands::info::run(),
+        },
+        Commands::Interpret {
+            name,
+            context,
+            context_json,
+            format,
+            save,
+            mock_ai,
+        } => tokio::runtime::Runtime::new()
             .unwrap()
-            .block_on(commands::interpret::run(&name, context.as_deref())),
+            .block_on(commands::interpret::run(
+                &name,
+                context.as_deref(),
+                context_json.as_deref(),
+                &format,
+                save,
+                mock_ai,
+            )),
     }
 }
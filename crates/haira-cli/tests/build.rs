@@ -0,0 +1,155 @@
+//! End-to-end tests for `haira build`.
+
+use std::process::Command;
+
+#[test]
+fn test_emit_obj_does_not_link() {
+    let dir = std::env::temp_dir().join(format!("haira_build_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main.haira");
+    std::fs::write(&program, "double(x) {\n    x * 2\n}\nprint(double(21))\n").unwrap();
+    let output = dir.join("main");
+
+    // With no `cc` on the PATH, linking would fail.
+    let result = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .args(["build", "--emit", "obj", "-o"])
+        .arg(&output)
+        .arg(&program)
+        .env("PATH", "")
+        .output()
+        .unwrap();
+
+    let exists = output.exists();
+    let object = std::fs::read(output.with_extension("o"));
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    assert!(!exists);
+    let object = object.unwrap();
+    assert!(object.starts_with(b"\x7fELF") || object.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]));
+}
+
+#[test]
+fn test_interpret_ai_with_mock_backend() {
+    let dir = std::env::temp_dir().join(format!("haira_build_ai_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main.haira");
+    std::fs::write(
+        &program,
+        "x = ai(n: int) -> int {\n    Double the number\n}(21)\nprint(x)\n",
+    )
+    .unwrap();
+    let output = dir.join("main");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .args([
+            "build",
+            "--interpret-ai",
+            "--mock-ai",
+            "--emit",
+            "obj",
+            "-o",
+        ])
+        .arg(&output)
+        .arg(&program)
+        .output()
+        .unwrap();
+
+    let object = output.with_extension("o").exists();
+    std::fs::remove_dir_all(&dir).ok();
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    assert!(object);
+}
+
+#[test]
+fn test_interpret_ai_reuses_hif_cache() {
+    let dir = std::env::temp_dir().join(format!("haira_build_hif_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main.haira");
+    let source = "x = ai(n: int) -> int {\n    Double the number\n}(21)\nprint(x)\n";
+    let output = dir.join("main");
+    // With HOME pointing at an empty directory there is no local model
+    let build = |backend: &str| {
+        Command::new(env!("CARGO_BIN_EXE_haira"))
+            .args(["build", "--interpret-ai", backend, "--emit", "obj", "-o"])
+            .arg(&output)
+            .arg(&program)
+            .env("HOME", &dir)
+            .output()
+            .unwrap()
+    };
+
+    std::fs::write(&program, source).unwrap();
+    let first = build("--mock-ai");
+    let hif = std::fs::read_to_string(dir.join("haira.hif"));
+    let cached = build("--local-ai");
+    std::fs::write(&program, source.replace("Double", "Triple")).unwrap();
+    let edited = build("--local-ai");
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(
+        first.status.success(),
+        "{}",
+        String::from_utf8_lossy(&first.stderr)
+    );
+    assert!(hif.unwrap().contains("intent __ai_anon_"));
+    assert!(
+        cached.status.success(),
+        "{}",
+        String::from_utf8_lossy(&cached.stderr)
+    );
+    assert!(!edited.status.success());
+}
+
+#[test]
+fn test_timings_lists_each_phase() {
+    let dir = std::env::temp_dir().join(format!("haira_build_timings_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main.haira");
+    std::fs::write(&program, "print(1 + 2)\n").unwrap();
+    let output = dir.join("main");
+
+    let result = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .args(["build", "--timings", "--emit", "obj", "-o"])
+        .arg(&output)
+        .arg(&program)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(result.status.success(), "{}", stderr);
+    let timings: Vec<(&str, f64)> = stderr
+        .lines()
+        .skip_while(|line| *line != "Timings:")
+        .skip(1)
+        .map(|line| {
+            let (phase, ms) = line.trim().split_once(' ').unwrap();
+            let ms = ms.trim().strip_suffix("ms").unwrap();
+            (phase, ms.parse().unwrap())
+        })
+        .collect();
+    let phases: Vec<_> = timings.iter().map(|(phase, _)| *phase).collect();
+    assert_eq!(
+        phases,
+        vec![
+            "parse",
+            "interpret",
+            "infer-fields",
+            "fold",
+            "codegen",
+            "link",
+            "total"
+        ],
+        "{}",
+        stderr
+    );
+    assert!(timings.iter().all(|(_, ms)| *ms >= 0.0));
+}
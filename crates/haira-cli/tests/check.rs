@@ -0,0 +1,82 @@
+//! End-to-end tests for `haira check`.
+
+use std::process::Command;
+
+#[test]
+fn test_check_directory_reports_files_in_order() {
+    let dir = std::env::temp_dir().join(format!("haira_check_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    let files = [
+        ("a.haira", "x = 1\n"),
+        ("b.haira", "print(\"unclosed\"\n"),
+        ("c.haira", "y = 2\n"),
+        ("d.haira", "f(a {\n}\n"),
+        ("nested/e.haira", "z = 3\n"),
+        ("notes.txt", "not haira (\n"),
+    ];
+    for (name, source) in files {
+        std::fs::write(dir.join(name), source).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .arg("check")
+        .arg(&dir)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let checked: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Checking: "))
+        .map(|path| path.strip_prefix(dir.to_str().unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        checked,
+        [
+            "/a.haira",
+            "/b.haira",
+            "/c.haira",
+            "/d.haira",
+            "/nested/e.haira"
+        ]
+    );
+
+    // Each file's diagnostics directly follow its own header, with a snippet
+    // of the offending line.
+    let sections: Vec<_> = stdout.split("Checking: ").skip(1).collect();
+    assert!(sections[0].ends_with("  ok\n"));
+    assert!(sections[1].contains("b.haira:2:1"));
+    assert!(sections[1].contains("print(\"unclosed\""));
+    assert!(sections[2].ends_with("  ok\n"));
+    assert!(sections[3].contains("f(a {"));
+    assert!(sections[4].starts_with(&format!("{}/nested/e.haira\n  ok\n", dir.display())));
+    assert!(stdout.contains("Check complete: "));
+    assert!(!stdout.contains("Check complete: 0 error(s)"));
+}
+
+#[test]
+fn test_check_reports_private_import() {
+    let dir = std::env::temp_dir().join(format!("haira_check_import_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("main.haira"),
+        "import \"util\"\n\nprint(double(21))\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("util.haira"), "double(n) {\n    return n * 2\n}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .arg("check")
+        .arg(dir.join("main.haira"))
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("function `double` is private to another module"));
+    assert!(stdout.contains("print(double(21))"));
+    assert!(stdout.contains("Check complete: 1 error(s)"));
+}
@@ -0,0 +1,127 @@
+//! End-to-end tests for `haira interpret`.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Interpret `get_user_name` with the mock backend in `dir`.
+fn interpret(dir: &Path, extra: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_haira"))
+        .args(["interpret", "get_user_name", "--mock-ai"])
+        .args(extra)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("haira_interpret_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_interpret_formats() {
+    let dir = temp_dir("formats");
+    let cir = interpret(&dir, &["--format", "cir"]);
+    let hif = interpret(&dir, &["--format", "hif"]);
+    let haira = interpret(&dir, &["--format", "haira"]);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(cir.status.success());
+    let stdout = String::from_utf8_lossy(&cir.stdout);
+    let json = &stdout[stdout.find("Generated CIR:\n").unwrap() + 15..];
+    let func: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(func["name"], "get_user_name");
+
+    assert!(hif.status.success());
+    let stdout = String::from_utf8_lossy(&hif.stdout);
+    assert!(stdout.contains("Generated HIF:\n"), "{}", stdout);
+    assert!(stdout.contains("intent get_user_name @"), "{}", stdout);
+
+    assert!(haira.status.success());
+    let stdout = String::from_utf8_lossy(&haira.stdout);
+    assert!(
+        stdout.contains("Generated Haira:\nget_user_name() {\n    return none\n}\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_interpret_save() {
+    let dir = temp_dir("save");
+    let output = interpret(&dir, &["--save"]);
+    let saved = std::fs::read_to_string(dir.join("haira.hif"));
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(output.status.success());
+    assert!(saved.unwrap().contains("intent get_user_name @"));
+}
+
+const CONTEXT: &str = r#"{
+    "types_in_scope": [{ "name": "User", "fields": [] }],
+    "call_site": { "file": "main.haira", "line": 3, "arguments": [] }
+}"#;
+
+#[test]
+fn test_interpret_inline_context() {
+    let dir = temp_dir("inline_context");
+    let output = interpret(&dir, &["--context-json", CONTEXT]);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Context: 1 type(s) in scope, called from main.haira:3"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_interpret_context_file() {
+    let dir = temp_dir("context_file");
+    std::fs::write(dir.join("context.json"), CONTEXT).unwrap();
+    let output = interpret(&dir, &["--context", "context.json"]);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Context: 1 type(s) in scope, called from main.haira:3"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_interpret_without_context() {
+    let dir = temp_dir("no_context");
+    let output = interpret(&dir, &[]);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Context: 0 type(s) in scope, called from <interpret>:1"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_interpret_invalid_context_names_the_field() {
+    let dir = temp_dir("invalid_context");
+    let json =
+        r#"{ "types_in_scope": [], "call_site": { "file": "a", "line": "x", "arguments": [] } }"#;
+    let output = interpret(&dir, &["--context-json", json]);
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid context JSON at `call_site`"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,34 @@
+//! End-to-end tests for `haira lex`.
+
+use std::process::Command;
+
+#[test]
+fn test_lex_json() {
+    let dir = std::env::temp_dir().join(format!("haira_lex_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main.haira");
+    std::fs::write(&program, "x = \"hi\" + 1 $").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .args(["lex", "--json"])
+        .arg(&program)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    // The stray `$` is a lexer error, reported in the stream
+    assert!(!output.status.success());
+    let tokens: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        tokens,
+        serde_json::json!([
+            { "kind": { "Ident": "x" }, "span": { "start": 0, "end": 1 }, "text": "x" },
+            { "kind": "Eq", "span": { "start": 2, "end": 3 }, "text": "=" },
+            { "kind": { "String": "hi" }, "span": { "start": 4, "end": 8 }, "text": "\"hi\"" },
+            { "kind": "Plus", "span": { "start": 9, "end": 10 }, "text": "+" },
+            { "kind": { "Int": 1 }, "span": { "start": 11, "end": 12 }, "text": "1" },
+            { "error": "unexpected character", "span": { "start": 13, "end": 14 } },
+            { "kind": "Eof", "span": { "start": 14, "end": 14 }, "text": "" },
+        ])
+    );
+}
@@ -0,0 +1,73 @@
+//! End-to-end tests for `haira run`.
+
+use haira_codegen::testing::runtime_library;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Write `source` to a fresh file in the temp directory.
+fn write_program(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}_{}.haira", name, std::process::id()));
+    std::fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn test_run_propagates_exit_code() {
+    let program = write_program(
+        "exit_code",
+        "print(\"before exit\")\nexit(3)\nprint(\"after\")\n",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .arg("run")
+        .arg(&program)
+        .env("HAIRA_RUNTIME_LIB", runtime_library())
+        .output()
+        .unwrap();
+    std::fs::remove_file(&program).ok();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before exit\n");
+}
+
+#[test]
+fn test_run_does_not_execute_on_build_failure() {
+    let program = write_program("parse_error", "print(\"never\"\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .arg("run")
+        .arg(&program)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&program).ok();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_run_program_importing_another_file() {
+    let dir = std::env::temp_dir().join(format!("haira_run_import_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("main.haira"),
+        "import \"util\"\n\nprint(double(21))\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("util.haira"),
+        "public double(n) {\n    return n * 2\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haira"))
+        .arg("run")
+        .arg(dir.join("main.haira"))
+        .env("HAIRA_RUNTIME_LIB", runtime_library())
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+}
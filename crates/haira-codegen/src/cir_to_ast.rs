@@ -66,6 +66,7 @@ pub fn cir_to_function_def(cir: &CIRFunction) -> Result<FunctionDef, ConversionE
             statements,
             span: dummy_span(),
         },
+        doc: None,
     })
 }
 
@@ -92,6 +93,7 @@ pub fn cir_types_to_ast(types: &[TypeDefinition]) -> Result<Vec<TypeDef>, Conver
                 is_public: false,
                 name: Spanned::new(SmolStr::from(&t.name), dummy_span()),
                 fields,
+                doc: None,
             })
         })
         .collect()
@@ -614,6 +616,7 @@ fn make_assignment(target: &str, value: Expr) -> Spanned<StatementKind> {
                 ty: None,
             }],
             value,
+            op: None,
         }),
         dummy_span(),
     )
@@ -2,18 +2,107 @@
 
 #![allow(clippy::result_large_err)]
 
+use cranelift::codegen::ir::{InstructionData, Opcode, ValueDef};
 use cranelift::prelude::*;
 use cranelift_module::{DataDescription, FuncId, Linkage, Module};
 use cranelift_object::{ObjectBuilder, ObjectModule};
+use haira_ast::visit::{walk_expr, walk_stmt, Visitor};
 use haira_ast::{
-    AssignPath, BinaryOp, Block, Expr, ExprKind, Item, ItemKind, Literal, MethodDef, SourceFile,
-    Statement, StatementKind, TypeDef, UnaryOp,
+    AiBlock, AssignPath, BinaryOp, Block, EnumDef, Expr, ExprKind, IfStatement, ItemKind, Literal,
+    MethodDef, SourceFile, Span, Statement, StatementKind, TypeDef, UnaryOp,
 };
+use indexmap::IndexMap;
 use smol_str::SmolStr;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::Path;
 use std::process::Command;
 
+/// Offset of the element count in a list's `HairaArray` header.
+const LIST_LEN_OFFSET: i32 = 0;
+
+/// Offset of the data pointer in a list's `HairaArray` header.
+const LIST_DATA_OFFSET: i32 = 16;
+
+/// Size of the runtime's `HairaString`: `{ data: *mut u8, len: i64, cap: i64 }`.
+const HAIRA_STRING_SIZE: i64 = 24;
+
+/// Offset of the data pointer in a `HairaString`.
+const HAIRA_STRING_DATA_OFFSET: i32 = 0;
+
+/// Offset of the byte length in a `HairaString`.
+const HAIRA_STRING_LEN_OFFSET: i32 = 8;
+
+/// Offset of the capacity in a `HairaString`.
+const HAIRA_STRING_CAP_OFFSET: i32 = 16;
+
+// Delimiters printed around struct fields and enum payloads. They go through
+// `define_string` like any literal, so each is emitted once per module.
+
+/// Between struct fields, enum payload values and tuple elements.
+const SEPARATOR: &str = ", ";
+
+/// After the last field of a struct.
+const STRUCT_CLOSE: &str = " }";
+
+/// Around string values nested in structs, enums and tuples.
+const QUOTE: &str = "\"";
+
+/// Before an enum payload or the elements of a tuple.
+const PAYLOAD_OPEN: &str = "(";
+
+/// After an enum payload or the elements of a tuple.
+const PAYLOAD_CLOSE: &str = ")";
+
+/// The type of an `if` whose branches have types `a` and `b`: float if either
+/// is float, or None if they can't be unified.
+fn unify_branch_types(a: &ValueType, b: &ValueType) -> Option<ValueType> {
+    match (a, b) {
+        _ if a == b => Some(a.clone()),
+        (ValueType::Float, ValueType::Int | ValueType::Bool)
+        | (ValueType::Int | ValueType::Bool, ValueType::Float) => Some(ValueType::Float),
+        (ValueType::Int, ValueType::Bool) | (ValueType::Bool, ValueType::Int) => {
+            Some(ValueType::Int)
+        }
+        _ => None,
+    }
+}
+
+/// How a string comparison tests the runtime's result against zero:
+/// `haira_string_eq`'s 0/1 for equality and `haira_string_cmp`'s -1/0/1 for
+/// orderings. None for operators that don't compare.
+fn string_comparison(op: &BinaryOp) -> Option<IntCC> {
+    match op {
+        BinaryOp::Eq => Some(IntCC::NotEqual),
+        BinaryOp::Ne => Some(IntCC::Equal),
+        BinaryOp::Lt => Some(IntCC::SignedLessThan),
+        BinaryOp::Le => Some(IntCC::SignedLessThanOrEqual),
+        BinaryOp::Gt => Some(IntCC::SignedGreaterThan),
+        BinaryOp::Ge => Some(IntCC::SignedGreaterThanOrEqual),
+        _ => None,
+    }
+}
+
+/// The error for an unannotated `return a, b` whose value at `position`
+/// has types that differ between returns.
+fn unknown_return_type(function: &str, position: usize) -> CodegenError {
+    CodegenError::TypeMismatch(format!(
+        "can't infer the type of value {} returned by {}; annotate its return type, as in `-> (int, string)`",
+        position + 1,
+        function
+    ))
+}
+
+/// The error for a `??` reaching the operators that take evaluated operands;
+/// it is compiled as control flow by `compile_default`.
+fn evaluated_default_operands() -> CodegenError {
+    CodegenError::Unsupported("`??` with evaluated operands".to_string())
+}
+
+/// Offset of element `index` in a tuple, after its length word.
+fn tuple_offset(index: usize) -> i32 {
+    8 * (index as i32 + 1)
+}
+
 /// Information about a struct type.
 #[derive(Debug, Clone)]
 struct StructInfo {
@@ -27,6 +116,143 @@ struct StructInfo {
     size: usize,
 }
 
+impl StructInfo {
+    /// Compute the layout of a struct type definition.
+    ///
+    /// `struct_names` holds every type defined in the file, so fields can
+    /// refer to structs declared later on.
+    fn from_type_def(type_def: &TypeDef, struct_names: &HashSet<SmolStr>) -> Self {
+        let mut fields = Vec::new();
+        let mut field_types = Vec::new();
+        let mut field_offsets = Vec::new();
+        let mut offset = 0;
+
+        for field in &type_def.fields {
+            fields.push(field.name.node.clone());
+            // Infer type from type annotation if present, otherwise default to Ptr
+            // (since strings are common and we can't know without type inference)
+            let field_type = if let Some(ref ty) = field.ty {
+                match &ty.node {
+                    haira_ast::Type::Named(name) => match name.as_str() {
+                        "int" | "i64" | "i32" | "i16" | "i8" => ValueType::Int,
+                        "float" | "f64" | "f32" => ValueType::Float,
+                        "bool" => ValueType::Bool,
+                        "string" | "str" => ValueType::Ptr,
+                        _ if struct_names.contains(name) => ValueType::Struct(name.clone()),
+                        _ => ValueType::Ptr, // Default to Ptr for unknown types
+                    },
+                    _ => ValueType::Ptr,
+                }
+            } else {
+                // No type annotation - default to Int (most common for untyped fields)
+                ValueType::Int
+            };
+            field_types.push(field_type);
+            field_offsets.push(offset);
+            // All fields are 8 bytes (i64 or f64 or ptr)
+            offset += 8;
+        }
+
+        StructInfo {
+            fields,
+            field_types,
+            field_offsets,
+            size: offset,
+        }
+    }
+}
+
+/// Information about an enum type.
+#[derive(Debug, Clone)]
+struct EnumInfo {
+    /// Variants in declaration order; a variant's index is its tag.
+    variants: Vec<VariantInfo>,
+}
+
+/// Information about an enum variant.
+///
+/// Enum values are heap allocated as a tag word followed by one 8-byte
+/// word per payload field.
+#[derive(Debug, Clone)]
+struct VariantInfo {
+    /// Variant name.
+    name: SmolStr,
+    /// Payload field types, in order.
+    field_types: Vec<ValueType>,
+}
+
+/// Names of all struct types defined in a source file.
+fn struct_names(ast: &SourceFile) -> HashSet<SmolStr> {
+    ast.items
+        .iter()
+        .filter_map(|item| match &item.node {
+            ItemKind::TypeDef(type_def) => Some(type_def.name.node.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The memory layout of a struct type, as laid out by the compiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+    /// Struct type name.
+    pub name: String,
+    /// Fields in declaration order.
+    pub fields: Vec<FieldLayout>,
+    /// Total size of the struct in bytes.
+    pub size: usize,
+}
+
+/// The layout of a single struct field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// Field name.
+    pub name: String,
+    /// Inferred value type (`int`, `float`, `string`, a struct name, or `[elem]`).
+    pub ty: String,
+    /// Byte offset from the start of the struct.
+    pub offset: usize,
+}
+
+/// Compute the layout of every struct defined in `ast`, in source order.
+pub fn struct_layouts(ast: &SourceFile) -> Vec<StructLayout> {
+    let struct_names = struct_names(ast);
+    ast.items
+        .iter()
+        .filter_map(|item| match &item.node {
+            ItemKind::TypeDef(type_def) => Some(type_def),
+            _ => None,
+        })
+        .map(|type_def| {
+            let info = StructInfo::from_type_def(type_def, &struct_names);
+            StructLayout {
+                name: type_def.name.node.to_string(),
+                fields: info
+                    .fields
+                    .iter()
+                    .zip(&info.field_types)
+                    .zip(&info.field_offsets)
+                    .map(|((name, ty), &offset)| FieldLayout {
+                        name: name.to_string(),
+                        ty: ty.to_string(),
+                        offset,
+                    })
+                    .collect(),
+                size: info.size,
+            }
+        })
+        .collect()
+}
+
+/// A non-fatal problem found during code generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenWarning {
+    /// Human-readable description.
+    pub message: String,
+    /// Source range the warning refers to.
+    pub span: Span,
+}
+
 /// Code generation options.
 #[derive(Default, Clone)]
 pub struct CodegenOptions {
@@ -36,6 +262,54 @@ pub struct CodegenOptions {
     pub debug_info: bool,
     /// Target triple (e.g., "x86_64-unknown-linux-gnu").
     pub target: Option<String>,
+    /// Write the Cranelift IR of every function next to the output (`.clif`).
+    pub emit_clif: bool,
+    /// Write the machine code of every function next to the output (`.s`).
+    pub emit_asm: bool,
+    /// Keep the object file (`.o`) next to the output.
+    pub emit_object: bool,
+    /// Stop after writing the object file instead of linking an executable.
+    pub skip_link: bool,
+    /// Linker to invoke instead of `$HAIRA_LINKER`, or `cc` when unset.
+    pub linker: Option<String>,
+    /// Extra arguments passed to the linker after the default ones.
+    pub link_args: Vec<String>,
+    /// Runtime library to link instead of `$HAIRA_RUNTIME_LIB`, or the one
+    /// found next to the compiler or in the build directory.
+    pub runtime_lib: Option<std::path::PathBuf>,
+    /// Abort on integer overflow in `+`, `-` and `*` instead of wrapping.
+    pub overflow_checks: bool,
+    /// Abort with a "stack overflow" message when a thread's stack grows
+    /// past this many bytes, checked at every function entry.
+    pub stack_guard: Option<u64>,
+    /// Abort when a list index is negative or past the end, instead of
+    /// reading or writing outside the list.
+    pub bounds_checks: bool,
+}
+
+impl CodegenOptions {
+    /// Whether the target is `wasm32-unknown-unknown` (or another wasm32 triple).
+    pub fn is_wasm(&self) -> bool {
+        self.target
+            .as_deref()
+            .is_some_and(|target| target.starts_with("wasm32"))
+    }
+
+    /// Whether the target's architecture or operating system differs from
+    /// the host's, so the host toolchain can't link for it.
+    pub fn is_cross(&self) -> bool {
+        let Some(target) = self.target.as_deref() else {
+            return false;
+        };
+        let host = target_lexicon::Triple::host();
+        match target.parse::<target_lexicon::Triple>() {
+            Ok(triple) => {
+                triple.architecture != host.architecture
+                    || triple.operating_system != host.operating_system
+            }
+            Err(_) => true,
+        }
+    }
 }
 
 /// Code generation error.
@@ -55,15 +329,123 @@ pub enum CodegenError {
     UndefinedFunction(String),
     #[error("Undefined variable: {0}")]
     UndefinedVariable(String),
+    #[error("Invalid call: {0}")]
+    InvalidCall(String),
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(String),
+    #[error("Non-exhaustive match: {0}")]
+    NonExhaustiveMatch(String),
+    #[error("Duplicate definition: {0}")]
+    DuplicateDefinition(String),
+    #[error("Type mismatch: {0}")]
+    TypeMismatch(String),
+    #[error("Non-constant value: {0}")]
+    NonConstant(String),
 }
 
 /// Function signature for type tracking.
 #[derive(Debug, Clone)]
 struct FuncSignature {
-    /// Parameter types (true = F64, false = I64)
-    params_are_float: Vec<bool>,
-    /// Return type (true = F64, false = I64)
-    returns_float: bool,
+    /// Parameter types, in order
+    params: Vec<ValueType>,
+    /// Return type
+    returns: ValueType,
+    /// Whether the last parameter is a rest parameter, a list collecting
+    /// the trailing arguments of each call
+    rest: bool,
+    /// Parameter names, for named arguments (empty for runtime functions)
+    names: Vec<SmolStr>,
+    /// Default values of parameters that have one
+    defaults: Vec<Option<Expr>>,
+}
+
+impl FuncSignature {
+    /// Match the arguments of a call to this function's parameters.
+    ///
+    /// Positional arguments fill the parameters in order, skipping any
+    /// already given by name, and named arguments fill the parameter of
+    /// that name. Parameters left unfilled take their default value.
+    /// Returns one expression per fixed parameter, followed by the
+    /// arguments collected into the rest parameter.
+    fn bind_args<'e>(
+        &'e self,
+        func_name: &str,
+        args: &'e [haira_ast::Argument],
+    ) -> Result<(Vec<&'e Expr>, Vec<&'e Expr>), CodegenError> {
+        let fixed = self.params.len() - usize::from(self.rest);
+        let mut slots: Vec<Option<&Expr>> = vec![None; fixed];
+        let mut rest = Vec::new();
+        let mut next = 0;
+
+        for arg in args {
+            let index = match &arg.name {
+                Some(name) => self.names[..fixed.min(self.names.len())]
+                    .iter()
+                    .position(|param| *param == name.node)
+                    .ok_or_else(|| {
+                        CodegenError::InvalidCall(format!(
+                            "{} has no parameter named `{}`",
+                            func_name, name.node
+                        ))
+                    })?,
+                None => {
+                    while next < fixed && slots[next].is_some() {
+                        next += 1;
+                    }
+                    if next == fixed {
+                        if self.rest {
+                            rest.push(&arg.value);
+                            continue;
+                        }
+                        return Err(CodegenError::InvalidCall(format!(
+                            "{} takes {} argument(s) but {} were given",
+                            func_name,
+                            fixed,
+                            args.len()
+                        )));
+                    }
+                    next
+                }
+            };
+            if slots[index].replace(&arg.value).is_some() {
+                return Err(CodegenError::InvalidCall(format!(
+                    "argument `{}` of {} is given more than once",
+                    self.names[index], func_name
+                )));
+            }
+        }
+
+        let bound = slots
+            .into_iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                slot.or_else(|| self.defaults.get(i)?.as_ref())
+                    .ok_or_else(|| {
+                        let param = self
+                            .names
+                            .get(i)
+                            .map_or_else(|| format!("{}", i + 1), |name| format!("`{}`", name));
+                        CodegenError::InvalidCall(format!(
+                            "missing argument {} in call to {}",
+                            param, func_name
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((bound, rest))
+    }
+
+    /// The signature of a method without its first parameter, the receiver,
+    /// for binding the arguments written at a call.
+    fn without_receiver(&self) -> FuncSignature {
+        FuncSignature {
+            params: self.params[1..].to_vec(),
+            returns: self.returns.clone(),
+            rest: self.rest,
+            names: self.names.iter().skip(1).cloned().collect(),
+            defaults: self.defaults.iter().skip(1).cloned().collect(),
+        }
+    }
 }
 
 /// Haira compiler using Cranelift.
@@ -75,38 +457,87 @@ pub struct Compiler {
     /// Cranelift context (reused).
     ctx: codegen::Context,
     /// Map of function names to their IDs.
-    functions: HashMap<SmolStr, FuncId>,
+    functions: IndexMap<SmolStr, FuncId>,
     /// Map of function names to their type signatures.
-    func_signatures: HashMap<SmolStr, FuncSignature>,
-    /// Map of string constants to their data IDs.
-    strings: HashMap<SmolStr, cranelift_module::DataId>,
+    func_signatures: IndexMap<SmolStr, FuncSignature>,
+    /// Map of string constants to their data IDs, in the order they were
+    /// first used, which numbers their `.str.N` symbols.
+    strings: IndexMap<SmolStr, cranelift_module::DataId>,
     /// Map of struct type names to their info.
-    structs: HashMap<SmolStr, StructInfo>,
+    structs: IndexMap<SmolStr, StructInfo>,
+    /// Map of enum type names to their info.
+    enums: IndexMap<SmolStr, EnumInfo>,
+    /// Values of the `const` items, substituted at each use.
+    constants: IndexMap<SmolStr, Literal>,
+    /// Methods that never use `self`, by symbol name, which can be called
+    /// as `Type.method(...)`.
+    static_methods: HashSet<SmolStr>,
     /// Pointer type for the target.
     ptr_type: Type,
     /// Counter for generating unique spawn function names.
     spawn_counter: usize,
-    /// Map of spawn block span start to their function names.
-    spawn_functions: HashMap<u32, SmolStr>,
-    /// Collected spawn blocks from AST (span start -> block).
-    spawn_blocks: Vec<(u32, Block)>,
+    /// Map of spawn blocks to their function names.
+    spawn_functions: IndexMap<BlockKey, SmolStr>,
+    /// Collected spawn blocks from AST.
+    spawn_blocks: Vec<(BlockKey, Block)>,
     /// Counter for generating unique async function names.
     async_counter: usize,
-    /// Map of async block span start to their function names (one per statement in block).
-    async_functions: HashMap<u32, Vec<SmolStr>>,
-    /// Collected async blocks from AST (span start -> block).
-    async_blocks: Vec<(u32, Block)>,
+    /// Map of async blocks to their function names (one per statement in block).
+    async_functions: IndexMap<BlockKey, Vec<SmolStr>>,
+    /// Collected async blocks from AST.
+    async_blocks: Vec<(BlockKey, Block)>,
+    /// Textual Cranelift IR of every defined function, when enabled.
+    clif: Option<String>,
+    /// Disassembly of each compiled function, when dumping is enabled.
+    asm: Option<String>,
+    /// Whether integer arithmetic traps on overflow.
+    overflow_checks: bool,
+    /// Stack limit in bytes checked at function entry, if guarded.
+    stack_guard: Option<u64>,
+    /// Whether list indexing traps out of bounds.
+    bounds_checks: bool,
+    /// Warnings collected while compiling.
+    warnings: Vec<CodegenWarning>,
+    /// Types of the values of each `return a, b` in the body being compiled,
+    /// while inferring its return type.
+    returned: Option<Vec<Vec<ValueType>>>,
 }
 
 impl Compiler {
-    /// Create a new compiler.
+    /// Create a new compiler that optimizes for speed.
     pub fn new() -> Result<Self, CodegenError> {
+        Self::with_opt_level(2)
+    }
+
+    /// Create a new compiler for the host at optimization level `opt_level`.
+    pub fn with_opt_level(opt_level: u8) -> Result<Self, CodegenError> {
+        Self::for_target(None, opt_level)
+    }
+
+    /// Create a new compiler emitting code for the `target` triple, or the
+    /// host when `None`, at optimization level `opt_level`: 0 doesn't
+    /// optimize, 1 and 2 optimize for speed, and 3 or above for speed and
+    /// size.
+    pub fn for_target(target: Option<&str>, opt_level: u8) -> Result<Self, CodegenError> {
+        let cranelift_level = match opt_level {
+            0 => "none",
+            1 | 2 => "speed",
+            _ => "speed_and_size",
+        };
         let mut flag_builder = settings::builder();
-        flag_builder.set("opt_level", "speed").unwrap();
-        flag_builder.set("is_pic", "true").unwrap();
+        flag_builder
+            .set("opt_level", cranelift_level)
+            .map_err(|e| CodegenError::CraneliftError(e.to_string()))?;
+        flag_builder
+            .set("is_pic", "true")
+            .map_err(|e| CodegenError::CraneliftError(e.to_string()))?;
 
-        let isa_builder =
-            cranelift_native::builder().map_err(|e| CodegenError::CraneliftError(e.to_string()))?;
+        let isa_builder = match target {
+            Some(target) => cranelift::codegen::isa::lookup_by_name(target)
+                .map_err(|e| CodegenError::Unsupported(format!("target '{}': {}", target, e)))?,
+            None => cranelift_native::builder()
+                .map_err(|e| CodegenError::CraneliftError(e.to_string()))?,
+        };
         let isa = isa_builder
             .finish(settings::Flags::new(flag_builder))
             .map_err(|e| CodegenError::CraneliftError(e.to_string()))?;
@@ -126,32 +557,40 @@ impl Compiler {
             module,
             builder_ctx: FunctionBuilderContext::new(),
             ctx: codegen::Context::new(),
-            functions: HashMap::new(),
-            func_signatures: HashMap::new(),
-            strings: HashMap::new(),
-            structs: HashMap::new(),
+            functions: IndexMap::new(),
+            func_signatures: IndexMap::new(),
+            strings: IndexMap::new(),
+            constants: IndexMap::new(),
+            structs: IndexMap::new(),
+            enums: IndexMap::new(),
+            static_methods: HashSet::new(),
             ptr_type,
             spawn_counter: 0,
-            spawn_functions: HashMap::new(),
+            spawn_functions: IndexMap::new(),
             spawn_blocks: Vec::new(),
             async_counter: 0,
-            async_functions: HashMap::new(),
+            async_functions: IndexMap::new(),
             async_blocks: Vec::new(),
+            clif: None,
+            asm: None,
+            overflow_checks: false,
+            stack_guard: None,
+            bounds_checks: false,
+            warnings: Vec::new(),
+            returned: None,
         })
     }
 
     /// Register a function signature for type tracking.
-    fn register_func_signature(
-        &mut self,
-        name: &str,
-        params_are_float: Vec<bool>,
-        returns_float: bool,
-    ) {
+    fn register_func_signature(&mut self, name: &str, params: Vec<ValueType>, returns: ValueType) {
         self.func_signatures.insert(
             SmolStr::from(name),
             FuncSignature {
-                params_are_float,
-                returns_float,
+                params,
+                returns,
+                rest: false,
+                names: Vec::new(),
+                defaults: Vec::new(),
             },
         );
     }
@@ -167,6 +606,41 @@ impl Compiler {
             .declare_function("haira_print", Linkage::Import, &sig)?;
         self.functions.insert(SmolStr::from("print"), print_id);
 
+        // haira_panic_overflow(op_ptr, op_len)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        let panic_overflow_id =
+            self.module
+                .declare_function("haira_panic_overflow", Linkage::Import, &sig)?;
+        self.functions
+            .insert(SmolStr::from("panic_overflow"), panic_overflow_id);
+
+        // haira_panic_index(index, len)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_panic_index", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("panic_index"), id);
+
+        // haira_check_stack(limit)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_check_stack", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("check_stack"), id);
+
+        // haira_panic_div_zero()
+        let sig = self.module.make_signature();
+        let panic_div_zero_id =
+            self.module
+                .declare_function("haira_panic_div_zero", Linkage::Import, &sig)?;
+        self.functions
+            .insert(SmolStr::from("panic_div_zero"), panic_div_zero_id);
+
         // haira_print_int(i64)
         let mut sig = self.module.make_signature();
         sig.params.push(AbiParam::new(types::I64));
@@ -185,9 +659,9 @@ impl Compiler {
         self.functions
             .insert(SmolStr::from("print_float"), print_float_id);
 
-        // haira_print_bool(i8)
+        // haira_print_bool(i64)
         let mut sig = self.module.make_signature();
-        sig.params.push(AbiParam::new(types::I8));
+        sig.params.push(AbiParam::new(types::I64));
         let print_bool_id =
             self.module
                 .declare_function("haira_print_bool", Linkage::Import, &sig)?;
@@ -218,6 +692,95 @@ impl Compiler {
             .declare_function("haira_free", Linkage::Import, &sig)?;
         self.functions.insert(SmolStr::from("free"), free_id);
 
+        // haira_array_new() -> HairaArray*
+        let mut sig = self.module.make_signature();
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_array_new", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("array_new"), id);
+
+        // haira_array_push(arr, value)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_array_push", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("array_push"), id);
+
+        // haira_array_pop(arr) -> value
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_array_pop", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("array_pop"), id);
+
+        // haira_array_get(arr, index) -> value
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_array_get", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("array_get"), id);
+
+        // haira_array_len(arr) -> i64
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_array_len", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("array_len"), id);
+
+        // haira_map_new() -> HairaMap*, also the `map()` builtin
+        let mut sig = self.module.make_signature();
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_map_new", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("map_new"), id);
+        self.functions.insert(SmolStr::from("map"), id);
+
+        // haira_map_set(map, key_ptr, key_len, value)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_map_set", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("map_set"), id);
+
+        // haira_map_get / haira_map_has / haira_map_remove(map, key_ptr, key_len) -> i64
+        for name in ["get", "has", "remove"] {
+            let mut sig = self.module.make_signature();
+            sig.params.push(AbiParam::new(self.ptr_type));
+            sig.params.push(AbiParam::new(self.ptr_type));
+            sig.params.push(AbiParam::new(types::I64));
+            sig.returns.push(AbiParam::new(types::I64));
+            let id = self.module.declare_function(
+                &format!("haira_map_{}", name),
+                Linkage::Import,
+                &sig,
+            )?;
+            self.functions
+                .insert(SmolStr::from(format!("map_{}", name)), id);
+        }
+
+        // haira_map_free(map)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_map_free", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("map_free"), id);
+
         // haira_string_concat(a_ptr, a_len, b_ptr, b_len) -> HairaString*
         let mut sig = self.module.make_signature();
         sig.params.push(AbiParam::new(self.ptr_type)); // a ptr
@@ -271,6 +834,24 @@ impl Compiler {
         self.functions
             .insert(SmolStr::from("set_error"), set_error_id);
 
+        // haira_set_error_message(message)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type)); // HairaString*
+        let id = self
+            .module
+            .declare_function("haira_set_error_message", Linkage::Import, &sig)?;
+        self.functions
+            .insert(SmolStr::from("set_error_message"), id);
+
+        // haira_get_error_message() -> HairaString*
+        let mut sig = self.module.make_signature();
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_get_error_message", Linkage::Import, &sig)?;
+        self.functions
+            .insert(SmolStr::from("get_error_message"), id);
+
         // haira_get_error() -> i64
         let mut sig = self.module.make_signature();
         sig.returns.push(AbiParam::new(types::I64));
@@ -451,6 +1032,30 @@ impl Compiler {
             .declare_function("haira_string_contains", Linkage::Import, &sig)?;
         self.functions.insert(SmolStr::from("contains"), id);
 
+        // haira_string_eq(ptr, len, other_ptr, other_len) -> i64
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_string_eq", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("string_eq"), id);
+
+        // haira_string_cmp(ptr, len, other_ptr, other_len) -> i64 (-1, 0 or 1)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_string_cmp", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("string_cmp"), id);
+
         // haira_string_starts_with(ptr, len, prefix_ptr, prefix_len) -> i64
         let mut sig = self.module.make_signature();
         sig.params.push(AbiParam::new(self.ptr_type));
@@ -487,6 +1092,29 @@ impl Compiler {
             .declare_function("haira_string_index_of", Linkage::Import, &sig)?;
         self.functions.insert(SmolStr::from("index_of"), id);
 
+        // haira_string_split(ptr, len, sep_ptr, sep_len) -> HairaArray* of HairaString*
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_string_split", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("split"), id);
+
+        // haira_string_join(arr, sep_ptr, sep_len) -> HairaString*
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_string_join", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("join"), id);
+
         // haira_string_replace(ptr, len, old_ptr, old_len, new_ptr, new_len) -> HairaString*
         let mut sig = self.module.make_signature();
         sig.params.push(AbiParam::new(self.ptr_type));
@@ -830,23 +1458,101 @@ impl Compiler {
         self.functions.insert(SmolStr::from("time_monotonic"), id);
 
         // ====================================================================
-        // Standard Library - Testing Functions
+        // Standard Library - JSON Functions
         // ====================================================================
+        //
+        // JSON values are opaque handles owned by the program. They are only
+        // ever passed back to these functions; a null handle marks a failed
+        // operation, which also sets the error flag.
 
-        // haira_test_start(name_ptr, name_len)
+        // haira_json_parse(str_ptr, str_len) -> handle
         let mut sig = self.module.make_signature();
         sig.params.push(AbiParam::new(self.ptr_type));
         sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(self.ptr_type));
         let id = self
             .module
-            .declare_function("haira_test_start", Linkage::Import, &sig)?;
-        self.functions.insert(SmolStr::from("test_start"), id);
+            .declare_function("haira_json_parse", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("json_parse"), id);
 
-        // haira_test_pass()
-        let sig = self.module.make_signature();
-        let id = self
-            .module
-            .declare_function("haira_test_pass", Linkage::Import, &sig)?;
+        // haira_json_get_field(handle, key_ptr, key_len) -> handle
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_json_get_field", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("json_get_field"), id);
+
+        // haira_json_as_int(handle) -> i64
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_json_as_int", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("json_as_int"), id);
+
+        // haira_json_as_string(handle) -> HairaString*
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_json_as_string", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("json_as_string"), id);
+
+        // haira_json_stringify(handle) -> HairaString*
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_json_stringify", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("json_stringify"), id);
+
+        // haira_json_free(handle)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_json_free", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("json_free"), id);
+
+        // ====================================================================
+        // Standard Library - HTTP Functions
+        // ====================================================================
+
+        // haira_http_get(url_ptr, url_len) -> HairaString*
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        sig.returns.push(AbiParam::new(self.ptr_type));
+        let id = self
+            .module
+            .declare_function("haira_http_get", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("http_get"), id);
+
+        // ====================================================================
+        // Standard Library - Testing Functions
+        // ====================================================================
+
+        // haira_test_start(name_ptr, name_len)
+        let mut sig = self.module.make_signature();
+        sig.params.push(AbiParam::new(self.ptr_type));
+        sig.params.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_function("haira_test_start", Linkage::Import, &sig)?;
+        self.functions.insert(SmolStr::from("test_start"), id);
+
+        // haira_test_pass()
+        let sig = self.module.make_signature();
+        let id = self
+            .module
+            .declare_function("haira_test_pass", Linkage::Import, &sig)?;
         self.functions.insert(SmolStr::from("test_pass"), id);
 
         // haira_test_fail(msg_ptr, msg_len)
@@ -950,16 +1656,20 @@ impl Compiler {
             "sqrt", "log", "log10", "exp", "sin", "cos", "tan", "asin", "acos", "atan", "floor",
             "ceil", "round",
         ] {
-            self.register_func_signature(name, vec![true], true);
+            self.register_func_signature(name, vec![ValueType::Float], ValueType::Float);
         }
         // Two float params, float return
         for name in &["pow", "atan2"] {
-            self.register_func_signature(name, vec![true, true], true);
+            self.register_func_signature(
+                name,
+                vec![ValueType::Float, ValueType::Float],
+                ValueType::Float,
+            );
         }
         // random_float takes no params, returns float
-        self.register_func_signature("random_float", vec![], true);
+        self.register_func_signature("random_float", vec![], ValueType::Float);
         // print_float takes float param
-        self.register_func_signature("print_float", vec![true], false);
+        self.register_func_signature("print_float", vec![ValueType::Float], ValueType::Int);
 
         // ====================================================================
         // Standard Library - Regex Functions
@@ -1034,57 +1744,96 @@ impl Compiler {
     }
 
     /// Register a struct type definition.
-    fn register_struct(&mut self, type_def: &TypeDef) {
-        let mut fields = Vec::new();
-        let mut field_types = Vec::new();
-        let mut field_offsets = Vec::new();
-        let mut offset = 0;
+    fn register_struct(&mut self, type_def: &TypeDef, struct_names: &HashSet<SmolStr>) {
+        let info = StructInfo::from_type_def(type_def, struct_names);
+        self.structs.insert(type_def.name.node.clone(), info);
+    }
 
-        for field in &type_def.fields {
-            fields.push(field.name.node.clone());
-            // Infer type from type annotation if present, otherwise default to Ptr
-            // (since strings are common and we can't know without type inference)
-            let field_type = if let Some(ref ty) = field.ty {
-                match &ty.node {
-                    haira_ast::Type::Named(name) => match name.as_str() {
-                        "int" | "i64" | "i32" | "i16" | "i8" => ValueType::Int,
-                        "float" | "f64" | "f32" => ValueType::Float,
-                        "string" | "str" => ValueType::Ptr,
-                        _ => ValueType::Ptr, // Default to Ptr for unknown/struct types
-                    },
-                    _ => ValueType::Ptr,
+    /// Register every enum type definition.
+    ///
+    /// Variants are constructed and matched by bare name, so a variant name
+    /// may only be defined once per file.
+    fn register_enums(&mut self, ast: &SourceFile) -> Result<(), CodegenError> {
+        let enum_defs: Vec<&EnumDef> = ast
+            .items
+            .iter()
+            .filter_map(|item| match &item.node {
+                ItemKind::EnumDef(enum_def) => Some(enum_def),
+                _ => None,
+            })
+            .collect();
+
+        // Register the names first so payloads can refer to any enum
+        for enum_def in &enum_defs {
+            self.enums.insert(
+                enum_def.name.node.clone(),
+                EnumInfo {
+                    variants: Vec::new(),
+                },
+            );
+        }
+
+        let mut owners: HashMap<SmolStr, SmolStr> = HashMap::new();
+        for enum_def in &enum_defs {
+            let mut variants = Vec::new();
+            for variant in &enum_def.variants {
+                if let Some(owner) =
+                    owners.insert(variant.name.node.clone(), enum_def.name.node.clone())
+                {
+                    return Err(CodegenError::DuplicateDefinition(format!(
+                        "variant {} is defined by both {} and {}",
+                        variant.name.node, owner, enum_def.name.node
+                    )));
                 }
-            } else {
-                // No type annotation - default to Int (most common for untyped fields)
-                ValueType::Int
-            };
-            field_types.push(field_type);
-            field_offsets.push(offset);
-            // All fields are 8 bytes (i64 or f64 or ptr)
-            offset += 8;
+                let field_types = variant
+                    .fields
+                    .iter()
+                    .map(|ty| ValueType::from_annotation(&ty.node, &self.structs, &self.enums))
+                    .collect();
+                variants.push(VariantInfo {
+                    name: variant.name.node.clone(),
+                    field_types,
+                });
+            }
+            self.enums
+                .insert(enum_def.name.node.clone(), EnumInfo { variants });
         }
 
-        let info = StructInfo {
-            fields,
-            field_types,
-            field_offsets,
-            size: offset,
-        };
+        Ok(())
+    }
 
-        self.structs.insert(type_def.name.node.clone(), info);
+    /// Register the value of every `const` item.
+    ///
+    /// Constants have no runtime storage: each use compiles to the value.
+    fn register_constants(&mut self, ast: &SourceFile) -> Result<(), CodegenError> {
+        self.constants = haira_ast::const_values(ast).into_iter().collect();
+        for item in &ast.items {
+            if let ItemKind::Const(const_def) = &item.node {
+                if !self.constants.contains_key(&const_def.name.node) {
+                    return Err(CodegenError::NonConstant(format!(
+                        "constant {} must be a literal or operators over constants",
+                        const_def.name.node
+                    )));
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Compile the AST.
     pub fn compile(&mut self, ast: &SourceFile) -> Result<(), CodegenError> {
         // Declare runtime functions
         self.declare_runtime_functions()?;
+        self.register_constants(ast)?;
 
         // First pass: register all struct types
+        let struct_names = struct_names(ast);
         for item in &ast.items {
             if let ItemKind::TypeDef(type_def) = &item.node {
-                self.register_struct(type_def);
+                self.register_struct(type_def, &struct_names);
             }
         }
+        self.register_enums(ast)?;
 
         // Collect all spawn blocks from the AST
         self.collect_spawn_blocks(ast);
@@ -1094,40 +1843,86 @@ impl Compiler {
             if let ItemKind::FunctionDef(func) = &item.node {
                 let mut sig = self.module.make_signature();
 
-                // Add parameters
-                for _param in &func.params {
-                    // For now, assume all params are i64 (or pointer for structs)
-                    sig.params.push(AbiParam::new(types::I64));
-                }
+                let (params, rest) = self.param_types(&func.params, &func.name.node)?;
+                // `return a, b` packs its values into a tuple, whose element
+                // types are inferred once every function is declared
+                let returns = match (&func.return_ty, return_arity(&func.body)) {
+                    (None, Some(arity)) => ValueType::Tuple(vec![ValueType::Int; arity]),
+                    (Some(ty), _) => {
+                        ValueType::from_annotation(&ty.node, &self.structs, &self.enums)
+                    }
+                    (None, None) => ValueType::Int,
+                };
 
-                // Return type (assume i64 for now)
-                sig.returns.push(AbiParam::new(types::I64));
+                for ty in &params {
+                    sig.params.push(AbiParam::new(ty.cranelift_type()));
+                }
+                sig.returns.push(AbiParam::new(returns.cranelift_type()));
 
                 let id =
                     self.module
                         .declare_function(func.name.node.as_str(), Linkage::Export, &sig)?;
                 self.functions.insert(func.name.node.clone(), id);
+                self.func_signatures.insert(
+                    func.name.node.clone(),
+                    FuncSignature {
+                        params,
+                        returns,
+                        rest,
+                        names: func.params.iter().map(|p| p.name.node.clone()).collect(),
+                        defaults: func.params.iter().map(|p| p.default.clone()).collect(),
+                    },
+                );
             }
 
             if let ItemKind::MethodDef(method) = &item.node {
                 let mut sig = self.module.make_signature();
 
-                // First parameter is self (pointer to struct)
-                sig.params.push(AbiParam::new(self.ptr_type));
+                // Method name: TypeName_methodName
+                let method_full_name = format!("{}_{}", method.type_name.node, method.name.node);
+                let self_ty = if self.enums.contains_key(&method.type_name.node) {
+                    ValueType::Enum(method.type_name.node.clone())
+                } else {
+                    ValueType::Struct(method.type_name.node.clone())
+                };
 
-                // Add other parameters
-                for _param in &method.params {
-                    sig.params.push(AbiParam::new(types::I64));
+                // First parameter is self (pointer to struct), then the declared ones
+                let (method_params, rest) = self.param_types(&method.params, &method_full_name)?;
+                let mut params = vec![self_ty];
+                params.extend(method_params);
+                for ty in &params {
+                    sig.params.push(AbiParam::new(ty.cranelift_type()));
                 }
+                let mut names = vec![SmolStr::from("self")];
+                names.extend(method.params.iter().map(|p| p.name.node.clone()));
 
-                // Return type (assume i64 for now)
-                sig.returns.push(AbiParam::new(types::I64));
-
-                // Method name: TypeName_methodName
-                let method_full_name = format!("{}_{}", method.type_name.node, method.name.node);
+                // Unannotated returns are ints, or a tuple for `return a, b`
+                let returns = match (&method.return_ty, return_arity(&method.body)) {
+                    (Some(ty), _) => {
+                        ValueType::from_annotation(&ty.node, &self.structs, &self.enums)
+                    }
+                    (None, Some(arity)) => ValueType::Tuple(vec![ValueType::Int; arity]),
+                    (None, None) => ValueType::Int,
+                };
+                sig.returns.push(AbiParam::new(returns.cranelift_type()));
+                let mut defaults = vec![None];
+                defaults.extend(method.params.iter().map(|p| p.default.clone()));
+                self.func_signatures.insert(
+                    SmolStr::from(&method_full_name),
+                    FuncSignature {
+                        params,
+                        returns,
+                        rest,
+                        names,
+                        defaults,
+                    },
+                );
                 let id = self
                     .module
                     .declare_function(&method_full_name, Linkage::Export, &sig)?;
+                if !uses_self(&method.body) {
+                    self.static_methods.insert(SmolStr::from(&method_full_name));
+                }
                 self.functions.insert(SmolStr::from(&method_full_name), id);
             }
         }
@@ -1138,13 +1933,15 @@ impl Compiler {
         // Declare async block functions (no params, returns i64)
         self.declare_async_functions()?;
 
+        self.infer_return_types(ast)?;
+
         // Third pass: compile function and method bodies
-        for item in &ast.items {
+        for (index, item) in ast.items.iter().enumerate() {
             if let ItemKind::FunctionDef(func) = &item.node {
-                self.compile_function(func)?;
+                self.compile_function(index, func)?;
             }
             if let ItemKind::MethodDef(method) = &item.node {
-                self.compile_method(method)?;
+                self.compile_method(index, method)?;
             }
         }
 
@@ -1160,216 +1957,30 @@ impl Compiler {
         Ok(())
     }
 
-    /// Collect all spawn blocks from the AST.
+    /// Collect all spawn and async blocks from the AST and name the
+    /// functions they will be compiled into.
     fn collect_spawn_blocks(&mut self, ast: &SourceFile) {
-        for item in &ast.items {
-            self.collect_spawn_blocks_from_item(item);
-        }
-    }
-
-    fn collect_spawn_blocks_from_item(&mut self, item: &Item) {
-        match &item.node {
-            ItemKind::FunctionDef(func) => {
-                self.collect_spawn_blocks_from_block(&func.body);
-            }
-            ItemKind::MethodDef(method) => {
-                self.collect_spawn_blocks_from_block(&method.body);
-            }
-            ItemKind::Statement(stmt) => {
-                self.collect_spawn_blocks_from_stmt(stmt);
-            }
-            _ => {}
+        let mut collector = ConcurrentBlockCollector::default();
+        for (index, item) in ast.items.iter().enumerate() {
+            collector.item = index;
+            collector.visit_item(item);
         }
-    }
-
-    fn collect_spawn_blocks_from_block(&mut self, block: &Block) {
-        for stmt in &block.statements {
-            self.collect_spawn_blocks_from_stmt(stmt);
-        }
-    }
 
-    fn collect_spawn_blocks_from_stmt(&mut self, stmt: &Statement) {
-        match &stmt.node {
-            StatementKind::Expr(expr) => {
-                self.collect_spawn_blocks_from_expr(expr);
-            }
-            StatementKind::Assignment(assign) => {
-                self.collect_spawn_blocks_from_expr(&assign.value);
-            }
-            StatementKind::If(if_stmt) => {
-                self.collect_spawn_blocks_from_expr(&if_stmt.condition);
-                self.collect_spawn_blocks_from_block(&if_stmt.then_branch);
-                if let Some(else_branch) = &if_stmt.else_branch {
-                    match else_branch {
-                        haira_ast::ElseBranch::Block(block) => {
-                            self.collect_spawn_blocks_from_block(block);
-                        }
-                        haira_ast::ElseBranch::ElseIf(else_if) => {
-                            let else_if_stmt = Statement {
-                                node: StatementKind::If(else_if.node.clone()),
-                                span: else_if.span,
-                            };
-                            self.collect_spawn_blocks_from_stmt(&else_if_stmt);
-                        }
-                    }
-                }
-            }
-            StatementKind::While(while_stmt) => {
-                self.collect_spawn_blocks_from_expr(&while_stmt.condition);
-                self.collect_spawn_blocks_from_block(&while_stmt.body);
-            }
-            StatementKind::For(for_stmt) => {
-                self.collect_spawn_blocks_from_expr(&for_stmt.iterator);
-                self.collect_spawn_blocks_from_block(&for_stmt.body);
-            }
-            StatementKind::Return(ret) => {
-                for val in &ret.values {
-                    self.collect_spawn_blocks_from_expr(val);
-                }
-            }
-            StatementKind::Try(try_stmt) => {
-                self.collect_spawn_blocks_from_block(&try_stmt.body);
-                self.collect_spawn_blocks_from_block(&try_stmt.catch_body);
-            }
-            StatementKind::Match(match_expr) => {
-                self.collect_spawn_blocks_from_expr(&match_expr.subject);
-                for arm in &match_expr.arms {
-                    match &arm.body {
-                        haira_ast::MatchArmBody::Expr(expr) => {
-                            self.collect_spawn_blocks_from_expr(expr);
-                        }
-                        haira_ast::MatchArmBody::Block(block) => {
-                            self.collect_spawn_blocks_from_block(block);
-                        }
-                    }
-                }
-            }
-            _ => {}
+        for (key, block) in collector.spawn_blocks {
+            let func_name = SmolStr::from(format!("__spawn_block_{}", self.spawn_counter));
+            self.spawn_counter += 1;
+            self.spawn_functions.insert(key, func_name);
+            self.spawn_blocks.push((key, block));
         }
-    }
 
-    fn collect_spawn_blocks_from_expr(&mut self, expr: &Expr) {
-        match &expr.node {
-            ExprKind::Spawn(block) => {
-                // Found a spawn block! Record it with its span start as key
-                let span_start = expr.span.start;
-                let func_name = SmolStr::from(format!("__spawn_block_{}", self.spawn_counter));
-                self.spawn_counter += 1;
-                self.spawn_functions.insert(span_start, func_name);
-                self.spawn_blocks.push((span_start, block.clone()));
-                // Also collect any nested spawn blocks within
-                self.collect_spawn_blocks_from_block(block);
-            }
-            ExprKind::Binary(bin) => {
-                self.collect_spawn_blocks_from_expr(&bin.left);
-                self.collect_spawn_blocks_from_expr(&bin.right);
-            }
-            ExprKind::Unary(unary) => {
-                self.collect_spawn_blocks_from_expr(&unary.operand);
-            }
-            ExprKind::Call(call) => {
-                self.collect_spawn_blocks_from_expr(&call.callee);
-                for arg in &call.args {
-                    self.collect_spawn_blocks_from_expr(&arg.value);
-                }
-            }
-            ExprKind::MethodCall(method_call) => {
-                self.collect_spawn_blocks_from_expr(&method_call.receiver);
-                for arg in &method_call.args {
-                    self.collect_spawn_blocks_from_expr(&arg.value);
-                }
-            }
-            ExprKind::If(if_stmt) => {
-                self.collect_spawn_blocks_from_expr(&if_stmt.condition);
-                self.collect_spawn_blocks_from_block(&if_stmt.then_branch);
-                if let Some(else_branch) = &if_stmt.else_branch {
-                    match else_branch {
-                        haira_ast::ElseBranch::Block(block) => {
-                            self.collect_spawn_blocks_from_block(block);
-                        }
-                        haira_ast::ElseBranch::ElseIf(_) => {}
-                    }
-                }
-            }
-            ExprKind::Block(block) => {
-                self.collect_spawn_blocks_from_block(block);
-            }
-            ExprKind::Match(match_expr) => {
-                self.collect_spawn_blocks_from_expr(&match_expr.subject);
-                for arm in &match_expr.arms {
-                    match &arm.body {
-                        haira_ast::MatchArmBody::Expr(expr) => {
-                            self.collect_spawn_blocks_from_expr(expr);
-                        }
-                        haira_ast::MatchArmBody::Block(block) => {
-                            self.collect_spawn_blocks_from_block(block);
-                        }
-                    }
-                }
-            }
-            ExprKind::Paren(inner) => {
-                self.collect_spawn_blocks_from_expr(inner);
-            }
-            ExprKind::Propagate(inner) => {
-                self.collect_spawn_blocks_from_expr(inner);
-            }
-            ExprKind::Some(inner) => {
-                self.collect_spawn_blocks_from_expr(inner);
-            }
-            ExprKind::Lambda(lambda) => match &lambda.body {
-                haira_ast::LambdaBody::Expr(expr) => {
-                    self.collect_spawn_blocks_from_expr(expr);
-                }
-                haira_ast::LambdaBody::Block(block) => {
-                    self.collect_spawn_blocks_from_block(block);
-                }
-            },
-            ExprKind::Async(block) => {
-                // Found an async block! Record it with its span start as key
-                // Each statement in the block will become a separate function
-                let span_start = expr.span.start;
-                let mut func_names = Vec::new();
-                for (i, _stmt) in block.statements.iter().enumerate() {
-                    let func_name =
-                        SmolStr::from(format!("__async_block_{}_{}", self.async_counter, i));
-                    func_names.push(func_name);
-                }
-                self.async_counter += 1;
-                self.async_functions.insert(span_start, func_names);
-                self.async_blocks.push((span_start, block.clone()));
-                // Also collect any nested spawn/async blocks within
-                self.collect_spawn_blocks_from_block(block);
-            }
-            ExprKind::Pipe(pipe) => {
-                self.collect_spawn_blocks_from_expr(&pipe.left);
-                self.collect_spawn_blocks_from_expr(&pipe.right);
-            }
-            ExprKind::List(elements) => {
-                for elem in elements {
-                    self.collect_spawn_blocks_from_expr(elem);
-                }
-            }
-            ExprKind::Index(index_expr) => {
-                self.collect_spawn_blocks_from_expr(&index_expr.object);
-                self.collect_spawn_blocks_from_expr(&index_expr.index);
-            }
-            ExprKind::Field(field_expr) => {
-                self.collect_spawn_blocks_from_expr(&field_expr.object);
-            }
-            ExprKind::Instance(instance) => {
-                for field in &instance.fields {
-                    self.collect_spawn_blocks_from_expr(&field.value);
-                }
-            }
-            ExprKind::Range(range) => {
-                self.collect_spawn_blocks_from_expr(&range.start);
-                self.collect_spawn_blocks_from_expr(&range.end);
-            }
-            ExprKind::Ai(_ai_block) => {
-                // AI blocks are handled separately during pre-interpretation.
-                // No nested spawn/async blocks to collect from the intent text.
-            }
-            _ => {}
+        for (key, block) in collector.async_blocks {
+            // Each statement in the block will become a separate function
+            let func_names = (0..block.statements.len())
+                .map(|i| SmolStr::from(format!("__async_block_{}_{}", self.async_counter, i)))
+                .collect();
+            self.async_counter += 1;
+            self.async_functions.insert(key, func_names);
+            self.async_blocks.push((key, block));
         }
     }
 
@@ -1393,9 +2004,9 @@ impl Compiler {
         // Take ownership of spawn_blocks to avoid borrow issues
         let spawn_blocks = std::mem::take(&mut self.spawn_blocks);
 
-        for (span_start, block) in spawn_blocks {
-            let func_name = self.spawn_functions.get(&span_start).unwrap().clone();
-            self.compile_spawn_block_function(&func_name, &block)?;
+        for (key, block) in spawn_blocks {
+            let func_name = self.spawn_functions.get(&key).unwrap().clone();
+            self.compile_spawn_block_function(key.0, &func_name, &block)?;
         }
 
         Ok(())
@@ -1404,6 +2015,7 @@ impl Compiler {
     /// Compile a single spawn block as a function.
     fn compile_spawn_block_function(
         &mut self,
+        item: usize,
         func_name: &SmolStr,
         block: &Block,
     ) -> Result<(), CodegenError> {
@@ -1434,10 +2046,22 @@ impl Compiler {
                 functions: &self.functions,
                 func_signatures: &self.func_signatures,
                 structs: &self.structs,
+                enums: &self.enums,
+                constants: &self.constants,
+                static_methods: &self.static_methods,
                 ptr_type: self.ptr_type,
                 spawn_functions: &self.spawn_functions,
                 async_functions: &self.async_functions,
+                item,
+                overflow_checks: self.overflow_checks,
+                stack_guard: self.stack_guard,
+                bounds_checks: self.bounds_checks,
+                loops: Vec::new(),
+                warnings: &mut self.warnings,
+                returns: ValueType::Int,
+                returned: None,
             };
+            func_compiler.check_stack(&mut builder);
 
             let result = func_compiler.compile_block(block, &mut scope, &mut builder)?;
 
@@ -1449,11 +2073,7 @@ impl Compiler {
             builder.finalize();
         }
 
-        self.module
-            .define_function(func_id, &mut self.ctx)
-            .map_err(CodegenError::ModuleError)?;
-
-        self.ctx.clear();
+        self.define_function(func_id, func_name)?;
 
         Ok(())
     }
@@ -1480,12 +2100,12 @@ impl Compiler {
         // Take ownership of async_blocks to avoid borrow issues
         let async_blocks = std::mem::take(&mut self.async_blocks);
 
-        for (span_start, block) in async_blocks {
-            let func_names = self.async_functions.get(&span_start).unwrap().clone();
+        for (key, block) in async_blocks {
+            let func_names = self.async_functions.get(&key).unwrap().clone();
             // Compile each statement as a separate function
             for (i, stmt) in block.statements.iter().enumerate() {
                 if i < func_names.len() {
-                    self.compile_async_statement_function(&func_names[i], stmt)?;
+                    self.compile_async_statement_function(key.0, &func_names[i], stmt)?;
                 }
             }
         }
@@ -1496,6 +2116,7 @@ impl Compiler {
     /// Compile a single async statement as a function.
     fn compile_async_statement_function(
         &mut self,
+        item: usize,
         func_name: &SmolStr,
         stmt: &Statement,
     ) -> Result<(), CodegenError> {
@@ -1526,10 +2147,22 @@ impl Compiler {
                 functions: &self.functions,
                 func_signatures: &self.func_signatures,
                 structs: &self.structs,
+                enums: &self.enums,
+                constants: &self.constants,
+                static_methods: &self.static_methods,
                 ptr_type: self.ptr_type,
                 spawn_functions: &self.spawn_functions,
                 async_functions: &self.async_functions,
+                item,
+                overflow_checks: self.overflow_checks,
+                stack_guard: self.stack_guard,
+                bounds_checks: self.bounds_checks,
+                loops: Vec::new(),
+                warnings: &mut self.warnings,
+                returns: ValueType::Int,
+                returned: None,
             };
+            func_compiler.check_stack(&mut builder);
 
             let result = func_compiler.compile_statement(stmt, &mut scope, &mut builder)?;
 
@@ -1541,22 +2174,38 @@ impl Compiler {
             builder.finalize();
         }
 
-        self.module
-            .define_function(func_id, &mut self.ctx)
-            .map_err(CodegenError::ModuleError)?;
-
-        self.ctx.clear();
+        self.define_function(func_id, func_name)?;
 
         Ok(())
     }
 
     /// Compile a user-defined function.
-    fn compile_function(&mut self, func: &haira_ast::FunctionDef) -> Result<(), CodegenError> {
+    fn compile_function(
+        &mut self,
+        item: usize,
+        func: &haira_ast::FunctionDef,
+    ) -> Result<(), CodegenError> {
+        let func_id = self.build_function(item, func)?;
+        self.define_function(func_id, &func.name.node)
+    }
+
+    /// Build the body of a function definition into the context.
+    fn build_function(
+        &mut self,
+        item: usize,
+        func: &haira_ast::FunctionDef,
+    ) -> Result<FuncId, CodegenError> {
         let func_id = *self
             .functions
             .get(&func.name.node)
             .ok_or_else(|| CodegenError::UndefinedFunction(func.name.node.to_string()))?;
 
+        let signature = self
+            .func_signatures
+            .get(&func.name.node)
+            .cloned()
+            .ok_or_else(|| CodegenError::UndefinedFunction(func.name.node.to_string()))?;
+
         self.ctx.func.signature = self
             .module
             .declarations()
@@ -1577,12 +2226,12 @@ impl Compiler {
             // Create scope for variables
             let mut scope = FunctionScope::new(self.ptr_type);
 
-            // Bind parameters to variables
+            // Bind parameters to variables of their declared types
             let params = builder.block_params(entry_block).to_vec();
             for (i, param) in func.params.iter().enumerate() {
                 if i < params.len() {
-                    // Create a Cranelift variable for each parameter
-                    let var = scope.declare_var(&param.name.node, &mut builder);
+                    let ty = signature.params[i].clone();
+                    let var = scope.declare_var_typed(&param.name.node, ty, &mut builder);
                     builder.def_var(var, params[i]);
                 }
             }
@@ -1594,38 +2243,91 @@ impl Compiler {
                 functions: &self.functions,
                 func_signatures: &self.func_signatures,
                 structs: &self.structs,
+                enums: &self.enums,
+                constants: &self.constants,
+                static_methods: &self.static_methods,
                 ptr_type: self.ptr_type,
                 spawn_functions: &self.spawn_functions,
                 async_functions: &self.async_functions,
+                item,
+                overflow_checks: self.overflow_checks,
+                stack_guard: self.stack_guard,
+                bounds_checks: self.bounds_checks,
+                loops: Vec::new(),
+                warnings: &mut self.warnings,
+                returns: signature.returns.clone(),
+                returned: self.returned.as_mut(),
             };
+            func_compiler.check_stack(&mut builder);
 
             // Compile function body
-            let result = func_compiler.compile_block(&func.body, &mut scope, &mut builder)?;
+            let result = func_compiler.compile_block_typed(&func.body, &mut scope, &mut builder)?;
+            let result_expr = block_value_expr(&func.body);
 
             // Only add a return if the current block is not already terminated
             // is_unreachable() returns true if we're after a terminator instruction
             if !builder.is_unreachable() {
-                // Return the result or 0
-                let ret_val = result.unwrap_or_else(|| builder.ins().iconst(types::I64, 0));
+                // Return the result or 0, as the declared return type
+                let ret_val = match result {
+                    Some(result) => {
+                        func_compiler.coerce_return(result, result_expr, &mut builder)?
+                    }
+                    None if signature.returns == ValueType::Float => builder.ins().f64const(0.0),
+                    None => builder.ins().iconst(types::I64, 0),
+                };
                 builder.ins().return_(&[ret_val]);
             }
 
             builder.finalize();
         }
 
-        self.module
-            .define_function(func_id, &mut self.ctx)
-            .map_err(CodegenError::ModuleError)?;
-
-        self.ctx.clear();
-
-        Ok(())
+        Ok(func_id)
     }
 
-    /// Compile a method definition.
-    fn compile_method(&mut self, method: &MethodDef) -> Result<(), CodegenError> {
-        let method_full_name = format!("{}_{}", method.type_name.node, method.name.node);
-        let func_id = *self
+    /// The types `params` are passed as, and whether the last one is a rest
+    /// parameter.
+    ///
+    /// Unannotated parameters are ints, and a rest parameter is passed as a
+    /// list of its annotated type.
+    fn param_types(
+        &self,
+        params: &[haira_ast::Param],
+        owner: &str,
+    ) -> Result<(Vec<ValueType>, bool), CodegenError> {
+        if params.iter().rev().skip(1).any(|param| param.is_rest) {
+            return Err(CodegenError::Unsupported(format!(
+                "Only the last parameter of {} can be a rest parameter",
+                owner
+            )));
+        }
+        let types = params
+            .iter()
+            .map(|param| {
+                let ty = param.ty.as_ref().map_or(ValueType::Int, |ty| {
+                    ValueType::from_annotation(&ty.node, &self.structs, &self.enums)
+                });
+                if param.is_rest {
+                    ValueType::List(Box::new(ty))
+                } else {
+                    ty
+                }
+            })
+            .collect();
+        let rest = params.last().is_some_and(|param| param.is_rest);
+        Ok((types, rest))
+    }
+
+    /// Compile a method definition.
+    fn compile_method(&mut self, item: usize, method: &MethodDef) -> Result<(), CodegenError> {
+        let func_id = self.build_method(item, method)?;
+        let method_full_name = format!("{}_{}", method.type_name.node, method.name.node);
+        self.define_function(func_id, &method_full_name)
+    }
+
+    /// Build the body of a method definition into the context.
+    fn build_method(&mut self, item: usize, method: &MethodDef) -> Result<FuncId, CodegenError> {
+        let method_full_name = format!("{}_{}", method.type_name.node, method.name.node);
+        let func_id = *self
             .functions
             .get(&SmolStr::from(&method_full_name))
             .ok_or_else(|| CodegenError::UndefinedFunction(method_full_name.clone()))?;
@@ -1636,6 +2338,12 @@ impl Compiler {
             .get_function_decl(func_id)
             .signature
             .clone();
+        let signature = self
+            .func_signatures
+            .get(method_full_name.as_str())
+            .cloned()
+            .ok_or_else(|| CodegenError::UndefinedFunction(method_full_name.clone()))?;
+        let returns = signature.returns.clone();
 
         // Build method body
         {
@@ -1651,16 +2359,25 @@ impl Compiler {
             // Bind parameters to variables
             let params = builder.block_params(entry_block).to_vec();
 
-            // First parameter is 'self' - the struct pointer
+            // First parameter is 'self' - a pointer to the receiver, typed so
+            // `self.field` resolves within the receiver's own layout
             if !params.is_empty() {
-                let self_var = scope.declare_var(&SmolStr::from("self"), &mut builder);
+                let type_name = method.type_name.node.clone();
+                let self_ty = if self.enums.contains_key(&type_name) {
+                    ValueType::Enum(type_name)
+                } else {
+                    ValueType::Struct(type_name)
+                };
+                let self_var =
+                    scope.declare_var_typed(&SmolStr::from("self"), self_ty, &mut builder);
                 builder.def_var(self_var, params[0]);
             }
 
-            // Bind other parameters
+            // Bind other parameters to variables of their declared types
             for (i, param) in method.params.iter().enumerate() {
                 if i + 1 < params.len() {
-                    let var = scope.declare_var(&param.name.node, &mut builder);
+                    let ty = signature.params[i + 1].clone();
+                    let var = scope.declare_var_typed(&param.name.node, ty, &mut builder);
                     builder.def_var(var, params[i + 1]);
                 }
             }
@@ -1671,27 +2388,93 @@ impl Compiler {
                 functions: &self.functions,
                 func_signatures: &self.func_signatures,
                 structs: &self.structs,
+                enums: &self.enums,
+                constants: &self.constants,
+                static_methods: &self.static_methods,
                 ptr_type: self.ptr_type,
                 spawn_functions: &self.spawn_functions,
                 async_functions: &self.async_functions,
+                item,
+                overflow_checks: self.overflow_checks,
+                stack_guard: self.stack_guard,
+                bounds_checks: self.bounds_checks,
+                loops: Vec::new(),
+                warnings: &mut self.warnings,
+                returns: returns.clone(),
+                returned: self.returned.as_mut(),
             };
+            func_compiler.check_stack(&mut builder);
 
-            let result = func_compiler.compile_block(&method.body, &mut scope, &mut builder)?;
+            let result =
+                func_compiler.compile_block_typed(&method.body, &mut scope, &mut builder)?;
+            let result_expr = block_value_expr(&method.body);
 
             if !builder.is_unreachable() {
-                let ret_val = result.unwrap_or_else(|| builder.ins().iconst(types::I64, 0));
+                let ret_val = match result {
+                    Some(result) => {
+                        func_compiler.coerce_return(result, result_expr, &mut builder)?
+                    }
+                    None if returns == ValueType::Float => builder.ins().f64const(0.0),
+                    None => builder.ins().iconst(types::I64, 0),
+                };
                 builder.ins().return_(&[ret_val]);
             }
 
             builder.finalize();
         }
 
-        self.module
-            .define_function(func_id, &mut self.ctx)
-            .map_err(CodegenError::ModuleError)?;
+        Ok(func_id)
+    }
 
-        self.ctx.clear();
+    /// Infer the element types of each unannotated `return a, b` from the
+    /// values the function returns, by building its body without defining
+    /// it. Functions are inferred in order, so one calling a later function
+    /// of this kind sees that function's values as ints.
+    fn infer_return_types(&mut self, ast: &SourceFile) -> Result<(), CodegenError> {
+        for (index, item) in ast.items.iter().enumerate() {
+            let name = match &item.node {
+                ItemKind::FunctionDef(func) if func.return_ty.is_none() => func.name.node.clone(),
+                ItemKind::MethodDef(method) if method.return_ty.is_none() => {
+                    SmolStr::from(format!("{}_{}", method.type_name.node, method.name.node))
+                }
+                _ => continue,
+            };
+            if !matches!(
+                self.func_signatures.get(&name).map(|sig| &sig.returns),
+                Some(ValueType::Tuple(_))
+            ) {
+                continue;
+            }
 
+            // Building again when compiling repeats any warnings
+            let warnings = self.warnings.len();
+            self.returned = Some(Vec::new());
+            let built = match &item.node {
+                ItemKind::FunctionDef(func) => self.build_function(index, func),
+                ItemKind::MethodDef(method) => self.build_method(index, method),
+                _ => unreachable!(),
+            };
+            let returned = self.returned.take().unwrap_or_default();
+            self.warnings.truncate(warnings);
+            self.ctx.clear();
+            built?;
+
+            let mut types: Vec<Option<ValueType>> = Vec::new();
+            for values in returned {
+                types.resize(values.len(), None);
+                for (position, ty) in values.into_iter().enumerate() {
+                    let unified = match &types[position] {
+                        Some(seen) => unify_branch_types(seen, &ty),
+                        None => Some(ty),
+                    };
+                    types[position] =
+                        Some(unified.ok_or_else(|| unknown_return_type(&name, position))?);
+                }
+            }
+            if let Some(sig) = self.func_signatures.get_mut(&name) {
+                sig.returns = ValueType::Tuple(types.into_iter().flatten().collect());
+            }
+        }
         Ok(())
     }
 
@@ -1724,14 +2507,27 @@ impl Compiler {
                 functions: &self.functions,
                 func_signatures: &self.func_signatures,
                 structs: &self.structs,
+                enums: &self.enums,
+                constants: &self.constants,
+                static_methods: &self.static_methods,
                 ptr_type: self.ptr_type,
                 spawn_functions: &self.spawn_functions,
                 async_functions: &self.async_functions,
+                item: 0,
+                overflow_checks: self.overflow_checks,
+                stack_guard: self.stack_guard,
+                bounds_checks: self.bounds_checks,
+                loops: Vec::new(),
+                warnings: &mut self.warnings,
+                returns: ValueType::Int,
+                returned: None,
             };
+            func_compiler.check_stack(&mut builder);
 
             // Compile all top-level statements (not function defs)
-            for item in &ast.items {
+            for (index, item) in ast.items.iter().enumerate() {
                 if let ItemKind::Statement(stmt) = &item.node {
+                    func_compiler.item = index;
                     func_compiler.compile_statement(stmt, &mut scope, &mut builder)?;
                 }
             }
@@ -1743,39 +2539,282 @@ impl Compiler {
             builder.finalize();
         }
 
+        self.define_function(main_id, "main")?;
+
+        Ok(())
+    }
+
+    /// Record the Cranelift IR of each function compiled from now on.
+    pub fn enable_clif_dump(&mut self) {
+        self.clif.get_or_insert_with(String::new);
+    }
+
+    /// Record the disassembled machine code of each function compiled from
+    /// now on.
+    pub fn enable_asm_dump(&mut self) {
+        self.asm.get_or_insert_with(String::new);
+    }
+
+    /// Check `+`, `-` and `*` on integers for overflow in functions compiled
+    /// from now on.
+    pub fn enable_overflow_checks(&mut self) {
+        self.overflow_checks = true;
+    }
+
+    /// Abort when a thread's stack grows past `limit` bytes, checked on
+    /// entry to functions compiled from now on.
+    pub fn enable_stack_guard(&mut self, limit: u64) {
+        self.stack_guard = Some(limit);
+    }
+
+    /// Check list indices against the list's length in functions compiled
+    /// from now on.
+    pub fn enable_bounds_checks(&mut self) {
+        self.bounds_checks = true;
+    }
+
+    /// The recorded Cranelift IR, if dumping was enabled.
+    pub fn clif(&self) -> Option<&str> {
+        self.clif.as_deref()
+    }
+
+    /// The recorded disassembly, if dumping was enabled.
+    pub fn asm(&self) -> Option<&str> {
+        self.asm.as_deref()
+    }
+
+    /// Define the function built in `self.ctx` and reset the context.
+    fn define_function(&mut self, func_id: FuncId, name: &str) -> Result<(), CodegenError> {
+        if let Some(clif) = &mut self.clif {
+            clif.push_str(&format!("; {}\n{}\n", name, self.ctx.func.display()));
+        }
+        self.ctx.set_disasm(self.asm.is_some());
+
+        // Name the function and point at the offending instruction while developing
+        if cfg!(debug_assertions) {
+            codegen::verify_function(&self.ctx.func, self.module.isa()).map_err(|errors| {
+                CodegenError::CraneliftError(format!(
+                    "invalid IR in `{}`:\n{}",
+                    name,
+                    codegen::print_errors::pretty_verifier_error(&self.ctx.func, None, errors)
+                ))
+            })?;
+        }
+
         self.module
-            .define_function(main_id, &mut self.ctx)
+            .define_function(func_id, &mut self.ctx)
             .map_err(CodegenError::ModuleError)?;
 
-        self.ctx.clear();
+        if let Some(asm) = &mut self.asm {
+            let code = self
+                .ctx
+                .compiled_code()
+                .and_then(|code| code.vcode.as_deref());
+            asm.push_str(&format!("; {}\n{}\n", name, code.unwrap_or_default()));
+        }
 
+        self.ctx.clear();
         Ok(())
     }
 
     /// Finish compilation and return object bytes.
-    pub fn finish(self) -> Vec<u8> {
+    pub fn finish(self) -> Result<Vec<u8>, CodegenError> {
         let product = self.module.finish();
-        product.emit().unwrap()
+        product
+            .emit()
+            .map_err(|e| CodegenError::CraneliftError(format!("failed to emit object: {}", e)))
+    }
+}
+
+/// A `spawn` or `async` block: the index of the item holding it and its
+/// span start. The items of a multi-file program keep spans relative to
+/// their own file, so the span alone doesn't tell two blocks apart.
+type BlockKey = (usize, u32);
+
+/// Collects `spawn` and `async` blocks in source order.
+#[derive(Default)]
+struct ConcurrentBlockCollector {
+    /// Index of the item being walked.
+    item: usize,
+    spawn_blocks: Vec<(BlockKey, Block)>,
+    async_blocks: Vec<(BlockKey, Block)>,
+}
+
+impl Visitor for ConcurrentBlockCollector {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let key = (self.item, expr.span.start);
+        match &expr.node {
+            ExprKind::Spawn(block) => self.spawn_blocks.push((key, block.clone())),
+            ExprKind::Async(block) => self.async_blocks.push((key, block.clone())),
+            _ => {}
+        }
+        // Nested blocks are collected too
+        walk_expr(self, expr);
+    }
+}
+
+/// The error for an `ai` block that reached codegen uninterpreted.
+fn uninterpreted_ai_block(ai_block: &AiBlock) -> CodegenError {
+    let name = ai_block
+        .name
+        .as_ref()
+        .map(|n| n.node.as_str())
+        .unwrap_or("<anonymous>");
+    CodegenError::Unsupported(format!(
+        "AI block '{}' requires pre-interpretation. \
+         Run `haira build --interpret-ai` to generate code from AI intents.",
+        name
+    ))
+}
+
+/// Whether a method body refers to `self`.
+fn uses_self(body: &Block) -> bool {
+    #[derive(Default)]
+    struct SelfFinder {
+        found: bool,
+    }
+
+    impl Visitor for SelfFinder {
+        fn visit_stmt(&mut self, stmt: &Statement) {
+            if let StatementKind::Assignment(assign) = &stmt.node {
+                for target in &assign.targets {
+                    let mut path = &target.path;
+                    while let AssignPath::Field { object, .. } | AssignPath::Index { object, .. } =
+                        path
+                    {
+                        path = object;
+                    }
+                    if matches!(path, AssignPath::Identifier(name) if name.node == "self") {
+                        self.found = true;
+                    }
+                }
+            }
+            walk_stmt(self, stmt);
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            if matches!(&expr.node, ExprKind::Identifier(name) if name == "self") {
+                self.found = true;
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    let mut finder = SelfFinder::default();
+    finder.visit_block(body);
+    finder.found
+}
+
+/// The variables a loop body assigns to directly, as in `x = ...` or
+/// `x += ...`. Assigning a field or element of `x` doesn't assign `x`.
+fn assigned_names(body: &Block) -> BTreeSet<SmolStr> {
+    #[derive(Default)]
+    struct AssignmentFinder {
+        names: BTreeSet<SmolStr>,
+    }
+
+    impl Visitor for AssignmentFinder {
+        fn visit_stmt(&mut self, stmt: &Statement) {
+            if let StatementKind::Assignment(assign) = &stmt.node {
+                for target in &assign.targets {
+                    if let AssignPath::Identifier(name) = &target.path {
+                        self.names.insert(name.node.clone());
+                    }
+                }
+            }
+            walk_stmt(self, stmt);
+        }
+    }
+
+    let mut finder = AssignmentFinder::default();
+    finder.visit_block(body);
+    finder.names
+}
+
+/// The number of values returned by the widest `return a, b` in a body, if
+/// any returns several. Lambdas and concurrent blocks return on their own.
+fn return_arity(body: &Block) -> Option<usize> {
+    #[derive(Default)]
+    struct ReturnFinder {
+        arity: Option<usize>,
+    }
+
+    impl Visitor for ReturnFinder {
+        fn visit_stmt(&mut self, stmt: &Statement) {
+            if let StatementKind::Return(ret) = &stmt.node {
+                if ret.values.len() > 1 {
+                    self.arity = self.arity.max(Some(ret.values.len()));
+                }
+            }
+            walk_stmt(self, stmt);
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            if !matches!(
+                &expr.node,
+                ExprKind::Lambda(_) | ExprKind::Spawn(_) | ExprKind::Async(_)
+            ) {
+                walk_expr(self, expr);
+            }
+        }
     }
+
+    let mut finder = ReturnFinder::default();
+    finder.visit_block(body);
+    finder.arity
 }
 
 /// Helper struct for compiling function bodies.
 /// This is separate from Compiler to avoid borrow issues.
 struct FunctionCompiler<'a> {
     module: &'a mut ObjectModule,
-    strings: &'a mut HashMap<SmolStr, cranelift_module::DataId>,
-    functions: &'a HashMap<SmolStr, FuncId>,
-    func_signatures: &'a HashMap<SmolStr, FuncSignature>,
-    structs: &'a HashMap<SmolStr, StructInfo>,
+    strings: &'a mut IndexMap<SmolStr, cranelift_module::DataId>,
+    functions: &'a IndexMap<SmolStr, FuncId>,
+    func_signatures: &'a IndexMap<SmolStr, FuncSignature>,
+    structs: &'a IndexMap<SmolStr, StructInfo>,
+    enums: &'a IndexMap<SmolStr, EnumInfo>,
+    /// Values of the `const` items.
+    constants: &'a IndexMap<SmolStr, Literal>,
+    static_methods: &'a HashSet<SmolStr>,
     ptr_type: Type,
-    /// Map of spawn block span start to their function names.
-    spawn_functions: &'a HashMap<u32, SmolStr>,
-    /// Map of async block span start to their function names.
-    async_functions: &'a HashMap<u32, Vec<SmolStr>>,
+    /// Map of spawn blocks to their function names.
+    spawn_functions: &'a IndexMap<BlockKey, SmolStr>,
+    /// Map of async blocks to their function names.
+    async_functions: &'a IndexMap<BlockKey, Vec<SmolStr>>,
+    /// Index of the item whose code is being compiled, keying its blocks.
+    item: usize,
+    /// Whether integer arithmetic traps on overflow.
+    overflow_checks: bool,
+    /// Stack limit in bytes checked at function entry, if guarded.
+    stack_guard: Option<u64>,
+    /// Whether list indexing traps out of bounds.
+    bounds_checks: bool,
+    /// Enclosing loops, innermost last.
+    loops: Vec<LoopContext>,
+    /// Warnings collected while compiling.
+    warnings: &'a mut Vec<CodegenWarning>,
+    /// Declared return type of the function being compiled.
+    returns: ValueType,
+    /// Where to record the types of `return a, b` values instead of
+    /// converting them, while inferring the return type.
+    returned: Option<&'a mut Vec<Vec<ValueType>>>,
+}
+
+/// Jump targets of an enclosing loop for `break` and `continue`.
+struct LoopContext {
+    /// Target of `continue`.
+    continue_block: cranelift::codegen::ir::Block,
+    /// Variables passed, in order, as the block arguments of `continue_block`.
+    continue_vars: Vec<Variable>,
+    /// Target of `break`.
+    break_block: cranelift::codegen::ir::Block,
 }
 
 impl<'a> FunctionCompiler<'a> {
     /// Define a string constant and return its data ID.
+    ///
+    /// Constants are shared by every function of the module, so each
+    /// distinct string is emitted once.
     fn define_string(&mut self, s: &str) -> Result<cranelift_module::DataId, CodegenError> {
         let key = SmolStr::from(s);
         if let Some(&id) = self.strings.get(&key) {
@@ -1796,72 +2835,256 @@ impl<'a> FunctionCompiler<'a> {
         Ok(id)
     }
 
-    /// Compile a block of statements.
+    /// Compile a block of statements in its own scope, returning its value.
     fn compile_block(
         &mut self,
         block: &Block,
         scope: &mut FunctionScope,
         builder: &mut FunctionBuilder,
     ) -> Result<Option<Value>, CodegenError> {
-        let mut last_value = None;
+        scope.push_scope();
+        let value = self.compile_block_typed(block, scope, builder)?;
+        scope.pop_scope();
+
+        Ok(value.map(|value| value.value))
+    }
+
+    /// Compile a block used as a value, in its own scope; 0 if it has none.
+    fn compile_block_value(
+        &mut self,
+        block: &Block,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        scope.push_scope();
+        let value = self.compile_block_typed(block, scope, builder)?;
+        scope.pop_scope();
+
+        Ok(value.unwrap_or_else(|| TypedValue {
+            value: builder.ins().iconst(types::I64, 0),
+            ty: ValueType::Int,
+        }))
+    }
+
+    /// The statements of `block` up to and including the first one that always
+    /// leaves it (`return`, `break` or `continue`).
+    ///
+    /// Anything after that point can never run, so it is dropped with a warning.
+    fn reachable_statements<'b>(&mut self, block: &'b Block) -> &'b [Statement] {
+        let Some(end) = block.statements.iter().position(|stmt| {
+            matches!(
+                stmt.node,
+                StatementKind::Return(_) | StatementKind::Break | StatementKind::Continue
+            )
+        }) else {
+            return &block.statements;
+        };
+
+        let (reachable, unreachable) = block.statements.split_at(end + 1);
+        if let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) {
+            let keyword = match reachable[end].node {
+                StatementKind::Return(_) => "return",
+                StatementKind::Break => "break",
+                _ => "continue",
+            };
+            self.warnings.push(CodegenWarning {
+                message: format!("unreachable code after `{}`", keyword),
+                span: first.span.merge(last.span),
+            });
+        }
+        reachable
+    }
 
-        for stmt in &block.statements {
-            last_value = self.compile_statement(stmt, scope, builder)?;
+    /// Compile a block whose trailing expression is its typed value, as in a
+    /// function body.
+    ///
+    /// The value comes from [`Block::value`]; a block ending in any other
+    /// statement, or cut short by `return`, `break` or `continue`, has none.
+    fn compile_block_typed(
+        &mut self,
+        block: &Block,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Option<TypedValue>, CodegenError> {
+        let statements = self.reachable_statements(block);
+        let value = block
+            .value()
+            .filter(|_| statements.len() == block.statements.len());
+        let statements = match value {
+            Some(_) => &statements[..statements.len() - 1],
+            None => statements,
+        };
+
+        for stmt in statements {
+            self.compile_statement(stmt, scope, builder)?;
+        }
+
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        match &value.node {
+            StatementKind::Expr(expr) => self.compile_expr_typed(expr, scope, builder).map(Some),
+            StatementKind::If(if_stmt) => self.compile_if_expr(if_stmt, scope, builder).map(Some),
+            StatementKind::Match(match_expr) => self
+                .compile_match_expr(match_expr, scope, builder)
+                .map(Some),
+            _ => {
+                self.compile_statement(value, scope, builder)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Compile an `if` used as a value: the value of whichever branch runs.
+    ///
+    /// The branches' types are unified, so an int branch is converted to
+    /// float when the other is float. A branch without a value, such as a
+    /// missing else, gives the zero of the other's type.
+    fn compile_if_expr(
+        &mut self,
+        if_stmt: &IfStatement,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let cond = self
+            .compile_expr_typed(&if_stmt.condition, scope, builder)?
+            .value;
+
+        let then_block = builder.create_block();
+        let else_block = builder.create_block();
+        builder.ins().brif(cond, then_block, &[], else_block, &[]);
+
+        // Then - seal since only predecessor is branch source
+        builder.switch_to_block(then_block);
+        builder.seal_block(then_block);
+        scope.push_scope();
+        let then_val = self.compile_block_typed(&if_stmt.then_branch, scope, builder)?;
+        scope.pop_scope();
+        let then_exit = Self::branch_exit(then_val, builder);
+
+        // Else - seal since only predecessor is branch source
+        builder.switch_to_block(else_block);
+        builder.seal_block(else_block);
+        let else_val = match &if_stmt.else_branch {
+            Some(haira_ast::ElseBranch::Block(block)) => {
+                scope.push_scope();
+                let value = self.compile_block_typed(block, scope, builder)?;
+                scope.pop_scope();
+                value
+            }
+            Some(haira_ast::ElseBranch::ElseIf(else_if)) => {
+                Some(self.compile_if_expr(&else_if.node, scope, builder)?)
+            }
+            None => None,
+        };
+        let else_exit = Self::branch_exit(else_val, builder);
+
+        let ty = match (&then_exit.1, &else_exit.1) {
+            (Some(then_ty), Some(else_ty)) => {
+                unify_branch_types(then_ty, else_ty).ok_or_else(|| {
+                    CodegenError::TypeMismatch(format!(
+                        "`if` branches have incompatible types {} and {}",
+                        then_ty, else_ty
+                    ))
+                })?
+            }
+            (Some(ty), None) | (None, Some(ty)) => ty.clone(),
+            (None, None) => ValueType::Int,
+        };
+
+        // Both branches convert their value to the unified type
+        let merge_block = builder.create_block();
+        builder.append_block_param(merge_block, ty.cranelift_type());
+        for (exit, branch_ty) in [then_exit, else_exit] {
+            builder.switch_to_block(exit);
+            builder.seal_block(exit);
+            let value = match branch_ty {
+                Some(branch_ty) => {
+                    let value = builder.block_params(exit)[0];
+                    self.coerce_to(
+                        TypedValue {
+                            value,
+                            ty: branch_ty,
+                        },
+                        &ty,
+                        builder,
+                    )
+                    .value
+                }
+                None if ty == ValueType::Float => builder.ins().f64const(0.0),
+                None => builder.ins().iconst(types::I64, 0),
+            };
+            builder.ins().jump(merge_block, &[value]);
         }
 
-        Ok(last_value)
+        // Merge - seal since both predecessors have jumped
+        builder.switch_to_block(merge_block);
+        builder.seal_block(merge_block);
+        Ok(TypedValue {
+            value: builder.block_params(merge_block)[0],
+            ty,
+        })
+    }
+
+    /// End an `if` branch by jumping to a new block taking its value, if it
+    /// has one, so the value can be converted once both branches' types are
+    /// known.
+    fn branch_exit(
+        value: Option<TypedValue>,
+        builder: &mut FunctionBuilder,
+    ) -> (cranelift::codegen::ir::Block, Option<ValueType>) {
+        let exit = builder.create_block();
+        match value {
+            Some(value) => {
+                builder.append_block_param(exit, value.ty.cranelift_type());
+                builder.ins().jump(exit, &[value.value]);
+                (exit, Some(value.ty))
+            }
+            None => {
+                builder.ins().jump(exit, &[]);
+                (exit, None)
+            }
+        }
     }
 
     /// Compile an assignment target (variable, field, or index).
     /// Compile an assignment target with type awareness.
+    ///
+    /// `literal` says whether the value is an int literal rather than a
+    /// possibly untyped word.
     fn compile_assign_target_typed(
         &mut self,
         path: &AssignPath,
         typed_value: TypedValue,
+        literal: bool,
         scope: &mut FunctionScope,
         builder: &mut FunctionBuilder,
     ) -> Result<(), CodegenError> {
         match path {
             AssignPath::Identifier(name) => {
                 // Simple variable assignment with proper type
-                let var = scope.get_or_declare_var_typed(&name.node, typed_value.ty, builder);
+                let var =
+                    scope.get_or_declare_var_typed(&name.node, typed_value.ty, literal, builder);
                 builder.def_var(var, typed_value.value);
                 Ok(())
             }
             AssignPath::Field { object, field } => {
-                // Field assignment: obj.field = value
-                // For struct fields, we still store as raw value (I64 or F64)
-                let obj_ptr = self.compile_assign_path_to_ptr(object, scope, builder)?;
-                let field_name = &field.node;
-
-                for (_, struct_info) in self.structs.iter() {
-                    if let Some(field_idx) = struct_info.fields.iter().position(|f| f == field_name)
-                    {
-                        let offset = struct_info.field_offsets[field_idx];
-                        let offset_val = builder.ins().iconst(types::I64, offset as i64);
-                        let field_ptr = builder.ins().iadd(obj_ptr, offset_val);
-                        builder
-                            .ins()
-                            .store(MemFlags::new(), typed_value.value, field_ptr, 0);
-                        return Ok(());
-                    }
-                }
-
-                Err(CodegenError::Unsupported(format!(
-                    "Unknown field: {}",
-                    field_name
-                )))
+                // Field assignment: obj.field = value, stored as the field's type
+                let object = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let (offset, ty) = self.field_slot(&object.ty, &field.node)?;
+                let value = self.coerce_to(typed_value, &ty, builder).value;
+                builder
+                    .ins()
+                    .store(MemFlags::new(), value, object.value, offset as i32);
+                Ok(())
             }
             AssignPath::Index { object, index } => {
                 // Index assignment: arr[i] = value
-                let arr_ptr = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let arr_ptr = self
+                    .compile_assign_path_to_ptr(object, scope, builder)?
+                    .value;
                 let index_val = self.compile_expr(index, scope, builder)?;
-
-                let eight = builder.ins().iconst(types::I64, 8);
-                let offset = builder.ins().imul(index_val, eight);
-                let base_offset = builder.ins().iadd(offset, eight);
-                let elem_ptr = builder.ins().iadd(arr_ptr, base_offset);
-
+                let elem_ptr = self.list_element_addr(arr_ptr, index_val, builder);
                 builder
                     .ins()
                     .store(MemFlags::new(), typed_value.value, elem_ptr, 0);
@@ -1870,62 +3093,221 @@ impl<'a> FunctionCompiler<'a> {
         }
     }
 
-    /// Get the pointer for an assignment path (used for nested field/index access).
+    /// Compile a compound assignment such as `x += 1`, `user.count += 1` or
+    /// `arr[i] *= 2`, returning the stored value.
+    ///
+    /// The place is evaluated once: its object and index are computed before
+    /// the current value is loaded and the result stored back.
+    fn compile_compound_assign(
+        &mut self,
+        path: &AssignPath,
+        op: &BinaryOp,
+        value: TypedValue,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        match path {
+            AssignPath::Identifier(_) => {
+                let current = self.compile_assign_path_to_ptr(path, scope, builder)?;
+                let result = self.compile_binary_op_typed(op, current, value, builder)?;
+                self.compile_assign_target_typed(path, result.clone(), false, scope, builder)?;
+                Ok(result)
+            }
+            AssignPath::Field { object, field } => {
+                let object = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let (offset, ty) = self.field_slot(&object.ty, &field.node)?;
+                let current = TypedValue {
+                    value: builder.ins().load(
+                        ty.cranelift_type(),
+                        MemFlags::new(),
+                        object.value,
+                        offset as i32,
+                    ),
+                    ty: ty.clone(),
+                };
+                let result = self.compile_binary_op_typed(op, current, value, builder)?;
+                let result = self.coerce_to(result, &ty, builder);
+                builder
+                    .ins()
+                    .store(MemFlags::new(), result.value, object.value, offset as i32);
+                Ok(result)
+            }
+            AssignPath::Index { object, index } => {
+                let list = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let elem_ty = match list.ty {
+                    ValueType::List(elem_ty) => *elem_ty,
+                    _ => ValueType::Int,
+                };
+                let index_val = self.compile_expr(index, scope, builder)?;
+                let elem_ptr = self.list_element_addr(list.value, index_val, builder);
+                let current = TypedValue {
+                    value: builder.ins().load(
+                        elem_ty.cranelift_type(),
+                        MemFlags::new(),
+                        elem_ptr,
+                        0,
+                    ),
+                    ty: elem_ty.clone(),
+                };
+                let result = self.compile_binary_op_typed(op, current, value, builder)?;
+                let result = self.coerce_to(result, &elem_ty, builder);
+                builder
+                    .ins()
+                    .store(MemFlags::new(), result.value, elem_ptr, 0);
+                Ok(result)
+            }
+        }
+    }
+
+    /// Get the value and static type of an assignment path (used for nested field/index access).
     fn compile_assign_path_to_ptr(
         &mut self,
         path: &AssignPath,
         scope: &mut FunctionScope,
         builder: &mut FunctionBuilder,
-    ) -> Result<Value, CodegenError> {
+    ) -> Result<TypedValue, CodegenError> {
         match path {
             AssignPath::Identifier(name) => {
                 // Get the variable value (which should be a pointer for structs)
                 if let Some(var) = scope.get_var(&name.node) {
-                    Ok(builder.use_var(var))
+                    Ok(TypedValue {
+                        value: builder.use_var(var),
+                        ty: scope.get_var_type(&name.node).unwrap_or(ValueType::Int),
+                    })
                 } else {
                     Err(CodegenError::UndefinedVariable(name.node.to_string()))
                 }
             }
             AssignPath::Field { object, field } => {
-                // Get the object pointer, then compute field pointer
-                let obj_ptr = self.compile_assign_path_to_ptr(object, scope, builder)?;
-                let field_name = &field.node;
-
-                for (_, struct_info) in self.structs.iter() {
-                    if let Some(field_idx) = struct_info.fields.iter().position(|f| f == field_name)
-                    {
-                        let offset = struct_info.field_offsets[field_idx];
-                        let offset_val = builder.ins().iconst(types::I64, offset as i64);
-                        let field_ptr = builder.ins().iadd(obj_ptr, offset_val);
-                        // Load the pointer value at this field (for nested struct access)
-                        let value = builder
-                            .ins()
-                            .load(types::I64, MemFlags::new(), field_ptr, 0);
-                        return Ok(value);
-                    }
-                }
-
-                Err(CodegenError::Unsupported(format!(
-                    "Unknown field: {}",
-                    field_name
-                )))
+                // Load the field from the object (for nested struct access)
+                let object = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let (offset, ty) = self.field_slot(&object.ty, &field.node)?;
+                let value = builder.ins().load(
+                    ty.cranelift_type(),
+                    MemFlags::new(),
+                    object.value,
+                    offset as i32,
+                );
+                Ok(TypedValue { value, ty })
             }
             AssignPath::Index { object, index } => {
                 // Get the array pointer, then compute element pointer
-                let arr_ptr = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let list = self.compile_assign_path_to_ptr(object, scope, builder)?;
+                let elem_ty = match list.ty {
+                    ValueType::List(elem_ty) => *elem_ty,
+                    _ => ValueType::Int,
+                };
                 let index_val = self.compile_expr(index, scope, builder)?;
+                let value = self.load_list_element(list.value, index_val, &elem_ty, builder);
+                Ok(TypedValue { value, ty: elem_ty })
+            }
+        }
+    }
 
-                let eight = builder.ins().iconst(types::I64, 8);
-                let offset = builder.ins().imul(index_val, eight);
-                let base_offset = builder.ins().iadd(offset, eight);
-                let elem_ptr = builder.ins().iadd(arr_ptr, base_offset);
+    /// Find the offset and type of a field on an object of type `object_ty`.
+    ///
+    /// Tuple elements are fields named by their index. Untyped objects (plain
+    /// `Int` words) resolve only when a single struct declares the field;
+    /// otherwise the offset would depend on which struct the object is.
+    fn field_slot(
+        &self,
+        object_ty: &ValueType,
+        field_name: &SmolStr,
+    ) -> Result<(usize, ValueType), CodegenError> {
+        let slot = |struct_info: &StructInfo| {
+            let field_idx = struct_info.fields.iter().position(|f| f == field_name)?;
+            let ty = struct_info
+                .field_types
+                .get(field_idx)
+                .cloned()
+                .unwrap_or(ValueType::Int);
+            Some((struct_info.field_offsets[field_idx], ty))
+        };
 
-                let value = builder.ins().load(types::I64, MemFlags::new(), elem_ptr, 0);
-                Ok(value)
+        match object_ty {
+            ValueType::Struct(type_name) => {
+                let struct_info = self.structs.get(type_name).ok_or_else(|| {
+                    CodegenError::Unsupported(format!("Unknown type: {}", type_name))
+                })?;
+                slot(struct_info).ok_or_else(|| {
+                    CodegenError::Unsupported(format!(
+                        "Unknown field: {} in type {}",
+                        field_name, type_name
+                    ))
+                })
             }
+            ValueType::Int => {
+                let mut candidates = self.structs.values().filter_map(slot);
+                match (candidates.next(), candidates.next()) {
+                    (Some(found), None) => Ok(found),
+                    (None, _) => Err(CodegenError::Unsupported(format!(
+                        "Unknown field: {}",
+                        field_name
+                    ))),
+                    (Some(_), Some(_)) => Err(CodegenError::Unsupported(format!(
+                        "Cannot access field {} on a value that is not a known struct; \
+                         several structs declare it",
+                        field_name
+                    ))),
+                }
+            }
+            ValueType::Tuple(elements) => field_name
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| Some((tuple_offset(i) as usize, elements.get(i)?.clone())))
+                .ok_or_else(|| {
+                    CodegenError::Unsupported(format!(
+                        "No element {} in a tuple of {} element(s)",
+                        field_name,
+                        elements.len()
+                    ))
+                }),
+            _ => Err(CodegenError::Unsupported(format!(
+                "Cannot access field {} on a value that is not a struct",
+                field_name
+            ))),
         }
     }
 
+    /// The value each of an assignment's `targets` receives.
+    ///
+    /// Several targets destructure a tuple of the same arity, `(a, b) = pair`,
+    /// one element each; otherwise every target gets the whole value.
+    fn assigned_values(
+        &mut self,
+        value: TypedValue,
+        targets: usize,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Vec<TypedValue>, CodegenError> {
+        let ValueType::Tuple(element_types) = &value.ty else {
+            return Ok(vec![value; targets]);
+        };
+        if targets == 1 {
+            return Ok(vec![value]);
+        }
+        if element_types.len() != targets {
+            return Err(CodegenError::TypeMismatch(format!(
+                "cannot destructure a tuple of {} element(s) into {} targets",
+                element_types.len(),
+                targets
+            )));
+        }
+        Ok(element_types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let word =
+                    builder
+                        .ins()
+                        .load(types::I64, MemFlags::new(), value.value, tuple_offset(i));
+                TypedValue {
+                    value: Self::from_word(word, ty, builder),
+                    ty: ty.clone(),
+                }
+            })
+            .collect())
+    }
+
     /// Compile a statement.
     fn compile_statement(
         &mut self,
@@ -1940,23 +3322,65 @@ impl<'a> FunctionCompiler<'a> {
             }
             StatementKind::Assignment(assign) => {
                 let typed_value = self.compile_expr_typed(&assign.value, scope, builder)?;
-                let result_value = typed_value.value;
-                for target in &assign.targets {
-                    self.compile_assign_target_typed(
+                if let Some(op) = &assign.op {
+                    let [target] = assign.targets.as_slice() else {
+                        return Err(CodegenError::Unsupported(
+                            "Compound assignment to several targets".to_string(),
+                        ));
+                    };
+                    let result = self.compile_compound_assign(
                         &target.path,
-                        typed_value.clone(),
+                        &op.node,
+                        typed_value,
                         scope,
                         builder,
                     )?;
+                    return Ok(Some(result.value));
                 }
-                Ok(Some(result_value))
+                let literal = matches!(assign.value.node, ExprKind::Literal(Literal::Int(_)));
+                let result_value = typed_value.value;
+                let values = self.assigned_values(typed_value, assign.targets.len(), builder)?;
+                for (target, typed_value) in assign.targets.iter().zip(values) {
+                    let Some(annotation) = &target.ty else {
+                        self.compile_assign_target_typed(
+                            &target.path,
+                            typed_value.clone(),
+                            literal,
+                            scope,
+                            builder,
+                        )?;
+                        continue;
+                    };
+
+                    // An explicit annotation decides the variable's type
+                    let ty = ValueType::from_annotation(&annotation.node, self.structs, self.enums);
+                    let value = self.coerce_to(typed_value.clone(), &ty, builder);
+                    match &target.path {
+                        AssignPath::Identifier(name) => {
+                            let var = scope.declare_var_typed(&name.node, ty, builder);
+                            builder.def_var(var, value.value);
+                        }
+                        path => {
+                            self.compile_assign_target_typed(path, value, true, scope, builder)?;
+                        }
+                    }
+                }
+                Ok(Some(result_value))
             }
             StatementKind::Return(ret) => {
                 if ret.values.is_empty() {
                     let zero = builder.ins().iconst(types::I64, 0);
                     builder.ins().return_(&[zero]);
                 } else {
-                    let val = self.compile_expr(&ret.values[0], scope, builder)?;
+                    let (result, expr) = if let [value] = ret.values.as_slice() {
+                        (self.compile_expr_typed(value, scope, builder)?, Some(value))
+                    } else {
+                        (
+                            self.compile_return_values(&ret.values, scope, builder)?,
+                            None,
+                        )
+                    };
+                    let val = self.coerce_return(result, expr, builder)?;
                     builder.ins().return_(&[val]);
                 }
                 // Create an unreachable block to switch to after return
@@ -1990,10 +3414,7 @@ impl<'a> FunctionCompiler<'a> {
                             self.compile_block(block, scope, builder)?;
                         }
                         haira_ast::ElseBranch::ElseIf(else_if) => {
-                            let else_if_stmt = Statement {
-                                node: StatementKind::If(else_if.node.clone()),
-                                span: else_if.span,
-                            };
+                            let else_if_stmt = else_if.clone().map(StatementKind::If);
                             self.compile_statement(&else_if_stmt, scope, builder)?;
                         }
                     }
@@ -2007,33 +3428,30 @@ impl<'a> FunctionCompiler<'a> {
                 Ok(None)
             }
             StatementKind::While(while_stmt) => {
-                // For while loops, we need to collect all variables that might be modified
-                // in the loop and pass them as block parameters to handle SSA properly.
+                // For while loops, the variables modified in the loop are passed as
+                // block parameters of the header to handle SSA properly.
                 //
                 // Strategy: Use a pre-header block to get initial values, then use block
                 // parameters for the loop header to handle the phi nodes.
 
-                // Collect all variables currently in scope - they may be modified in the loop
-                let loop_vars: Vec<(SmolStr, Variable)> = scope
-                    .variables
+                // Collect the variables in scope that the body assigns to
+                let loop_vars: Vec<Binding> = assigned_names(&while_stmt.body)
                     .iter()
-                    .map(|(name, &var)| (name.clone(), var))
+                    .filter_map(|name| scope.binding(name).cloned())
                     .collect();
 
                 let header_block = builder.create_block();
                 let body_block = builder.create_block();
                 let exit_block = builder.create_block();
 
-                // Add block parameters for all variables that might be used in loop
-                for _ in &loop_vars {
-                    builder.append_block_param(header_block, types::I64);
+                // Add block parameters, of each variable's own type
+                for binding in &loop_vars {
+                    builder.append_block_param(header_block, binding.ty.cranelift_type());
                 }
 
                 // Get current values of all loop variables and jump to header
-                let initial_values: Vec<Value> = loop_vars
-                    .iter()
-                    .map(|(_, var)| builder.use_var(*var))
-                    .collect();
+                let initial_values: Vec<Value> =
+                    loop_vars.iter().map(|b| builder.use_var(b.var)).collect();
                 builder.ins().jump(header_block, &initial_values);
 
                 // Header block - DON'T seal yet, we need to add the back-edge from body
@@ -2041,8 +3459,8 @@ impl<'a> FunctionCompiler<'a> {
 
                 // Update variables with block parameters (phi values)
                 let header_params = builder.block_params(header_block).to_vec();
-                for (i, (_, var)) in loop_vars.iter().enumerate() {
-                    builder.def_var(*var, header_params[i]);
+                for (binding, &param) in loop_vars.iter().zip(&header_params) {
+                    builder.def_var(binding.var, param);
                 }
 
                 // Compile condition
@@ -2052,13 +3470,18 @@ impl<'a> FunctionCompiler<'a> {
                 // Body block - seal it since it only has one predecessor (header)
                 builder.switch_to_block(body_block);
                 builder.seal_block(body_block);
-                self.compile_block(&while_stmt.body, scope, builder)?;
+                self.loops.push(LoopContext {
+                    continue_block: header_block,
+                    continue_vars: loop_vars.iter().map(|b| b.var).collect(),
+                    break_block: exit_block,
+                });
+                let body = self.compile_block(&while_stmt.body, scope, builder);
+                self.loops.pop();
+                body?;
 
                 // Get current values after body and jump back to header
-                let loop_values: Vec<Value> = loop_vars
-                    .iter()
-                    .map(|(_, var)| builder.use_var(*var))
-                    .collect();
+                let loop_values: Vec<Value> =
+                    loop_vars.iter().map(|b| builder.use_var(b.var)).collect();
                 builder.ins().jump(header_block, &loop_values);
 
                 // NOW seal header - after the back-edge has been added
@@ -2085,11 +3508,14 @@ impl<'a> FunctionCompiler<'a> {
                                 "Only single variable for loops supported".to_string(),
                             ));
                         };
+                    // The loop variable lives only as long as the loop
+                    scope.push_scope();
                     let loop_var = scope.declare_var(&loop_var_name, builder);
                     builder.def_var(loop_var, start);
 
                     let header_block = builder.create_block();
                     let body_block = builder.create_block();
+                    let increment_block = builder.create_block();
                     let exit_block = builder.create_block();
 
                     // Jump to header (first predecessor)
@@ -2112,9 +3538,19 @@ impl<'a> FunctionCompiler<'a> {
                     // Body - seal since only predecessor is header
                     builder.switch_to_block(body_block);
                     builder.seal_block(body_block);
-                    self.compile_block(&for_stmt.body, scope, builder)?;
-
-                    // Increment
+                    self.loops.push(LoopContext {
+                        continue_block: increment_block,
+                        continue_vars: Vec::new(),
+                        break_block: exit_block,
+                    });
+                    let body = self.compile_block(&for_stmt.body, scope, builder);
+                    self.loops.pop();
+                    body?;
+                    builder.ins().jump(increment_block, &[]);
+
+                    // Increment - reached from the end of the body and from `continue`
+                    builder.switch_to_block(increment_block);
+                    builder.seal_block(increment_block);
                     let current = builder.use_var(loop_var);
                     let one = builder.ins().iconst(types::I64, 1);
                     let next = builder.ins().iadd(current, one);
@@ -2128,16 +3564,114 @@ impl<'a> FunctionCompiler<'a> {
                     // Exit block - seal since only predecessor is header
                     builder.switch_to_block(exit_block);
                     builder.seal_block(exit_block);
+                    scope.pop_scope();
                 } else {
-                    return Err(CodegenError::Unsupported(
-                        "Only range-based for loops are currently supported".to_string(),
-                    ));
+                    // List iteration: for x in [1, 2, 3]
+                    let list = self.compile_expr_typed(&for_stmt.iterator, scope, builder)?;
+                    let elem_ty = match list.ty {
+                        ValueType::List(elem_ty) => *elem_ty,
+                        // Lists of unknown origin hold plain words
+                        ValueType::Int => ValueType::Int,
+                        _ => {
+                            return Err(CodegenError::Unsupported(
+                                "For loops can only iterate over ranges and lists".to_string(),
+                            ));
+                        }
+                    };
+
+                    let loop_var_name =
+                        if let haira_ast::ForPattern::Single(name) = &for_stmt.pattern {
+                            name.node.clone()
+                        } else {
+                            return Err(CodegenError::Unsupported(
+                                "Only single variable for loops supported".to_string(),
+                            ));
+                        };
+
+                    let len = builder.ins().load(
+                        types::I64,
+                        MemFlags::new(),
+                        list.value,
+                        LIST_LEN_OFFSET,
+                    );
+                    let index_var = scope.declare_anonymous_var(ValueType::Int, builder);
+                    let zero = builder.ins().iconst(types::I64, 0);
+                    builder.def_var(index_var, zero);
+                    scope.push_scope();
+                    let loop_var =
+                        scope.declare_var_typed(&loop_var_name, elem_ty.clone(), builder);
+
+                    let header_block = builder.create_block();
+                    let body_block = builder.create_block();
+                    let increment_block = builder.create_block();
+                    let exit_block = builder.create_block();
+
+                    builder.ins().jump(header_block, &[]);
+
+                    // Header - sealed after the back-edge is added
+                    builder.switch_to_block(header_block);
+                    let index = builder.use_var(index_var);
+                    let cmp = builder.ins().icmp(IntCC::SignedLessThan, index, len);
+                    builder.ins().brif(cmp, body_block, &[], exit_block, &[]);
+
+                    // Body - bind the current element, then run the loop body
+                    builder.switch_to_block(body_block);
+                    builder.seal_block(body_block);
+                    let index = builder.use_var(index_var);
+                    let elem = self.load_list_element(list.value, index, &elem_ty, builder);
+                    builder.def_var(loop_var, elem);
+                    self.loops.push(LoopContext {
+                        continue_block: increment_block,
+                        continue_vars: Vec::new(),
+                        break_block: exit_block,
+                    });
+                    let body = self.compile_block(&for_stmt.body, scope, builder);
+                    self.loops.pop();
+                    body?;
+                    builder.ins().jump(increment_block, &[]);
+
+                    // Increment - reached from the end of the body and from `continue`
+                    builder.switch_to_block(increment_block);
+                    builder.seal_block(increment_block);
+                    let index = builder.use_var(index_var);
+                    let next = builder.ins().iadd_imm(index, 1);
+                    builder.def_var(index_var, next);
+                    builder.ins().jump(header_block, &[]);
+                    builder.seal_block(header_block);
+
+                    builder.switch_to_block(exit_block);
+                    builder.seal_block(exit_block);
+                    scope.pop_scope();
+                }
+
+                Ok(None)
+            }
+            StatementKind::Break | StatementKind::Continue => {
+                let is_break = matches!(stmt.node, StatementKind::Break);
+                let Some(loop_ctx) = self.loops.last() else {
+                    return Err(CodegenError::Unsupported(format!(
+                        "`{}` outside of a loop",
+                        if is_break { "break" } else { "continue" }
+                    )));
+                };
+
+                if is_break {
+                    builder.ins().jump(loop_ctx.break_block, &[]);
+                } else {
+                    let args: Vec<Value> = loop_ctx
+                        .continue_vars
+                        .iter()
+                        .map(|var| builder.use_var(*var))
+                        .collect();
+                    builder.ins().jump(loop_ctx.continue_block, &args);
                 }
 
+                // Like `return`, continue in a fresh unreachable block
+                let unreachable_block = builder.create_block();
+                builder.switch_to_block(unreachable_block);
+                builder.seal_block(unreachable_block);
                 Ok(None)
             }
-            StatementKind::Break => Ok(None),
-            StatementKind::Continue => Ok(None),
             StatementKind::Match(match_expr) => {
                 // Match as statement - compile as expression and discard result
                 let _val = self.compile_match_expr(match_expr, scope, builder)?;
@@ -2149,6 +3683,7 @@ impl<'a> FunctionCompiler<'a> {
                 // 2. Execute try body
                 // 3. Check for error
                 // 4. If error, bind error to variable and execute catch body
+                // 5. Clear the error again, so a later `?` can't see a stale one
 
                 let clear_error_id = *self.functions.get(&SmolStr::from("clear_error")).unwrap();
                 let clear_error_func = self
@@ -2186,34 +3721,110 @@ impl<'a> FunctionCompiler<'a> {
                 let call = builder.ins().call(get_error_func, &[]);
                 let err_val = builder.inst_results(call)[0];
 
-                let err_var = scope.get_or_declare_var(&try_stmt.error_name.node, builder);
+                scope.push_scope();
+                let err_var = scope.declare_var(&try_stmt.error_name.node, builder);
                 builder.def_var(err_var, err_val);
 
                 // Compile catch body
                 self.compile_block(&try_stmt.catch_body, scope, builder)?;
+                scope.pop_scope();
                 builder.ins().jump(continue_block, &[]);
 
-                // Continue block
+                // Continue block, after a clean try or the catch body
                 builder.switch_to_block(continue_block);
                 builder.seal_block(continue_block);
+                builder.ins().call(clear_error_func, &[]);
 
                 Ok(None)
             }
         }
     }
 
+    /// Convert a value to `ty` where ints and floats differ.
+    fn coerce_to(
+        &self,
+        tv: TypedValue,
+        ty: &ValueType,
+        builder: &mut FunctionBuilder,
+    ) -> TypedValue {
+        match ty {
+            ValueType::Float => self.coerce_to_float(tv, builder),
+            ValueType::Int => self.coerce_to_int(tv, builder),
+            ValueType::Bool => match tv.ty {
+                ValueType::Int | ValueType::Float => TypedValue {
+                    ty: ValueType::Bool,
+                    ..self.coerce_to_int(tv, builder)
+                },
+                _ => tv,
+            },
+            ValueType::Ptr
+            | ValueType::Struct(_)
+            | ValueType::List(_)
+            | ValueType::Map
+            | ValueType::Enum(_)
+            | ValueType::Tuple(_) => tv,
+        }
+    }
+
+    /// Convert a returned value to the declared return type.
+    ///
+    /// Ints, bools and floats convert into each other. Values of other types
+    /// must match the declared type, except that an `int` return may hold any
+    /// value, since unannotated functions return ints. For the same reason an
+    /// `Int` may be an untyped word holding any value, so only an int literal
+    /// `expr` is rejected when a string, struct or other pointer is declared.
+    fn coerce_return(
+        &self,
+        tv: TypedValue,
+        expr: Option<&Expr>,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Value, CodegenError> {
+        let literal = matches!(
+            expr.map(|expr| &expr.node),
+            Some(ExprKind::Literal(Literal::Int(_)))
+        );
+        let compatible = match (&tv.ty, &self.returns) {
+            (_, ValueType::Int) => true,
+            (ValueType::Int, _) if !literal => true,
+            (
+                ValueType::Int | ValueType::Bool | ValueType::Float,
+                ValueType::Float | ValueType::Bool,
+            ) => true,
+            (ValueType::Struct(a), ValueType::Struct(b))
+            | (ValueType::Enum(a), ValueType::Enum(b)) => a == b,
+            (ValueType::List(_), ValueType::List(_))
+            | (ValueType::Ptr, ValueType::Ptr)
+            | (ValueType::Map, ValueType::Map) => true,
+            (ValueType::Tuple(a), ValueType::Tuple(b)) => a.len() == b.len(),
+            _ => false,
+        };
+        if !compatible {
+            return Err(CodegenError::TypeMismatch(format!(
+                "cannot return {} from a function returning {}",
+                tv.ty, self.returns
+            )));
+        }
+        Ok(self.coerce_to(tv, &self.returns, builder).value)
+    }
+
     /// Convert a value to float if it's an integer.
     fn coerce_to_float(&self, tv: TypedValue, builder: &mut FunctionBuilder) -> TypedValue {
         match tv.ty {
             ValueType::Float => tv,
-            ValueType::Int => {
+            ValueType::Int | ValueType::Bool => {
                 let float_val = builder.ins().fcvt_from_sint(types::F64, tv.value);
                 TypedValue {
                     value: float_val,
                     ty: ValueType::Float,
                 }
             }
-            ValueType::Ptr | ValueType::Struct(_) => tv, // Can't coerce pointers or structs
+            // Can't coerce pointers, structs, lists, maps, enums or tuples
+            ValueType::Ptr
+            | ValueType::Struct(_)
+            | ValueType::List(_)
+            | ValueType::Map
+            | ValueType::Enum(_)
+            | ValueType::Tuple(_) => tv,
         }
     }
 
@@ -2228,7 +3839,17 @@ impl<'a> FunctionCompiler<'a> {
                     ty: ValueType::Int,
                 }
             }
-            ValueType::Ptr | ValueType::Struct(_) => tv, // Can't coerce pointers or structs
+            ValueType::Bool => TypedValue {
+                value: tv.value,
+                ty: ValueType::Int,
+            },
+            // Can't coerce pointers, structs, lists, maps, enums or tuples
+            ValueType::Ptr
+            | ValueType::Struct(_)
+            | ValueType::List(_)
+            | ValueType::Map
+            | ValueType::Enum(_)
+            | ValueType::Tuple(_) => tv,
         }
     }
 
@@ -2248,10 +3869,18 @@ impl<'a> FunctionCompiler<'a> {
                         value: builder.use_var(var),
                         ty,
                     })
+                } else if let Some(value) = self.constants.get(name).cloned() {
+                    self.compile_literal_typed(&value, scope, builder)
                 } else {
-                    Err(CodegenError::UndefinedVariable(name.to_string()))
+                    self.compile_variant(name, &[], scope, builder)
                 }
             }
+            ExprKind::Binary(bin) if matches!(bin.op.node, BinaryOp::And | BinaryOp::Or) => {
+                self.compile_logical_op(bin, scope, builder)
+            }
+            ExprKind::Binary(bin) if bin.op.node == BinaryOp::Coalesce => {
+                self.compile_default(bin, scope, builder)
+            }
             ExprKind::Binary(bin) => {
                 let left = self.compile_expr_typed(&bin.left, scope, builder)?;
                 let right = self.compile_expr_typed(&bin.right, scope, builder)?;
@@ -2262,28 +3891,20 @@ impl<'a> FunctionCompiler<'a> {
                 self.compile_unary_op_typed(&unary.op.node, operand, builder)
             }
             ExprKind::Call(call) => self.compile_call_typed(call, scope, builder),
+            ExprKind::Match(match_expr) => self.compile_match_expr(match_expr, scope, builder),
+            ExprKind::If(if_stmt) => self.compile_if_expr(if_stmt, scope, builder),
+            ExprKind::Block(block) => self.compile_block_value(block, scope, builder),
             ExprKind::Field(field_expr) => {
-                // Field access: look up the field type from the struct definition
-                let field_name = &field_expr.field.node;
-
-                // Find the struct and field type by checking all known structs
-                let mut field_type = ValueType::Int; // Default to Int
-                for (_, struct_info) in self.structs.iter() {
-                    if let Some(field_idx) = struct_info.fields.iter().position(|f| f == field_name)
-                    {
-                        // Found the field - get its type
-                        if field_idx < struct_info.field_types.len() {
-                            field_type = struct_info.field_types[field_idx].clone();
-                        }
-                        break;
-                    }
-                }
-
-                let value = self.compile_expr(expr, scope, builder)?;
-                Ok(TypedValue {
-                    value,
-                    ty: field_type,
-                })
+                // Field access: look up the field in the object's struct definition
+                let object = self.compile_expr_typed(&field_expr.object, scope, builder)?;
+                let (offset, ty) = self.field_slot(&object.ty, &field_expr.field.node)?;
+                let value = builder.ins().load(
+                    ty.cranelift_type(),
+                    MemFlags::new(),
+                    object.value,
+                    offset as i32,
+                );
+                Ok(TypedValue { value, ty })
             }
             ExprKind::Instance(instance) => {
                 // Struct instantiation - return the struct type
@@ -2294,6 +3915,22 @@ impl<'a> FunctionCompiler<'a> {
                     ty: ValueType::Struct(type_name),
                 })
             }
+            ExprKind::MethodCall(method_call) => {
+                self.compile_method_call_typed(method_call, scope, builder)
+            }
+            ExprKind::List(elements) => self.compile_list(elements, scope, builder),
+            ExprKind::Tuple(elements) => self.compile_tuple(elements, scope, builder),
+            ExprKind::Index(index_expr) => {
+                let list = self.compile_expr_typed(&index_expr.object, scope, builder)?;
+                let elem_ty = match list.ty {
+                    ValueType::List(elem_ty) => *elem_ty,
+                    // Unknown object type - elements are plain words
+                    _ => ValueType::Int,
+                };
+                let index = self.compile_expr(&index_expr.index, scope, builder)?;
+                let value = self.load_list_element(list.value, index, &elem_ty, builder);
+                Ok(TypedValue { value, ty: elem_ty })
+            }
             // For other expression types, fall back to untyped compilation
             _ => {
                 let value = self.compile_expr(expr, scope, builder)?;
@@ -2305,107 +3942,717 @@ impl<'a> FunctionCompiler<'a> {
         }
     }
 
-    /// Compile a literal with type information.
-    fn compile_literal_typed(
+    /// Compile a method call, dispatching on the receiver's type.
+    ///
+    /// Struct and enum receivers call `{Type}_{method}`. String receivers call the
+    /// builtin of the same name with the receiver as first argument, so
+    /// `s.upper()` is `upper(s)`. A type name as the receiver, as in
+    /// `User.from_json(s)`, calls `{Type}_{method}` with no receiver.
+    fn compile_method_call_typed(
         &mut self,
-        lit: &Literal,
+        method_call: &haira_ast::MethodCallExpr,
         scope: &mut FunctionScope,
         builder: &mut FunctionBuilder,
     ) -> Result<TypedValue, CodegenError> {
-        match lit {
-            Literal::Int(n) => Ok(TypedValue {
-                value: builder.ins().iconst(types::I64, *n),
-                ty: ValueType::Int,
-            }),
-            Literal::Float(n) => Ok(TypedValue {
-                value: builder.ins().f64const(*n),
-                ty: ValueType::Float,
-            }),
-            Literal::Bool(b) => Ok(TypedValue {
-                value: builder.ins().iconst(types::I64, if *b { 1 } else { 0 }),
-                ty: ValueType::Int,
-            }),
-            Literal::String(s) => {
-                // Create a HairaString* from the static string data
-                // This ensures consistent string handling throughout the codebase
-                let data_id = self.define_string(s)?;
-                let local_id = self.module.declare_data_in_func(data_id, builder.func);
-                let ptr = builder.ins().symbol_value(self.ptr_type, local_id);
-                let len = builder.ins().iconst(types::I64, s.len() as i64);
+        if let ExprKind::Identifier(type_name) = &method_call.receiver.node {
+            let is_type =
+                self.structs.contains_key(type_name) || self.enums.contains_key(type_name);
+            if is_type && scope.get_var(type_name).is_none() {
+                return self.compile_static_call(type_name, method_call, scope, builder);
+            }
+        }
 
-                // Call haira_string_from_static to wrap in HairaString*
-                let string_from_static_id = *self
-                    .functions
-                    .get(&SmolStr::from("string_from_static"))
-                    .unwrap();
-                let string_from_static_func = self
-                    .module
-                    .declare_func_in_func(string_from_static_id, builder.func);
-                let call = builder.ins().call(string_from_static_func, &[ptr, len]);
-                let haira_string_ptr = builder.inst_results(call)[0];
+        let receiver = self.compile_expr_typed(&method_call.receiver, scope, builder)?;
+        let method_name = &method_call.method.node;
 
-                Ok(TypedValue {
-                    value: haira_string_ptr,
-                    ty: ValueType::Ptr,
-                })
+        // Options are tagged words, so their methods read the tag
+        let is_option_method = matches!(method_name.as_str(), "is_some" | "is_none");
+        if is_option_method && receiver.ty == ValueType::Int && method_call.args.is_empty() {
+            let is_some = self.compile_option_is_some(receiver.value, builder);
+            let value = if method_name == "is_some" {
+                is_some
+            } else {
+                builder.ins().bxor_imm(is_some, 1)
+            };
+            return Ok(TypedValue {
+                value,
+                ty: ValueType::Bool,
+            });
+        }
+
+        let (full_method_name, func_id) = match &receiver.ty {
+            ValueType::Struct(type_name) | ValueType::Enum(type_name) => {
+                let full_method_name = SmolStr::from(format!("{}_{}", type_name, method_name));
+                let func_id = *self.functions.get(&full_method_name).ok_or_else(|| {
+                    CodegenError::UndefinedFunction(format!(
+                        "Method {} not found on type {}",
+                        method_name, type_name
+                    ))
+                })?;
+                (full_method_name, func_id)
             }
-            Literal::InterpolatedString(parts) => {
-                let value = self.compile_interpolated_string(parts, scope, builder)?;
-                Ok(TypedValue {
-                    value,
-                    ty: ValueType::Ptr,
-                })
+            ValueType::Ptr => {
+                if !self.functions.contains_key(method_name) {
+                    return Err(CodegenError::UndefinedFunction(format!(
+                        "Method {} not found on string",
+                        method_name
+                    )));
+                }
+
+                // Bind the receiver so it is evaluated once, then call the builtin
+                let receiver_name = SmolStr::from("$receiver");
+                let var = scope.declare_var_typed(&receiver_name, ValueType::Ptr, builder);
+                builder.def_var(var, receiver.value);
+                let span = method_call.receiver.span;
+                let mut args = vec![haira_ast::Argument {
+                    name: None,
+                    value: Expr::new(ExprKind::Identifier(receiver_name), span),
+                    span,
+                }];
+                args.extend(method_call.args.iter().cloned());
+                let call = haira_ast::CallExpr {
+                    callee: Box::new(Expr::new(
+                        ExprKind::Identifier(method_name.clone()),
+                        method_call.method.span,
+                    )),
+                    args,
+                };
+                return self.compile_call_typed(&call, scope, builder);
+            }
+            ValueType::List(elem_ty) => {
+                return self.compile_list_method(
+                    receiver.value,
+                    elem_ty,
+                    method_call,
+                    scope,
+                    builder,
+                );
             }
+            ValueType::Map => {
+                return self.compile_map_method(receiver.value, method_call, scope, builder);
+            }
+            // Untyped receivers resolve only when a single struct defines the method
+            ValueType::Int => {
+                let candidates: Vec<(&SmolStr, SmolStr, FuncId)> = self
+                    .structs
+                    .keys()
+                    .filter_map(|type_name| {
+                        let full_method_name =
+                            SmolStr::from(format!("{}_{}", type_name, method_name));
+                        let func_id = self.functions.get(&full_method_name).copied()?;
+                        Some((type_name, full_method_name, func_id))
+                    })
+                    .collect();
+                match candidates.as_slice() {
+                    [] => {
+                        return Err(CodegenError::UndefinedFunction(format!(
+                            "Method {} not found",
+                            method_name
+                        )))
+                    }
+                    [(_, full_method_name, func_id)] => (full_method_name.clone(), *func_id),
+                    _ => {
+                        let type_names: Vec<&str> =
+                            candidates.iter().map(|(name, ..)| name.as_str()).collect();
+                        return Err(CodegenError::InvalidCall(format!(
+                            "method {} on a value that is not a known struct is ambiguous; \
+                             it is defined on {}",
+                            method_name,
+                            type_names.join(", ")
+                        )));
+                    }
+                }
+            }
+            _ => {
+                return Err(CodegenError::UndefinedFunction(format!(
+                    "Method {} not found on a value that is not a struct",
+                    method_name
+                )));
+            }
+        };
+
+        let local_callee = self.module.declare_func_in_func(func_id, builder.func);
+
+        // First argument is self (the receiver), then other args
+        let mut args = vec![receiver.value];
+        args.extend(self.compile_method_args(&full_method_name, method_call, scope, builder)?);
+
+        let call_inst = builder.ins().call(local_callee, &args);
+        let results = builder.inst_results(call_inst);
+        let value = if results.is_empty() {
+            builder.ins().iconst(types::I64, 0)
+        } else {
+            results[0]
+        };
+        Ok(TypedValue {
+            value,
+            ty: self.method_returns(&full_method_name),
+        })
+    }
+
+    /// Compile the arguments of a call to the method `Type_method`, after
+    /// the receiver.
+    fn compile_method_args(
+        &mut self,
+        full_method_name: &SmolStr,
+        method_call: &haira_ast::MethodCallExpr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Vec<Value>, CodegenError> {
+        let sig = self
+            .func_signatures
+            .get(full_method_name)
+            .ok_or_else(|| CodegenError::UndefinedFunction(full_method_name.to_string()))?
+            .without_receiver();
+        self.compile_args(full_method_name, &sig, &method_call.args, scope, builder)
+    }
+
+    /// The declared return type of the method `Type_method`.
+    fn method_returns(&self, full_method_name: &SmolStr) -> ValueType {
+        self.func_signatures
+            .get(full_method_name)
+            .map_or(ValueType::Int, |sig| sig.returns.clone())
+    }
+
+    /// Compile `Type.method(args)`, a call to a method that doesn't use `self`.
+    ///
+    /// Methods all take the receiver first, so a null one is passed.
+    fn compile_static_call(
+        &mut self,
+        type_name: &SmolStr,
+        method_call: &haira_ast::MethodCallExpr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let method_name = &method_call.method.node;
+        let full_method_name = SmolStr::from(format!("{}_{}", type_name, method_name));
+        let func_id = *self.functions.get(&full_method_name).ok_or_else(|| {
+            CodegenError::UndefinedFunction(format!(
+                "Method {} not found on type {}",
+                method_name, type_name
+            ))
+        })?;
+        if !self.static_methods.contains(&full_method_name) {
+            return Err(CodegenError::InvalidCall(format!(
+                "method `{}.{}` uses `self`, so it must be called on a value",
+                type_name, method_name
+            )));
         }
+
+        let local_callee = self.module.declare_func_in_func(func_id, builder.func);
+        let mut args = vec![builder.ins().iconst(self.ptr_type, 0)];
+        args.extend(self.compile_method_args(&full_method_name, method_call, scope, builder)?);
+
+        let call_inst = builder.ins().call(local_callee, &args);
+        let value = builder.inst_results(call_inst)[0];
+        Ok(TypedValue {
+            value,
+            ty: self.method_returns(&full_method_name),
+        })
     }
 
-    /// Compile a binary operation with type awareness.
-    fn compile_binary_op_typed(
+    /// Compile `push`, `pop`, `get` or `len` on a list.
+    fn compile_list_method(
         &mut self,
-        op: &BinaryOp,
-        left: TypedValue,
-        right: TypedValue,
+        list: Value,
+        elem_ty: &ValueType,
+        method_call: &haira_ast::MethodCallExpr,
+        scope: &mut FunctionScope,
         builder: &mut FunctionBuilder,
     ) -> Result<TypedValue, CodegenError> {
-        // If either operand is float, promote both to float
-        let (left, right, result_ty) =
-            if left.ty == ValueType::Float || right.ty == ValueType::Float {
-                let left = self.coerce_to_float(left, builder);
-                let right = self.coerce_to_float(right, builder);
-                (left, right, ValueType::Float)
-            } else {
-                (left, right, ValueType::Int)
-            };
+        let method_name = method_call.method.node.as_str();
+        let (runtime_name, arg_count) = match method_name {
+            "push" => ("array_push", 1),
+            "pop" => ("array_pop", 0),
+            "get" => ("array_get", 1),
+            "len" => ("array_len", 0),
+            _ => {
+                return Err(CodegenError::UndefinedFunction(format!(
+                    "Method {} not found on list",
+                    method_name
+                )))
+            }
+        };
+        if method_call.args.len() != arg_count {
+            return Err(CodegenError::Unsupported(format!(
+                "List method {} takes {} argument(s)",
+                method_name, arg_count
+            )));
+        }
 
-        let value = match result_ty {
-            ValueType::Float => match op {
-                BinaryOp::Add => builder.ins().fadd(left.value, right.value),
-                BinaryOp::Sub => builder.ins().fsub(left.value, right.value),
-                BinaryOp::Mul => builder.ins().fmul(left.value, right.value),
-                BinaryOp::Div => builder.ins().fdiv(left.value, right.value),
-                BinaryOp::Mod => {
-                    // F64 modulo: a - floor(a/b) * b
-                    let div = builder.ins().fdiv(left.value, right.value);
-                    let floored = builder.ins().floor(div);
-                    let mult = builder.ins().fmul(floored, right.value);
-                    builder.ins().fsub(left.value, mult)
+        let mut args = vec![list];
+        if let Some(arg) = method_call.args.first() {
+            let value = self.compile_expr_typed(&arg.value, scope, builder)?;
+            let value = match method_name {
+                // Pushed values are stored in the list's element type
+                "push" => {
+                    let value = self.coerce_to(value, elem_ty, builder);
+                    Self::to_word(value.value, elem_ty, builder)
                 }
-                // Comparison operators return int (0 or 1)
-                BinaryOp::Eq => {
-                    let cmp = builder.ins().fcmp(FloatCC::Equal, left.value, right.value);
-                    return Ok(TypedValue {
-                        value: builder.ins().uextend(types::I64, cmp),
-                        ty: ValueType::Int,
-                    });
+                _ => self.coerce_to_int(value, builder).value,
+            };
+            args.push(value);
+        }
+
+        let func_id = *self.functions.get(&SmolStr::from(runtime_name)).unwrap();
+        let func = self.module.declare_func_in_func(func_id, builder.func);
+        let call = builder.ins().call(func, &args);
+
+        Ok(match method_name {
+            "pop" | "get" => {
+                let word = builder.inst_results(call)[0];
+                TypedValue {
+                    value: Self::from_word(word, elem_ty, builder),
+                    ty: elem_ty.clone(),
                 }
-                BinaryOp::Ne => {
-                    let cmp = builder
-                        .ins()
-                        .fcmp(FloatCC::NotEqual, left.value, right.value);
-                    return Ok(TypedValue {
-                        value: builder.ins().uextend(types::I64, cmp),
-                        ty: ValueType::Int,
-                    });
+            }
+            "len" => TypedValue {
+                value: builder.inst_results(call)[0],
+                ty: ValueType::Int,
+            },
+            _ => TypedValue {
+                value: builder.ins().iconst(types::I64, 0),
+                ty: ValueType::Int,
+            },
+        })
+    }
+
+    /// Compile `get`, `set`, `has` or `remove` on a map.
+    ///
+    /// Keys are strings and values are integers. Getting a missing key
+    /// sets the runtime error flag and yields 0.
+    fn compile_map_method(
+        &mut self,
+        map: Value,
+        method_call: &haira_ast::MethodCallExpr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let method_name = method_call.method.node.as_str();
+        let arg_count = match method_name {
+            "get" | "has" | "remove" => 1,
+            "set" => 2,
+            _ => {
+                return Err(CodegenError::UndefinedFunction(format!(
+                    "Method {} not found on map",
+                    method_name
+                )))
+            }
+        };
+        if method_call.args.len() != arg_count {
+            return Err(CodegenError::Unsupported(format!(
+                "Map method {} takes {} argument(s)",
+                method_name, arg_count
+            )));
+        }
+
+        let (key_ptr, key_len) =
+            self.get_string_ptr_len(&method_call.args[0].value, scope, builder)?;
+        let mut args = vec![map, key_ptr, key_len];
+        if let Some(arg) = method_call.args.get(1) {
+            let value = self.compile_expr_typed(&arg.value, scope, builder)?;
+            args.push(self.coerce_to_int(value, builder).value);
+        }
+
+        let runtime_name = SmolStr::from(format!("map_{}", method_name));
+        let func_id = *self.functions.get(&runtime_name).unwrap();
+        let func = self.module.declare_func_in_func(func_id, builder.func);
+        let call = builder.ins().call(func, &args);
+
+        Ok(match method_name {
+            "set" => TypedValue {
+                value: builder.ins().iconst(types::I64, 0),
+                ty: ValueType::Int,
+            },
+            "has" => TypedValue {
+                value: builder.inst_results(call)[0],
+                ty: ValueType::Bool,
+            },
+            _ => TypedValue {
+                value: builder.inst_results(call)[0],
+                ty: ValueType::Int,
+            },
+        })
+    }
+
+    /// Compile a list literal.
+    ///
+    /// Lists are runtime `HairaArray`s: a `{len, cap, data}` header whose
+    /// `data` buffer holds one 8-byte slot per element. Elements share a
+    /// single type: a list with any float element is a float list,
+    /// otherwise the first element decides.
+    fn compile_list(
+        &mut self,
+        elements: &[Expr],
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let values = elements
+            .iter()
+            .map(|elem| self.compile_expr_typed(elem, scope, builder))
+            .collect::<Result<Vec<_>, _>>()?;
+        let elem_ty = if values.iter().any(|v| v.ty == ValueType::Float) {
+            ValueType::Float
+        } else {
+            values.first().map_or(ValueType::Int, |v| v.ty.clone())
+        };
+
+        Ok(TypedValue {
+            value: self.build_list(values, &elem_ty, builder),
+            ty: ValueType::List(Box::new(elem_ty)),
+        })
+    }
+
+    /// Compile a tuple literal: a length word followed by one word per
+    /// element, each keeping its own type.
+    fn compile_tuple(
+        &mut self,
+        elements: &[Expr],
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let values = elements
+            .iter()
+            .map(|elem| self.compile_expr_typed(elem, scope, builder))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.build_tuple(values, builder))
+    }
+
+    /// Compile the values of `return a, b` into a tuple, each converted to
+    /// its element of the declared return type.
+    fn compile_return_values(
+        &mut self,
+        values: &[Expr],
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let element_types = match &self.returns {
+            ValueType::Tuple(element_types) if element_types.len() == values.len() => {
+                element_types.clone()
+            }
+            returns => {
+                return Err(CodegenError::TypeMismatch(format!(
+                    "cannot return {} values from a function returning {}",
+                    values.len(),
+                    returns
+                )))
+            }
+        };
+        let mut values = values
+            .iter()
+            .map(|value| self.compile_expr_typed(value, scope, builder))
+            .collect::<Result<Vec<_>, CodegenError>>()?;
+        match &mut self.returned {
+            Some(returned) => returned.push(values.iter().map(|v| v.ty.clone()).collect()),
+            None => {
+                values = values
+                    .into_iter()
+                    .zip(&element_types)
+                    .map(|(value, ty)| self.coerce_to(value, ty, builder))
+                    .collect();
+            }
+        }
+        Ok(self.build_tuple(values, builder))
+    }
+
+    /// Allocate a tuple holding `values`.
+    fn build_tuple(
+        &mut self,
+        values: Vec<TypedValue>,
+        builder: &mut FunctionBuilder,
+    ) -> TypedValue {
+        let alloc_id = *self.functions.get(&SmolStr::from("alloc")).unwrap();
+        let alloc_func = self.module.declare_func_in_func(alloc_id, builder.func);
+        let size = builder
+            .ins()
+            .iconst(types::I64, 8 * (values.len() as i64 + 1));
+        let call = builder.ins().call(alloc_func, &[size]);
+        let ptr = builder.inst_results(call)[0];
+
+        let len = builder.ins().iconst(types::I64, values.len() as i64);
+        builder.ins().store(MemFlags::new(), len, ptr, 0);
+        let mut element_types = Vec::with_capacity(values.len());
+        for (i, value) in values.into_iter().enumerate() {
+            let word = Self::to_word(value.value, &value.ty, builder);
+            builder
+                .ins()
+                .store(MemFlags::new(), word, ptr, tuple_offset(i));
+            element_types.push(value.ty);
+        }
+
+        TypedValue {
+            value: ptr,
+            ty: ValueType::Tuple(element_types),
+        }
+    }
+
+    /// Allocate a list holding `values`, each coerced to `elem_ty`.
+    fn build_list(
+        &mut self,
+        values: Vec<TypedValue>,
+        elem_ty: &ValueType,
+        builder: &mut FunctionBuilder,
+    ) -> Value {
+        let new_id = *self.functions.get(&SmolStr::from("array_new")).unwrap();
+        let new_func = self.module.declare_func_in_func(new_id, builder.func);
+        let call = builder.ins().call(new_func, &[]);
+        let ptr = builder.inst_results(call)[0];
+
+        let push_id = *self.functions.get(&SmolStr::from("array_push")).unwrap();
+        let push_func = self.module.declare_func_in_func(push_id, builder.func);
+        for value in values {
+            let value = self.coerce_to(value, elem_ty, builder);
+            let word = Self::to_word(value.value, elem_ty, builder);
+            builder.ins().call(push_func, &[ptr, word]);
+        }
+        ptr
+    }
+
+    /// Compute the address of the slot at `index` in a list's data buffer.
+    fn list_element_addr(
+        &mut self,
+        list: Value,
+        index: Value,
+        builder: &mut FunctionBuilder,
+    ) -> Value {
+        if self.bounds_checks {
+            self.check_bounds(list, index, builder);
+        }
+        let data = builder
+            .ins()
+            .load(types::I64, MemFlags::new(), list, LIST_DATA_OFFSET);
+        let eight = builder.ins().iconst(types::I64, 8);
+        let offset = builder.ins().imul(index, eight);
+        builder.ins().iadd(data, offset)
+    }
+
+    /// Branch to `haira_panic_index` unless `0 <= index < len`. A negative
+    /// index compares as a huge unsigned one, so one comparison covers both.
+    fn check_bounds(&mut self, list: Value, index: Value, builder: &mut FunctionBuilder) {
+        let len = builder
+            .ins()
+            .load(types::I64, MemFlags::new(), list, LIST_LEN_OFFSET);
+        let out_of_bounds = builder
+            .ins()
+            .icmp(IntCC::UnsignedGreaterThanOrEqual, index, len);
+        let panic_block = builder.create_block();
+        let continue_block = builder.create_block();
+        builder
+            .ins()
+            .brif(out_of_bounds, panic_block, &[], continue_block, &[]);
+
+        builder.switch_to_block(panic_block);
+        builder.seal_block(panic_block);
+        let panic_id = *self.functions.get(&SmolStr::from("panic_index")).unwrap();
+        let local_callee = self.module.declare_func_in_func(panic_id, builder.func);
+        builder.ins().call(local_callee, &[index, len]);
+        // The runtime aborts, so this is never reached
+        builder.ins().trap(TrapCode::HEAP_OUT_OF_BOUNDS);
+
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
+    }
+
+    /// Load the element at `index` from a list, using the element's type.
+    fn load_list_element(
+        &mut self,
+        list: Value,
+        index: Value,
+        elem_ty: &ValueType,
+        builder: &mut FunctionBuilder,
+    ) -> Value {
+        let elem_ptr = self.list_element_addr(list, index, builder);
+        builder
+            .ins()
+            .load(elem_ty.cranelift_type(), MemFlags::new(), elem_ptr, 0)
+    }
+
+    /// Reinterpret a value as the 8-byte word stored in a list slot.
+    fn to_word(value: Value, ty: &ValueType, builder: &mut FunctionBuilder) -> Value {
+        match ty {
+            ValueType::Float => builder.ins().bitcast(types::I64, MemFlags::new(), value),
+            _ => value,
+        }
+    }
+
+    /// Reinterpret a list slot word as a value of type `ty`.
+    fn from_word(word: Value, ty: &ValueType, builder: &mut FunctionBuilder) -> Value {
+        match ty {
+            ValueType::Float => builder.ins().bitcast(types::F64, MemFlags::new(), word),
+            _ => word,
+        }
+    }
+
+    /// Look up an enum variant by name, returning its enum's name and tag.
+    fn find_variant(&self, name: &str) -> Option<(SmolStr, usize, &VariantInfo)> {
+        self.enums.iter().find_map(|(enum_name, info)| {
+            info.variants
+                .iter()
+                .enumerate()
+                .find(|(_, variant)| variant.name == name)
+                .map(|(tag, variant)| (enum_name.clone(), tag, variant))
+        })
+    }
+
+    /// Construct an enum variant: `Empty` or `Circle(2.0)`.
+    fn compile_variant(
+        &mut self,
+        name: &str,
+        args: &[haira_ast::Argument],
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let (enum_name, tag, variant) = self
+            .find_variant(name)
+            .ok_or_else(|| CodegenError::UndefinedVariable(name.to_string()))?;
+        let field_types = variant.field_types.clone();
+        if args.len() != field_types.len() {
+            return Err(CodegenError::InvalidCall(format!(
+                "{} takes {} argument(s) but {} were given",
+                name,
+                field_types.len(),
+                args.len()
+            )));
+        }
+
+        let alloc_id = *self.functions.get(&SmolStr::from("alloc")).unwrap();
+        let alloc_func = self.module.declare_func_in_func(alloc_id, builder.func);
+        let size = builder
+            .ins()
+            .iconst(types::I64, 8 * (field_types.len() as i64 + 1));
+        let call = builder.ins().call(alloc_func, &[size]);
+        let ptr = builder.inst_results(call)[0];
+
+        let tag = builder.ins().iconst(types::I64, tag as i64);
+        builder.ins().store(MemFlags::new(), tag, ptr, 0);
+        for (i, (arg, ty)) in args.iter().zip(&field_types).enumerate() {
+            let value = self.compile_expr_typed(&arg.value, scope, builder)?;
+            let value = self.coerce_to(value, ty, builder);
+            let word = Self::to_word(value.value, ty, builder);
+            builder
+                .ins()
+                .store(MemFlags::new(), word, ptr, 8 * (i as i32 + 1));
+        }
+
+        Ok(TypedValue {
+            value: ptr,
+            ty: ValueType::Enum(enum_name),
+        })
+    }
+
+    /// Compile a literal with type information.
+    fn compile_literal_typed(
+        &mut self,
+        lit: &Literal,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        match lit {
+            Literal::Int(n) => Ok(TypedValue {
+                value: builder.ins().iconst(types::I64, *n),
+                ty: ValueType::Int,
+            }),
+            Literal::Float(n) => Ok(TypedValue {
+                value: builder.ins().f64const(*n),
+                ty: ValueType::Float,
+            }),
+            Literal::Bool(b) => Ok(TypedValue {
+                value: builder.ins().iconst(types::I64, if *b { 1 } else { 0 }),
+                ty: ValueType::Bool,
+            }),
+            // Code points fit in 32 bits but are widened to Int, like `char_at`
+            Literal::Char(c) => Ok(TypedValue {
+                value: builder.ins().iconst(types::I64, *c as i64),
+                ty: ValueType::Int,
+            }),
+            Literal::String(s) => {
+                // Create a HairaString* from the static string data
+                // This ensures consistent string handling throughout the codebase
+                let data_id = self.define_string(s)?;
+                let local_id = self.module.declare_data_in_func(data_id, builder.func);
+                let ptr = builder.ins().symbol_value(self.ptr_type, local_id);
+                let len = builder.ins().iconst(types::I64, s.len() as i64);
+
+                // Call haira_string_from_static to wrap in HairaString*
+                let string_from_static_id = *self
+                    .functions
+                    .get(&SmolStr::from("string_from_static"))
+                    .unwrap();
+                let string_from_static_func = self
+                    .module
+                    .declare_func_in_func(string_from_static_id, builder.func);
+                let call = builder.ins().call(string_from_static_func, &[ptr, len]);
+                let haira_string_ptr = builder.inst_results(call)[0];
+
+                Ok(TypedValue {
+                    value: haira_string_ptr,
+                    ty: ValueType::Ptr,
+                })
+            }
+            Literal::InterpolatedString(parts) => {
+                let value = self.compile_interpolated_string(parts, scope, builder)?;
+                Ok(TypedValue {
+                    value,
+                    ty: ValueType::Ptr,
+                })
+            }
+        }
+    }
+
+    /// Compile a binary operation with type awareness.
+    fn compile_binary_op_typed(
+        &mut self,
+        op: &BinaryOp,
+        left: TypedValue,
+        right: TypedValue,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        // Strings compare by contents, not by pointer
+        if left.ty == ValueType::Ptr && right.ty == ValueType::Ptr {
+            if let Some(cc) = string_comparison(op) {
+                return Ok(self.compile_string_compare(op, cc, left, right, builder));
+            }
+        }
+
+        // If either operand is float, promote both to float
+        let (left, right, result_ty) =
+            if left.ty == ValueType::Float || right.ty == ValueType::Float {
+                let left = self.coerce_to_float(left, builder);
+                let right = self.coerce_to_float(right, builder);
+                (left, right, ValueType::Float)
+            } else {
+                (left, right, ValueType::Int)
+            };
+
+        let value = match result_ty {
+            ValueType::Float => match op {
+                BinaryOp::Add => builder.ins().fadd(left.value, right.value),
+                BinaryOp::Sub => builder.ins().fsub(left.value, right.value),
+                BinaryOp::Mul => builder.ins().fmul(left.value, right.value),
+                BinaryOp::Div => builder.ins().fdiv(left.value, right.value),
+                BinaryOp::Mod => {
+                    // F64 modulo: a - floor(a/b) * b
+                    let div = builder.ins().fdiv(left.value, right.value);
+                    let floored = builder.ins().floor(div);
+                    let mult = builder.ins().fmul(floored, right.value);
+                    builder.ins().fsub(left.value, mult)
+                }
+                // Comparison operators return bools
+                BinaryOp::Eq => {
+                    let cmp = builder.ins().fcmp(FloatCC::Equal, left.value, right.value);
+                    return Ok(TypedValue {
+                        value: builder.ins().uextend(types::I64, cmp),
+                        ty: ValueType::Bool,
+                    });
+                }
+                BinaryOp::Ne => {
+                    let cmp = builder
+                        .ins()
+                        .fcmp(FloatCC::NotEqual, left.value, right.value);
+                    return Ok(TypedValue {
+                        value: builder.ins().uextend(types::I64, cmp),
+                        ty: ValueType::Bool,
+                    });
                 }
                 BinaryOp::Lt => {
                     let cmp = builder
@@ -2413,7 +4660,7 @@ impl<'a> FunctionCompiler<'a> {
                         .fcmp(FloatCC::LessThan, left.value, right.value);
                     return Ok(TypedValue {
                         value: builder.ins().uextend(types::I64, cmp),
-                        ty: ValueType::Int,
+                        ty: ValueType::Bool,
                     });
                 }
                 BinaryOp::Le => {
@@ -2422,7 +4669,7 @@ impl<'a> FunctionCompiler<'a> {
                         .fcmp(FloatCC::LessThanOrEqual, left.value, right.value);
                     return Ok(TypedValue {
                         value: builder.ins().uextend(types::I64, cmp),
-                        ty: ValueType::Int,
+                        ty: ValueType::Bool,
                     });
                 }
                 BinaryOp::Gt => {
@@ -2431,7 +4678,7 @@ impl<'a> FunctionCompiler<'a> {
                         .fcmp(FloatCC::GreaterThan, left.value, right.value);
                     return Ok(TypedValue {
                         value: builder.ins().uextend(types::I64, cmp),
-                        ty: ValueType::Int,
+                        ty: ValueType::Bool,
                     });
                 }
                 BinaryOp::Ge => {
@@ -2441,7 +4688,7 @@ impl<'a> FunctionCompiler<'a> {
                             .fcmp(FloatCC::GreaterThanOrEqual, left.value, right.value);
                     return Ok(TypedValue {
                         value: builder.ins().uextend(types::I64, cmp),
-                        ty: ValueType::Int,
+                        ty: ValueType::Bool,
                     });
                 }
                 BinaryOp::And | BinaryOp::Or => {
@@ -2458,16 +4705,34 @@ impl<'a> FunctionCompiler<'a> {
                         ty: ValueType::Int,
                     });
                 }
+                BinaryOp::BitAnd
+                | BinaryOp::BitOr
+                | BinaryOp::BitXor
+                | BinaryOp::Shl
+                | BinaryOp::Shr => {
+                    return Err(CodegenError::TypeMismatch(
+                        "bitwise operators need int operands, found float".to_string(),
+                    ));
+                }
+                BinaryOp::Coalesce => return Err(evaluated_default_operands()),
             },
-            ValueType::Int => {
+            ValueType::Int | ValueType::Bool => {
+                // Comparisons are boolean, as are logical ops on two booleans
+                let ty = if string_comparison(op).is_some()
+                    || matches!(op, BinaryOp::And | BinaryOp::Or)
+                        && left.ty == ValueType::Bool
+                        && right.ty == ValueType::Bool
+                {
+                    ValueType::Bool
+                } else {
+                    ValueType::Int
+                };
+
                 // Use existing integer binary op logic
                 let result = self.compile_binary_op(op, left.value, right.value, builder)?;
-                return Ok(TypedValue {
-                    value: result,
-                    ty: ValueType::Int,
-                });
+                return Ok(TypedValue { value: result, ty });
             }
-            ValueType::Ptr => {
+            ValueType::Ptr | ValueType::List(_) | ValueType::Map => {
                 return Err(CodegenError::Unsupported(
                     "Binary operations on pointers".to_string(),
                 ));
@@ -2477,6 +4742,16 @@ impl<'a> FunctionCompiler<'a> {
                     "Binary operations on structs".to_string(),
                 ));
             }
+            ValueType::Enum(_) => {
+                return Err(CodegenError::Unsupported(
+                    "Binary operations on enums".to_string(),
+                ));
+            }
+            ValueType::Tuple(_) => {
+                return Err(CodegenError::Unsupported(
+                    "Binary operations on tuples".to_string(),
+                ));
+            }
         };
 
         Ok(TypedValue {
@@ -2485,29 +4760,183 @@ impl<'a> FunctionCompiler<'a> {
         })
     }
 
-    /// Compile a unary operation with type awareness.
-    fn compile_unary_op_typed(
-        &self,
-        op: &UnaryOp,
-        operand: TypedValue,
+    /// Compare two strings with `==`, `!=`, `<`, `<=`, `>` or `>=`.
+    ///
+    /// Equality calls `haira_string_eq`; orderings compare the result of
+    /// `haira_string_cmp` to zero with `cc`.
+    fn compile_string_compare(
+        &mut self,
+        op: &BinaryOp,
+        cc: IntCC,
+        left: TypedValue,
+        right: TypedValue,
         builder: &mut FunctionBuilder,
-    ) -> Result<TypedValue, CodegenError> {
-        match op {
-            UnaryOp::Neg => match operand.ty {
+    ) -> TypedValue {
+        let (left_data, left_len) = self.load_string_parts(left.value, builder);
+        let (right_data, right_len) = self.load_string_parts(right.value, builder);
+        let runtime = match op {
+            BinaryOp::Eq | BinaryOp::Ne => "string_eq",
+            _ => "string_cmp",
+        };
+        let func_id = *self.functions.get(&SmolStr::from(runtime)).unwrap();
+        let func = self.module.declare_func_in_func(func_id, builder.func);
+        let call = builder
+            .ins()
+            .call(func, &[left_data, left_len, right_data, right_len]);
+        let result = builder.inst_results(call)[0];
+        let cmp = builder.ins().icmp_imm(cc, result, 0);
+        TypedValue {
+            value: builder.ins().uextend(types::I64, cmp),
+            ty: ValueType::Bool,
+        }
+    }
+
+    /// Compile `and`/`or` as control flow, evaluating the right operand only
+    /// when the left one doesn't decide the result.
+    ///
+    /// The result is a bool, passed to the merge block as a block parameter.
+    fn compile_logical_op(
+        &mut self,
+        bin: &haira_ast::BinaryExpr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let left = self.compile_truth(&bin.left, scope, builder)?;
+
+        let right_block = builder.create_block();
+        let merge_block = builder.create_block();
+        builder.append_block_param(merge_block, types::I64);
+
+        // `and` stops at a false left side, `or` at a true one
+        if bin.op.node == BinaryOp::And {
+            builder
+                .ins()
+                .brif(left, right_block, &[], merge_block, &[left]);
+        } else {
+            builder
+                .ins()
+                .brif(left, merge_block, &[left], right_block, &[]);
+        }
+
+        builder.switch_to_block(right_block);
+        builder.seal_block(right_block);
+        let right = self.compile_truth(&bin.right, scope, builder)?;
+        builder.ins().jump(merge_block, &[right]);
+
+        builder.switch_to_block(merge_block);
+        builder.seal_block(merge_block);
+        Ok(TypedValue {
+            value: builder.block_params(merge_block)[0],
+            ty: ValueType::Bool,
+        })
+    }
+
+    /// Compile `option ?? fallback`: the option's value if it is Some,
+    /// otherwise `fallback`, which is only evaluated in that case.
+    ///
+    /// Options are tagged words (see `ExprKind::Some`), so the result takes
+    /// its type from the fallback.
+    fn compile_default(
+        &mut self,
+        bin: &haira_ast::BinaryExpr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        let option = self.compile_expr(&bin.left, scope, builder)?;
+        let is_some = self.compile_option_is_some(option, builder);
+
+        let some_block = builder.create_block();
+        let fallback_block = builder.create_block();
+        let merge_block = builder.create_block();
+        builder.append_block_param(merge_block, types::I64);
+        builder
+            .ins()
+            .brif(is_some, some_block, &[], fallback_block, &[]);
+
+        builder.switch_to_block(some_block);
+        builder.seal_block(some_block);
+        let value = builder.ins().ushr_imm(option, 1);
+        builder.ins().jump(merge_block, &[value]);
+
+        builder.switch_to_block(fallback_block);
+        builder.seal_block(fallback_block);
+        let fallback = self.compile_expr_typed(&bin.right, scope, builder)?;
+        let word = Self::to_word(fallback.value, &fallback.ty, builder);
+        builder.ins().jump(merge_block, &[word]);
+
+        builder.switch_to_block(merge_block);
+        builder.seal_block(merge_block);
+        let word = builder.block_params(merge_block)[0];
+        Ok(TypedValue {
+            value: Self::from_word(word, &fallback.ty, builder),
+            ty: fallback.ty,
+        })
+    }
+
+    /// Test an option's tag: 1 if it is Some, 0 if it is None.
+    fn compile_option_is_some(&self, option: Value, builder: &mut FunctionBuilder) -> Value {
+        let tag = builder.ins().band_imm(option, 1);
+        let is_some = builder.ins().icmp_imm(IntCC::NotEqual, tag, 0);
+        builder.ins().uextend(types::I64, is_some)
+    }
+
+    /// Compile an operand of `and`/`or` to 0 or 1.
+    ///
+    /// Ints and bools are true when nonzero, and floats when `!= 0.0`.
+    fn compile_truth(
+        &mut self,
+        expr: &Expr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Value, CodegenError> {
+        let operand = self.compile_expr_typed(expr, scope, builder)?;
+        let is_true = match operand.ty {
+            ValueType::Int | ValueType::Bool => {
+                builder.ins().icmp_imm(IntCC::NotEqual, operand.value, 0)
+            }
+            ValueType::Float => {
+                let zero = builder.ins().f64const(0.0);
+                builder.ins().fcmp(FloatCC::NotEqual, operand.value, zero)
+            }
+            ty => {
+                return Err(CodegenError::TypeMismatch(format!(
+                    "operands of `and` and `or` must be booleans, found {}",
+                    ty
+                )))
+            }
+        };
+        Ok(builder.ins().uextend(types::I64, is_true))
+    }
+
+    /// Compile a unary operation with type awareness.
+    fn compile_unary_op_typed(
+        &self,
+        op: &UnaryOp,
+        operand: TypedValue,
+        builder: &mut FunctionBuilder,
+    ) -> Result<TypedValue, CodegenError> {
+        match op {
+            UnaryOp::Neg => match operand.ty {
                 ValueType::Float => Ok(TypedValue {
                     value: builder.ins().fneg(operand.value),
                     ty: ValueType::Float,
                 }),
-                ValueType::Int => Ok(TypedValue {
+                ValueType::Int | ValueType::Bool => Ok(TypedValue {
                     value: builder.ins().ineg(operand.value),
                     ty: ValueType::Int,
                 }),
-                ValueType::Ptr => Err(CodegenError::Unsupported(
-                    "Cannot negate a pointer".to_string(),
-                )),
+                ValueType::Ptr | ValueType::List(_) | ValueType::Map => Err(
+                    CodegenError::Unsupported("Cannot negate a pointer".to_string()),
+                ),
                 ValueType::Struct(_) => Err(CodegenError::Unsupported(
                     "Cannot negate a struct".to_string(),
                 )),
+                ValueType::Enum(_) => Err(CodegenError::Unsupported(
+                    "Cannot negate an enum".to_string(),
+                )),
+                ValueType::Tuple(_) => Err(CodegenError::Unsupported(
+                    "Cannot negate a tuple".to_string(),
+                )),
             },
             UnaryOp::Not => {
                 // Logical not: treat as integer
@@ -2516,7 +4945,7 @@ impl<'a> FunctionCompiler<'a> {
                 let is_zero = builder.ins().icmp(IntCC::Equal, int_val.value, zero);
                 Ok(TypedValue {
                     value: builder.ins().uextend(types::I64, is_zero),
-                    ty: ValueType::Int,
+                    ty: ValueType::Bool,
                 })
             }
         }
@@ -2530,14 +4959,21 @@ impl<'a> FunctionCompiler<'a> {
         builder: &mut FunctionBuilder,
     ) -> Result<TypedValue, CodegenError> {
         // Get function name
-        let func_name = match &call.callee.node {
-            ExprKind::Identifier(name) => name.clone(),
-            _ => {
-                return Err(CodegenError::Unsupported(
-                    "Only direct function calls are supported".to_string(),
-                ))
-            }
-        };
+        let func_name = self.callee_name(&call.callee)?;
+
+        // The `map()` builtin, unless a user function shadows it
+        if func_name == "map" && self.functions.get("map") == self.functions.get("map_new") {
+            let value = self.compile_call(call, scope, builder)?;
+            return Ok(TypedValue {
+                value,
+                ty: ValueType::Map,
+            });
+        }
+
+        // Enum variant construction, unless a function has the same name
+        if !self.functions.contains_key(&func_name) && self.find_variant(&func_name).is_some() {
+            return self.compile_variant(&func_name, &call.args, scope, builder);
+        }
 
         // Check if this is a known float function
         let func_sig = self.func_signatures.get(&func_name).cloned();
@@ -2551,28 +4987,11 @@ impl<'a> FunctionCompiler<'a> {
 
             let local_callee = self.module.declare_func_in_func(func_id, builder.func);
 
-            // Compile and coerce arguments
-            let mut args = Vec::new();
-            for (i, arg) in call.args.iter().enumerate() {
-                let typed_val = self.compile_expr_typed(&arg.value, scope, builder)?;
-                let needs_float = sig.params_are_float.get(i).copied().unwrap_or(false);
-
-                let coerced = if needs_float {
-                    self.coerce_to_float(typed_val, builder)
-                } else {
-                    self.coerce_to_int(typed_val, builder)
-                };
-                args.push(coerced.value);
-            }
-
+            let args = self.compile_args(&func_name, &sig, &call.args, scope, builder)?;
             let call_inst = builder.ins().call(local_callee, &args);
             let results = builder.inst_results(call_inst);
 
-            let result_ty = if sig.returns_float {
-                ValueType::Float
-            } else {
-                ValueType::Int
-            };
+            let result_ty = sig.returns;
 
             if results.is_empty() {
                 Ok(TypedValue {
@@ -2601,6 +5020,11 @@ impl<'a> FunctionCompiler<'a> {
                 "regex_find",
                 "regex_replace",
                 "regex_replace_all",
+                "json_as_string",
+                "json_stringify",
+                "http_get",
+                "join",
+                "get_error_message",
             ];
 
             let returns_ptr = string_returning_funcs.contains(&func_name.as_str());
@@ -2611,6 +5035,8 @@ impl<'a> FunctionCompiler<'a> {
                 value,
                 ty: if returns_ptr {
                     ValueType::Ptr
+                } else if func_name == "split" {
+                    ValueType::List(Box::new(ValueType::Ptr))
                 } else {
                     ValueType::Int
                 },
@@ -2618,6 +5044,34 @@ impl<'a> FunctionCompiler<'a> {
         }
     }
 
+    /// Compile the arguments of a call to `sig`, in parameter order and
+    /// converted to the parameters' types. Trailing arguments are collected
+    /// into the rest parameter's list.
+    fn compile_args(
+        &mut self,
+        func_name: &str,
+        sig: &FuncSignature,
+        args: &[haira_ast::Argument],
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Vec<Value>, CodegenError> {
+        let (bound, rest) = sig.bind_args(func_name, args)?;
+        let mut values = Vec::new();
+        for (expr, param_ty) in bound.into_iter().zip(&sig.params) {
+            let typed_val = self.compile_expr_typed(expr, scope, builder)?;
+            values.push(self.coerce_to(typed_val, param_ty, builder).value);
+        }
+
+        if let Some(ValueType::List(elem_ty)) = sig.params.last().filter(|_| sig.rest) {
+            let rest = rest
+                .into_iter()
+                .map(|expr| self.compile_expr_typed(expr, scope, builder))
+                .collect::<Result<Vec<_>, _>>()?;
+            values.push(self.build_list(rest, elem_ty, builder));
+        }
+        Ok(values)
+    }
+
     /// Compile an expression.
     fn compile_expr(
         &mut self,
@@ -2631,10 +5085,18 @@ impl<'a> FunctionCompiler<'a> {
                 // Use Cranelift variable
                 if let Some(var) = scope.get_var(name) {
                     Ok(builder.use_var(var))
+                } else if let Some(value) = self.constants.get(name).cloned() {
+                    self.compile_literal(&value, scope, builder)
                 } else {
-                    Err(CodegenError::UndefinedVariable(name.to_string()))
+                    Ok(self.compile_variant(name, &[], scope, builder)?.value)
                 }
             }
+            ExprKind::Binary(bin) if matches!(bin.op.node, BinaryOp::And | BinaryOp::Or) => {
+                Ok(self.compile_logical_op(bin, scope, builder)?.value)
+            }
+            ExprKind::Binary(bin) if bin.op.node == BinaryOp::Coalesce => {
+                Ok(self.compile_default(bin, scope, builder)?.value)
+            }
             ExprKind::Binary(bin) => {
                 let left = self.compile_expr(&bin.left, scope, builder)?;
                 let right = self.compile_expr(&bin.right, scope, builder)?;
@@ -2647,86 +5109,16 @@ impl<'a> FunctionCompiler<'a> {
             ExprKind::Call(call) => self.compile_call(call, scope, builder),
             ExprKind::MethodCall(method_call) => {
                 // Method call: obj.method(args)
-                // Compile receiver (the object)
-                let receiver = self.compile_expr(&method_call.receiver, scope, builder)?;
-
-                // Try to find the method - we need to search through all types
-                // For now, we'll try each struct type to find a matching method
-                let method_name = &method_call.method.node;
-
-                for (type_name, _) in self.structs.iter() {
-                    let full_method_name = format!("{}_{}", type_name, method_name);
-                    if let Some(&func_id) = self.functions.get(&SmolStr::from(&full_method_name)) {
-                        let local_callee = self.module.declare_func_in_func(func_id, builder.func);
-
-                        // First argument is self (the receiver), then other args
-                        let mut args = vec![receiver];
-                        for arg in &method_call.args {
-                            args.push(self.compile_expr(&arg.value, scope, builder)?);
-                        }
-
-                        let call_inst = builder.ins().call(local_callee, &args);
-                        let results = builder.inst_results(call_inst);
-
-                        return if results.is_empty() {
-                            Ok(builder.ins().iconst(types::I64, 0))
-                        } else {
-                            Ok(results[0])
-                        };
-                    }
-                }
-
-                Err(CodegenError::UndefinedFunction(format!(
-                    "Method {} not found",
-                    method_name
-                )))
+                Ok(self
+                    .compile_method_call_typed(method_call, scope, builder)?
+                    .value)
             }
             ExprKind::Paren(inner) => self.compile_expr(inner, scope, builder),
-            ExprKind::If(if_stmt) => {
-                // If as expression
-                let cond = self.compile_expr(&if_stmt.condition, scope, builder)?;
-
-                let then_block = builder.create_block();
-                let else_block = builder.create_block();
-                let merge_block = builder.create_block();
-                builder.append_block_param(merge_block, types::I64);
-
-                builder.ins().brif(cond, then_block, &[], else_block, &[]);
-
-                // Then - seal since only predecessor is branch source
-                builder.switch_to_block(then_block);
-                builder.seal_block(then_block);
-                let then_val = self
-                    .compile_block(&if_stmt.then_branch, scope, builder)?
-                    .unwrap_or_else(|| builder.ins().iconst(types::I64, 0));
-                builder.ins().jump(merge_block, &[then_val]);
-
-                // Else - seal since only predecessor is branch source
-                builder.switch_to_block(else_block);
-                builder.seal_block(else_block);
-                let else_val = if let Some(else_branch) = &if_stmt.else_branch {
-                    match else_branch {
-                        haira_ast::ElseBranch::Block(block) => self
-                            .compile_block(block, scope, builder)?
-                            .unwrap_or_else(|| builder.ins().iconst(types::I64, 0)),
-                        haira_ast::ElseBranch::ElseIf(_) => builder.ins().iconst(types::I64, 0),
-                    }
-                } else {
-                    builder.ins().iconst(types::I64, 0)
-                };
-                builder.ins().jump(merge_block, &[else_val]);
-
-                // Merge - seal since both predecessors have jumped
-                builder.switch_to_block(merge_block);
-                builder.seal_block(merge_block);
-
-                Ok(builder.block_params(merge_block)[0])
-            }
-            ExprKind::Block(block) => {
-                let val = self.compile_block(block, scope, builder)?;
-                Ok(val.unwrap_or_else(|| builder.ins().iconst(types::I64, 0)))
+            ExprKind::If(if_stmt) => Ok(self.compile_if_expr(if_stmt, scope, builder)?.value),
+            ExprKind::Block(block) => Ok(self.compile_block_value(block, scope, builder)?.value),
+            ExprKind::Match(match_expr) => {
+                Ok(self.compile_match_expr(match_expr, scope, builder)?.value)
             }
-            ExprKind::Match(match_expr) => self.compile_match_expr(match_expr, scope, builder),
             ExprKind::Propagate(inner) => {
                 // Error propagation: expr?
                 // 1. Evaluate the expression
@@ -2822,72 +5214,23 @@ impl<'a> FunctionCompiler<'a> {
 
                 Ok(ptr)
             }
-            ExprKind::Field(field_expr) => {
+            ExprKind::Field(_) => {
                 // Field access: obj.field
-                let obj_ptr = self.compile_expr(&field_expr.object, scope, builder)?;
-                let field_name = &field_expr.field.node;
-
-                // We need to determine the type of the object to find the field offset
-                // For now, we'll try to infer it from the scope or use a simple approach
-                // This is a simplified version - a full implementation would need type inference
-
-                // Try to find the struct type by checking all known structs
-                for (_, struct_info) in self.structs.iter() {
-                    if let Some(field_idx) = struct_info.fields.iter().position(|f| f == field_name)
-                    {
-                        let offset = struct_info.field_offsets[field_idx];
-                        let offset_val = builder.ins().iconst(types::I64, offset as i64);
-                        let field_ptr = builder.ins().iadd(obj_ptr, offset_val);
-                        let value = builder
-                            .ins()
-                            .load(types::I64, MemFlags::new(), field_ptr, 0);
-                        return Ok(value);
-                    }
-                }
-
-                Err(CodegenError::Unsupported(format!(
-                    "Unknown field: {}",
-                    field_name
-                )))
+                Ok(self.compile_expr_typed(expr, scope, builder)?.value)
             }
             ExprKind::List(elements) => {
                 // List literal: [1, 2, 3]
-                // Allocate memory for the list: 8 bytes for length + 8 bytes per element
-                let num_elements = elements.len();
-                let total_size = 8 + (num_elements * 8); // length + elements
-                let size_val = builder.ins().iconst(types::I64, total_size as i64);
-
-                let alloc_id = *self.functions.get(&SmolStr::from("alloc")).unwrap();
-                let alloc_func = self.module.declare_func_in_func(alloc_id, builder.func);
-                let call = builder.ins().call(alloc_func, &[size_val]);
-                let ptr = builder.inst_results(call)[0];
-
-                // Store length at offset 0
-                let len_val = builder.ins().iconst(types::I64, num_elements as i64);
-                builder.ins().store(MemFlags::new(), len_val, ptr, 0);
-
-                // Store each element at offset 8 + (index * 8)
-                for (i, elem) in elements.iter().enumerate() {
-                    let value = self.compile_expr(elem, scope, builder)?;
-                    let offset = 8 + (i * 8);
-                    let offset_val = builder.ins().iconst(types::I64, offset as i64);
-                    let elem_ptr = builder.ins().iadd(ptr, offset_val);
-                    builder.ins().store(MemFlags::new(), value, elem_ptr, 0);
-                }
-
-                Ok(ptr)
+                Ok(self.compile_list(elements, scope, builder)?.value)
+            }
+            ExprKind::Tuple(elements) => {
+                // Tuple literal: (1, "x")
+                Ok(self.compile_tuple(elements, scope, builder)?.value)
             }
             ExprKind::Index(index_expr) => {
                 // Index access: arr[i]
                 let arr_ptr = self.compile_expr(&index_expr.object, scope, builder)?;
                 let index = self.compile_expr(&index_expr.index, scope, builder)?;
-
-                // Element is at offset 8 + (index * 8)
-                let eight = builder.ins().iconst(types::I64, 8);
-                let offset = builder.ins().imul(index, eight);
-                let base_offset = builder.ins().iadd(offset, eight);
-                let elem_ptr = builder.ins().iadd(arr_ptr, base_offset);
-
+                let elem_ptr = self.list_element_addr(arr_ptr, index, builder);
                 let value = builder.ins().load(types::I64, MemFlags::new(), elem_ptr, 0);
                 Ok(value)
             }
@@ -2905,12 +5248,15 @@ impl<'a> FunctionCompiler<'a> {
                 // Async blocks run operations concurrently and wait for all to complete
                 // Look up the pre-compiled functions for each statement
                 let span_start = expr.span.start;
-                let func_names = self.async_functions.get(&span_start).ok_or_else(|| {
-                    CodegenError::Unsupported(format!(
-                        "Async block not found (span {}). This is a compiler bug.",
-                        span_start
-                    ))
-                })?;
+                let func_names = self
+                    .async_functions
+                    .get(&(self.item, span_start))
+                    .ok_or_else(|| {
+                        CodegenError::Unsupported(format!(
+                            "Async block not found (span {}). This is a compiler bug.",
+                            span_start
+                        ))
+                    })?;
 
                 // Get runtime functions
                 let spawn_joinable_id = *self
@@ -2955,12 +5301,15 @@ impl<'a> FunctionCompiler<'a> {
                 // Spawn blocks create a new thread to run the block
                 // Look up the pre-compiled function for this spawn block using its span
                 let span_start = expr.span.start;
-                let func_name = self.spawn_functions.get(&span_start).ok_or_else(|| {
-                    CodegenError::Unsupported(format!(
-                        "Spawn block not found (span {}). This is a compiler bug.",
-                        span_start
-                    ))
-                })?;
+                let func_name = self
+                    .spawn_functions
+                    .get(&(self.item, span_start))
+                    .ok_or_else(|| {
+                        CodegenError::Unsupported(format!(
+                            "Spawn block not found (span {}). This is a compiler bug.",
+                            span_start
+                        ))
+                    })?;
 
                 // Get the function ID
                 let func_id = *self
@@ -2991,6 +5340,11 @@ impl<'a> FunctionCompiler<'a> {
                 // The left side becomes the first argument of the right side
                 let left_val = self.compile_expr(&pipe.left, scope, builder)?;
 
+                if !self.pipes_into(&pipe.right, scope) {
+                    let right_val = self.compile_expr(&pipe.right, scope, builder)?;
+                    return self.compile_binary_op(&BinaryOp::BitOr, left_val, right_val, builder);
+                }
+
                 // The right side should be a call expression
                 match &pipe.right.node {
                     ExprKind::Call(call) => {
@@ -3065,30 +5419,8 @@ impl<'a> FunctionCompiler<'a> {
                 let tagged = builder.ins().bor(shifted, one);
                 Ok(tagged)
             }
-            ExprKind::Ai(ai_block) => {
-                // AI blocks require pre-interpretation before compilation.
-                // The AI engine must interpret the intent and generate CIR,
-                // which is then compiled to native code.
-                //
-                // For now, we return an error indicating that AI blocks need
-                // to be pre-processed. In a full implementation:
-                // 1. A pre-compilation pass would interpret all AI blocks
-                // 2. The generated CIR would be stored alongside the AST
-                // 3. This code would compile the pre-generated CIR
-                //
-                // See `haira-ai` crate's `AIEngine::interpret_intent()` for
-                // the AI interpretation logic.
-                let name = ai_block
-                    .name
-                    .as_ref()
-                    .map(|n| n.node.as_str())
-                    .unwrap_or("<anonymous>");
-                Err(CodegenError::Unsupported(format!(
-                    "AI block '{}' requires pre-interpretation. \
-                     Run `haira build --interpret-ai` to generate code from AI intents.",
-                    name
-                )))
-            }
+            // Interpreted AI blocks are only reachable by calling them
+            ExprKind::Ai(ai_block) => Err(uninterpreted_ai_block(ai_block)),
             _ => Err(CodegenError::Unsupported(format!(
                 "Expression type not yet supported: {:?}",
                 std::mem::discriminant(&expr.node)
@@ -3096,6 +5428,29 @@ impl<'a> FunctionCompiler<'a> {
         }
     }
 
+    /// Whether `left | right` pipes into a function rather than being a
+    /// bitwise or.
+    ///
+    /// The parser leaves `|` undecided when `right` could name a function.
+    /// A name pipes when it's a function, and a call pipes when the function
+    /// has a parameter left for the piped value: `x | add(1)` pipes, while
+    /// `flags | mask()` ors in the call's result.
+    fn pipes_into(&self, right: &Expr, scope: &FunctionScope) -> bool {
+        match &right.node {
+            ExprKind::Identifier(name) => {
+                self.functions.contains_key(name) || scope.get_var(name).is_none()
+            }
+            ExprKind::Call(call) => match &call.callee.node {
+                ExprKind::Identifier(name) => self
+                    .func_signatures
+                    .get(name)
+                    .is_none_or(|sig| sig.rest || call.args.len() < sig.params.len()),
+                _ => true,
+            },
+            _ => false,
+        }
+    }
+
     /// Compile a literal.
     fn compile_literal(
         &mut self,
@@ -3106,7 +5461,8 @@ impl<'a> FunctionCompiler<'a> {
         match lit {
             Literal::Int(n) => Ok(builder.ins().iconst(types::I64, *n)),
             Literal::Float(n) => Ok(builder.ins().f64const(*n)),
-            Literal::Bool(b) => Ok(builder.ins().iconst(types::I8, if *b { 1 } else { 0 })),
+            Literal::Bool(b) => Ok(builder.ins().iconst(types::I64, if *b { 1 } else { 0 })),
+            Literal::Char(c) => Ok(builder.ins().iconst(types::I64, *c as i64)),
             Literal::String(s) => {
                 // Store string data and return pointer
                 let data_id = self.define_string(s)?;
@@ -3149,29 +5505,49 @@ impl<'a> FunctionCompiler<'a> {
                     string_parts.push((ptr, len));
                 }
                 haira_ast::StringPart::Expr(expr) => {
-                    // Compile the expression and convert to string
-                    let value = self.compile_expr(expr, scope, builder)?;
-
-                    // Detect expression type and convert to string
-                    // For now, assume integers (most common case)
-                    // TODO: Add type inference for proper handling
-                    let int_to_string_id =
-                        *self.functions.get(&SmolStr::from("int_to_string")).unwrap();
-                    let int_to_string_func = self
-                        .module
-                        .declare_func_in_func(int_to_string_id, builder.func);
-                    let call = builder.ins().call(int_to_string_func, &[value]);
-                    let haira_string_ptr = builder.inst_results(call)[0];
+                    // Compile the expression and convert it to a HairaString*
+                    let value = self.compile_expr_typed(expr, scope, builder)?;
+                    let haira_string_ptr = match value.ty {
+                        // Strings are already HairaString*
+                        ValueType::Ptr => value.value,
+                        ValueType::Bool => {
+                            let true_str = self.compile_literal_typed(
+                                &Literal::String("true".into()),
+                                scope,
+                                builder,
+                            )?;
+                            let false_str = self.compile_literal_typed(
+                                &Literal::String("false".into()),
+                                scope,
+                                builder,
+                            )?;
+                            builder
+                                .ins()
+                                .select(value.value, true_str.value, false_str.value)
+                        }
+                        ValueType::Float => {
+                            let float_to_string_id = *self
+                                .functions
+                                .get(&SmolStr::from("float_to_string"))
+                                .unwrap();
+                            let float_to_string_func = self
+                                .module
+                                .declare_func_in_func(float_to_string_id, builder.func);
+                            let call = builder.ins().call(float_to_string_func, &[value.value]);
+                            builder.inst_results(call)[0]
+                        }
+                        _ => {
+                            let int_to_string_id =
+                                *self.functions.get(&SmolStr::from("int_to_string")).unwrap();
+                            let int_to_string_func = self
+                                .module
+                                .declare_func_in_func(int_to_string_id, builder.func);
+                            let call = builder.ins().call(int_to_string_func, &[value.value]);
+                            builder.inst_results(call)[0]
+                        }
+                    };
 
-                    // HairaString struct: { data: *char, len: i64, cap: i64 }
-                    // Load data pointer (offset 0) and len (offset 8)
-                    let data_ptr =
-                        builder
-                            .ins()
-                            .load(self.ptr_type, MemFlags::new(), haira_string_ptr, 0);
-                    let len = builder
-                        .ins()
-                        .load(types::I64, MemFlags::new(), haira_string_ptr, 8);
+                    let (data_ptr, len) = self.load_string_parts(haira_string_ptr, builder);
 
                     string_parts.push((data_ptr, len));
                 }
@@ -3183,20 +5559,7 @@ impl<'a> FunctionCompiler<'a> {
             // Single part - just return it as-is
             // But we need to wrap it in a HairaString for consistency
             let (ptr, len) = string_parts[0];
-
-            // Allocate a HairaString struct (24 bytes: ptr, len, cap)
-            let alloc_id = *self.functions.get(&SmolStr::from("alloc")).unwrap();
-            let alloc_func = self.module.declare_func_in_func(alloc_id, builder.func);
-            let size = builder.ins().iconst(types::I64, 24);
-            let call = builder.ins().call(alloc_func, &[size]);
-            let result_ptr = builder.inst_results(call)[0];
-
-            // Store data, len, cap
-            builder.ins().store(MemFlags::new(), ptr, result_ptr, 0);
-            builder.ins().store(MemFlags::new(), len, result_ptr, 8);
-            builder.ins().store(MemFlags::new(), len, result_ptr, 16); // cap = len
-
-            return Ok(result_ptr);
+            return Ok(self.alloc_string(ptr, len, builder));
         }
 
         // Multiple parts - concatenate them pairwise
@@ -3213,32 +5576,11 @@ impl<'a> FunctionCompiler<'a> {
             let new_haira_string = builder.inst_results(call)[0];
 
             // Load new data pointer and length
-            result_ptr = builder
-                .ins()
-                .load(self.ptr_type, MemFlags::new(), new_haira_string, 0);
-            result_len = builder
-                .ins()
-                .load(types::I64, MemFlags::new(), new_haira_string, 8);
+            (result_ptr, result_len) = self.load_string_parts(new_haira_string, builder);
         }
 
-        // Allocate final HairaString struct
-        let alloc_id = *self.functions.get(&SmolStr::from("alloc")).unwrap();
-        let alloc_func = self.module.declare_func_in_func(alloc_id, builder.func);
-        let size = builder.ins().iconst(types::I64, 24);
-        let call = builder.ins().call(alloc_func, &[size]);
-        let final_ptr = builder.inst_results(call)[0];
-
-        builder
-            .ins()
-            .store(MemFlags::new(), result_ptr, final_ptr, 0);
-        builder
-            .ins()
-            .store(MemFlags::new(), result_len, final_ptr, 8);
-        builder
-            .ins()
-            .store(MemFlags::new(), result_len, final_ptr, 16);
-
-        Ok(final_ptr)
+        // Wrap the final parts in a HairaString struct
+        Ok(self.alloc_string(result_ptr, result_len, builder))
     }
 
     /// Compile a match expression.
@@ -3247,9 +5589,31 @@ impl<'a> FunctionCompiler<'a> {
         match_expr: &haira_ast::MatchExpr,
         scope: &mut FunctionScope,
         builder: &mut FunctionBuilder,
-    ) -> Result<Value, CodegenError> {
+    ) -> Result<TypedValue, CodegenError> {
         // Compile the subject expression
-        let subject_val = self.compile_expr(&match_expr.subject, scope, builder)?;
+        let subject = self.compile_expr_typed(&match_expr.subject, scope, builder)?;
+        let subject_val = subject.value;
+
+        // Enum subjects are matched on the tag in their first word
+        let enum_name = match &subject.ty {
+            ValueType::Enum(name) => Some(name.clone()),
+            _ => match_expr
+                .arms
+                .iter()
+                .find_map(|arm| self.pattern_variant(&arm.pattern.node))
+                .map(|(enum_name, _, _)| enum_name),
+        };
+        let tag = match &enum_name {
+            Some(enum_name) => {
+                self.check_enum_match(enum_name, match_expr)?;
+                Some(
+                    builder
+                        .ins()
+                        .load(types::I64, MemFlags::new(), subject_val, 0),
+                )
+            }
+            None => None,
+        };
 
         // Create merge block for all arms to jump to with result
         let merge_block = builder.create_block();
@@ -3265,49 +5629,62 @@ impl<'a> FunctionCompiler<'a> {
         let default_block = builder.create_block();
 
         // Generate pattern matching logic as a chain of if-then-else
-        // We stay in the current block and branch to arm blocks or continue checking
-        let mut exhaustive = false;
-
+        // We stay in the current block and branch to arm blocks or continue checking.
+        // Each arm's bindings are kept aside until its body is compiled.
+        let mut arm_scopes = Vec::with_capacity(match_expr.arms.len());
         for (i, arm) in match_expr.arms.iter().enumerate() {
             let arm_block = arm_blocks[i];
-
-            // Compile pattern check
-            match &arm.pattern.node {
-                haira_ast::Pattern::Wildcard => {
-                    // Wildcard always matches - jump directly to arm
-                    builder.ins().jump(arm_block, &[]);
-                    // No more patterns will be checked after wildcard
-                    exhaustive = true;
-                    break;
-                }
-                haira_ast::Pattern::Literal(lit) => {
-                    // Compare subject with literal value
-                    let lit_val = self.compile_literal(lit, scope, builder)?;
-                    let cmp = builder.ins().icmp(IntCC::Equal, subject_val, lit_val);
-
-                    // Create a block for continuing to check next pattern
-                    let next_check = builder.create_block();
-                    builder.ins().brif(cmp, arm_block, &[], next_check, &[]);
-
-                    // Continue in next_check block
-                    builder.switch_to_block(next_check);
-                    builder.seal_block(next_check);
-                }
-                haira_ast::Pattern::Identifier(name) => {
-                    // Identifier pattern - binds the value to a variable
-                    // Always matches, but first bind the variable
-                    let var = scope.get_or_declare_var(name, builder);
-                    builder.def_var(var, subject_val);
-                    builder.ins().jump(arm_block, &[]);
-                    // No more patterns will be checked after identifier (catch-all)
-                    exhaustive = true;
-                    break;
+            let next_check = builder.create_block();
+            scope.push_scope();
+
+            // Test the pattern, continuing in a block where it has matched
+            if let (Some(tag), Some((_, variant_tag, field_types))) =
+                (tag, self.pattern_variant(&arm.pattern.node))
+            {
+                // Enum variant patterns: `Empty` or `Circle(radius)`
+                let is_variant = builder
+                    .ins()
+                    .icmp_imm(IntCC::Equal, tag, variant_tag as i64);
+                let bind_block = builder.create_block();
+                builder
+                    .ins()
+                    .brif(is_variant, bind_block, &[], next_check, &[]);
+
+                builder.switch_to_block(bind_block);
+                builder.seal_block(bind_block);
+                if let haira_ast::Pattern::Constructor { fields, .. } = &arm.pattern.node {
+                    for (j, (field, ty)) in fields.iter().zip(field_types).enumerate() {
+                        let word = builder.ins().load(
+                            types::I64,
+                            MemFlags::new(),
+                            subject_val,
+                            8 * (j as i32 + 1),
+                        );
+                        let value = Self::from_word(word, &ty, builder);
+                        let var = scope.declare_var_typed(&field.node, ty, builder);
+                        builder.def_var(var, value);
+                    }
                 }
-                haira_ast::Pattern::Constructor { name, fields } => {
-                    // Constructor pattern - for Option types like Some { value }
-                    let next_check = builder.create_block();
-
-                    if name == "Some" {
+            } else {
+                match &arm.pattern.node {
+                    // Wildcard always matches
+                    haira_ast::Pattern::Wildcard => {}
+                    haira_ast::Pattern::Literal(lit) => {
+                        // Compare subject with literal value
+                        let lit_val = self.compile_literal(lit, scope, builder)?;
+                        let cmp = builder.ins().icmp(IntCC::Equal, subject_val, lit_val);
+
+                        let matched_block = builder.create_block();
+                        builder.ins().brif(cmp, matched_block, &[], next_check, &[]);
+                        builder.switch_to_block(matched_block);
+                        builder.seal_block(matched_block);
+                    }
+                    haira_ast::Pattern::Identifier(name) => {
+                        // Identifier pattern - always matches, binding the value to a variable
+                        let var = scope.declare_var_typed(name, subject.ty.clone(), builder);
+                        builder.def_var(var, subject_val);
+                    }
+                    haira_ast::Pattern::Constructor { name, fields } if name == "Some" => {
                         // Some is represented as (value << 1) | 1
                         // Check if low bit is 1 (is Some)
                         let one = builder.ins().iconst(types::I64, 1);
@@ -3315,8 +5692,7 @@ impl<'a> FunctionCompiler<'a> {
                         let low_bit = builder.ins().band(subject_val, one);
                         let is_some = builder.ins().icmp(IntCC::NotEqual, low_bit, zero);
 
-                        // If matches, bind fields in the arm block
-                        // We need a separate block for binding
+                        // If matches, bind fields in a separate block
                         let bind_block = builder.create_block();
                         builder
                             .ins()
@@ -3326,76 +5702,313 @@ impl<'a> FunctionCompiler<'a> {
                         builder.seal_block(bind_block);
                         if !fields.is_empty() {
                             let field_name = &fields[0].node;
-                            let var = scope.get_or_declare_var(field_name, builder);
+                            let var = scope.declare_var(field_name, builder);
                             // Extract the value: (subject >> 1)
                             let one = builder.ins().iconst(types::I64, 1);
                             let extracted_val = builder.ins().ushr(subject_val, one);
                             builder.def_var(var, extracted_val);
                         }
-                        builder.ins().jump(arm_block, &[]);
-                    } else if name == "None" || name == "none" {
+                    }
+                    haira_ast::Pattern::Constructor { name, .. }
+                        if name == "None" || name == "none" =>
+                    {
                         // None is represented as 0
                         let zero = builder.ins().iconst(types::I64, 0);
                         let is_none = builder.ins().icmp(IntCC::Equal, subject_val, zero);
-                        builder.ins().brif(is_none, arm_block, &[], next_check, &[]);
-                    } else {
-                        // Other constructors - for now treat as always match
-                        builder.ins().jump(arm_block, &[]);
-                        exhaustive = true;
-                        break;
+
+                        let matched_block = builder.create_block();
+                        builder
+                            .ins()
+                            .brif(is_none, matched_block, &[], next_check, &[]);
+                        builder.switch_to_block(matched_block);
+                        builder.seal_block(matched_block);
                     }
+                    // Other constructors - for now treat as always match
+                    haira_ast::Pattern::Constructor { .. } => {}
+                    haira_ast::Pattern::Tuple(_) => {
+                        self.compile_element_pattern(
+                            &arm.pattern.node,
+                            subject.clone(),
+                            next_check,
+                            scope,
+                            builder,
+                        )?;
+                    }
+                }
+            }
 
-                    builder.switch_to_block(next_check);
-                    builder.seal_block(next_check);
+            // Enter the arm, unless its guard fails and we fall through to the next one
+            match &arm.guard {
+                Some(guard) => {
+                    let guard_val = self.compile_guard(guard, scope, builder)?;
+                    builder
+                        .ins()
+                        .brif(guard_val, arm_block, &[], next_check, &[]);
+                }
+                None => {
+                    builder.ins().jump(arm_block, &[]);
                 }
             }
-        }
+            arm_scopes.push(scope.pop_scope());
 
-        // Jump to default block if match is not exhaustive
-        if !exhaustive {
-            builder.ins().jump(default_block, &[]);
+            // Continue in next_check block
+            builder.switch_to_block(next_check);
+            builder.seal_block(next_check);
         }
 
+        // No arm matched
+        builder.ins().jump(default_block, &[]);
+
         // Default block - return 0 (should be unreachable in exhaustive match)
         builder.switch_to_block(default_block);
         builder.seal_block(default_block);
         let default_val = builder.ins().iconst(types::I64, 0);
         builder.ins().jump(merge_block, &[default_val]);
 
-        // Compile arm bodies
-        for (i, arm) in match_expr.arms.iter().enumerate() {
-            let arm_block = arm_blocks[i];
+        // Compile arm bodies; the first arm's type is the type of the match
+        let mut result_ty: Option<ValueType> = None;
+        for ((arm, arm_block), bindings) in match_expr.arms.iter().zip(arm_blocks).zip(arm_scopes) {
             builder.switch_to_block(arm_block);
             builder.seal_block(arm_block);
-
-            // Check guard if present
-            if let Some(guard) = &arm.guard {
-                let guard_val = self.compile_expr(guard, scope, builder)?;
-                let guard_true_block = builder.create_block();
-                let guard_false_block = default_block;
-                builder
-                    .ins()
-                    .brif(guard_val, guard_true_block, &[], guard_false_block, &[]);
-                builder.switch_to_block(guard_true_block);
-                builder.seal_block(guard_true_block);
-            }
+            scope.restore_scope(bindings);
 
             // Compile arm body
             let arm_val = match &arm.body {
-                haira_ast::MatchArmBody::Expr(expr) => self.compile_expr(expr, scope, builder)?,
-                haira_ast::MatchArmBody::Block(block) => self
-                    .compile_block(block, scope, builder)?
-                    .unwrap_or_else(|| builder.ins().iconst(types::I64, 0)),
+                haira_ast::MatchArmBody::Expr(expr) => {
+                    self.compile_expr_typed(expr, scope, builder)?
+                }
+                haira_ast::MatchArmBody::Block(block) => {
+                    match self.compile_block_typed(block, scope, builder)? {
+                        Some(value) => value,
+                        None => TypedValue {
+                            value: builder.ins().iconst(types::I64, 0),
+                            ty: ValueType::Int,
+                        },
+                    }
+                }
             };
+            scope.pop_scope();
+            let ty = result_ty.get_or_insert_with(|| arm_val.ty.clone()).clone();
+            let arm_val = self.coerce_to(arm_val, &ty, builder);
+            let word = Self::to_word(arm_val.value, &ty, builder);
 
-            builder.ins().jump(merge_block, &[arm_val]);
+            builder.ins().jump(merge_block, &[word]);
         }
 
         // Switch to merge block
         builder.switch_to_block(merge_block);
         builder.seal_block(merge_block);
 
-        Ok(builder.block_params(merge_block)[0])
+        let ty = result_ty.unwrap_or(ValueType::Int);
+        let word = builder.block_params(merge_block)[0];
+        Ok(TypedValue {
+            value: Self::from_word(word, &ty, builder),
+            ty,
+        })
+    }
+
+    /// Test a pattern nested in a tuple against `value`, branching to
+    /// `next_check` when it does not match.
+    ///
+    /// Wildcards and names always match, binding names in the current scope;
+    /// literals are compared and tuples tested element by element.
+    fn compile_element_pattern(
+        &mut self,
+        pattern: &haira_ast::Pattern,
+        value: TypedValue,
+        next_check: cranelift::prelude::Block,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        match pattern {
+            haira_ast::Pattern::Wildcard => {}
+            haira_ast::Pattern::Identifier(name) => {
+                let var = scope.declare_var_typed(name, value.ty, builder);
+                builder.def_var(var, value.value);
+            }
+            haira_ast::Pattern::Literal(lit) => {
+                let cmp = match value.ty {
+                    ValueType::Float => {
+                        let lit_val = self.compile_literal_typed(lit, scope, builder)?;
+                        let lit_val = self.coerce_to(lit_val, &ValueType::Float, builder);
+                        builder
+                            .ins()
+                            .fcmp(FloatCC::Equal, value.value, lit_val.value)
+                    }
+                    ValueType::Int | ValueType::Bool => {
+                        let lit_val = self.compile_literal(lit, scope, builder)?;
+                        builder.ins().icmp(IntCC::Equal, value.value, lit_val)
+                    }
+                    ty => {
+                        return Err(CodegenError::InvalidPattern(format!(
+                            "a literal cannot match a tuple element of type {}",
+                            ty
+                        )));
+                    }
+                };
+                let matched_block = builder.create_block();
+                builder.ins().brif(cmp, matched_block, &[], next_check, &[]);
+                builder.switch_to_block(matched_block);
+                builder.seal_block(matched_block);
+            }
+            haira_ast::Pattern::Tuple(patterns) => {
+                let ValueType::Tuple(element_types) = &value.ty else {
+                    return Err(CodegenError::InvalidPattern(format!(
+                        "a tuple pattern cannot match a value of type {}",
+                        value.ty
+                    )));
+                };
+                if patterns.len() != element_types.len() {
+                    return Err(CodegenError::InvalidPattern(format!(
+                        "the tuple has {} element(s) but the pattern has {}",
+                        element_types.len(),
+                        patterns.len()
+                    )));
+                }
+                for (i, (pattern, ty)) in patterns.iter().zip(element_types).enumerate() {
+                    let word = builder.ins().load(
+                        types::I64,
+                        MemFlags::new(),
+                        value.value,
+                        tuple_offset(i),
+                    );
+                    let element = TypedValue {
+                        value: Self::from_word(word, ty, builder),
+                        ty: ty.clone(),
+                    };
+                    self.compile_element_pattern(
+                        &pattern.node,
+                        element,
+                        next_check,
+                        scope,
+                        builder,
+                    )?;
+                }
+            }
+            haira_ast::Pattern::Constructor { name, .. } => {
+                return Err(CodegenError::InvalidPattern(format!(
+                    "{} cannot be matched inside a tuple",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile a match guard to a condition to branch on.
+    ///
+    /// Ints and bools branch on being nonzero, and floats on `!= 0.0`.
+    fn compile_guard(
+        &mut self,
+        guard: &Expr,
+        scope: &mut FunctionScope,
+        builder: &mut FunctionBuilder,
+    ) -> Result<Value, CodegenError> {
+        let guard_val = self.compile_expr_typed(guard, scope, builder)?;
+        match guard_val.ty {
+            ValueType::Int | ValueType::Bool => Ok(guard_val.value),
+            ValueType::Float => {
+                let zero = builder.ins().f64const(0.0);
+                Ok(builder.ins().fcmp(FloatCC::NotEqual, guard_val.value, zero))
+            }
+            ty => Err(CodegenError::InvalidPattern(format!(
+                "match guard must be a boolean, found {}",
+                ty
+            ))),
+        }
+    }
+
+    /// The enum variant a match pattern names, with its enum, tag and
+    /// payload types.
+    fn pattern_variant(
+        &self,
+        pattern: &haira_ast::Pattern,
+    ) -> Option<(SmolStr, usize, Vec<ValueType>)> {
+        let name = match pattern {
+            haira_ast::Pattern::Identifier(name) => name,
+            haira_ast::Pattern::Constructor { name, .. } => name,
+            _ => return None,
+        };
+        self.find_variant(name)
+            .map(|(enum_name, tag, variant)| (enum_name, tag, variant.field_types.clone()))
+    }
+
+    /// Check the patterns of a match over an enum.
+    ///
+    /// Every pattern must name a variant of the enum with the right number
+    /// of fields, and every variant must be covered by an unguarded arm
+    /// unless a wildcard or binding arm catches the rest.
+    fn check_enum_match(
+        &self,
+        enum_name: &SmolStr,
+        match_expr: &haira_ast::MatchExpr,
+    ) -> Result<(), CodegenError> {
+        let mut covered = HashSet::new();
+        let mut catch_all = false;
+
+        for arm in &match_expr.arms {
+            let name = match &arm.pattern.node {
+                haira_ast::Pattern::Identifier(name)
+                | haira_ast::Pattern::Constructor { name, .. } => name,
+                haira_ast::Pattern::Wildcard => {
+                    catch_all |= arm.guard.is_none();
+                    continue;
+                }
+                haira_ast::Pattern::Literal(_) => {
+                    return Err(CodegenError::InvalidPattern(format!(
+                        "a literal cannot match a value of enum {}",
+                        enum_name
+                    )));
+                }
+                haira_ast::Pattern::Tuple(_) => {
+                    return Err(CodegenError::InvalidPattern(format!(
+                        "a tuple cannot match a value of enum {}",
+                        enum_name
+                    )));
+                }
+            };
+            let Some((owner, _, field_types)) = self.pattern_variant(&arm.pattern.node) else {
+                // A name that is not a variant binds the whole value
+                catch_all |= arm.guard.is_none();
+                continue;
+            };
+
+            if &owner != enum_name {
+                return Err(CodegenError::InvalidPattern(format!(
+                    "{} is a variant of {}, not {}",
+                    name, owner, enum_name
+                )));
+            }
+            if let haira_ast::Pattern::Constructor { fields, .. } = &arm.pattern.node {
+                if fields.len() != field_types.len() {
+                    return Err(CodegenError::InvalidPattern(format!(
+                        "{} has {} field(s) but the pattern binds {}",
+                        name,
+                        field_types.len(),
+                        fields.len()
+                    )));
+                }
+            }
+            if arm.guard.is_none() {
+                covered.insert(name.clone());
+            }
+        }
+
+        let missing: Vec<&str> = self.enums[enum_name]
+            .variants
+            .iter()
+            .map(|variant| variant.name.as_str())
+            .filter(|name| !covered.contains(*name))
+            .collect();
+        if !catch_all && !missing.is_empty() {
+            return Err(CodegenError::NonExhaustiveMatch(format!(
+                "{} is missing {}",
+                enum_name,
+                missing.join(", ")
+            )));
+        }
+
+        Ok(())
     }
 
     /// Compile a binary operation.
@@ -3407,11 +6020,32 @@ impl<'a> FunctionCompiler<'a> {
         builder: &mut FunctionBuilder,
     ) -> Result<Value, CodegenError> {
         let result = match op {
+            BinaryOp::Add if self.overflow_checks => {
+                let (sum, overflowed) = builder.ins().sadd_overflow(left, right);
+                self.check_overflow("add", overflowed, builder)?;
+                sum
+            }
+            BinaryOp::Sub if self.overflow_checks => {
+                let (difference, overflowed) = builder.ins().ssub_overflow(left, right);
+                self.check_overflow("subtract", overflowed, builder)?;
+                difference
+            }
+            BinaryOp::Mul if self.overflow_checks => {
+                let (product, overflowed) = builder.ins().smul_overflow(left, right);
+                self.check_overflow("multiply", overflowed, builder)?;
+                product
+            }
             BinaryOp::Add => builder.ins().iadd(left, right),
             BinaryOp::Sub => builder.ins().isub(left, right),
             BinaryOp::Mul => builder.ins().imul(left, right),
-            BinaryOp::Div => builder.ins().sdiv(left, right),
-            BinaryOp::Mod => builder.ins().srem(left, right),
+            BinaryOp::Div => {
+                self.check_div_zero(right, builder)?;
+                builder.ins().sdiv(left, right)
+            }
+            BinaryOp::Mod => {
+                self.check_div_zero(right, builder)?;
+                builder.ins().srem(left, right)
+            }
             BinaryOp::Eq => {
                 let cmp = builder.ins().icmp(IntCC::Equal, left, right);
                 builder.ins().uextend(types::I64, cmp)
@@ -3440,12 +6074,102 @@ impl<'a> FunctionCompiler<'a> {
                 let cmp = builder.ins().bor(gt, eq);
                 builder.ins().uextend(types::I64, cmp)
             }
-            BinaryOp::And => builder.ins().band(left, right),
-            BinaryOp::Or => builder.ins().bor(left, right),
+            BinaryOp::And | BinaryOp::BitAnd => builder.ins().band(left, right),
+            BinaryOp::Or | BinaryOp::BitOr => builder.ins().bor(left, right),
+            BinaryOp::BitXor => builder.ins().bxor(left, right),
+            BinaryOp::Shl => builder.ins().ishl(left, right),
+            BinaryOp::Shr => builder.ins().ushr(left, right),
+            BinaryOp::Coalesce => return Err(evaluated_default_operands()),
         };
         Ok(result)
     }
 
+    /// Call `haira_check_stack` if the stack guard is on, at function entry.
+    fn check_stack(&mut self, builder: &mut FunctionBuilder) {
+        let Some(limit) = self.stack_guard else {
+            return;
+        };
+        let func_id = *self.functions.get(&SmolStr::from("check_stack")).unwrap();
+        let func = self.module.declare_func_in_func(func_id, builder.func);
+        let limit = builder.ins().iconst(types::I64, limit as i64);
+        builder.ins().call(func, &[limit]);
+    }
+
+    /// Branch to `haira_panic_overflow` when `overflowed` is set.
+    fn check_overflow(
+        &mut self,
+        op: &str,
+        overflowed: Value,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        let panic_block = builder.create_block();
+        let continue_block = builder.create_block();
+        builder
+            .ins()
+            .brif(overflowed, panic_block, &[], continue_block, &[]);
+
+        builder.switch_to_block(panic_block);
+        builder.seal_block(panic_block);
+        let panic_id = *self
+            .functions
+            .get(&SmolStr::from("panic_overflow"))
+            .unwrap();
+        let local_callee = self.module.declare_func_in_func(panic_id, builder.func);
+        let data_id = self.define_string(op)?;
+        let local_id = self.module.declare_data_in_func(data_id, builder.func);
+        let ptr = builder.ins().symbol_value(self.ptr_type, local_id);
+        let len = builder.ins().iconst(types::I64, op.len() as i64);
+        builder.ins().call(local_callee, &[ptr, len]);
+        // The runtime aborts, so this is never reached
+        builder.ins().trap(TrapCode::INTEGER_OVERFLOW);
+
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
+        Ok(())
+    }
+
+    /// Branch to `haira_panic_div_zero` when `divisor` is zero, instead of
+    /// letting `sdiv`/`srem` raise SIGFPE. Nonzero constants need no guard.
+    fn check_div_zero(
+        &mut self,
+        divisor: Value,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        let dfg = &builder.func.dfg;
+        if let ValueDef::Result(inst, _) = dfg.value_def(dfg.resolve_aliases(divisor)) {
+            if let InstructionData::UnaryImm {
+                opcode: Opcode::Iconst,
+                imm,
+            } = dfg.insts[inst]
+            {
+                if imm.bits() != 0 {
+                    return Ok(());
+                }
+            }
+        }
+
+        let zero_block = builder.create_block();
+        let continue_block = builder.create_block();
+        builder
+            .ins()
+            .brif(divisor, continue_block, &[], zero_block, &[]);
+
+        builder.switch_to_block(zero_block);
+        builder.seal_block(zero_block);
+        let panic_id = *self
+            .functions
+            .get(&SmolStr::from("panic_div_zero"))
+            .unwrap();
+        let local_callee = self.module.declare_func_in_func(panic_id, builder.func);
+        builder.ins().call(local_callee, &[]);
+        // The runtime exits, so this is never reached
+        builder.ins().trap(TrapCode::INTEGER_DIVISION_BY_ZERO);
+
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
+        Ok(())
+    }
+
     /// Compile a unary operation.
     fn compile_unary_op(
         &mut self,
@@ -3464,6 +6188,27 @@ impl<'a> FunctionCompiler<'a> {
     }
 
     /// Compile a function call.
+    /// The name of the function `callee` calls directly.
+    ///
+    /// An inline `ai` block calls the function generated for it by the
+    /// pre-interpretation pass.
+    fn callee_name(&self, callee: &Expr) -> Result<SmolStr, CodegenError> {
+        match &callee.node {
+            ExprKind::Identifier(name) => Ok(name.clone()),
+            ExprKind::Ai(ai_block) => {
+                let name = ai_block.function_name();
+                if self.functions.contains_key(&name) {
+                    Ok(name)
+                } else {
+                    Err(uninterpreted_ai_block(ai_block))
+                }
+            }
+            _ => Err(CodegenError::Unsupported(
+                "Only direct function calls are supported".to_string(),
+            )),
+        }
+    }
+
     fn compile_call(
         &mut self,
         call: &haira_ast::CallExpr,
@@ -3471,34 +6216,41 @@ impl<'a> FunctionCompiler<'a> {
         builder: &mut FunctionBuilder,
     ) -> Result<Value, CodegenError> {
         // Get function name
-        let func_name = match &call.callee.node {
-            ExprKind::Identifier(name) => name.clone(),
-            _ => {
-                return Err(CodegenError::Unsupported(
-                    "Only direct function calls are supported".to_string(),
-                ))
-            }
-        };
+        let func_name = self.callee_name(&call.callee)?;
 
         // Handle print specially - detect argument types
         if func_name.as_str() == "print" {
             return self.compile_print_call(call, scope, builder);
         }
 
+        // Enum variant construction, unless a function has the same name
+        if !self.functions.contains_key(&func_name) && self.find_variant(&func_name).is_some() {
+            return Ok(self
+                .compile_variant(&func_name, &call.args, scope, builder)?
+                .value);
+        }
+
         // Handle err() - set error and return error value
         if func_name.as_str() == "err" {
-            let set_error_id = *self.functions.get(&SmolStr::from("set_error")).unwrap();
-            let set_error_func = self.module.declare_func_in_func(set_error_id, builder.func);
-
             // Get error value from argument (default to 1 if no arg)
-            let err_val = if call.args.is_empty() {
-                builder.ins().iconst(types::I64, 1)
-            } else {
-                self.compile_expr(&call.args[0].value, scope, builder)?
+            let err_val = match call.args.first() {
+                Some(arg) => self.compile_expr_typed(&arg.value, scope, builder)?,
+                None => TypedValue {
+                    value: builder.ins().iconst(types::I64, 1),
+                    ty: ValueType::Int,
+                },
             };
 
-            builder.ins().call(set_error_func, &[err_val]);
-            return Ok(err_val);
+            // A string is the error's message, for `get_error_message()`
+            let setter = if err_val.ty == ValueType::Ptr {
+                "set_error_message"
+            } else {
+                "set_error"
+            };
+            let set_error_id = *self.functions.get(&SmolStr::from(setter)).unwrap();
+            let set_error_func = self.module.declare_func_in_func(set_error_id, builder.func);
+            builder.ins().call(set_error_func, &[err_val.value]);
+            return Ok(err_val.value);
         }
 
         // Handle channel() - create a new channel
@@ -3558,8 +6310,17 @@ impl<'a> FunctionCompiler<'a> {
 
         // String functions that take (ptr, len) from HairaString* or string literal
         // These need special handling to unpack the string
-        let string_funcs_1arg = ["len", "is_empty", "upper", "lower", "trim", "reverse"];
-        let string_funcs_2arg = ["contains", "starts_with", "ends_with", "index_of"];
+        let string_funcs_1arg = [
+            "len",
+            "is_empty",
+            "upper",
+            "lower",
+            "trim",
+            "reverse",
+            "json_parse",
+            "http_get",
+        ];
+        let string_funcs_2arg = ["contains", "starts_with", "ends_with", "index_of", "split"];
 
         if string_funcs_1arg.contains(&func_name.as_str()) && !call.args.is_empty() {
             // Single string argument -> unpack to (ptr, len)
@@ -3621,6 +6382,42 @@ impl<'a> FunctionCompiler<'a> {
             return Ok(results[0]);
         }
 
+        if func_name.as_str() == "join" && call.args.len() >= 2 {
+            // join(list, sep) -> (list, sep_ptr, sep_len)
+            let func_id = *self
+                .functions
+                .get(&func_name)
+                .ok_or_else(|| CodegenError::UndefinedFunction(func_name.to_string()))?;
+            let local_callee = self.module.declare_func_in_func(func_id, builder.func);
+
+            let list = self.compile_expr(&call.args[0].value, scope, builder)?;
+            let (sep_ptr, sep_len) =
+                self.get_string_ptr_len(&call.args[1].value, scope, builder)?;
+
+            let call_inst = builder.ins().call(local_callee, &[list, sep_ptr, sep_len]);
+            let results = builder.inst_results(call_inst);
+            return Ok(results[0]);
+        }
+
+        if func_name.as_str() == "json_get_field" && call.args.len() >= 2 {
+            // json_get_field(handle, key) -> (handle, key_ptr, key_len)
+            let func_id = *self
+                .functions
+                .get(&func_name)
+                .ok_or_else(|| CodegenError::UndefinedFunction(func_name.to_string()))?;
+            let local_callee = self.module.declare_func_in_func(func_id, builder.func);
+
+            let handle = self.compile_expr(&call.args[0].value, scope, builder)?;
+            let (key_ptr, key_len) =
+                self.get_string_ptr_len(&call.args[1].value, scope, builder)?;
+
+            let call_inst = builder
+                .ins()
+                .call(local_callee, &[handle, key_ptr, key_len]);
+            let results = builder.inst_results(call_inst);
+            return Ok(results[0]);
+        }
+
         if func_name.as_str() == "repeat" && call.args.len() >= 2 {
             // repeat(str, n) -> (ptr, len, n)
             let func_id = *self
@@ -3711,6 +6508,11 @@ impl<'a> FunctionCompiler<'a> {
             return Ok(results[0]);
         }
 
+        // Functions with known signatures need their arguments coerced
+        if self.func_signatures.contains_key(&func_name) {
+            return Ok(self.compile_call_typed(call, scope, builder)?.value);
+        }
+
         // Look up function
         let func_id = *self
             .functions
@@ -3806,17 +6608,9 @@ impl<'a> FunctionCompiler<'a> {
                 builder.ins().call(local_callee, &[]);
             }
             ExprKind::Literal(Literal::InterpolatedString(_)) => {
-                // Interpolated string returns a HairaString* (ptr to struct with data, len, cap)
+                // Interpolated string returns a HairaString*
                 let haira_string_ptr = self.compile_expr(arg, scope, builder)?;
-
-                // Load data pointer (offset 0) and len (offset 8)
-                let data_ptr =
-                    builder
-                        .ins()
-                        .load(self.ptr_type, MemFlags::new(), haira_string_ptr, 0);
-                let len = builder
-                    .ins()
-                    .load(types::I64, MemFlags::new(), haira_string_ptr, 8);
+                let (data_ptr, len) = self.load_string_parts(haira_string_ptr, builder);
 
                 // Call haira_print with data and length
                 let print_id = *self.functions.get(&SmolStr::from("print")).unwrap();
@@ -3842,34 +6636,42 @@ impl<'a> FunctionCompiler<'a> {
                         builder.ins().call(local_callee, &[typed_val.value]);
                     }
                     ValueType::Ptr => {
-                        // Pointer type - assume it's a HairaString* (ptr to struct with data, len, cap)
-                        // Load data pointer (offset 0) and len (offset 8)
+                        // Pointer type - assume it's a HairaString*
                         let haira_string_ptr = typed_val.value;
-                        let data_ptr =
-                            builder
-                                .ins()
-                                .load(self.ptr_type, MemFlags::new(), haira_string_ptr, 0);
-                        let len =
-                            builder
-                                .ins()
-                                .load(types::I64, MemFlags::new(), haira_string_ptr, 8);
+                        let (data_ptr, len) = self.load_string_parts(haira_string_ptr, builder);
 
                         // Call haira_print with data and length
                         let print_id = *self.functions.get(&SmolStr::from("print")).unwrap();
                         let local_callee = self.module.declare_func_in_func(print_id, builder.func);
                         builder.ins().call(local_callee, &[data_ptr, len]);
                     }
-                    ValueType::Int => {
+                    ValueType::Int | ValueType::List(_) | ValueType::Map => {
                         let print_int_id =
                             *self.functions.get(&SmolStr::from("print_int")).unwrap();
                         let local_callee =
                             self.module.declare_func_in_func(print_int_id, builder.func);
                         builder.ins().call(local_callee, &[typed_val.value]);
                     }
+                    ValueType::Bool => {
+                        let print_bool_id =
+                            *self.functions.get(&SmolStr::from("print_bool")).unwrap();
+                        let local_callee = self
+                            .module
+                            .declare_func_in_func(print_bool_id, builder.func);
+                        builder.ins().call(local_callee, &[typed_val.value]);
+                    }
                     ValueType::Struct(struct_name) => {
                         // Print struct in format: StructName { field1: value1, field2: value2, ... }
                         self.compile_print_struct(&struct_name, typed_val.value, builder)?;
                     }
+                    ValueType::Enum(enum_name) => {
+                        // Print enum in format: Variant(payload1, payload2, ...)
+                        self.compile_print_enum(&enum_name, typed_val.value, true, builder)?;
+                    }
+                    ValueType::Tuple(elements) => {
+                        // Print tuple in format: (elem0, elem1, ...)
+                        self.compile_print_tuple(&elements, typed_val.value, builder)?;
+                    }
                 }
 
                 let println_id = *self.functions.get(&SmolStr::from("println")).unwrap();
@@ -3881,6 +6683,41 @@ impl<'a> FunctionCompiler<'a> {
         Ok(builder.ins().iconst(types::I64, 0))
     }
 
+    /// Load the data pointer and length of the `HairaString` at `string`.
+    fn load_string_parts(&self, string: Value, builder: &mut FunctionBuilder) -> (Value, Value) {
+        let data = builder.ins().load(
+            self.ptr_type,
+            MemFlags::new(),
+            string,
+            HAIRA_STRING_DATA_OFFSET,
+        );
+        let len = builder
+            .ins()
+            .load(types::I64, MemFlags::new(), string, HAIRA_STRING_LEN_OFFSET);
+        (data, len)
+    }
+
+    /// Allocate a `HairaString` over `len` bytes at `data`, with no spare capacity.
+    fn alloc_string(&mut self, data: Value, len: Value, builder: &mut FunctionBuilder) -> Value {
+        let alloc_id = *self.functions.get(&SmolStr::from("alloc")).unwrap();
+        let alloc_func = self.module.declare_func_in_func(alloc_id, builder.func);
+        let size = builder.ins().iconst(types::I64, HAIRA_STRING_SIZE);
+        let call = builder.ins().call(alloc_func, &[size]);
+        let string = builder.inst_results(call)[0];
+
+        let flags = MemFlags::new();
+        builder
+            .ins()
+            .store(flags, data, string, HAIRA_STRING_DATA_OFFSET);
+        builder
+            .ins()
+            .store(flags, len, string, HAIRA_STRING_LEN_OFFSET);
+        builder
+            .ins()
+            .store(flags, len, string, HAIRA_STRING_CAP_OFFSET);
+        string
+    }
+
     /// Get (ptr, len) from a string expression.
     /// For string literals, returns (ptr, len) directly.
     /// For HairaString*, loads ptr and len from the struct.
@@ -3901,13 +6738,7 @@ impl<'a> FunctionCompiler<'a> {
             _ => {
                 // Assume it's a HairaString* - load ptr and len from struct
                 let haira_string_ptr = self.compile_expr(expr, scope, builder)?;
-                let data_ptr =
-                    builder
-                        .ins()
-                        .load(self.ptr_type, MemFlags::new(), haira_string_ptr, 0);
-                let len = builder
-                    .ins()
-                    .load(types::I64, MemFlags::new(), haira_string_ptr, 8);
+                let (data_ptr, len) = self.load_string_parts(haira_string_ptr, builder);
                 Ok((data_ptr, len))
             }
         }
@@ -3931,31 +6762,18 @@ impl<'a> FunctionCompiler<'a> {
         let print_id = *self.functions.get(&SmolStr::from("print")).unwrap();
         let print_int_id = *self.functions.get(&SmolStr::from("print_int")).unwrap();
         let print_float_id = *self.functions.get(&SmolStr::from("print_float")).unwrap();
+        let print_bool_id = *self.functions.get(&SmolStr::from("print_bool")).unwrap();
 
         // Print "StructName { "
-        let open_str = format!("{} {{ ", struct_name);
-        let open_data_id = self.define_string(&open_str)?;
-        let open_local_id = self.module.declare_data_in_func(open_data_id, builder.func);
-        let open_ptr = builder.ins().symbol_value(self.ptr_type, open_local_id);
-        let open_len = builder.ins().iconst(types::I64, open_str.len() as i64);
-        let print_func = self.module.declare_func_in_func(print_id, builder.func);
-        builder.ins().call(print_func, &[open_ptr, open_len]);
+        self.compile_print_str(&format!("{} {{ ", struct_name), builder)?;
 
         // Print each field
         for (i, field_name) in struct_info.fields.iter().enumerate() {
             // Print "field_name: "
-            let field_prefix = if i > 0 {
-                format!(", {}: ", field_name)
-            } else {
-                format!("{}: ", field_name)
-            };
-            let prefix_data_id = self.define_string(&field_prefix)?;
-            let prefix_local_id = self
-                .module
-                .declare_data_in_func(prefix_data_id, builder.func);
-            let prefix_ptr = builder.ins().symbol_value(self.ptr_type, prefix_local_id);
-            let prefix_len = builder.ins().iconst(types::I64, field_prefix.len() as i64);
-            builder.ins().call(print_func, &[prefix_ptr, prefix_len]);
+            if i > 0 {
+                self.compile_print_str(SEPARATOR, builder)?;
+            }
+            self.compile_print_str(&format!("{}: ", field_name), builder)?;
 
             // Load field value from struct
             let offset = struct_info.field_offsets[i];
@@ -3970,7 +6788,7 @@ impl<'a> FunctionCompiler<'a> {
                 .unwrap_or(ValueType::Int);
 
             match field_type {
-                ValueType::Int => {
+                ValueType::Int | ValueType::List(_) | ValueType::Map => {
                     let value = builder
                         .ins()
                         .load(types::I64, MemFlags::new(), field_ptr, 0);
@@ -3978,6 +6796,15 @@ impl<'a> FunctionCompiler<'a> {
                         self.module.declare_func_in_func(print_int_id, builder.func);
                     builder.ins().call(print_int_func, &[value]);
                 }
+                ValueType::Bool => {
+                    let value = builder
+                        .ins()
+                        .load(types::I64, MemFlags::new(), field_ptr, 0);
+                    let print_bool_func = self
+                        .module
+                        .declare_func_in_func(print_bool_id, builder.func);
+                    builder.ins().call(print_bool_func, &[value]);
+                }
                 ValueType::Float => {
                     let value = builder
                         .ins()
@@ -3994,27 +6821,14 @@ impl<'a> FunctionCompiler<'a> {
                             .ins()
                             .load(self.ptr_type, MemFlags::new(), field_ptr, 0);
 
-                    // Print opening quote
-                    let quote_data_id = self.define_string("\"")?;
-                    let quote_local_id = self
-                        .module
-                        .declare_data_in_func(quote_data_id, builder.func);
-                    let quote_ptr = builder.ins().symbol_value(self.ptr_type, quote_local_id);
-                    let quote_len = builder.ins().iconst(types::I64, 1);
-                    builder.ins().call(print_func, &[quote_ptr, quote_len]);
-
                     // Load data pointer and length from HairaString
-                    let data_ptr =
-                        builder
-                            .ins()
-                            .load(self.ptr_type, MemFlags::new(), haira_string_ptr, 0);
-                    let len = builder
-                        .ins()
-                        .load(types::I64, MemFlags::new(), haira_string_ptr, 8);
-                    builder.ins().call(print_func, &[data_ptr, len]);
+                    let (data_ptr, len) = self.load_string_parts(haira_string_ptr, builder);
 
-                    // Print closing quote
-                    builder.ins().call(print_func, &[quote_ptr, quote_len]);
+                    // Print the contents between quotes
+                    self.compile_print_str(QUOTE, builder)?;
+                    let print_func = self.module.declare_func_in_func(print_id, builder.func);
+                    builder.ins().call(print_func, &[data_ptr, len]);
+                    self.compile_print_str(QUOTE, builder)?;
                 }
                 ValueType::Struct(nested_struct_name) => {
                     // Recursively print nested struct
@@ -4024,90 +6838,340 @@ impl<'a> FunctionCompiler<'a> {
                             .load(self.ptr_type, MemFlags::new(), field_ptr, 0);
                     self.compile_print_struct(&nested_struct_name, nested_ptr, builder)?;
                 }
+                ValueType::Enum(enum_name) => {
+                    let enum_ptr = builder
+                        .ins()
+                        .load(self.ptr_type, MemFlags::new(), field_ptr, 0);
+                    self.compile_print_enum(&enum_name, enum_ptr, false, builder)?;
+                }
+                ValueType::Tuple(elements) => {
+                    let tuple_ptr =
+                        builder
+                            .ins()
+                            .load(self.ptr_type, MemFlags::new(), field_ptr, 0);
+                    self.compile_print_tuple(&elements, tuple_ptr, builder)?;
+                }
             }
         }
 
         // Print " }"
-        let close_str = " }";
-        let close_data_id = self.define_string(close_str)?;
-        let close_local_id = self
-            .module
-            .declare_data_in_func(close_data_id, builder.func);
-        let close_ptr = builder.ins().symbol_value(self.ptr_type, close_local_id);
-        let close_len = builder.ins().iconst(types::I64, close_str.len() as i64);
-        builder.ins().call(print_func, &[close_ptr, close_len]);
+        self.compile_print_str(STRUCT_CLOSE, builder)?;
 
         Ok(())
     }
-}
 
-/// Scope for variables within a function.
-/// Uses Cranelift Variables for proper SSA handling.
-/// Runtime type for values during compilation.
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum ValueType {
-    /// 64-bit integer
-    Int,
-    /// 64-bit floating point
-    Float,
-    /// Pointer to a string (HairaString*)
-    Ptr,
-    /// Pointer to a struct instance (includes the struct type name)
-    Struct(SmolStr),
-}
+    /// Print an enum value as its variant name, followed by its payload in
+    /// parentheses when `payload` is set.
+    ///
+    /// Payloads that are themselves enums print only their variant name, so
+    /// recursive enums don't recurse forever at compile time.
+    fn compile_print_enum(
+        &mut self,
+        enum_name: &str,
+        enum_ptr: Value,
+        payload: bool,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        let enum_info = self
+            .enums
+            .get(enum_name)
+            .ok_or_else(|| CodegenError::Unsupported(format!("Unknown enum type: {}", enum_name)))?
+            .clone();
 
-impl ValueType {
-    /// Get the Cranelift type for this value type.
-    fn cranelift_type(&self) -> Type {
-        match self {
-            ValueType::Int => types::I64,
-            ValueType::Float => types::F64,
-            ValueType::Ptr => types::I64,       // Pointers are I64
-            ValueType::Struct(_) => types::I64, // Struct pointers are I64
-        }
-    }
-}
+        let tag = builder.ins().load(types::I64, MemFlags::new(), enum_ptr, 0);
+        let done_block = builder.create_block();
 
-/// A typed value during compilation.
-#[derive(Debug, Clone)]
-struct TypedValue {
-    value: Value,
-    ty: ValueType,
-}
+        for (i, variant) in enum_info.variants.iter().enumerate() {
+            let variant_block = builder.create_block();
+            let next_block = builder.create_block();
+            let is_variant = builder.ins().icmp_imm(IntCC::Equal, tag, i as i64);
+            builder
+                .ins()
+                .brif(is_variant, variant_block, &[], next_block, &[]);
 
-struct FunctionScope {
-    /// Map of variable names to Cranelift Variables.
-    variables: HashMap<SmolStr, Variable>,
-    /// Map of variable names to their types.
-    var_types: HashMap<SmolStr, ValueType>,
-    /// Counter for generating unique variable indices.
-    next_var: usize,
-    #[allow(dead_code)]
-    ptr_type: Type,
-}
+            builder.switch_to_block(variant_block);
+            builder.seal_block(variant_block);
 
-impl FunctionScope {
-    fn new(ptr_type: Type) -> Self {
-        Self {
-            variables: HashMap::new(),
-            var_types: HashMap::new(),
-            next_var: 0,
+            self.compile_print_str(&variant.name, builder)?;
+
+            if payload && !variant.field_types.is_empty() {
+                self.compile_print_str(PAYLOAD_OPEN, builder)?;
+                for (j, field_type) in variant.field_types.iter().enumerate() {
+                    if j > 0 {
+                        self.compile_print_str(SEPARATOR, builder)?;
+                    }
+                    let offset = 8 * (j as i32 + 1);
+                    let word = builder
+                        .ins()
+                        .load(types::I64, MemFlags::new(), enum_ptr, offset);
+                    self.compile_print_word(word, field_type, builder)?;
+                }
+                self.compile_print_str(PAYLOAD_CLOSE, builder)?;
+            }
+            builder.ins().jump(done_block, &[]);
+
+            builder.switch_to_block(next_block);
+            builder.seal_block(next_block);
+        }
+        builder.ins().jump(done_block, &[]);
+
+        builder.switch_to_block(done_block);
+        builder.seal_block(done_block);
+        Ok(())
+    }
+
+    /// Print a tuple as `(elem0, elem1, ...)`.
+    fn compile_print_tuple(
+        &mut self,
+        elements: &[ValueType],
+        tuple_ptr: Value,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        self.compile_print_str(PAYLOAD_OPEN, builder)?;
+        for (i, ty) in elements.iter().enumerate() {
+            if i > 0 {
+                self.compile_print_str(SEPARATOR, builder)?;
+            }
+            let word = builder
+                .ins()
+                .load(types::I64, MemFlags::new(), tuple_ptr, tuple_offset(i));
+            self.compile_print_word(word, ty, builder)?;
+        }
+        self.compile_print_str(PAYLOAD_CLOSE, builder)
+    }
+
+    /// Print a value of type `ty` held in a word, as an enum payload or
+    /// tuple element.
+    ///
+    /// Strings are quoted, as in structs, and enums print only their
+    /// variant name, so recursive enums don't recurse forever at compile time.
+    fn compile_print_word(
+        &mut self,
+        word: Value,
+        ty: &ValueType,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        let print = |name: &str| *self.functions.get(&SmolStr::from(name)).unwrap();
+        match ty {
+            ValueType::Int | ValueType::List(_) | ValueType::Map => {
+                let func = self
+                    .module
+                    .declare_func_in_func(print("print_int"), builder.func);
+                builder.ins().call(func, &[word]);
+            }
+            ValueType::Bool => {
+                let func = self
+                    .module
+                    .declare_func_in_func(print("print_bool"), builder.func);
+                builder.ins().call(func, &[word]);
+            }
+            ValueType::Float => {
+                let value = Self::from_word(word, ty, builder);
+                let func = self
+                    .module
+                    .declare_func_in_func(print("print_float"), builder.func);
+                builder.ins().call(func, &[value]);
+            }
+            ValueType::Ptr => {
+                let (data_ptr, len) = self.load_string_parts(word, builder);
+                let print_id = print("print");
+                self.compile_print_str(QUOTE, builder)?;
+                let func = self.module.declare_func_in_func(print_id, builder.func);
+                builder.ins().call(func, &[data_ptr, len]);
+                self.compile_print_str(QUOTE, builder)?;
+            }
+            ValueType::Struct(struct_name) => {
+                self.compile_print_struct(struct_name, word, builder)?;
+            }
+            ValueType::Enum(enum_name) => {
+                self.compile_print_enum(enum_name, word, false, builder)?;
+            }
+            ValueType::Tuple(elements) => {
+                self.compile_print_tuple(elements, word, builder)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a string constant without a trailing newline.
+    fn compile_print_str(
+        &mut self,
+        s: &str,
+        builder: &mut FunctionBuilder,
+    ) -> Result<(), CodegenError> {
+        let print_id = *self.functions.get(&SmolStr::from("print")).unwrap();
+        let data_id = self.define_string(s)?;
+        let local_id = self.module.declare_data_in_func(data_id, builder.func);
+        let ptr = builder.ins().symbol_value(self.ptr_type, local_id);
+        let len = builder.ins().iconst(types::I64, s.len() as i64);
+        let print_func = self.module.declare_func_in_func(print_id, builder.func);
+        builder.ins().call(print_func, &[ptr, len]);
+        Ok(())
+    }
+}
+
+/// Scope for variables within a function.
+/// Uses Cranelift Variables for proper SSA handling.
+/// Runtime type for values during compilation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ValueType {
+    /// 64-bit integer
+    Int,
+    /// 64-bit floating point
+    Float,
+    /// Boolean, stored as a 64-bit word holding 0 or 1
+    Bool,
+    /// Pointer to a string (HairaString*)
+    Ptr,
+    /// Pointer to a struct instance (includes the struct type name)
+    Struct(SmolStr),
+    /// Pointer to a list (length word followed by 8-byte elements)
+    List(Box<ValueType>),
+    /// Pointer to a runtime hash map with string keys
+    Map,
+    /// Pointer to an enum value (tag word followed by payload words)
+    Enum(SmolStr),
+    /// Pointer to a tuple (length word followed by 8-byte elements)
+    Tuple(Vec<ValueType>),
+}
+
+impl ValueType {
+    /// The value type for an explicit type annotation.
+    fn from_annotation(
+        ty: &haira_ast::Type,
+        structs: &IndexMap<SmolStr, StructInfo>,
+        enums: &IndexMap<SmolStr, EnumInfo>,
+    ) -> Self {
+        match ty {
+            haira_ast::Type::Named(name) => match name.as_str() {
+                "int" | "i64" | "i32" | "i16" | "i8" => ValueType::Int,
+                "float" | "f64" | "f32" => ValueType::Float,
+                "bool" => ValueType::Bool,
+                "map" => ValueType::Map,
+                name if structs.contains_key(name) => ValueType::Struct(name.into()),
+                name if enums.contains_key(name) => ValueType::Enum(name.into()),
+                _ => ValueType::Ptr,
+            },
+            haira_ast::Type::List(elem) => {
+                ValueType::List(Box::new(Self::from_annotation(&elem.node, structs, enums)))
+            }
+            haira_ast::Type::Tuple(elements) => ValueType::Tuple(
+                elements
+                    .iter()
+                    .map(|e| Self::from_annotation(&e.node, structs, enums))
+                    .collect(),
+            ),
+            _ => ValueType::Ptr,
+        }
+    }
+
+    /// Get the Cranelift type for this value type.
+    fn cranelift_type(&self) -> Type {
+        match self {
+            ValueType::Int => types::I64,
+            ValueType::Float => types::F64,
+            ValueType::Bool => types::I64, // Booleans are widened to I64
+            ValueType::Ptr => types::I64,  // Pointers are I64
+            ValueType::Struct(_) => types::I64, // Struct pointers are I64
+            ValueType::List(_) => types::I64, // List pointers are I64
+            ValueType::Map => types::I64,  // Map pointers are I64
+            ValueType::Enum(_) => types::I64, // Enum pointers are I64
+            ValueType::Tuple(_) => types::I64, // Tuple pointers are I64
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueType::Int => write!(f, "int"),
+            ValueType::Float => write!(f, "float"),
+            ValueType::Bool => write!(f, "bool"),
+            ValueType::Ptr => write!(f, "string"),
+            ValueType::Struct(name) => write!(f, "{}", name),
+            ValueType::List(elem) => write!(f, "[{}]", elem),
+            ValueType::Map => write!(f, "map"),
+            ValueType::Enum(name) => write!(f, "{}", name),
+            ValueType::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", elements.join(", "))
+            }
+        }
+    }
+}
+
+/// A typed value during compilation.
+#[derive(Debug, Clone)]
+struct TypedValue {
+    value: Value,
+    ty: ValueType,
+}
+
+struct FunctionScope {
+    /// Bindings of each enclosing block, innermost last. The first frame
+    /// holds the function's parameters.
+    frames: Vec<HashMap<SmolStr, Binding>>,
+    /// Counter for generating unique variable indices.
+    next_var: usize,
+    #[allow(dead_code)]
+    ptr_type: Type,
+}
+
+/// A named variable and its type.
+#[derive(Debug, Clone)]
+struct Binding {
+    var: Variable,
+    ty: ValueType,
+}
+
+impl FunctionScope {
+    fn new(ptr_type: Type) -> Self {
+        Self {
+            frames: vec![HashMap::new()],
+            next_var: 0,
             ptr_type,
         }
     }
 
-    /// Declare a new Cranelift variable with a specific type.
+    /// Enter a nested block.
+    fn push_scope(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    /// Leave a nested block, dropping its bindings and so uncovering any
+    /// outer ones they shadowed. The bindings are returned so the block can
+    /// be re-entered with [`FunctionScope::restore_scope`].
+    fn pop_scope(&mut self) -> HashMap<SmolStr, Binding> {
+        debug_assert!(self.frames.len() > 1, "popped the function's own scope");
+        self.frames.pop().unwrap_or_default()
+    }
+
+    /// Re-enter a block left with [`FunctionScope::pop_scope`].
+    fn restore_scope(&mut self, bindings: HashMap<SmolStr, Binding>) {
+        self.frames.push(bindings);
+    }
+
+    /// Declare a new Cranelift variable with a specific type in the
+    /// innermost block, shadowing any binding of the same name.
     fn declare_var_typed(
         &mut self,
         name: &SmolStr,
         ty: ValueType,
         builder: &mut FunctionBuilder,
     ) -> Variable {
+        let var = self.declare_anonymous_var(ty.clone(), builder);
+        self.frames
+            .last_mut()
+            .expect("a function scope always has a frame")
+            .insert(name.clone(), Binding { var, ty });
+        var
+    }
+
+    /// Declare a Cranelift variable that has no name in the source, such as a loop index.
+    fn declare_anonymous_var(&mut self, ty: ValueType, builder: &mut FunctionBuilder) -> Variable {
         let var = Variable::new(self.next_var);
         self.next_var += 1;
         builder.declare_var(var, ty.cranelift_type());
-        self.variables.insert(name.clone(), var);
-        self.var_types.insert(name.clone(), ty);
         var
     }
 
@@ -4116,64 +7180,221 @@ impl FunctionScope {
         self.declare_var_typed(name, ValueType::Int, builder)
     }
 
-    /// Get an existing variable or declare a new one with the given type.
+    /// The variable to assign a value of type `ty` to `name`, where
+    /// `literal` says whether the value is an int literal.
+    ///
+    /// Assigning to a visible variable updates it, unless the value's type
+    /// doesn't fit, in which case the assignment makes a new binding that
+    /// shadows the old one until the end of the block.
     fn get_or_declare_var_typed(
         &mut self,
         name: &SmolStr,
         ty: ValueType,
+        literal: bool,
         builder: &mut FunctionBuilder,
     ) -> Variable {
-        if let Some(&var) = self.variables.get(name) {
-            var
-        } else {
-            self.declare_var_typed(name, ty, builder)
+        match self.binding(name) {
+            Some(binding) if !shadows(&binding.ty, &ty, literal) => binding.var,
+            _ => self.declare_var_typed(name, ty, builder),
         }
     }
 
-    /// Get an existing variable or declare a new one (defaults to I64).
-    fn get_or_declare_var(&mut self, name: &SmolStr, builder: &mut FunctionBuilder) -> Variable {
-        self.get_or_declare_var_typed(name, ValueType::Int, builder)
+    /// The innermost binding of `name`.
+    fn binding(&self, name: &SmolStr) -> Option<&Binding> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
     }
 
     /// Get an existing variable.
     fn get_var(&self, name: &SmolStr) -> Option<Variable> {
-        self.variables.get(name).copied()
+        self.binding(name).map(|binding| binding.var)
     }
 
     /// Get the type of a variable.
     fn get_var_type(&self, name: &SmolStr) -> Option<ValueType> {
-        self.var_types.get(name).cloned()
+        self.binding(name).map(|binding| binding.ty.clone())
     }
 }
 
+/// The expression a block evaluates to, if its trailing statement is one.
+fn block_value_expr(block: &Block) -> Option<&Expr> {
+    match &block.value()?.node {
+        StatementKind::Expr(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+/// Whether assigning a value of type `new` to a variable of type `old`
+/// shadows the variable rather than updating it.
+///
+/// Plain `Int` values may be untyped words, so they update variables of any
+/// type in place, and lists are updated with lists of any element type. An
+/// int `literal` is known to be an int, so it only updates `Int` and `Bool`
+/// variables.
+fn shadows(old: &ValueType, new: &ValueType, literal: bool) -> bool {
+    match (old, new) {
+        (ValueType::Int | ValueType::Bool, ValueType::Int) | (ValueType::Int, ValueType::Bool) => {
+            false
+        }
+        (_, ValueType::Int) if !literal => false,
+        (ValueType::List(_), ValueType::List(_)) => false,
+        (old, new) => old != new,
+    }
+}
+
+/// Compile `ast` in memory without writing an object file, returning any warnings.
+pub fn check(ast: &SourceFile) -> Result<Vec<CodegenWarning>, CodegenError> {
+    let mut compiler = Compiler::new()?;
+    compiler.compile(ast)?;
+    Ok(compiler.warnings)
+}
+
+/// Compile `ast` to an object file in memory, without writing any files.
+///
+/// For wasm32 targets the bytes are a WebAssembly module instead. The
+/// `emit_*` and linking options don't apply.
+pub fn compile_to_object(
+    ast: &SourceFile,
+    options: &CodegenOptions,
+) -> Result<Vec<u8>, CodegenError> {
+    if options.is_wasm() {
+        return crate::wasm::compile_to_wasm(ast);
+    }
+    compile_module(ast, options)?.finish()
+}
+
+/// Compile `ast` natively with the code generation settings of `options`.
+fn compile_module(ast: &SourceFile, options: &CodegenOptions) -> Result<Compiler, CodegenError> {
+    let mut compiler = Compiler::for_target(options.target.as_deref(), options.opt_level)?;
+    if options.emit_clif {
+        compiler.enable_clif_dump();
+    }
+    if options.emit_asm {
+        compiler.enable_asm_dump();
+    }
+    if options.overflow_checks {
+        compiler.enable_overflow_checks();
+    }
+    if let Some(limit) = options.stack_guard {
+        compiler.enable_stack_guard(limit);
+    }
+    if options.bounds_checks {
+        compiler.enable_bounds_checks();
+    }
+    compiler.compile(ast)?;
+    Ok(compiler)
+}
+
 /// Compile AST to executable.
+///
+/// For wasm32 targets this writes a `.wasm` module next to `output_path`
+/// instead of a native executable. When cross-compiling, the object file is
+/// written as `.o` and left unlinked, since the host linker can't link for
+/// another target, and with `skip_link`. With `emit_clif` and `emit_asm`,
+/// the Cranelift IR and the disassembly are written alongside as `.clif`
+/// and `.s`.
 pub fn compile_to_executable(
     ast: &SourceFile,
     output_path: &Path,
-    _options: CodegenOptions,
+    options: CodegenOptions,
 ) -> Result<(), CodegenError> {
-    let mut compiler = Compiler::new()?;
-    compiler.compile(ast)?;
+    write_object(ast, output_path, &options)?;
+    link(output_path, &options)
+}
 
-    let object_bytes = compiler.finish();
+/// Compile AST to the `.o` file next to `output_path`, which [`link`] then
+/// turns into the executable, or to the `.wasm` module for wasm32 targets.
+/// The `.clif` and `.s` listings are written as for [`compile_to_executable`].
+pub fn write_object(
+    ast: &SourceFile,
+    output_path: &Path,
+    options: &CodegenOptions,
+) -> Result<(), CodegenError> {
+    // WebAssembly has its own emitter and needs no linking
+    if options.is_wasm() {
+        let wasm_bytes = compile_to_object(ast, options)?;
+        std::fs::write(output_path.with_extension("wasm"), wasm_bytes)?;
+        return Ok(());
+    }
 
-    // Write object file
-    let obj_path = output_path.with_extension("o");
-    std::fs::write(&obj_path, &object_bytes)?;
+    let object_bytes = if options.emit_clif || options.emit_asm {
+        let compiler = compile_module(ast, options)?;
+        if let Some(clif) = compiler.clif() {
+            std::fs::write(output_path.with_extension("clif"), clif)?;
+        }
+        if let Some(asm) = compiler.asm() {
+            std::fs::write(output_path.with_extension("s"), asm)?;
+        }
+        compiler.finish()?
+    } else {
+        compile_to_object(ast, options)?
+    };
+
+    std::fs::write(output_path.with_extension("o"), &object_bytes)?;
+    Ok(())
+}
+
+/// Link the `.o` file [`write_object`] wrote next to `output_path` with the
+/// runtime, then remove it unless `emit_object` is set. Does nothing for
+/// wasm32, when cross-compiling, or with `skip_link`.
+pub fn link(output_path: &Path, options: &CodegenOptions) -> Result<(), CodegenError> {
+    if options.is_wasm() || options.skip_link || options.is_cross() {
+        return Ok(());
+    }
 
     // Link with runtime
-    link_executable(&obj_path, output_path)?;
+    let obj_path = output_path.with_extension("o");
+    link_executable(&obj_path, output_path, options)?;
 
     // Clean up object file
-    std::fs::remove_file(&obj_path).ok();
+    if !options.emit_object {
+        std::fs::remove_file(&obj_path).ok();
+    }
 
     Ok(())
 }
 
 /// Link object file with runtime to create executable.
-fn link_executable(obj_path: &Path, output_path: &Path) -> Result<(), CodegenError> {
+fn link_executable(
+    obj_path: &Path,
+    output_path: &Path,
+    options: &CodegenOptions,
+) -> Result<(), CodegenError> {
     // Find the haira-runtime staticlib
-    let runtime_path = find_runtime_library()?;
+    let runtime_path = match &options.runtime_lib {
+        Some(path) => path.clone(),
+        None => find_runtime_library()?,
+    };
+
+    let mut cmd = link_command(obj_path, &runtime_path, output_path, options);
+    let status = cmd.status().map_err(|e| {
+        CodegenError::LinkerError(format!(
+            "failed to run `{}`: {}",
+            cmd.get_program().to_string_lossy(),
+            e
+        ))
+    })?;
+
+    if !status.success() {
+        return Err(CodegenError::LinkerError("Linker failed".to_string()));
+    }
+
+    Ok(())
+}
+
+/// The command linking `obj_path` with the runtime into `output_path`.
+///
+/// The linker is `options.linker`, else `$HAIRA_LINKER`, else `cc`.
+fn link_command(
+    obj_path: &Path,
+    runtime_path: &Path,
+    output_path: &Path,
+    options: &CodegenOptions,
+) -> Command {
+    let linker = options
+        .linker
+        .clone()
+        .or_else(|| std::env::var("HAIRA_LINKER").ok())
+        .unwrap_or_else(|| "cc".to_string());
 
     // Determine platform-specific linker flags
     #[cfg(target_os = "macos")]
@@ -4185,10 +7406,10 @@ fn link_executable(obj_path: &Path, output_path: &Path) -> Result<(), CodegenErr
     #[cfg(target_os = "windows")]
     let platform_libs = vec!["-lws2_32", "-luserenv"];
 
-    // Use cc to link with pthread for concurrency support
-    let mut cmd = Command::new("cc");
+    // Link with pthread for concurrency support
+    let mut cmd = Command::new(linker);
     cmd.arg(obj_path)
-        .arg(&runtime_path)
+        .arg(runtime_path)
         .arg("-o")
         .arg(output_path)
         .arg("-lpthread");
@@ -4198,13 +7419,8 @@ fn link_executable(obj_path: &Path, output_path: &Path) -> Result<(), CodegenErr
         cmd.arg(lib);
     }
 
-    let status = cmd.status()?;
-
-    if !status.success() {
-        return Err(CodegenError::LinkerError("Linker failed".to_string()));
-    }
-
-    Ok(())
+    cmd.args(&options.link_args);
+    cmd
 }
 
 /// Find the haira-runtime static library.
@@ -4276,3 +7492,1214 @@ fn find_runtime_library() -> Result<std::path::PathBuf, CodegenError> {
             .to_string(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::runtime_library;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Compile and run `source`, linking it with the runtime library.
+    fn run(source: &str, options: CodegenOptions) -> std::process::Output {
+        run_ast(&haira_parser::parse(source).ast, options)
+    }
+
+    /// Compile and run `ast`, linking it with the runtime library.
+    fn run_ast(ast: &SourceFile, options: CodegenOptions) -> std::process::Output {
+        static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "haira-codegen-{}-{}",
+            std::process::id(),
+            RUNS.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("program");
+        let options = CodegenOptions {
+            runtime_lib: Some(runtime_library()),
+            ..options
+        };
+        compile_to_executable(ast, &exe, options).unwrap();
+
+        let output = Command::new(&exe).output().unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        output
+    }
+
+    #[test]
+    fn test_clif_dump_contains_functions() {
+        let result = haira_parser::parse("double(x) {\n    x * 2\n}\nprint(double(21))\n");
+        assert!(result.errors.is_empty());
+
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+
+        let clif = compiler.clif().unwrap();
+        assert!(clif.contains("; double"));
+        assert!(clif.contains("; main"));
+        assert!(clif.contains("function "));
+    }
+
+    #[test]
+    fn test_float_annotation_stores_f64() {
+        let result = haira_parser::parse("ratio: float = 1\nprint(ratio)\n");
+        assert!(result.errors.is_empty());
+
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+
+        // The int literal is converted so the variable holds an f64
+        let clif = compiler.clif().unwrap();
+        assert!(clif.contains("fcvt_from_sint.f64"));
+    }
+
+    #[test]
+    fn test_overflow_checks_emit_checked_arithmetic() {
+        let result = haira_parser::parse("x = 2000000000\nprint(x + x)\n");
+        assert!(result.errors.is_empty());
+
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+        assert!(!compiler.clif().unwrap().contains("sadd_overflow"));
+
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.enable_overflow_checks();
+        compiler.compile(&result.ast).unwrap();
+        assert!(compiler.clif().unwrap().contains("sadd_overflow"));
+    }
+
+    #[test]
+    fn test_overflow_checks_abort_at_runtime() {
+        let options = CodegenOptions {
+            overflow_checks: true,
+            ..Default::default()
+        };
+
+        // Ints are 64-bit, so 2000000000 + 2000000000 is still in range
+        let output = run("x = 2000000000\nprint(x + x)\n", options.clone());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "4000000000");
+
+        let output = run("x = 9223372036854775807\nprint(x + 1)\n", options);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("attempt to add with overflow"));
+    }
+
+    #[test]
+    fn test_bounds_checks_abort_at_runtime() {
+        let options = CodegenOptions {
+            bounds_checks: true,
+            ..Default::default()
+        };
+
+        let output = run(
+            "xs = [1, 2, 3]\nxs[2] = 30\nprint(xs[0] + xs[2])\n",
+            options.clone(),
+        );
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "31\n");
+
+        for (source, message) in [
+            (
+                "xs = [1, 2, 3]\nprint(xs[3])\n",
+                "index 3 out of bounds for a list of length 3",
+            ),
+            (
+                "xs = [1, 2, 3]\ni = 0 - 1\nprint(xs[i])\n",
+                "index -1 out of bounds for a list of length 3",
+            ),
+            (
+                "xs = [1, 2]\nxs[5] = 1\n",
+                "index 5 out of bounds for a list of length 2",
+            ),
+            (
+                "xs = []\nprint(xs[0])\n",
+                "index 0 out of bounds for a list of length 0",
+            ),
+        ] {
+            let output = run(source, options.clone());
+            assert!(!output.status.success(), "{}", source);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(stderr.contains(message), "{}: {}", source, stderr);
+        }
+    }
+
+    #[test]
+    fn test_recursion() {
+        let source = "factorial(n: int) -> int {\n    if n <= 1 {\n        1\n    } else {\n        n * factorial(n - 1)\n    }\n}\n\nis_even(n: int) -> bool {\n    if n == 0 {\n        true\n    } else {\n        is_odd(n - 1)\n    }\n}\n\nis_odd(n: int) -> bool {\n    if n == 0 {\n        false\n    } else {\n        is_even(n - 1)\n    }\n}\n\nprint(factorial(10))\nprint(is_even(10))\nprint(is_odd(7))\nprint(is_even(7))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "3628800\ntrue\ntrue\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn test_stack_guard_stops_unbounded_recursion() {
+        let options = CodegenOptions {
+            stack_guard: Some(1 << 20),
+            ..Default::default()
+        };
+
+        // Bounded recursion stays within the limit
+        let source = "depth(n: int) -> int {\n    if n == 0 {\n        0\n    } else {\n        1 + depth(n - 1)\n    }\n}\n\nprint(depth(100))\n";
+        let output = run(source, options.clone());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "100\n");
+
+        let source = "forever(n: int) -> int {\n    1 + forever(n + 1)\n}\n\nprint(forever(0))\n";
+        let output = run(source, options);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("panic: stack overflow"));
+    }
+
+    #[test]
+    fn test_div_by_constant_has_no_zero_guard() {
+        let result = haira_parser::parse("half(x) {\n    x / 2\n}\n");
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+        assert!(!compiler.clif().unwrap().contains("trap int_divz"));
+
+        let result = haira_parser::parse("divide(x, y) {\n    x / y\n}\n");
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+        assert!(compiler.clif().unwrap().contains("trap int_divz"));
+    }
+
+    #[test]
+    fn test_div_by_zero_aborts_cleanly() {
+        let output = run("x = 10\ny = 0\nprint(x / y)\n", CodegenOptions::default());
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("division by zero"));
+    }
+
+    #[test]
+    fn test_float_returning_function() {
+        let source = "half(x) -> float {\n    x / 2.0\n}\nprint(half(7))\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+        let clif = compiler.clif().unwrap();
+        assert!(clif.contains("(i64) -> f64"));
+
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3.5");
+    }
+
+    #[test]
+    fn test_break_exits_while_loop() {
+        let source = "i = 0\nwhile i < 10 {\n    if i == 3 {\n        break\n    }\n    print(i)\n    i = i + 1\n}\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n1\n2\n");
+    }
+
+    #[test]
+    fn test_continue_skips_to_next_iteration() {
+        let source =
+            "for i in 0..6 {\n    if i % 2 == 0 {\n        continue\n    }\n    print(i)\n}\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n3\n5\n");
+    }
+
+    #[test]
+    fn test_for_loop_sums_list() {
+        let source = "total = 0\nfor x in [1, 2, 3] {\n    total = total + x\n}\nprint(total)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "6");
+    }
+
+    #[test]
+    fn test_for_loop_concatenates_string_list() {
+        let source = "words = [\"a\", \"b\", \"c\"]\nout = \"\"\nfor w in words {\n    out = \"{out}{w}\"\n}\nprint(out)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "abc");
+    }
+
+    #[test]
+    fn test_field_assignment_uses_object_struct_type() {
+        let source = "Left { value, pad }\nRight { pad, value }\n\na = Left { value = 0, pad = 0 }\nb = Right { pad = 0, value = 0 }\na.value = 1\nb.value = 2\nprint(a)\nprint(b)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "Left { value: 1, pad: 0 }\nRight { pad: 0, value: 2 }\n"
+        );
+
+        // Through an untyped parameter the object could be either struct
+        let source = "Left { value, pad }\nRight { pad, value }\n\nstore(o) {\n    o.value = 5\n}\n\nb = Right { pad = 0, value = 0 }\nstore(b)\nprint(b)\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+        let err = Compiler::new().unwrap().compile(&result.ast).unwrap_err();
+        assert!(
+            matches!(err, CodegenError::Unsupported(ref msg) if msg.contains("not a known struct")),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_nested_field_assignment_follows_field_types() {
+        let source = "Left { value, pad }\nRight { pad, value }\nOuter { tag, inner: Right }\n\nb = Right { pad = 0, value = 0 }\no = Outer { tag = 0, inner = b }\no.inner.value = 5\nprint(b)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "Right { pad: 0, value: 5 }\n"
+        );
+    }
+
+    #[test]
+    fn test_int_assigned_to_float_field_is_converted() {
+        let source = "P { v: float }\n\np = P { v = 1.5 }\np.v = 2\nprint(p.v)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+    }
+
+    #[test]
+    fn test_field_read_uses_object_struct_type() {
+        let source = "Left { value, pad }\nRight { pad, value }\n\na = Left { value = 1, pad = 10 }\nb = Right { pad = 20, value = 2 }\nprint(a.value)\nprint(b.value)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n2\n");
+    }
+
+    #[test]
+    fn test_shared_field_read_on_untyped_object_is_an_error() {
+        // `o` is untyped and `value` sits at a different offset in each struct
+        let source = "A { value: int, pad: int }\nB { pad: int, value: int }\n\nget(o) {\n    return o.value\n}\n\nb = B { pad = 1, value = 2 }\nprint(get(b))\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+        let err = Compiler::new().unwrap().compile(&result.ast).unwrap_err();
+        assert!(
+            matches!(err, CodegenError::Unsupported(ref msg) if msg.contains("not a known struct")),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_unique_field_on_untyped_object_resolves() {
+        let source = "A { value: int, pad: int }\nB { pad: int, extra: int }\n\nbump(o) {\n    o.value = o.value + 1\n}\n\na = A { value = 1, pad = 0 }\nbump(a)\nprint(a)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "A { value: 2, pad: 0 }\n"
+        );
+    }
+
+    #[test]
+    fn test_field_read_on_non_struct_is_an_error() {
+        let result = haira_parser::parse("Point { x, y }\n\nf() {\n    s = \"text\"\n    s.x\n}\n");
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(matches!(err, CodegenError::Unsupported(ref msg) if msg.contains("not a struct")));
+    }
+
+    #[test]
+    fn test_method_call_dispatches_on_receiver_type() {
+        let source = "Cat { lives }\nDog { tricks }\n\nCat.describe() {\n    return 1\n}\n\nDog.describe() {\n    return 2\n}\n\nc = Cat { lives = 9 }\nd = Dog { tricks = 3 }\nprint(c.describe())\nprint(d.describe())\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n2\n");
+    }
+
+    #[test]
+    fn test_method_on_untyped_receiver_needs_a_single_definition() {
+        let source = "Cat { lives }\nDog { tricks }\n\nCat.describe() {\n    return 1\n}\n\nDog.describe() {\n    return 2\n}\n\nshow(p) {\n    return p.describe()\n}\n\nd = Dog { tricks = 3 }\nprint(show(d))\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+        let err = Compiler::new().unwrap().compile(&result.ast).unwrap_err();
+        assert!(
+            matches!(err, CodegenError::InvalidCall(ref msg) if msg.contains("on Cat, Dog")),
+            "{}",
+            err
+        );
+
+        let source = "Cat { lives }\nDog { tricks }\n\nDog.fetch() {\n    return self.tricks\n}\n\nplay(p) {\n    return p.fetch()\n}\n\nd = Dog { tricks = 3 }\nprint(play(d))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+    }
+
+    #[test]
+    fn test_missing_method_on_receiver_type_is_an_error() {
+        let source = "Cat { lives }\nDog { tricks }\n\nDog.fetch() {\n    return 1\n}\n\nc = Cat { lives = 9 }\nc.fetch()\n";
+        let result = haira_parser::parse(source);
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(
+            matches!(err, CodegenError::UndefinedFunction(ref msg) if msg.contains("fetch") && msg.contains("Cat"))
+        );
+    }
+
+    #[test]
+    fn test_struct_layouts_of_mixed_struct() {
+        let result = haira_parser::parse("Sample { count: int, ratio: float, label: string }\n");
+        let layouts = struct_layouts(&result.ast);
+        assert_eq!(
+            layouts,
+            vec![StructLayout {
+                name: "Sample".to_string(),
+                fields: vec![
+                    FieldLayout {
+                        name: "count".to_string(),
+                        ty: "int".to_string(),
+                        offset: 0,
+                    },
+                    FieldLayout {
+                        name: "ratio".to_string(),
+                        ty: "float".to_string(),
+                        offset: 8,
+                    },
+                    FieldLayout {
+                        name: "label".to_string(),
+                        ty: "string".to_string(),
+                        offset: 16,
+                    },
+                ],
+                size: 24,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bool_fields_print_as_booleans() {
+        let source = "Flags { on: bool, off: bool }\n\nf = Flags { on = true, off = false }\nprint(f)\nprint(f.on)\nprint(not f.on)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "Flags { on: true, off: false }\ntrue\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn test_code_after_return_is_dropped_with_warning() {
+        // The call after `return` would fail to compile if it weren't dropped
+        let result = haira_parser::parse("f() {\n    return 1\n    undefined_function()\n}\n");
+        let warnings = check(&result.ast).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "unreachable code after `return`");
+    }
+
+    #[test]
+    fn test_list_push_pop_and_len() {
+        let source = "xs = []\nxs.push(10)\nxs.push(20)\nxs.push(30)\nxs.push(40)\nxs.push(50)\nprint(xs.len())\nprint(xs[1])\nprint(xs.get(4))\nprint(xs.pop())\ntotal = 0\nfor x in xs {\n    total = total + x\n}\nprint(total)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "5\n20\n50\n50\n100\n"
+        );
+    }
+
+    #[test]
+    fn test_list_literals_with_trailing_commas() {
+        let source = "a = []\nb = [1,]\nc = [1, 2, 3,]\nprint(a.len())\nprint(b.len())\nprint(c.len())\nprint(c[2])\nfor x in a {\n    print(x)\n}\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "0\n1\n3\n3\n");
+    }
+
+    #[test]
+    fn test_map_round_trips_keys() {
+        let source = "m = map()\nm.set(\"one\", 1)\nm.set(\"two\", 2)\nm.set(\"three\", 3)\nm.set(\"two\", 22)\nprint(m.get(\"one\"))\nprint(m.get(\"two\"))\nprint(m.get(\"three\"))\nprint(m.has(\"three\"))\nprint(m.remove(\"three\"))\nprint(m.has(\"three\"))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "1\n22\n3\ntrue\n3\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn test_json_parse_field_and_stringify() {
+        let source = "doc = json_parse(\"\\{\\\"a\\\":1\\}\")\na = json_get_field(doc, \"a\")\nprint(json_as_int(a))\nprint(json_stringify(doc))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n{\"a\":1}\n");
+    }
+
+    #[test]
+    fn test_string_split() {
+        let source = "for part in \"a,b,c\".split(\",\") {\n    print(part)\n}\nparts = \"x,,y,\".split(\",\")\nprint(parts.len())\nprint(parts[2])\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "a\nb\nc\n4\ny\n");
+    }
+
+    #[test]
+    fn test_string_join() {
+        let source = "print(join([\"a\", \"b\", \"c\"], \"-\"))\nprint(join(\"x\".split(\",\"), \", \"))\nprint(join(\"a,b\".split(\",\"), \"\"))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "a-b-c\nx\nab\n");
+    }
+
+    #[test]
+    fn test_rest_parameter_collects_trailing_arguments() {
+        let source = "sum(nums...) {\n    total = 0\n    for n in nums {\n        total = total + n\n    }\n    return total\n}\n\nlabel(prefix, values...) {\n    return prefix + values.len()\n}\n\nprint(sum(1, 2, 3))\nprint(sum())\nprint(label(100, 7, 8))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "6\n0\n102\n");
+    }
+
+    #[test]
+    fn test_named_and_default_arguments() {
+        let source = "scale(value, factor = 10, offset = 0) {\n    return value * factor + offset\n}\n\nprint(scale(offset = 1, factor = 2, value = 5))\nprint(scale(3))\nprint(scale(3, offset = 4))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "11\n30\n34\n");
+    }
+
+    #[test]
+    fn test_invalid_call_arguments_are_errors() {
+        for source in [
+            "f(a, b) {\n    return a + b\n}\nprint(f(1))\n",
+            "f(a) {\n    return a\n}\nprint(f(b = 1))\n",
+            "f(a) {\n    return a\n}\nprint(f(1, 2))\n",
+        ] {
+            let result = haira_parser::parse(source);
+            let mut compiler = Compiler::new().unwrap();
+            let err = compiler.compile(&result.ast).unwrap_err();
+            assert!(matches!(err, CodegenError::InvalidCall(_)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_enum_construction_and_match() {
+        let source = "Shape {\n    Circle(float)\n    Rect(int, int)\n    Empty\n}\n\narea(s: Shape) -> float {\n    return match s {\n        Circle(r) => r * r * 3.0\n        Rect(w, h) => w * h\n        Empty => 0.0\n    }\n}\n\nprint(area(Circle(2.0)))\nprint(area(Rect(2, 3)))\nprint(area(Empty))\nprint(Rect(4, 5))\nprint(Empty)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "12\n6\n0\nRect(4, 5)\nEmpty\n"
+        );
+    }
+
+    #[test]
+    fn test_non_exhaustive_enum_match_is_an_error() {
+        let source = "Color { Red, Green, Blue }\nc = Green\nprint(match c {\n    Red => 1\n    Green => 2\n})\n";
+        let result = haira_parser::parse(source);
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(matches!(err, CodegenError::NonExhaustiveMatch(ref msg) if msg.contains("Blue")));
+    }
+
+    #[test]
+    fn test_failed_guard_falls_through_to_later_arm() {
+        let source = "classify(n) -> string {\n    return match n {\n        x if x > 10 => \"big\"\n        x if x > 0 => \"small\"\n        0 => \"zero\"\n        _ => \"negative\"\n    }\n}\n\nShape {\n    Circle(float)\n    Empty\n}\n\ndescribe(s: Shape) -> string {\n    return match s {\n        Circle(r) if r > 1.0 => \"large circle\"\n        Circle(r) => \"circle\"\n        Empty => \"empty\"\n    }\n}\n\nprint(classify(20))\nprint(classify(5))\nprint(classify(0))\nprint(classify(-3))\nprint(describe(Circle(2.5)))\nprint(describe(Circle(0.5)))\nprint(match 2 {\n    _ if 0.0 => 1\n    _ => 2\n})\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "big\nsmall\nzero\nnegative\nlarge circle\ncircle\n2\n"
+        );
+    }
+
+    #[test]
+    fn test_pointer_guard_is_an_error() {
+        let result =
+            haira_parser::parse("print(match 1 {\n    _ if \"yes\" => 1\n    _ => 0\n})\n");
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(matches!(err, CodegenError::InvalidPattern(ref msg) if msg.contains("guard")));
+    }
+
+    #[test]
+    fn test_negative_and_scientific_floats() {
+        let source = "print(-2.5)\nprint(1e3 + 0.5)\nx = 2.5e-3\nprint(-x)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "-2.5\n1000.5\n-0.0025\n"
+        );
+    }
+
+    #[test]
+    fn test_static_method_call() {
+        let source = "Point { x, y }\n\nPoint.sum() {\n    return self.x + self.y\n}\n\nPoint.double(n) {\n    return n * 2\n}\n\nprint(Point.double(21))\np = Point { x = 1, y = 2 }\nprint(p.sum())\nprint(Point.double(p.sum()))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n3\n6\n");
+    }
+
+    #[test]
+    fn test_method_parameters_use_their_annotations() {
+        let source = "Box { width: float }\n\nBox.scaled(factor: float) -> float {\n    return self.width * factor\n}\n\nBox.half(n: float) -> float {\n    return n / 4\n}\n\nb = Box { width = 1.5 }\nprint(b.scaled(3))\nprint(Box.half(5))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "4.5\n1.25\n");
+    }
+
+    #[test]
+    fn test_method_named_and_default_arguments() {
+        let source = "Counter { n }\n\nCounter.add(by = 1, times = 1) {\n    return self.n + by * times\n}\n\nCounter.make(start = 7) {\n    return start\n}\n\nc = Counter { n = 10 }\nprint(c.add())\nprint(c.add(5))\nprint(c.add(times = 3))\nprint(Counter.make())\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "11\n15\n13\n7\n");
+    }
+
+    #[test]
+    fn test_static_call_of_method_using_self_is_an_error() {
+        let result = haira_parser::parse(
+            "Point { x, y }\n\nPoint.sum() {\n    return self.x + self.y\n}\n\nprint(Point.sum())\n",
+        );
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(matches!(err, CodegenError::InvalidCall(ref msg) if msg.contains("`Point.sum`")));
+    }
+
+    #[test]
+    fn test_every_opt_level_compiles() {
+        let source = "square(n) {\n    return n * n\n}\n\ntotal = 0\nfor i in 0..4 {\n    total = total + square(i)\n}\nprint(total)\n";
+        let result = haira_parser::parse(source);
+        for opt_level in 0..=3 {
+            let mut compiler = Compiler::with_opt_level(opt_level).unwrap();
+            compiler.compile(&result.ast).unwrap();
+            assert!(!compiler.finish().unwrap().is_empty());
+        }
+
+        for opt_level in 0..=3 {
+            let options = CodegenOptions {
+                opt_level,
+                ..Default::default()
+            };
+            let output = run(source, options);
+            assert!(output.status.success());
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "14\n");
+        }
+    }
+
+    #[test]
+    fn test_cross_compile_emits_foreign_object() {
+        let result = haira_parser::parse("double(x) {\n    x * 2\n}\nprint(double(21))\n");
+        let (target, machine) = if cfg!(target_arch = "aarch64") {
+            ("x86_64-unknown-linux-gnu", 62) // EM_X86_64
+        } else {
+            ("aarch64-unknown-linux-gnu", 183) // EM_AARCH64
+        };
+        let options = CodegenOptions {
+            target: Some(target.to_string()),
+            ..Default::default()
+        };
+        assert!(options.is_cross());
+
+        let dir = std::env::temp_dir().join(format!("haira-cross-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("program");
+        compile_to_executable(&result.ast, &output, options).unwrap();
+
+        // Only the object is written, as an ELF file for the target machine
+        assert!(!output.exists());
+        let object = std::fs::read(output.with_extension("o")).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(&object[..4], b"\x7fELF");
+        assert_eq!(u16::from_le_bytes([object[18], object[19]]), machine);
+    }
+
+    #[test]
+    fn test_unknown_target_is_unsupported() {
+        let err = Compiler::for_target(Some("sparc-unknown-none"), 0)
+            .err()
+            .unwrap();
+        assert!(matches!(err, CodegenError::Unsupported(ref msg) if msg.contains("sparc")));
+    }
+
+    #[test]
+    fn test_emit_object_skips_linking() {
+        let result = haira_parser::parse("double(x) {\n    x * 2\n}\nprint(double(21))\n");
+        let dir = std::env::temp_dir().join(format!("haira-emit-obj-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("program");
+        let options = CodegenOptions {
+            skip_link: true,
+            emit_asm: true,
+            ..Default::default()
+        };
+        compile_to_executable(&result.ast, &output, options).unwrap();
+
+        let exists = output.exists();
+        let object = std::fs::read(output.with_extension("o")).unwrap();
+        let asm = std::fs::read_to_string(output.with_extension("s")).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(!exists);
+        assert!(object.starts_with(b"\x7fELF") || object.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]));
+        assert!(asm.contains("; double\n"));
+        assert!(asm.contains("; main\n"));
+    }
+
+    #[test]
+    fn test_link_command_uses_configured_linker() {
+        let options = CodegenOptions {
+            linker: Some("/bin/true".to_string()),
+            link_args: vec!["-static".to_string(), "-Wl,--gc-sections".to_string()],
+            ..Default::default()
+        };
+        let mut cmd = link_command(
+            Path::new("program.o"),
+            Path::new("libhaira_runtime.a"),
+            Path::new("program"),
+            &options,
+        );
+
+        assert_eq!(cmd.get_program(), "/bin/true");
+        let args: Vec<_> = cmd.get_args().map(|arg| arg.to_str().unwrap()).collect();
+        assert_eq!(
+            &args[..4],
+            ["program.o", "libhaira_runtime.a", "-o", "program"]
+        );
+        assert_eq!(&args[args.len() - 2..], ["-static", "-Wl,--gc-sections"]);
+        if Path::new("/bin/true").exists() {
+            assert!(cmd.status().unwrap().success());
+        }
+    }
+
+    #[test]
+    fn test_method_self_fields_use_own_struct_layout() {
+        // `Pair` shares field names with `Point`, at different offsets
+        let source = "Pair { label, y, x }\nPoint { x, y }\n\nPoint.shift(dx) {\n    self.x = self.x + dx\n    return self.y\n}\n\np = Point { x = 1, y = 2 }\nprint(p.shift(10))\nprint(p.x)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n11\n");
+    }
+
+    #[test]
+    fn test_return_values_coerce_to_declared_type() {
+        let source = "zero() -> float {\n    return 0\n}\n\nscale(n: int) -> float {\n    if n > 0 {\n        return n * 2\n    }\n    return 1\n}\n\ndouble(x: float) -> int {\n    return x * 2.0\n}\n\nprint(zero() + 0.5)\nprint(scale(3) / 4.0)\nprint(double(1.75))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "0.5\n1.5\n3\n");
+    }
+
+    #[test]
+    fn test_incompatible_return_is_an_error() {
+        let result = haira_parser::parse(
+            "Point { x, y }\n\norigin() -> Point {\n    return \"origin\"\n}\n\nprint(origin().x)\n",
+        );
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(
+            matches!(err, CodegenError::TypeMismatch(ref msg) if msg == "cannot return string from a function returning Point"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_int_returned_as_string_is_an_error() {
+        let result = haira_parser::parse("f() -> string {\n    return 5\n}\n\nprint(f())\n");
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(
+            matches!(err, CodegenError::TypeMismatch(ref msg) if msg == "cannot return int from a function returning string"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_unannotated_call_returned_as_struct() {
+        let source = "Point { x, y }\n\nmake() {\n    return Point { x = 1, y = 2 }\n}\n\nwrap() -> Point {\n    return make()\n}\n\nprint(wrap().x)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    }
+
+    #[test]
+    fn test_shadowing_in_block_restores_outer_binding() {
+        let source = "x = 1\ncount = 0\nif true {\n    x = \"hello\"\n    count = count + 1\n    print(x)\n}\nprint(x + 1)\nprint(count)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n2\n1\n");
+    }
+
+    #[test]
+    fn test_rebinding_with_new_type_in_same_scope() {
+        let source = "x = 1\nx = \"hi\"\nprint(x)\ny = 2.5\ni = 0\nwhile i < 2 {\n    y = \"done\"\n    print(y)\n    i = i + 1\n}\nprint(y)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hi\ndone\ndone\n2.5\n"
+        );
+    }
+
+    #[test]
+    fn test_rebinding_string_to_int_in_same_scope() {
+        let source = "x = \"hi\"\nx = 5\nprint(x)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "5\n");
+    }
+
+    #[test]
+    fn test_untyped_value_in_block_updates_outer_string() {
+        let source = "label() {\n    return \"custom\"\n}\n\nname = \"default\"\nif true {\n    name = label()\n}\nprint(name)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "custom\n");
+    }
+
+    #[test]
+    fn test_block_variables_do_not_outlive_their_block() {
+        for source in [
+            "if true {\n    inner = 1\n}\nprint(inner)\n",
+            "for i in 0..3 {\n    print(i)\n}\nprint(i)\n",
+            "n = 0\nwhile n < 3 {\n    step = 1\n    n = n + step\n}\nprint(step)\n",
+        ] {
+            let result = haira_parser::parse(source);
+            let mut compiler = Compiler::new().unwrap();
+            let err = compiler.compile(&result.ast).unwrap_err();
+            assert!(
+                matches!(err, CodegenError::UndefinedVariable(_)),
+                "{}: {}",
+                source,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_match_arms_bind_their_own_variables() {
+        let source = "Shape {\n    Circle(float)\n    Square(int)\n}\n\ndescribe(s: Shape) {\n    match s {\n        Circle(r) => print(r)\n        Square(r) => print(r * 2)\n    }\n}\n\ndescribe(Circle(1.5))\ndescribe(Square(4))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1.5\n8\n");
+    }
+
+    #[test]
+    fn test_invalid_ir_is_a_structured_error() {
+        // A function declared to return an int that returns a float
+        let mut compiler = Compiler::new().unwrap();
+        let mut sig = compiler.module.make_signature();
+        sig.returns.push(AbiParam::new(types::I64));
+        let func_id = compiler
+            .module
+            .declare_function("malformed", Linkage::Local, &sig)
+            .unwrap();
+        compiler.ctx.func.signature = sig;
+        let mut builder = FunctionBuilder::new(&mut compiler.ctx.func, &mut compiler.builder_ctx);
+        let entry = builder.create_block();
+        builder.switch_to_block(entry);
+        builder.seal_block(entry);
+        let value = builder.ins().f64const(1.0);
+        builder.ins().return_(&[value]);
+        builder.finalize();
+
+        let err = compiler.define_function(func_id, "malformed").unwrap_err();
+        if cfg!(debug_assertions) {
+            assert!(
+                matches!(err, CodegenError::CraneliftError(ref msg) if msg.starts_with("invalid IR in `malformed`")),
+                "{}",
+                err
+            );
+        } else {
+            assert!(matches!(err, CodegenError::ModuleError(_)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_identical_strings_share_one_data_object() {
+        let source = "Point { x, y }\n\ngreet() {\n    print(\"hello there\")\n}\n\nwave() {\n    print(\"hello there\")\n    a = Point { x = 1, y = 2 }\n    b = Point { x = 3, y = 4 }\n    print(a)\n    print(b)\n}\n\ngreet()\nwave()\n";
+        let result = haira_parser::parse(source);
+        let mut compiler = Compiler::new().unwrap();
+        compiler.compile(&result.ast).unwrap();
+        let mut strings: Vec<String> = compiler.strings.keys().map(|s| s.to_string()).collect();
+        strings.sort_unstable();
+        assert_eq!(
+            strings,
+            [" }", ", ", "Point { ", "hello there", "x: ", "y: "]
+        );
+
+        let object = compiler.finish().unwrap();
+        let count = |needle: &[u8]| {
+            object
+                .windows(needle.len())
+                .filter(|w| *w == needle)
+                .count()
+        };
+        assert_eq!(count(b"hello there"), 1);
+        assert_eq!(count(b".str."), strings.len());
+    }
+
+    #[test]
+    fn test_string_layout_matches_runtime() {
+        use haira_runtime::HairaString;
+        use std::mem::{offset_of, size_of};
+
+        assert_eq!(size_of::<HairaString>() as i64, HAIRA_STRING_SIZE);
+        assert_eq!(
+            offset_of!(HairaString, data) as i32,
+            HAIRA_STRING_DATA_OFFSET
+        );
+        assert_eq!(offset_of!(HairaString, len) as i32, HAIRA_STRING_LEN_OFFSET);
+        assert_eq!(offset_of!(HairaString, cap) as i32, HAIRA_STRING_CAP_OFFSET);
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        let source = "Counter {\n    count: int\n}\n\nhit(c: Counter, result: bool) -> bool {\n    c.count += 1\n    result\n}\n\nc = Counter { count = 0 }\na = false and hit(c, true)\nprint(c.count)\nb = true and hit(c, true)\nprint(c.count)\nd = true or hit(c, false)\nprint(c.count)\ne = false or hit(c, true)\nprint(c.count)\nprint(a)\nprint(b)\nprint(d)\nprint(e)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "0\n1\n1\n2\nfalse\ntrue\ntrue\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let source = "Counter {\n    count: int\n}\n\nx = 10\nx += 5\nx -= 3\nx *= 4\nx /= 6\nx %= 5\nx <<= 3\nx >>= 1\nx |= 1\nx &= 7\nx ^= 2\nprint(x)\nf = 1.25\nf *= 2\nprint(f)\nc = Counter { count = 1 }\nc.count += 41\nprint(c.count)\narr = [1, 2, 3]\ni = 0\narr[i + 1] *= 10\narr[2] -= 1\nprint(arr[1])\nprint(arr[2])\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "7\n2.5\n42\n20\n2\n"
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment_needs_a_variable() {
+        let ast = haira_parser::parse("x += 1\n").ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert!(matches!(err, CodegenError::UndefinedVariable(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let source = "print(6 & 3)\nprint(1 << 4)\nprint(0xFF ^ 0x0F)\nprint(-16 >> 60)\nflags = 4\nmask = 1\nprint(flags | mask)\nprint(flags | 2)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "2\n16\n240\n15\n5\n6\n"
+        );
+    }
+
+    #[test]
+    fn test_bitwise_or_with_calls_and_parens() {
+        let source = "mask() {\n    return 3\n}\n\nadd(a, b) {\n    return a + b\n}\n\ndouble(n) {\n    return n * 2\n}\n\nflags = 8\nb = 16\nprint(flags | mask())\nprint(flags | (b))\nprint(flags | add(1))\nprint(flags | double)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "11\n24\n9\n16\n");
+    }
+
+    #[test]
+    fn test_string_comparison() {
+        let source = "a = \"apple\"\nb = \"banana\"\np = \"app\"\nc = \"{p}le\"\nprint(\"hi\" == \"hi\")\nprint(a == c)\nprint(a != b)\nprint(a < b)\nprint(b <= a)\nprint(\"b\" > \"abc\")\nprint(\"ab\" >= \"abc\")\nprint(c >= a)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "true\ntrue\ntrue\ntrue\nfalse\ntrue\nfalse\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn test_option_checks_and_default() {
+        let source = "Counter {\n    count: int\n}\n\nfallback(c: Counter) -> int {\n    c.count += 1\n    0\n}\n\nc = Counter { count = 0 }\nfound = some(5)\nmissing = none\nprint(found.is_some())\nprint(found.is_none())\nprint(missing.is_some())\nprint(missing.is_none())\nprint(found ?? fallback(c))\nprint(c.count)\nprint(missing ?? 7)\nprint(missing ?? fallback(c))\nprint(c.count)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "true\nfalse\nfalse\ntrue\n5\n0\n7\n0\n1\n"
+        );
+    }
+
+    #[test]
+    fn test_caught_error_message() {
+        let source = "fail(reason: string) -> int {\n    err(reason)\n}\n\ntry {\n    fail(\"disk full\")\n} catch e {\n    print(get_error_message())\n}\ntry {\n    err(7)\n} catch e {\n    print(e)\n    print(get_error_message())\n}\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "disk full\n7\n\n");
+    }
+
+    #[test]
+    fn test_caught_error_does_not_reach_a_later_propagate() {
+        let source = "check(n: int) -> int {\n    n\n}\n\nrun() -> int {\n    try {\n        err(1)\n    } catch e {\n        err(\"while handling\")\n    }\n    x = check(5)?\n    x + 1\n}\n\nprint(run())\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "6\n");
+    }
+
+    #[test]
+    fn test_catch_variable_is_scoped_to_the_catch() {
+        let ast =
+            haira_parser::parse("try {\n    err(1)\n} catch e {\n    print(e)\n}\nprint(e)\n").ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert!(matches!(err, CodegenError::UndefinedVariable(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_block_values() {
+        let source = "sign(x: int) -> string {\n    if x < 0 {\n        \"negative\"\n    } else if x == 0 {\n        \"zero\"\n    } else {\n        \"positive\"\n    }\n}\n\nhalf(x: int) -> float {\n    match x {\n        0 => 0.0\n        _ => x / 2.0\n    }\n}\n\nz = {\n    a = 2\n    a * 3\n}\nprint(z)\ny = if z > 5 {\n    b = z + 1\n    b * 2\n} else {\n    0\n}\nprint(y)\nprint(sign(-3))\nprint(sign(0))\nprint(sign(8))\nprint(half(5))\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "6\n14\nnegative\nzero\npositive\n2.5\n"
+        );
+    }
+
+    #[test]
+    fn test_if_expression_unifies_branch_types() {
+        let source = "c = true\nx = if c { 1.5 } else { 2.5 }\nprint(x)\ny = if not c { 1.5 } else { 2 }\nprint(y)\nz = if c { 1 } else if x > 1.0 { 2.5 } else { 3 }\nprint(z)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1.5\n2\n1\n");
+    }
+
+    #[test]
+    fn test_if_expression_with_incompatible_branches_is_an_error() {
+        let ast = haira_parser::parse(
+            "Point {\n    x: int\n}\n\nc = true\np = if c { Point { x = 1 } } else { 2 }\n",
+        )
+        .ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert!(matches!(err, CodegenError::TypeMismatch(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_print_uses_call_and_field_types() {
+        let source = "Point {\n    x: float\n    y: float\n}\n\nPoint.half() -> float {\n    self.x / 2.0\n}\n\nPoint.label() -> string {\n    \"point\"\n}\n\ngreet(name: string) -> string {\n    \"hi {name}\"\n}\n\np = Point { x = 3.0, y = 1.5 }\nprint(greet(\"bob\"))\nprint(p.y)\nprint(p.half())\nprint(p.label())\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hi bob\n1.5\n1.5\npoint\n"
+        );
+    }
+
+    #[test]
+    fn test_multiple_return_values() {
+        let source = "divmod(a, b) {\n    return a / b, a % b\n}\n\nsplit(n: int) -> (int, string) {\n    if n > 0 {\n        return n, \"positive\"\n    }\n    return 0 - n, \"negative\"\n}\n\nq, r = divmod(17, 5)\nprint(q)\nprint(r)\n(m, sign) = split(0 - 3)\nprint(m)\nprint(sign)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "3\n2\n3\nnegative\n"
+        );
+    }
+
+    #[test]
+    fn test_unannotated_multiple_return_infers_types() {
+        let source = "pair() {\n    return 1, \"two\"\n}\n\nstats(n: int) {\n    label = \"n={n}\"\n    if n > 1 {\n        return n * 2, label, true\n    }\n    return 0, \"{label}!\", n < 0\n}\n\na, b = pair()\nprint(a)\nprint(b)\nx, y, z = stats(1)\nprint(x)\nprint(y)\nprint(z)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "1\ntwo\n0\nn=1!\nfalse\n"
+        );
+
+        // Values are typed as they compile, calls included
+        let source = "name() -> string {\n    return \"ada\"\n}\n\npair() {\n    return 1, name()\n}\n\na, b = pair()\nprint(b)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ada\n");
+
+        let ast = haira_parser::parse(
+            "pair(n: int) {\n    if n > 0 {\n        return 1, \"one\"\n    }\n    return 0, 0\n}\n",
+        )
+        .ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Type mismatch: can't infer the type of value 2 returned by pair; annotate its return type, as in `-> (int, string)`"
+        );
+    }
+
+    #[test]
+    fn test_multiple_return_arity_mismatch_is_an_error() {
+        let ast = haira_parser::parse(
+            "divmod(a, b) {\n    return a / b, a % b\n}\n\nq, r, extra = divmod(17, 5)\n",
+        )
+        .ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert!(matches!(err, CodegenError::TypeMismatch(_)), "{}", err);
+
+        let ast = haira_parser::parse("pair() -> (int, int) {\n    return 1, 2, 3\n}\n").ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert!(matches!(err, CodegenError::TypeMismatch(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_ambiguous_lookups_compile_deterministically() {
+        // `p` is untyped and several types define `describe`, so the call is
+        // an error listing them in definition order
+        let source = "C { x }\nA { x }\nB { x }\n\nC.describe() {\n    return 3\n}\n\nA.describe() {\n    return 1\n}\n\nB.describe() {\n    return 2\n}\n\nshow(p) {\n    return p.describe()\n}\n\nb = B { x = 4 }\nprint(show(b))\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+
+        for _ in 0..10 {
+            let err = Compiler::new().unwrap().compile(&result.ast).unwrap_err();
+            assert!(
+                matches!(err, CodegenError::InvalidCall(ref msg) if msg.ends_with("defined on C, A, B")),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_registries_compile_deterministically() {
+        let source = "Zebra { stripes }\nApe { arms }\nMole { depth }\n\nZebra.describe() {\n    return self.stripes\n}\n\nApe.describe() {\n    return self.arms\n}\n\nMole.describe() {\n    return self.depth\n}\n\ntotal(z: Zebra, a: Ape, m: Mole) {\n    return z.describe() + a.describe() + m.describe()\n}\n\nz = Zebra { stripes = 40 }\na = Ape { arms = 2 }\nm = Mole { depth = 3 }\nprint(total(z, a, m))\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+
+        let compile = || {
+            let mut compiler = Compiler::new().unwrap();
+            compiler.compile(&result.ast).unwrap();
+            compiler.finish().unwrap()
+        };
+        let first = compile();
+        for _ in 0..10 {
+            assert!(compile() == first, "object output differs between runs");
+        }
+    }
+
+    #[test]
+    fn test_identical_sources_compile_to_identical_objects() {
+        let source = "greet(name: string) {\n    print(\"hello {name}\")\n}\n\nfarewell(name: string) {\n    print(\"bye {name}\")\n}\n\nfor word in [\"a\", \"b\", \"c\", \"d\", \"e\", \"f\"] {\n    greet(word)\n    farewell(word)\n}\nh = spawn {\n    print(\"spawned\")\n}\nasync {\n    print(\"one\")\n    print(\"two\")\n}\n";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty());
+
+        let compile = || {
+            let mut compiler = Compiler::new().unwrap();
+            compiler.compile(&result.ast).unwrap();
+            compiler.finish().unwrap()
+        };
+        assert!(compile() == compile(), "object output differs between runs");
+    }
+
+    #[test]
+    fn test_blocks_at_the_same_offset_in_merged_files() {
+        // Merged files keep their own spans, so both async blocks start at
+        // the same offset
+        let mut ast =
+            haira_parser::parse("one_a() {\n    async {\n        print(\"a\")\n    }\n}\n").ast;
+        let other =
+            haira_parser::parse("one_b() {\n    async {\n        print(\"b\")\n    }\n}\n").ast;
+        let main = haira_parser::parse("one_a()\none_b()\n").ast;
+        ast.items.extend(other.items);
+        ast.items.extend(main.items);
+
+        let output = run_ast(&ast, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "a\nb\n");
+    }
+
+    #[test]
+    fn test_compile_to_object_in_memory() {
+        let ast = haira_parser::parse("print(1)\n").ast;
+        let bytes = compile_to_object(&ast, &CodegenOptions::default()).unwrap();
+        assert!(!bytes.is_empty());
+        assert!(bytes.windows(5).any(|window| window == b"main\0"));
+    }
+
+    #[test]
+    fn test_const_sizes_a_list() {
+        let source = "const SIZE = 2 * 4\nconst LAST = SIZE - 1\nconst LABEL = \"size \"\n\nfill(n) -> [int] {\n    xs = []\n    for i in 0..n {\n        xs.push(i)\n    }\n    return xs\n}\n\nxs = fill(SIZE)\nprint(LABEL)\nprint(xs.len())\nprint(xs[LAST])\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "size \n8\n7\n");
+    }
+
+    #[test]
+    fn test_non_constant_const_is_an_error() {
+        let ast = haira_parser::parse("const X = random_int(10)\nprint(X)\n").ast;
+        let err = compile_to_object(&ast, &CodegenOptions::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Non-constant value: constant X must be a literal or operators over constants"
+        );
+    }
+
+    #[test]
+    fn test_while_loop_carries_float_accumulator() {
+        let source = "total = 0.0\ni = 0\nwhile i < 5 {\n    total = total + 0.25\n    i = i + 1\n}\nprint(total)\nprint(i)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "1.25\n5\n");
+    }
+
+    #[test]
+    fn test_while_loop_only_carries_assigned_variables() {
+        let result = haira_parser::parse(
+            "a = 1\nb = 2\nc = 3\nd = 4.5\ni = 0\nwhile i < a + b + c {\n    i = i + 1\n}\nprint(d)\nprint(i)\n",
+        );
+        assert!(result.errors.is_empty());
+
+        let mut compiler = Compiler::new().unwrap();
+        compiler.enable_clif_dump();
+        compiler.compile(&result.ast).unwrap();
+
+        // Only `i` is threaded through the loop header
+        let clif = compiler.clif().unwrap();
+        assert!(clif.contains("block1(v5: i64):"), "{}", clif);
+        assert_eq!(clif.matches("jump block1(").count(), 2);
+    }
+
+    #[test]
+    fn test_comparisons_are_bools() {
+        let source = "print(1 < 2)\nx = 3 == 3\nprint(x)\nprint(2.5 > 3.0)\nprint(\"a\" < \"b\")\nprint((1 < 2) and true)\nprint(x + 1)\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "true\ntrue\nfalse\ntrue\ntrue\n2\n"
+        );
+    }
+
+    #[test]
+    fn test_tuples() {
+        let source = "t = (1, \"x\", 2.5)\nprint(t.0)\nprint(t.1)\nprint(t.2)\nprint(t)\n(a, b, c) = t\nprint(c)\nprint(b)\nmatch t {\n    (0, _, _) => print(\"zero\")\n    (n, _, f) => print(n + 1)\n    _ => print(\"other\")\n}\n";
+        let output = run(source, CodegenOptions::default());
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "1\nx\n2.5\n(1, \"x\", 2.5)\n2.5\nx\n2\n"
+        );
+    }
+
+    #[test]
+    fn test_tuple_destructuring_checks_arity() {
+        let ast = haira_parser::parse("t = (1, 2)\n(a, b, c) = t\n").ast;
+        let err = Compiler::new().unwrap().compile(&ast).unwrap_err();
+        assert!(matches!(err, CodegenError::TypeMismatch(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_break_outside_loop_is_an_error() {
+        let result = haira_parser::parse("f() {\n    break\n}\n");
+        let mut compiler = Compiler::new().unwrap();
+        let err = compiler.compile(&result.ast).unwrap_err();
+        assert!(matches!(err, CodegenError::Unsupported(ref msg) if msg.contains("break")));
+    }
+
+    #[test]
+    fn test_clif_dump_disabled_by_default() {
+        let result = haira_parser::parse("double(x) {\n    x * 2\n}\n");
+        let mut compiler = Compiler::new().unwrap();
+        compiler.compile(&result.ast).unwrap();
+        assert!(compiler.clif().is_none());
+    }
+}
@@ -1,9 +1,17 @@
 //! Code generation for the Haira programming language.
 //!
-//! This crate handles lowering AST to native code via Cranelift.
+//! This crate handles lowering AST to native code via Cranelift,
+//! or to a WebAssembly module for the `wasm32-unknown-unknown` target.
 
 mod cir_to_ast;
 mod compiler;
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
+mod wasm;
 
 pub use cir_to_ast::{cir_to_function_def, cir_types_to_ast, ConversionError};
-pub use compiler::{compile_to_executable, CodegenError, CodegenOptions};
+pub use compiler::{
+    check, compile_to_executable, compile_to_object, link, struct_layouts, write_object,
+    CodegenError, CodegenOptions, CodegenWarning, FieldLayout, StructLayout,
+};
+pub use wasm::compile_to_wasm;
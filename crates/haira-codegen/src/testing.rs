@@ -0,0 +1,30 @@
+//! Helpers for tests that link and run compiled programs.
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// The runtime staticlib, built by cargo the first time it's needed.
+pub fn runtime_library() -> PathBuf {
+    static RUNTIME: OnceLock<PathBuf> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            let output = Command::new(env!("CARGO"))
+                .args(["build", "-p", "haira-runtime", "--message-format=json"])
+                .current_dir(env!("CARGO_MANIFEST_DIR"))
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "building haira-runtime failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            // The artifact messages list the staticlib among their filenames
+            String::from_utf8_lossy(&output.stdout)
+                .split('"')
+                .find(|token| token.ends_with("libhaira_runtime.a"))
+                .map(PathBuf::from)
+                .expect("cargo built no haira-runtime staticlib")
+        })
+        .clone()
+}
@@ -0,0 +1,975 @@
+//! WebAssembly backend for the `wasm32-unknown-unknown` target.
+//!
+//! Cranelift has no wasm emitter, so this module encodes the module directly.
+//! It covers the integer/float arithmetic subset: functions, locals, `if`,
+//! `while`, range `for` loops, arithmetic and comparisons, and calls between
+//! Haira functions. Runtime functions are imported from the host under the
+//! `haira` module; anything else is rejected with `CodegenError::Unsupported`.
+
+#![allow(clippy::result_large_err)]
+
+use crate::CodegenError;
+use haira_ast::{
+    AssignPath, BinaryOp, Block, ElseBranch, Expr, ExprKind, ForPattern, FunctionDef, ItemKind,
+    Literal, SourceFile, Statement, StatementKind, Type, UnaryOp,
+};
+use smol_str::SmolStr;
+use std::collections::HashMap;
+use wasm_encoder::{
+    BlockType, CodeSection, EntityType, ExportKind, ExportSection, Function, FunctionSection,
+    ImportSection, Instruction, Module, TypeSection, ValType,
+};
+
+/// Module name the host must provide runtime imports under.
+const HOST_MODULE: &str = "haira";
+
+/// Runtime functions imported from the host: (name, params).
+const HOST_IMPORTS: &[(&str, &[ValType])] = &[
+    ("print_int", &[ValType::I64]),
+    ("print_float", &[ValType::F64]),
+];
+
+/// Signature of a function callable from wasm code.
+#[derive(Debug, Clone)]
+struct WasmSignature {
+    index: u32,
+    params: Vec<ValType>,
+    result: Option<ValType>,
+}
+
+/// Compile an AST to a WebAssembly module.
+///
+/// Each Haira function is exported under its own name; top-level statements
+/// become an exported `main` function.
+pub fn compile_to_wasm(ast: &SourceFile) -> Result<Vec<u8>, CodegenError> {
+    let mut functions: HashMap<SmolStr, WasmSignature> = HashMap::new();
+    let mut types = TypeSection::new();
+    let mut imports = ImportSection::new();
+    let mut next_index = 0u32;
+
+    for (name, params) in HOST_IMPORTS {
+        types.ty().function(params.iter().copied(), []);
+        imports.import(HOST_MODULE, name, EntityType::Function(next_index));
+        functions.insert(
+            SmolStr::from(*name),
+            WasmSignature {
+                index: next_index,
+                params: params.to_vec(),
+                result: None,
+            },
+        );
+        next_index += 1;
+    }
+
+    // Declare user functions first so bodies can call each other in any order
+    let mut user_functions: Vec<&FunctionDef> = Vec::new();
+    for item in &ast.items {
+        match &item.node {
+            ItemKind::FunctionDef(func) => {
+                let sig = WasmSignature {
+                    index: next_index,
+                    params: func
+                        .params
+                        .iter()
+                        .map(|p| val_type(p.ty.as_ref().map(|t| &t.node)))
+                        .collect(),
+                    result: Some(val_type(func.return_ty.as_ref().map(|t| &t.node))),
+                };
+                functions.insert(func.name.node.clone(), sig);
+                user_functions.push(func);
+                next_index += 1;
+            }
+            ItemKind::MethodDef(method) => {
+                return Err(CodegenError::Unsupported(format!(
+                    "method '{}.{}' on the wasm32 target",
+                    method.type_name.node, method.name.node
+                )));
+            }
+            ItemKind::AiFunctionDef(_) => {
+                return Err(CodegenError::Unsupported(
+                    "uninterpreted AI blocks on the wasm32 target".to_string(),
+                ));
+            }
+            ItemKind::EnumDef(enum_def) => {
+                return Err(CodegenError::Unsupported(format!(
+                    "enum '{}' on the wasm32 target",
+                    enum_def.name.node
+                )));
+            }
+            ItemKind::Const(const_def) => {
+                return Err(CodegenError::Unsupported(format!(
+                    "constant '{}' on the wasm32 target",
+                    const_def.name.node
+                )));
+            }
+            ItemKind::TypeDef(_)
+            | ItemKind::TypeAlias(_)
+            | ItemKind::Statement(_)
+            | ItemKind::Import(_) => {}
+        }
+    }
+
+    let mut func_section = FunctionSection::new();
+    let mut exports = ExportSection::new();
+    let mut code = CodeSection::new();
+    let mut type_index = HOST_IMPORTS.len() as u32;
+
+    for func in &user_functions {
+        let sig = &functions[&func.name.node];
+        types
+            .ty()
+            .function(sig.params.iter().copied(), sig.result.iter().copied());
+        func_section.function(type_index);
+        exports.export(func.name.node.as_str(), ExportKind::Func, sig.index);
+
+        let mut compiler = WasmFunctionCompiler::new(&functions, sig.result);
+        for (param, ty) in func.params.iter().zip(&sig.params) {
+            compiler.declare_param(&param.name.node, *ty);
+        }
+        code.function(&compiler.compile_body(&func.body.statements)?);
+        type_index += 1;
+    }
+
+    // Top-level statements
+    let top_level: Vec<Statement> = ast
+        .items
+        .iter()
+        .filter_map(|item| match &item.node {
+            ItemKind::Statement(stmt) => Some(stmt.clone()),
+            _ => None,
+        })
+        .collect();
+    types.ty().function([], []);
+    func_section.function(type_index);
+    exports.export("main", ExportKind::Func, next_index);
+    let mut compiler = WasmFunctionCompiler::new(&functions, None);
+    code.function(&compiler.compile_body(&top_level)?);
+
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&imports)
+        .section(&func_section)
+        .section(&exports)
+        .section(&code);
+
+    Ok(module.finish())
+}
+
+/// Map a type annotation to a wasm value type. Everything but `float` is i64,
+/// matching the native backend.
+fn val_type(ty: Option<&Type>) -> ValType {
+    match ty {
+        Some(Type::Named(name)) if name == "float" => ValType::F64,
+        _ => ValType::I64,
+    }
+}
+
+/// Compiles a single function body to wasm instructions.
+struct WasmFunctionCompiler<'a> {
+    functions: &'a HashMap<SmolStr, WasmSignature>,
+    result: Option<ValType>,
+    locals: HashMap<SmolStr, (u32, ValType)>,
+    /// Types of non-parameter locals, in index order.
+    extra_locals: Vec<ValType>,
+    param_count: u32,
+    instructions: Vec<Instruction<'static>>,
+    /// Number of enclosing blocks/loops/ifs.
+    depth: u32,
+    /// (break depth, continue depth) for each enclosing loop.
+    loops: Vec<(u32, u32)>,
+}
+
+impl<'a> WasmFunctionCompiler<'a> {
+    fn new(functions: &'a HashMap<SmolStr, WasmSignature>, result: Option<ValType>) -> Self {
+        Self {
+            functions,
+            result,
+            locals: HashMap::new(),
+            extra_locals: Vec::new(),
+            param_count: 0,
+            instructions: Vec::new(),
+            depth: 0,
+            loops: Vec::new(),
+        }
+    }
+
+    fn declare_param(&mut self, name: &SmolStr, ty: ValType) {
+        self.locals.insert(name.clone(), (self.param_count, ty));
+        self.param_count += 1;
+    }
+
+    fn declare_local(&mut self, name: &SmolStr, ty: ValType) -> u32 {
+        let index = self.param_count + self.extra_locals.len() as u32;
+        self.extra_locals.push(ty);
+        self.locals.insert(name.clone(), (index, ty));
+        index
+    }
+
+    fn emit(&mut self, instruction: Instruction<'static>) {
+        self.instructions.push(instruction);
+    }
+
+    /// Compile a function body. The last statement is the implicit return
+    /// value, as in the native backend.
+    fn compile_body(&mut self, statements: &[Statement]) -> Result<Function, CodegenError> {
+        match self.result {
+            Some(result) => self.compile_statements_as(statements, result)?,
+            None => {
+                if self.compile_statements(statements)?.is_some() {
+                    self.emit(Instruction::Drop);
+                }
+            }
+        }
+        self.emit(Instruction::End);
+
+        let mut func = Function::new(self.extra_locals.iter().map(|ty| (1, *ty)));
+        for instruction in &self.instructions {
+            func.instruction(instruction);
+        }
+        Ok(func)
+    }
+
+    /// Compile statements, dropping every value except the last one.
+    fn compile_statements(
+        &mut self,
+        statements: &[Statement],
+    ) -> Result<Option<ValType>, CodegenError> {
+        let mut last = None;
+        for (i, stmt) in statements.iter().enumerate() {
+            let value = self.compile_statement(stmt)?;
+            if i + 1 < statements.len() && value.is_some() {
+                self.emit(Instruction::Drop);
+            } else {
+                last = value;
+            }
+        }
+        Ok(last)
+    }
+
+    /// Compile statements, leaving the last one's value converted to `ty`.
+    ///
+    /// A trailing `if` gives the value of the branch taken; a trailing
+    /// statement without a value gives zero.
+    fn compile_statements_as(
+        &mut self,
+        statements: &[Statement],
+        ty: ValType,
+    ) -> Result<(), CodegenError> {
+        let Some((last, rest)) = statements.split_last() else {
+            self.emit_zero(ty);
+            return Ok(());
+        };
+        if self.compile_statements(rest)?.is_some() {
+            self.emit(Instruction::Drop);
+        }
+        match &last.node {
+            StatementKind::If(if_stmt) => self.compile_if_value(if_stmt, ty)?,
+            _ => match self.compile_statement(last)? {
+                Some(value) => self.coerce(value, ty),
+                None => self.emit_zero(ty),
+            },
+        }
+        Ok(())
+    }
+
+    /// Compile a nested block whose value is not used.
+    fn compile_block(&mut self, block: &Block) -> Result<(), CodegenError> {
+        if self.compile_statements(&block.statements)?.is_some() {
+            self.emit(Instruction::Drop);
+        }
+        Ok(())
+    }
+
+    fn compile_statement(&mut self, stmt: &Statement) -> Result<Option<ValType>, CodegenError> {
+        match &stmt.node {
+            StatementKind::Expr(expr) => self.compile_expr(expr),
+            StatementKind::Assignment(assign) => {
+                let [target] = assign.targets.as_slice() else {
+                    return Err(CodegenError::Unsupported(
+                        "multiple assignment on the wasm32 target".to_string(),
+                    ));
+                };
+                let AssignPath::Identifier(name) = &target.path else {
+                    return Err(CodegenError::Unsupported(
+                        "field and index assignment on the wasm32 target".to_string(),
+                    ));
+                };
+
+                if let Some(op) = &assign.op {
+                    // Compound assignment: `x += 1` computes in the local's type
+                    let &(index, existing) = self
+                        .locals
+                        .get(&name.node)
+                        .ok_or_else(|| CodegenError::UndefinedVariable(name.node.to_string()))?;
+                    self.emit(Instruction::LocalGet(index));
+                    let ty = self.compile_value(&assign.value)?;
+                    self.coerce(ty, existing);
+                    let ty = self.compile_binary_op(op.node, existing)?;
+                    self.coerce(ty, existing);
+                    self.emit(Instruction::LocalSet(index));
+                    return Ok(None);
+                }
+
+                let ty = self.compile_value(&assign.value)?;
+                let index = match (&target.ty, self.locals.get(&name.node)) {
+                    // An annotation declares the local with the written type
+                    (Some(annotation), _) => {
+                        let declared = val_type(Some(&annotation.node));
+                        self.coerce(ty, declared);
+                        self.declare_local(&name.node, declared)
+                    }
+                    (None, Some(&(index, existing))) => {
+                        self.coerce(ty, existing);
+                        index
+                    }
+                    (None, None) => self.declare_local(&name.node, ty),
+                };
+                self.emit(Instruction::LocalSet(index));
+                Ok(None)
+            }
+            StatementKind::Return(ret) => {
+                match (ret.values.as_slice(), self.result) {
+                    ([], Some(result)) => self.emit_zero(result),
+                    ([value], Some(result)) => {
+                        let ty = self.compile_value(value)?;
+                        self.coerce(ty, result);
+                    }
+                    ([], None) => {}
+                    // Functions without a result, like `main`, discard the value
+                    ([value], None) => {
+                        if self.compile_expr(value)?.is_some() {
+                            self.emit(Instruction::Drop);
+                        }
+                    }
+                    _ => {
+                        return Err(CodegenError::Unsupported(
+                            "multiple return values on the wasm32 target".to_string(),
+                        ))
+                    }
+                }
+                self.emit(Instruction::Return);
+                Ok(None)
+            }
+            StatementKind::If(if_stmt) => {
+                self.compile_if(if_stmt)?;
+                Ok(None)
+            }
+            StatementKind::While(while_stmt) => {
+                // block $break { loop $continue { br_if $break (!cond); body; br $continue } }
+                self.emit(Instruction::Block(BlockType::Empty));
+                self.depth += 1;
+                let break_depth = self.depth;
+                self.emit(Instruction::Loop(BlockType::Empty));
+                self.depth += 1;
+                let continue_depth = self.depth;
+
+                self.compile_condition(&while_stmt.condition)?;
+                self.emit(Instruction::I32Eqz);
+                self.emit(Instruction::BrIf(1));
+
+                self.loops.push((break_depth, continue_depth));
+                self.compile_block(&while_stmt.body)?;
+                self.loops.pop();
+
+                self.emit(Instruction::Br(0));
+                self.emit(Instruction::End);
+                self.emit(Instruction::End);
+                self.depth -= 2;
+                Ok(None)
+            }
+            StatementKind::For(for_stmt) => {
+                let (ForPattern::Single(var), ExprKind::Range(range)) =
+                    (&for_stmt.pattern, &for_stmt.iterator.node)
+                else {
+                    return Err(CodegenError::Unsupported(
+                        "for loops over non-range values on the wasm32 target".to_string(),
+                    ));
+                };
+
+                let start_ty = self.compile_value(&range.start)?;
+                self.coerce(start_ty, ValType::I64);
+                let counter = match self.locals.get(&var.node) {
+                    Some(&(index, ValType::I64)) => index,
+                    _ => self.declare_local(&var.node, ValType::I64),
+                };
+                self.emit(Instruction::LocalSet(counter));
+
+                let end_ty = self.compile_value(&range.end)?;
+                self.coerce(end_ty, ValType::I64);
+                let end =
+                    self.declare_local(&SmolStr::from(format!("__end{}", counter)), ValType::I64);
+                self.emit(Instruction::LocalSet(end));
+
+                // block $break { loop $next { br_if $break (i >= end); block $continue { body }; i += 1; br $next } }
+                self.emit(Instruction::Block(BlockType::Empty));
+                self.depth += 1;
+                let break_depth = self.depth;
+                self.emit(Instruction::Loop(BlockType::Empty));
+                self.depth += 1;
+
+                self.emit(Instruction::LocalGet(counter));
+                self.emit(Instruction::LocalGet(end));
+                self.emit(if range.inclusive {
+                    Instruction::I64GtS
+                } else {
+                    Instruction::I64GeS
+                });
+                self.emit(Instruction::BrIf(1));
+
+                self.emit(Instruction::Block(BlockType::Empty));
+                self.depth += 1;
+                self.loops.push((break_depth, self.depth));
+                self.compile_block(&for_stmt.body)?;
+                self.loops.pop();
+                self.emit(Instruction::End);
+                self.depth -= 1;
+
+                self.emit(Instruction::LocalGet(counter));
+                self.emit(Instruction::I64Const(1));
+                self.emit(Instruction::I64Add);
+                self.emit(Instruction::LocalSet(counter));
+                self.emit(Instruction::Br(0));
+                self.emit(Instruction::End);
+                self.emit(Instruction::End);
+                self.depth -= 2;
+                Ok(None)
+            }
+            StatementKind::Break | StatementKind::Continue => {
+                let &(break_depth, continue_depth) = self.loops.last().ok_or_else(|| {
+                    CodegenError::Unsupported("break/continue outside a loop".to_string())
+                })?;
+                let target = if matches!(stmt.node, StatementKind::Break) {
+                    break_depth
+                } else {
+                    continue_depth
+                };
+                self.emit(Instruction::Br(self.depth - target));
+                Ok(None)
+            }
+            StatementKind::Match(_) | StatementKind::Try(_) => Err(CodegenError::Unsupported(
+                "match and try statements on the wasm32 target".to_string(),
+            )),
+        }
+    }
+
+    fn compile_if(&mut self, if_stmt: &haira_ast::IfStatement) -> Result<(), CodegenError> {
+        self.compile_condition(&if_stmt.condition)?;
+        self.emit(Instruction::If(BlockType::Empty));
+        self.depth += 1;
+        self.compile_block(&if_stmt.then_branch)?;
+
+        match &if_stmt.else_branch {
+            Some(ElseBranch::Block(block)) => {
+                self.emit(Instruction::Else);
+                self.compile_block(block)?;
+            }
+            Some(ElseBranch::ElseIf(else_if)) => {
+                self.emit(Instruction::Else);
+                self.compile_if(&else_if.node)?;
+            }
+            None => {}
+        }
+
+        self.emit(Instruction::End);
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Compile an `if` whose branches each leave a value of type `ty`. A
+    /// missing `else` gives zero.
+    fn compile_if_value(
+        &mut self,
+        if_stmt: &haira_ast::IfStatement,
+        ty: ValType,
+    ) -> Result<(), CodegenError> {
+        self.compile_condition(&if_stmt.condition)?;
+        self.emit(Instruction::If(BlockType::Result(ty)));
+        self.depth += 1;
+        self.compile_statements_as(&if_stmt.then_branch.statements, ty)?;
+
+        self.emit(Instruction::Else);
+        match &if_stmt.else_branch {
+            Some(ElseBranch::Block(block)) => self.compile_statements_as(&block.statements, ty)?,
+            Some(ElseBranch::ElseIf(else_if)) => self.compile_if_value(&else_if.node, ty)?,
+            None => self.emit_zero(ty),
+        }
+
+        self.emit(Instruction::End);
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Compile an expression to an i32 truth value.
+    fn compile_condition(&mut self, expr: &Expr) -> Result<(), CodegenError> {
+        match self.compile_value(expr)? {
+            ValType::F64 => {
+                self.emit(Instruction::F64Const(0.0.into()));
+                self.emit(Instruction::F64Ne);
+            }
+            _ => {
+                self.emit(Instruction::I64Const(0));
+                self.emit(Instruction::I64Ne);
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile an expression that must produce a value.
+    fn compile_value(&mut self, expr: &Expr) -> Result<ValType, CodegenError> {
+        self.compile_expr(expr)?.ok_or_else(|| {
+            CodegenError::Unsupported("expression without a value used as a value".to_string())
+        })
+    }
+
+    fn compile_expr(&mut self, expr: &Expr) -> Result<Option<ValType>, CodegenError> {
+        let ty = match &expr.node {
+            ExprKind::Literal(Literal::Int(n)) => {
+                self.emit(Instruction::I64Const(*n));
+                ValType::I64
+            }
+            ExprKind::Literal(Literal::Float(n)) => {
+                self.emit(Instruction::F64Const((*n).into()));
+                ValType::F64
+            }
+            ExprKind::Literal(Literal::Char(c)) => {
+                self.emit(Instruction::I64Const(*c as i64));
+                ValType::I64
+            }
+            ExprKind::Literal(Literal::Bool(b)) => {
+                self.emit(Instruction::I64Const(*b as i64));
+                ValType::I64
+            }
+            ExprKind::Identifier(name) => {
+                let &(index, ty) = self
+                    .locals
+                    .get(name)
+                    .ok_or_else(|| CodegenError::UndefinedVariable(name.to_string()))?;
+                self.emit(Instruction::LocalGet(index));
+                ty
+            }
+            ExprKind::Paren(inner) => return self.compile_expr(inner),
+            ExprKind::Unary(unary) => {
+                let ty = self.compile_value(&unary.operand)?;
+                match (unary.op.node, ty) {
+                    (UnaryOp::Neg, ValType::F64) => {
+                        self.emit(Instruction::F64Neg);
+                        ty
+                    }
+                    (UnaryOp::Neg, _) => {
+                        self.emit(Instruction::I64Const(-1));
+                        self.emit(Instruction::I64Mul);
+                        ty
+                    }
+                    (UnaryOp::Not, _) => {
+                        self.coerce(ty, ValType::I64);
+                        self.emit(Instruction::I64Eqz);
+                        self.emit(Instruction::I64ExtendI32U);
+                        ValType::I64
+                    }
+                }
+            }
+            // Logical operators only evaluate the right side when needed
+            ExprKind::Binary(binary) if matches!(binary.op.node, BinaryOp::And | BinaryOp::Or) => {
+                self.compile_condition(&binary.left)?;
+                self.emit(Instruction::If(BlockType::Result(ValType::I64)));
+                self.depth += 1;
+                if binary.op.node == BinaryOp::And {
+                    self.compile_condition(&binary.right)?;
+                    self.emit(Instruction::I64ExtendI32U);
+                    self.emit(Instruction::Else);
+                    self.emit(Instruction::I64Const(0));
+                } else {
+                    self.emit(Instruction::I64Const(1));
+                    self.emit(Instruction::Else);
+                    self.compile_condition(&binary.right)?;
+                    self.emit(Instruction::I64ExtendI32U);
+                }
+                self.emit(Instruction::End);
+                self.depth -= 1;
+                ValType::I64
+            }
+            ExprKind::Binary(binary) => {
+                let left = self.compile_value(&binary.left)?;
+                let mark = self.instructions.len();
+                let right = self.compile_value(&binary.right)?;
+
+                let ty = if left == ValType::F64 || right == ValType::F64 {
+                    if left != ValType::F64 {
+                        self.instructions.insert(mark, Instruction::F64ConvertI64S);
+                    }
+                    self.coerce(right, ValType::F64);
+                    ValType::F64
+                } else {
+                    ValType::I64
+                };
+                self.compile_binary_op(binary.op.node, ty)?
+            }
+            ExprKind::Call(call) => {
+                let ExprKind::Identifier(name) = &call.callee.node else {
+                    return Err(CodegenError::Unsupported(
+                        "indirect calls on the wasm32 target".to_string(),
+                    ));
+                };
+
+                if matches!(name.as_str(), "print" | "println") && call.args.len() == 1 {
+                    let ty = self.compile_value(&call.args[0].value)?;
+                    let import = if ty == ValType::F64 {
+                        "print_float"
+                    } else {
+                        "print_int"
+                    };
+                    self.emit(Instruction::Call(self.functions[import].index));
+                    return Ok(None);
+                }
+
+                let sig = self
+                    .functions
+                    .get(name)
+                    .ok_or_else(|| CodegenError::UndefinedFunction(name.to_string()))?
+                    .clone();
+                if sig.params.len() != call.args.len() {
+                    return Err(CodegenError::Unsupported(format!(
+                        "call to '{}' with {} argument(s), expected {}",
+                        name,
+                        call.args.len(),
+                        sig.params.len()
+                    )));
+                }
+                for (arg, param_ty) in call.args.iter().zip(&sig.params) {
+                    let ty = self.compile_value(&arg.value)?;
+                    self.coerce(ty, *param_ty);
+                }
+                self.emit(Instruction::Call(sig.index));
+                return Ok(sig.result);
+            }
+            other => {
+                return Err(CodegenError::Unsupported(format!(
+                    "{} on the wasm32 target",
+                    expr_kind_name(other)
+                )))
+            }
+        };
+        Ok(Some(ty))
+    }
+
+    fn compile_binary_op(&mut self, op: BinaryOp, ty: ValType) -> Result<ValType, CodegenError> {
+        let float = ty == ValType::F64;
+        let instruction = match op {
+            BinaryOp::Add if float => Instruction::F64Add,
+            BinaryOp::Add => Instruction::I64Add,
+            BinaryOp::Sub if float => Instruction::F64Sub,
+            BinaryOp::Sub => Instruction::I64Sub,
+            BinaryOp::Mul if float => Instruction::F64Mul,
+            BinaryOp::Mul => Instruction::I64Mul,
+            BinaryOp::Div if float => Instruction::F64Div,
+            BinaryOp::Div => Instruction::I64DivS,
+            BinaryOp::Mod if float => {
+                return Err(CodegenError::Unsupported(
+                    "float modulo on the wasm32 target".to_string(),
+                ))
+            }
+            BinaryOp::Mod => Instruction::I64RemS,
+            BinaryOp::Coalesce => {
+                return Err(CodegenError::Unsupported(
+                    "the `??` operator on the wasm32 target".to_string(),
+                ))
+            }
+            BinaryOp::And | BinaryOp::Or if float => {
+                return Err(CodegenError::Unsupported(
+                    "logical operators on floats".to_string(),
+                ))
+            }
+            BinaryOp::And => Instruction::I64And,
+            BinaryOp::Or => Instruction::I64Or,
+            BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::Shl
+            | BinaryOp::Shr
+                if float =>
+            {
+                return Err(CodegenError::Unsupported(
+                    "bitwise operators on floats".to_string(),
+                ))
+            }
+            BinaryOp::BitAnd => Instruction::I64And,
+            BinaryOp::BitOr => Instruction::I64Or,
+            BinaryOp::BitXor => Instruction::I64Xor,
+            BinaryOp::Shl => Instruction::I64Shl,
+            BinaryOp::Shr => Instruction::I64ShrU,
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Gt
+            | BinaryOp::Le
+            | BinaryOp::Ge => {
+                let cmp = match (op, float) {
+                    (BinaryOp::Eq, true) => Instruction::F64Eq,
+                    (BinaryOp::Eq, false) => Instruction::I64Eq,
+                    (BinaryOp::Ne, true) => Instruction::F64Ne,
+                    (BinaryOp::Ne, false) => Instruction::I64Ne,
+                    (BinaryOp::Lt, true) => Instruction::F64Lt,
+                    (BinaryOp::Lt, false) => Instruction::I64LtS,
+                    (BinaryOp::Gt, true) => Instruction::F64Gt,
+                    (BinaryOp::Gt, false) => Instruction::I64GtS,
+                    (BinaryOp::Le, true) => Instruction::F64Le,
+                    (BinaryOp::Le, false) => Instruction::I64LeS,
+                    (BinaryOp::Ge, true) => Instruction::F64Ge,
+                    _ => Instruction::I64GeS,
+                };
+                self.emit(cmp);
+                self.emit(Instruction::I64ExtendI32U);
+                return Ok(ValType::I64);
+            }
+        };
+        self.emit(instruction);
+        Ok(ty)
+    }
+
+    /// Convert the value on top of the stack from `from` to `to`.
+    fn coerce(&mut self, from: ValType, to: ValType) {
+        match (from, to) {
+            (ValType::I64, ValType::F64) => self.emit(Instruction::F64ConvertI64S),
+            (ValType::F64, ValType::I64) => self.emit(Instruction::I64TruncSatF64S),
+            _ => {}
+        }
+    }
+
+    fn emit_zero(&mut self, ty: ValType) {
+        match ty {
+            ValType::F64 => self.emit(Instruction::F64Const(0.0.into())),
+            _ => self.emit(Instruction::I64Const(0)),
+        }
+    }
+}
+
+/// Human-readable name of an expression kind for error messages.
+fn expr_kind_name(kind: &ExprKind) -> &'static str {
+    match kind {
+        ExprKind::Literal(Literal::String(_) | Literal::InterpolatedString(_)) => "strings",
+        ExprKind::MethodCall(_) => "method calls",
+        ExprKind::Field(_) => "field access",
+        ExprKind::Index(_) => "indexing",
+        ExprKind::Pipe(_) => "pipes",
+        ExprKind::Lambda(_) => "lambdas",
+        ExprKind::Match(_) => "match expressions",
+        ExprKind::If(_) => "if expressions",
+        ExprKind::Block(_) => "block expressions",
+        ExprKind::List(_) => "lists",
+        ExprKind::Tuple(_) => "tuples",
+        ExprKind::Map(_) => "maps",
+        ExprKind::Instance(_) => "struct instances",
+        ExprKind::Range(_) => "ranges outside for loops",
+        ExprKind::Propagate(_) => "error propagation",
+        ExprKind::Some(_) | ExprKind::None => "options",
+        ExprKind::Async(_) | ExprKind::Spawn(_) | ExprKind::Select(_) => "concurrency",
+        ExprKind::Ai(_) => "AI blocks",
+        _ => "this expression",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> Result<Vec<u8>, CodegenError> {
+        let result = haira_parser::parse(source);
+        assert!(
+            result.errors.is_empty(),
+            "parse errors: {:?}",
+            result.errors
+        );
+        compile_to_wasm(&result.ast)
+    }
+
+    #[test]
+    fn test_arithmetic_module_validates() {
+        let bytes = compile(
+            r#"
+            add(a, b) { a + b }
+            half(n) -> float { n / 2.0 }
+            fact(n) {
+                result = 1
+                i = 1
+                while i <= n {
+                    result = result * i
+                    i = i + 1
+                }
+                result
+            }
+            print(add(2, 3) * 4 - 1)
+            print(half(3) * 1.5)
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(&bytes[..4], b"\0asm");
+        wasmparser::Validator::new().validate_all(&bytes).unwrap();
+    }
+
+    #[test]
+    fn test_control_flow_validates() {
+        let bytes = compile(
+            r#"
+            sum_odd(n) {
+                total = 0
+                for i in 0..n {
+                    if i % 2 == 0 {
+                        continue
+                    }
+                    if total > 100 {
+                        break
+                    }
+                    total = total + i
+                }
+                return total
+            }
+        "#,
+        )
+        .unwrap();
+
+        wasmparser::Validator::new().validate_all(&bytes).unwrap();
+    }
+
+    #[test]
+    fn test_logical_operators_validate() {
+        let bytes = compile(
+            r#"
+            in_range(x, low, high) { x >= low and x <= high }
+            outside(x) { x < 0 or x > 1.5 }
+        "#,
+        )
+        .unwrap();
+
+        wasmparser::Validator::new().validate_all(&bytes).unwrap();
+    }
+
+    /// Instantiate `bytes`, running `main`, and return the store holding
+    /// everything it printed.
+    fn instantiate(bytes: &[u8]) -> (wasmi::Store<Vec<String>>, wasmi::Instance) {
+        let engine = wasmi::Engine::default();
+        let module = wasmi::Module::new(&engine, bytes).unwrap();
+        let mut store = wasmi::Store::new(&engine, Vec::new());
+        let mut linker = wasmi::Linker::new(&engine);
+        linker
+            .func_wrap(
+                HOST_MODULE,
+                "print_int",
+                |mut caller: wasmi::Caller<'_, Vec<String>>, n: i64| {
+                    caller.data_mut().push(n.to_string())
+                },
+            )
+            .unwrap();
+        linker
+            .func_wrap(
+                HOST_MODULE,
+                "print_float",
+                |mut caller: wasmi::Caller<'_, Vec<String>>, n: f64| {
+                    caller.data_mut().push(n.to_string())
+                },
+            )
+            .unwrap();
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        instance
+            .get_typed_func::<(), ()>(&store, "main")
+            .unwrap()
+            .call(&mut store, ())
+            .unwrap();
+        (store, instance)
+    }
+
+    #[test]
+    fn test_functions_return_computed_values() {
+        let bytes = compile(
+            r#"
+            add(a, b) { a + b }
+            half(n) -> float { n / 2.0 }
+            fact(n) {
+                result = 1
+                for i in 1..=n {
+                    result = result * i
+                }
+                return result
+            }
+            print(add(2, 3) * 4 - 1)
+            print(fact(5))
+        "#,
+        )
+        .unwrap();
+
+        let (mut store, instance) = instantiate(&bytes);
+        assert_eq!(store.data(), &["19", "120"]);
+        let half = instance.get_typed_func::<i64, f64>(&store, "half").unwrap();
+        assert_eq!(half.call(&mut store, 3).unwrap(), 1.5);
+    }
+
+    #[test]
+    fn test_tail_if_is_the_return_value() {
+        let bytes = compile(
+            r#"
+            max(a, b) {
+                if a > b { a } else { b }
+            }
+            sign(n) -> float {
+                if n > 0 {
+                    1
+                } else if n < 0 {
+                    -1.5
+                } else {
+                    return 0.25
+                }
+            }
+            positive(n) {
+                if n > 0 { n }
+            }
+            print(max(3, 7))
+        "#,
+        )
+        .unwrap();
+
+        let (mut store, instance) = instantiate(&bytes);
+        assert_eq!(store.data(), &["7"]);
+
+        let max = instance
+            .get_typed_func::<(i64, i64), i64>(&store, "max")
+            .unwrap();
+        assert_eq!(max.call(&mut store, (9, 4)).unwrap(), 9);
+        assert_eq!(max.call(&mut store, (-2, 5)).unwrap(), 5);
+
+        let sign = instance.get_typed_func::<i64, f64>(&store, "sign").unwrap();
+        assert_eq!(sign.call(&mut store, 4).unwrap(), 1.0);
+        assert_eq!(sign.call(&mut store, -4).unwrap(), -1.5);
+        assert_eq!(sign.call(&mut store, 0).unwrap(), 0.25);
+
+        let positive = instance
+            .get_typed_func::<i64, i64>(&store, "positive")
+            .unwrap();
+        assert_eq!(positive.call(&mut store, 6).unwrap(), 6);
+        assert_eq!(positive.call(&mut store, -6).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_return_value_from_main_is_discarded() {
+        let bytes = compile(
+            "print(1)
+return 5
+print(2)
+",
+        )
+        .unwrap();
+
+        let (store, _) = instantiate(&bytes);
+        assert_eq!(store.data(), &["1"]);
+    }
+
+    #[test]
+    fn test_strings_unsupported() {
+        let err = compile(r#"greet() { "hello" }"#).unwrap_err();
+        assert!(matches!(err, CodegenError::Unsupported(_)));
+    }
+}
@@ -0,0 +1,414 @@
+//! Pre-interpretation of AI blocks.
+//!
+//! Before code generation, every `ai` block is handed to the AI engine and
+//! replaced with the function it generates. Named `ai` items become regular
+//! function definitions in place; inline `ai(...) { ... }` expressions get a
+//! hoisted function named by [`AiBlock::function_name`], which codegen calls
+//! in their stead.
+//!
+//! Interpretations are cached in a [`HIFFile`], keyed by function name and a
+//! hash of the block's signature and intent, so rebuilds reuse them without
+//! calling the model.
+
+use haira_ai::hif::{
+    cir_function_to_hif_intent, compute_context_hash, hif_intent_to_cir_function, HIFFile,
+};
+use haira_ai::{AIEngine, AIError};
+use haira_ast::visit::{walk_expr, walk_source_file, Visitor};
+use haira_ast::{AiBlock, Expr, ExprKind, FunctionDef, Item, ItemKind, SourceFile, Spanned, Type};
+use haira_cir::{
+    CIRFunction, CallSiteInfo, FieldDefinition, InterpretationContext, TypeDefinition,
+};
+use haira_codegen::{cir_to_function_def, ConversionError};
+use smol_str::SmolStr;
+use std::collections::HashSet;
+use std::path::Path;
+use thiserror::Error;
+use tracing::debug;
+
+/// Errors from interpreting AI blocks.
+#[derive(Debug, Error)]
+pub enum InterpretError {
+    #[error("failed to interpret AI block '{name}': {source}")]
+    Ai {
+        name: String,
+        #[source]
+        source: AIError,
+    },
+    #[error("failed to convert CIR to AST for '{name}': {source}")]
+    Conversion {
+        name: String,
+        #[source]
+        source: ConversionError,
+    },
+}
+
+/// How the AI blocks of a file were resolved.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InterpretStats {
+    /// Blocks interpreted by the AI engine.
+    pub interpreted: usize,
+    /// Blocks reused from the HIF cache.
+    pub cached: usize,
+}
+
+/// Replace every AI block in `ast` with a function generated by `engine`.
+///
+/// Blocks with an up-to-date entry in `hif` reuse it; the rest are
+/// interpreted and recorded in `hif`. The engine is only checked for
+/// availability (and a local server started) on the first cache miss, so a
+/// fully cached build never touches the model.
+pub async fn interpret_ai_blocks(
+    ast: &mut SourceFile,
+    engine: &mut AIEngine,
+    context: &InterpretationContext,
+    hif: &mut HIFFile,
+) -> Result<InterpretStats, InterpretError> {
+    let mut interpreter = Interpreter {
+        engine,
+        context,
+        hif,
+        ready: false,
+        stats: InterpretStats::default(),
+    };
+
+    for item in &mut ast.items {
+        if let ItemKind::AiFunctionDef(block) = &item.node {
+            let func = interpreter.function_for(block).await?;
+            item.node = ItemKind::FunctionDef(func);
+        }
+    }
+
+    let mut defined: HashSet<SmolStr> = ast
+        .items
+        .iter()
+        .filter_map(|item| match &item.node {
+            ItemKind::FunctionDef(func) => Some(func.name.node.clone()),
+            _ => None,
+        })
+        .collect();
+    let mut collector = InlineBlocks::default();
+    walk_source_file(&mut collector, ast);
+    for block in collector.blocks {
+        // Identical blocks share one function
+        if !defined.insert(block.function_name()) {
+            continue;
+        }
+        let func = interpreter.function_for(&block).await?;
+        ast.items.push(Item {
+            node: ItemKind::FunctionDef(func),
+            span: block.span,
+        });
+    }
+
+    Ok(interpreter.stats)
+}
+
+struct Interpreter<'a> {
+    engine: &'a mut AIEngine,
+    context: &'a InterpretationContext,
+    hif: &'a mut HIFFile,
+    /// Whether the engine has been checked and started.
+    ready: bool,
+    stats: InterpretStats,
+}
+
+impl Interpreter<'_> {
+    /// The function generated for `block`, from the cache if possible.
+    async fn function_for(&mut self, block: &AiBlock) -> Result<FunctionDef, InterpretError> {
+        let name = block.function_name();
+        let (params, return_type) = signature(block);
+        let hash = intent_hash(&name, &params, return_type.as_deref(), &block.intent);
+
+        if let Some(cached) = self.hif.get_intent(&name).filter(|i| i.hash == hash) {
+            match to_function_def(block, &hif_intent_to_cir_function(cached)) {
+                Ok(func) => {
+                    debug!("Using cached interpretation of {}", name);
+                    self.stats.cached += 1;
+                    return Ok(func);
+                }
+                Err(e) => debug!("Cached interpretation of {} is invalid: {}", name, e),
+            }
+        }
+
+        let ai_error = |source| InterpretError::Ai {
+            name: name.to_string(),
+            source,
+        };
+        if !self.ready {
+            self.engine.check_availability().await.map_err(ai_error)?;
+            self.engine.start_local_server().await.map_err(ai_error)?;
+            self.ready = true;
+        }
+        let cir = self
+            .engine
+            .interpret_intent(
+                Some(&name),
+                &block.intent,
+                &params,
+                return_type.as_deref(),
+                self.context.clone(),
+            )
+            .await
+            .map_err(ai_error)?;
+
+        let func = to_function_def(block, &cir)?;
+        self.hif.add_intent(cir_function_to_hif_intent(&cir, &hash));
+        self.stats.interpreted += 1;
+        Ok(func)
+    }
+}
+
+/// The `(name, type)` parameters and return type of `block`, as strings.
+fn signature(block: &AiBlock) -> (Vec<(String, String)>, Option<String>) {
+    let params = block
+        .params
+        .iter()
+        .map(|p| {
+            let ty =
+                p.ty.as_ref()
+                    .map(|t| type_to_string(&t.node))
+                    .unwrap_or_else(|| "any".to_string());
+            (p.name.node.to_string(), ty)
+        })
+        .collect();
+    let return_type = block.return_ty.as_ref().map(|t| type_to_string(&t.node));
+    (params, return_type)
+}
+
+/// Cache key for a block: changes whenever its signature or intent does.
+fn intent_hash(
+    name: &str,
+    params: &[(String, String)],
+    return_type: Option<&str>,
+    intent: &str,
+) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|(name, ty)| format!("{}: {}", name, ty))
+        .collect();
+    compute_context_hash(&format!(
+        "{}({}) -> {}\n{}",
+        name,
+        params.join(", "),
+        return_type.unwrap_or("none"),
+        intent.trim()
+    ))
+}
+
+fn to_function_def(block: &AiBlock, cir: &CIRFunction) -> Result<FunctionDef, InterpretError> {
+    let name = block.function_name();
+    let mut func = cir_to_function_def(cir).map_err(|source| InterpretError::Conversion {
+        name: name.to_string(),
+        source,
+    })?;
+    func.name = Spanned::new(name, block.name.as_ref().map_or(block.span, |n| n.span));
+    Ok(func)
+}
+
+/// Collects `ai` blocks used as expressions.
+#[derive(Default)]
+struct InlineBlocks {
+    blocks: Vec<AiBlock>,
+}
+
+impl Visitor for InlineBlocks {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Ai(block) = &expr.node {
+            self.blocks.push(block.clone());
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Build the interpretation context for `ast`: the types it defines.
+pub fn interpretation_context(ast: &SourceFile, file: &Path) -> InterpretationContext {
+    let mut types_in_scope = Vec::new();
+
+    // Extract all type definitions from the AST
+    for item in &ast.items {
+        if let ItemKind::TypeDef(type_def) = &item.node {
+            let fields = type_def
+                .fields
+                .iter()
+                .map(|f| {
+                    let ty =
+                        f.ty.as_ref()
+                            .map(|t| type_to_string(&t.node))
+                            .unwrap_or_else(|| "any".to_string());
+                    FieldDefinition {
+                        name: f.name.node.to_string(),
+                        ty,
+                        optional: false, // TODO: detect optionality
+                        default: None,
+                    }
+                })
+                .collect();
+
+            types_in_scope.push(TypeDefinition {
+                name: type_def.name.node.to_string(),
+                fields,
+            });
+        }
+    }
+
+    InterpretationContext {
+        types_in_scope,
+        call_site: CallSiteInfo {
+            file: file.display().to_string(),
+            line: 1,
+            arguments: vec![],
+            expected_return: None,
+        },
+        project_schema: Default::default(),
+    }
+}
+
+/// Convert an AST Type to a string representation.
+pub fn type_to_string(ty: &Type) -> String {
+    match ty {
+        Type::Named(name) => name.to_string(),
+        Type::List(inner) => format!("[{}]", type_to_string(&inner.node)),
+        Type::Map { key, value } => {
+            format!(
+                "{{{}:{}}}",
+                type_to_string(&key.node),
+                type_to_string(&value.node)
+            )
+        }
+        Type::Option(inner) => format!("Option<{}>", type_to_string(&inner.node)),
+        Type::Tuple(elements) => {
+            let elements_str = elements
+                .iter()
+                .map(|e| type_to_string(&e.node))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("({})", elements_str)
+        }
+        Type::Function { params, ret } => {
+            let params_str = params
+                .iter()
+                .map(|p| type_to_string(&p.node))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("({}) -> {}", params_str, type_to_string(&ret.node))
+        }
+        Type::Union(variants) => variants
+            .iter()
+            .map(|v| type_to_string(&v.node))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Type::Generic { name, args } => {
+            let args_str = args
+                .iter()
+                .map(|a| type_to_string(&a.node))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}<{}>", name, args_str)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use haira_ai::AIConfig;
+
+    const PROGRAM: &str = "x = ai(n: int) -> int {\n    Double the number\n}(21)\nprint(x)\n";
+
+    fn parse(source: &str) -> SourceFile {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        result.ast
+    }
+
+    /// An engine whose backend can never be reached.
+    fn offline_engine() -> AIEngine {
+        AIEngine::with_local_ai(AIConfig::default(), Some("haira-test-missing-model.gguf"))
+    }
+
+    async fn interpret(
+        ast: &mut SourceFile,
+        engine: &mut AIEngine,
+        hif: &mut HIFFile,
+    ) -> Result<InterpretStats, InterpretError> {
+        let context = interpretation_context(ast, Path::new("main.haira"));
+        interpret_ai_blocks(ast, engine, &context, hif).await
+    }
+
+    #[tokio::test]
+    async fn test_interpreted_ai_block_compiles() {
+        let mut ast = parse(PROGRAM);
+        assert!(haira_codegen::check(&ast).is_err());
+
+        let mut engine = AIEngine::mock(AIConfig::default());
+        let mut hif = HIFFile::new();
+        let stats = interpret(&mut ast, &mut engine, &mut hif).await.unwrap();
+
+        assert_eq!(
+            stats,
+            InterpretStats {
+                interpreted: 1,
+                cached: 0
+            }
+        );
+        assert!(ast.items.iter().any(|item| matches!(
+            &item.node,
+            ItemKind::FunctionDef(func) if func.name.node.starts_with("__ai_anon_")
+        )));
+        haira_codegen::check(&ast).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cached_interpretation_skips_the_engine() {
+        let mut hif = HIFFile::new();
+        let mut ast = parse(PROGRAM);
+        interpret(&mut ast, &mut AIEngine::mock(AIConfig::default()), &mut hif)
+            .await
+            .unwrap();
+        assert_eq!(hif.intents.len(), 1);
+
+        // Round-trip through the HIF text, as a rebuild would
+        let mut hif = haira_ai::hif::parse_hif(&haira_ai::hif::write_hif(&hif)).unwrap();
+        let mut ast = parse(PROGRAM);
+        let stats = interpret(&mut ast, &mut offline_engine(), &mut hif)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            InterpretStats {
+                interpreted: 0,
+                cached: 1
+            }
+        );
+        haira_codegen::check(&ast).unwrap();
+
+        // Editing the intent invalidates the entry, so the engine is needed
+        let mut ast = parse(&PROGRAM.replace("Double", "Triple"));
+        let result = interpret(&mut ast, &mut offline_engine(), &mut hif).await;
+        assert!(matches!(result, Err(InterpretError::Ai { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_cache_survives_code_moving_above_inline_block() {
+        let mut hif = HIFFile::new();
+        let mut ast = parse(PROGRAM);
+        interpret(&mut ast, &mut AIEngine::mock(AIConfig::default()), &mut hif)
+            .await
+            .unwrap();
+
+        // The block moves down a line, but keeps its name and cache entry
+        let mut ast = parse(&format!("print(0)\n{}", PROGRAM));
+        let stats = interpret(&mut ast, &mut offline_engine(), &mut hif)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            InterpretStats {
+                interpreted: 0,
+                cached: 1
+            }
+        );
+        haira_codegen::check(&ast).unwrap();
+    }
+}
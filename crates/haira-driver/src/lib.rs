@@ -10,12 +10,28 @@
 //! 7. MIR lowering
 //! 8. Code generation
 
+mod interpret;
+mod project;
+mod source;
+
+pub use interpret::{
+    interpret_ai_blocks, interpretation_context, type_to_string, InterpretError, InterpretStats,
+};
+pub use project::{compile_project, Project, ProjectFile};
+pub use source::function_to_source;
+
 use haira_ai::{AIConfig, AIEngine};
+use haira_ast::SourceFile;
 use haira_codegen::CodegenOptions;
+use haira_hir::HirModule;
+use haira_resolver::ResolvedModule;
+use miette::{LabeledSpan, NamedSource, Severity};
 use std::path::Path;
 
+/// Rough time one model call takes, for estimating interpretation time.
+const SECONDS_PER_AI_CALL: usize = 5;
+
 /// Compiler configuration.
-#[derive(Default)]
 pub struct CompilerConfig {
     /// AI configuration.
     pub ai: AIConfig,
@@ -23,6 +39,20 @@ pub struct CompilerConfig {
     pub codegen: CodegenOptions,
     /// Enable verbose output.
     pub verbose: bool,
+    /// Warn about slow builds when more calls than this need AI
+    /// interpretation.
+    pub ai_call_warning_threshold: usize,
+}
+
+impl Default for CompilerConfig {
+    fn default() -> Self {
+        Self {
+            ai: AIConfig::default(),
+            codegen: CodegenOptions::default(),
+            verbose: false,
+            ai_call_warning_threshold: 10,
+        }
+    }
 }
 
 /// Compilation result.
@@ -43,6 +73,20 @@ pub struct CompilationError {
     pub span: Option<std::ops::Range<usize>>,
 }
 
+impl CompilationError {
+    /// Render this error against `source`, the text its span points into,
+    /// so it displays with the offending lines underlined.
+    pub fn to_diagnostic(&self, source: &str) -> miette::Report {
+        diagnostic(
+            Severity::Error,
+            &self.message,
+            self.file.as_deref(),
+            self.span.clone(),
+            source,
+        )
+    }
+}
+
 /// A compilation warning.
 #[derive(Debug)]
 pub struct CompilationWarning {
@@ -51,6 +95,115 @@ pub struct CompilationWarning {
     pub span: Option<std::ops::Range<usize>>,
 }
 
+impl CompilationWarning {
+    /// Render this warning against `source`, like
+    /// [`CompilationError::to_diagnostic`].
+    pub fn to_diagnostic(&self, source: &str) -> miette::Report {
+        diagnostic(
+            Severity::Warning,
+            &self.message,
+            self.file.as_deref(),
+            self.span.clone(),
+            source,
+        )
+    }
+}
+
+fn diagnostic(
+    severity: Severity,
+    message: &str,
+    file: Option<&str>,
+    span: Option<std::ops::Range<usize>>,
+    source: &str,
+) -> miette::Report {
+    let labels: Vec<LabeledSpan> = span
+        .into_iter()
+        .map(|span| LabeledSpan::new_primary_with_span(Some("here".to_string()), span))
+        .collect();
+    miette::miette!(severity = severity, labels = labels, "{}", message).with_source_code(
+        NamedSource::new(file.unwrap_or("<input>"), source.to_string()),
+    )
+}
+
+/// The result of running the front end over a source file.
+///
+/// Phases after the first failing one are skipped, leaving their output
+/// as `None`.
+pub struct AnalysisResult {
+    /// The parsed source file, possibly partial if parsing failed.
+    pub ast: SourceFile,
+    /// Name resolution output, if parsing succeeded.
+    pub resolved: Option<ResolvedModule>,
+    /// The lowered HIR, if name resolution succeeded.
+    pub hir: Option<HirModule>,
+    /// Errors encountered.
+    pub errors: Vec<CompilationError>,
+    /// Warnings encountered.
+    pub warnings: Vec<CompilationWarning>,
+}
+
+/// Parse, resolve, and lower source code to HIR.
+pub fn analyze(source: &str) -> AnalysisResult {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let parse_result = haira_parser::parse(source);
+    for err in &parse_result.errors {
+        errors.push(CompilationError {
+            message: err.to_string(),
+            file: None,
+            span: Some(err.span()),
+        });
+    }
+    if !errors.is_empty() {
+        return AnalysisResult {
+            ast: parse_result.ast,
+            resolved: None,
+            hir: None,
+            errors,
+            warnings,
+        };
+    }
+
+    let resolved = haira_resolver::resolve(&parse_result.ast);
+    for err in &resolved.errors {
+        errors.push(CompilationError {
+            message: err.message.clone(),
+            file: None,
+            span: Some(err.span.clone()),
+        });
+    }
+    for warning in &resolved.warnings {
+        warnings.push(CompilationWarning {
+            message: warning.message.clone(),
+            file: None,
+            span: Some(warning.span.clone()),
+        });
+    }
+    let type_diagnostics = haira_types::check_field_defaults(&parse_result.ast)
+        .into_iter()
+        .chain(haira_types::check_matches(&parse_result.ast))
+        .chain(haira_types::check_consts(&parse_result.ast));
+    for diagnostic in type_diagnostics {
+        errors.push(CompilationError {
+            message: diagnostic.error.to_string(),
+            file: None,
+            span: Some(diagnostic.span),
+        });
+    }
+    let hir = errors
+        .is_empty()
+        .then(|| haira_hir::lower(&parse_result.ast));
+
+    AnalysisResult {
+        ast: parse_result.ast,
+        resolved: Some(resolved),
+        hir,
+        errors,
+        warnings,
+    }
+}
+
 /// Compile a single file.
 pub async fn compile_file(
     path: &Path,
@@ -110,6 +263,13 @@ pub async fn compile_source(
             span: Some(err.span.clone()),
         });
     }
+    for warning in &resolved.warnings {
+        warnings.push(CompilationWarning {
+            message: warning.message.clone(),
+            file: source_path.map(|p| p.display().to_string()),
+            span: Some(warning.span.clone()),
+        });
+    }
 
     // Phase 3: AI interpretation for unresolved calls
     if !resolved.unresolved_calls.is_empty() {
@@ -120,6 +280,12 @@ pub async fn compile_source(
             );
         }
 
+        warnings.extend(ai_call_advisory(
+            &resolved,
+            config.ai_call_warning_threshold,
+            source_path,
+        ));
+
         let _engine = AIEngine::new(config.ai);
 
         // TODO: Interpret unresolved calls and generate implementations
@@ -135,11 +301,35 @@ pub async fn compile_source(
         }
     }
 
-    // Phase 4-8: Type checking, lowering, codegen (TODO)
+    // Phase 4-7: Type checking, lowering (TODO)
     if config.verbose {
         tracing::info!("Compilation pipeline incomplete - remaining phases pending");
     }
 
+    // Phase 8: Code generation, checked in memory until the output is wired up
+    if errors.is_empty() && resolved.unresolved_calls.is_empty() {
+        if config.verbose {
+            tracing::info!("Generating code...");
+        }
+
+        match haira_codegen::check(&parse_result.ast) {
+            Ok(codegen_warnings) => {
+                for warning in codegen_warnings {
+                    warnings.push(CompilationWarning {
+                        message: warning.message,
+                        file: source_path.map(|p| p.display().to_string()),
+                        span: Some(warning.span.start as usize..warning.span.end as usize),
+                    });
+                }
+            }
+            Err(err) => errors.push(CompilationError {
+                message: err.to_string(),
+                file: source_path.map(|p| p.display().to_string()),
+                span: None,
+            }),
+        }
+    }
+
     Ok(CompilationResult {
         success: errors.is_empty(),
         errors,
@@ -147,6 +337,31 @@ pub async fn compile_source(
     })
 }
 
+/// A single warning that interpreting the unresolved calls of `resolved`
+/// will take a while, once there are more than `threshold` of them.
+fn ai_call_advisory(
+    resolved: &ResolvedModule,
+    threshold: usize,
+    source_path: Option<&Path>,
+) -> Option<CompilationWarning> {
+    let calls = resolved.unresolved_calls.len();
+    if calls <= threshold {
+        return None;
+    }
+    Some(CompilationWarning {
+        message: format!(
+            "{} functions need AI interpretation, making {} model calls \
+             (roughly {}s); commit the generated `{}` so later builds reuse them",
+            calls,
+            calls,
+            calls * SECONDS_PER_AI_CALL,
+            haira_ai::hif::HIF_FILENAME
+        ),
+        file: source_path.map(|p| p.display().to_string()),
+        span: None,
+    })
+}
+
 /// Check a source file without generating code.
 pub fn check_file(path: &Path) -> miette::Result<CompilationResult> {
     let source =
@@ -158,7 +373,7 @@ pub fn check_file(path: &Path) -> miette::Result<CompilationResult> {
 /// Check source code without generating code.
 pub fn check_source(source: &str, source_path: Option<&Path>) -> miette::Result<CompilationResult> {
     let mut errors = Vec::new();
-    let warnings = Vec::new();
+    let mut warnings = Vec::new();
 
     // Parse
     let parse_result = haira_parser::parse(source);
@@ -181,6 +396,26 @@ pub fn check_source(source: &str, source_path: Option<&Path>) -> miette::Result<
             span: Some(err.span.clone()),
         });
     }
+    for warning in &resolved.warnings {
+        warnings.push(CompilationWarning {
+            message: warning.message.clone(),
+            file: source_path.map(|p| p.display().to_string()),
+            span: Some(warning.span.clone()),
+        });
+    }
+
+    // Type check declarations and match exhaustiveness
+    let type_diagnostics = haira_types::check_field_defaults(&parse_result.ast)
+        .into_iter()
+        .chain(haira_types::check_matches(&parse_result.ast))
+        .chain(haira_types::check_consts(&parse_result.ast));
+    for diagnostic in type_diagnostics {
+        errors.push(CompilationError {
+            message: diagnostic.error.to_string(),
+            file: source_path.map(|p| p.display().to_string()),
+            span: Some(diagnostic.span),
+        });
+    }
 
     Ok(CompilationResult {
         success: errors.is_empty(),
@@ -188,3 +423,130 @@ pub fn check_source(source: &str, source_path: Option<&Path>) -> miette::Result<
         warnings,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_valid_program_reaches_hir() {
+        let result =
+            analyze("Point { x: int, y: int }\n\nadd(a, b) -> int {\n    return a + b\n}\n");
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.ast.items.len(), 2);
+        assert!(result.resolved.is_some());
+        let hir = result.hir.expect("HIR should be lowered");
+        let (_, point) = hir.types.iter().next().unwrap();
+        assert_eq!(point.name, "Point");
+        let (_, add) = hir.functions.iter().next().unwrap();
+        assert_eq!(add.name, "add");
+        assert_eq!(add.params.len(), 2);
+        assert_eq!(add.return_type, haira_types::Type::Int);
+    }
+
+    #[test]
+    fn test_ai_call_advisory_above_threshold() {
+        let resolved = |calls: usize| ResolvedModule {
+            definitions: Default::default(),
+            unresolved_calls: (0..calls)
+                .map(|i| haira_resolver::UnresolvedCall {
+                    name: format!("get_thing_{}", i).into(),
+                    span: i..i + 1,
+                    arg_count: 0,
+                    receiver_type: None,
+                })
+                .collect(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        assert!(ai_call_advisory(&resolved(10), 10, None).is_none());
+        let warning = ai_call_advisory(&resolved(25), 10, Some(Path::new("app.haira"))).unwrap();
+        assert_eq!(
+            warning.message,
+            "25 functions need AI interpretation, making 25 model calls (roughly 125s); \
+             commit the generated `haira.hif` so later builds reuse them"
+        );
+        assert_eq!(warning.file.as_deref(), Some("app.haira"));
+        assert!(warning.span.is_none());
+    }
+
+    #[test]
+    fn test_analyze_stops_at_resolution_errors() {
+        let source = "f(a) {\n    return a + missing\n}\n";
+        let result = analyze(source);
+
+        assert!(result.resolved.is_some());
+        assert!(result.hir.is_none());
+        assert_eq!(result.errors.len(), 1);
+        let span = result.errors[0].span.clone().unwrap();
+        assert_eq!(&source[span], "missing");
+    }
+
+    #[test]
+    fn test_field_default_type_mismatch() {
+        let source = "User {\n    age: int = \"hello\"\n}\n";
+        let result = analyze(source);
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(
+            result.errors[0].message,
+            "type mismatch: expected `int`, found `string`"
+        );
+        let span = result.errors[0].span.clone().unwrap();
+        assert_eq!(&source[span], "\"hello\"");
+    }
+
+    #[test]
+    fn test_field_default_referencing_field_is_undefined() {
+        let source = "Range {\n    start: int = 0\n    end: int = start\n}\n";
+        let result = analyze(source);
+
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].message, "undefined variable `start`");
+    }
+
+    #[test]
+    fn test_check_source_warns_on_unused_local() {
+        let source = "f(a) {\n    unused = a + 1\n    return a\n}\n";
+        let result = check_source(source, None).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].message, "unused variable `unused`");
+        let span = result.warnings[0].span.clone().unwrap();
+        assert_eq!(&source[span], "unused");
+    }
+
+    #[test]
+    fn test_error_diagnostic_underlines_source() {
+        let source = "f(a) {\n    return a + missing\n}\n";
+        let result = analyze(source);
+        let report = result.errors[0].to_diagnostic(source);
+
+        let mut rendered = String::new();
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+        assert!(rendered.contains("undefined variable `missing`"));
+        assert!(rendered.contains("<input>:2:16"));
+        assert!(rendered.contains("return a + missing"));
+        assert!(rendered.contains("here"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_code_is_reported_as_warning() {
+        let source = "f() {\n    return 1\n    print(2)\n}\n";
+        let result = compile_source(source, None, Path::new("out"), CompilerConfig::default())
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.warnings.len(), 1);
+        let warning = &result.warnings[0];
+        assert_eq!(warning.message, "unreachable code after `return`");
+        let span = warning.span.clone().unwrap();
+        assert_eq!(&source[span], "print(2)");
+    }
+}
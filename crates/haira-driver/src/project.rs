@@ -0,0 +1,484 @@
+//! Projects spanning several files.
+//!
+//! A file pulls in the definitions of another with `import "path"`, the path
+//! being relative to the importing file and `.haira` being implied. The
+//! entry file and everything it imports, directly or not, are parsed once
+//! each and merged into a single source file before name resolution. Other
+//! files can only use the functions, types and constants of a file that are
+//! `public`.
+
+use haira_ast::{Item, ItemKind, SourceFile, Span, Spanned};
+use haira_types::{TypeDiagnostic, TypeError};
+use smol_str::SmolStr;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::{CompilationError, CompilationResult, CompilationWarning};
+
+/// A file of a project.
+pub struct ProjectFile {
+    /// The file's path, as imported.
+    pub path: PathBuf,
+    /// The file's source.
+    pub source: String,
+    /// The indices of the file's items in the merged source file.
+    pub items: Range<usize>,
+}
+
+/// The files of a project merged into one compilation unit.
+///
+/// Spans in the merged source file are relative to the file each item came
+/// from.
+pub struct Project {
+    /// The items of every file, those of imported files first.
+    pub ast: SourceFile,
+    /// Every file of the project, in the order their items were merged.
+    pub files: Vec<ProjectFile>,
+    /// The outcome of parsing and resolving the project.
+    pub result: CompilationResult,
+}
+
+impl Project {
+    /// The file that item `index` of the merged source file came from.
+    pub fn file_of_item(&self, index: usize) -> Option<&ProjectFile> {
+        self.files.iter().find(|file| file.items.contains(&index))
+    }
+}
+
+/// Load the project whose entry file is `root`, resolve names across all of
+/// its files, and type check their declarations and matches.
+///
+/// Imports that can't be read or that form a cycle are reported as errors
+/// at the import, as are definitions clashing with one in another file.
+pub fn compile_project(root: &Path) -> miette::Result<Project> {
+    let source =
+        std::fs::read_to_string(root).map_err(|e| miette::miette!("Failed to read file: {}", e))?;
+    let key = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+    let mut loader = Loader::default();
+    loader.load(key, root.to_path_buf(), source);
+
+    // Merge the files, reporting definitions clashing across them
+    let mut items = Vec::new();
+    let mut files: Vec<ProjectFile> = Vec::new();
+    let mut defined: HashMap<SmolStr, usize> = HashMap::new();
+    for (index, file) in loader.files.into_iter().enumerate() {
+        for name in file
+            .ast
+            .items
+            .iter()
+            .flat_map(|item| defined_names(&item.node))
+        {
+            match defined.get(&name.node) {
+                Some(&other) if other != index => loader.errors.push(CompilationError {
+                    message: format!(
+                        "`{}` is defined in both `{}` and `{}`",
+                        name.node,
+                        files[other].path.display(),
+                        file.path.display()
+                    ),
+                    file: Some(file.path.display().to_string()),
+                    span: Some(name.span.start as usize..name.span.end as usize),
+                }),
+                Some(_) => {}
+                None => {
+                    defined.insert(name.node.clone(), index);
+                }
+            }
+        }
+        let start = items.len();
+        items.extend(file.ast.items);
+        files.push(ProjectFile {
+            path: file.path,
+            source: file.source,
+            items: start..items.len(),
+        });
+    }
+
+    let mut project = Project {
+        ast: SourceFile {
+            items,
+            span: Span::default(),
+        },
+        files,
+        result: CompilationResult {
+            success: false,
+            errors: loader.errors,
+            warnings: Vec::new(),
+        },
+    };
+
+    let modules: Vec<_> = project
+        .files
+        .iter()
+        .map(|file| file.items.clone())
+        .collect();
+    let resolved = haira_resolver::resolve_modules(&project.ast, &modules);
+    let file_name = |item: usize| {
+        project
+            .file_of_item(item)
+            .map(|file| file.path.display().to_string())
+    };
+    let errors: Vec<_> = resolved
+        .errors
+        .into_iter()
+        .map(|err| CompilationError {
+            file: file_name(err.item),
+            message: err.message,
+            span: Some(err.span),
+        })
+        .collect();
+    let warnings: Vec<_> = resolved
+        .warnings
+        .into_iter()
+        .map(|warning| CompilationWarning {
+            file: file_name(warning.item),
+            message: warning.message,
+            span: Some(warning.span),
+        })
+        .collect();
+
+    project.result.errors.extend(errors);
+    project.result.warnings.extend(warnings);
+    let type_errors = type_check(&project);
+    project.result.errors.extend(type_errors);
+    project.result.success = project.result.errors.is_empty();
+    Ok(project)
+}
+
+/// Whether a constant `diagnostic` belongs to the file with `items`: a
+/// non-constant value is reported by the file defining the constant only.
+fn reported_by(items: &[Item], diagnostic: &TypeDiagnostic) -> bool {
+    let TypeError::NonConstant(name) = &diagnostic.error else {
+        return true;
+    };
+    items.iter().any(
+        |item| matches!(&item.node, ItemKind::Const(const_def) if const_def.name.node == *name),
+    )
+}
+
+/// Run the declaration and match checks over each file of `project`.
+///
+/// A file is checked together with the definitions of the other files a
+/// check needs (aliases, enums, constants), so each diagnostic is known to
+/// come from the file being checked.
+fn type_check(project: &Project) -> Vec<CompilationError> {
+    let mut errors = Vec::new();
+    for file in &project.files {
+        let own = &project.ast.items[file.items.clone()];
+        let with_others = |needed: fn(&ItemKind) -> bool| SourceFile {
+            items: project
+                .ast
+                .items
+                .iter()
+                .enumerate()
+                .filter(|(index, item)| !file.items.contains(index) && needed(&item.node))
+                .map(|(_, item)| item)
+                .chain(own)
+                .cloned()
+                .collect(),
+            span: Span::default(),
+        };
+
+        let diagnostics = haira_types::check_field_defaults(&with_others(|item| {
+            matches!(item, ItemKind::TypeAlias(_))
+        }))
+        .into_iter()
+        .chain(haira_types::check_matches(&with_others(|item| {
+            matches!(item, ItemKind::EnumDef(_))
+        })))
+        .chain(
+            haira_types::check_consts(&with_others(|item| matches!(item, ItemKind::Const(_))))
+                .into_iter()
+                .filter(|diagnostic| reported_by(own, diagnostic)),
+        );
+        errors.extend(diagnostics.map(|diagnostic| CompilationError {
+            message: diagnostic.error.to_string(),
+            file: Some(file.path.display().to_string()),
+            span: Some(diagnostic.span),
+        }));
+    }
+    errors
+}
+
+/// The names an item defines at the top level.
+fn defined_names(item: &ItemKind) -> Vec<&Spanned<SmolStr>> {
+    match item {
+        ItemKind::TypeDef(type_def) => vec![&type_def.name],
+        ItemKind::EnumDef(enum_def) => std::iter::once(&enum_def.name)
+            .chain(enum_def.variants.iter().map(|variant| &variant.name))
+            .collect(),
+        ItemKind::FunctionDef(func) => vec![&func.name],
+        ItemKind::TypeAlias(alias) => vec![&alias.name],
+        ItemKind::AiFunctionDef(ai_block) => ai_block.name.iter().collect(),
+        ItemKind::Const(const_def) => vec![&const_def.name],
+        ItemKind::MethodDef(_) | ItemKind::Statement(_) | ItemKind::Import(_) => Vec::new(),
+    }
+}
+
+/// A parsed file, before merging.
+struct LoadedFile {
+    path: PathBuf,
+    source: String,
+    ast: SourceFile,
+}
+
+/// Parses the files of a project, depth first along imports.
+#[derive(Default)]
+struct Loader {
+    /// Loaded files, each after the files it imports.
+    files: Vec<LoadedFile>,
+    /// Canonical paths of the loaded files.
+    loaded: HashSet<PathBuf>,
+    /// The chain of files being loaded, as canonical and imported paths.
+    stack: Vec<(PathBuf, PathBuf)>,
+    errors: Vec<CompilationError>,
+}
+
+impl Loader {
+    /// Parse `source`, the file at `path` (canonically `key`), after the
+    /// files it imports.
+    fn load(&mut self, key: PathBuf, path: PathBuf, source: String) {
+        let file_name = path.display().to_string();
+        let parse_result = haira_parser::parse(&source);
+        for err in &parse_result.errors {
+            self.errors.push(CompilationError {
+                message: err.to_string(),
+                file: Some(file_name.clone()),
+                span: Some(err.span()),
+            });
+        }
+
+        self.stack.push((key.clone(), path.clone()));
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        for item in &parse_result.ast.items {
+            let ItemKind::Import(import) = &item.node else {
+                continue;
+            };
+            let span = Some(import.path.span.start as usize..import.path.span.end as usize);
+            let mut imported = dir.join(import.path.node.as_str());
+            if imported.extension().is_none() {
+                imported.set_extension("haira");
+            }
+
+            let loaded = imported.canonicalize().and_then(|imported_key| {
+                Ok((
+                    imported_key.clone(),
+                    std::fs::read_to_string(&imported_key)?,
+                ))
+            });
+            let (imported_key, imported_source) = match loaded {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    self.errors.push(CompilationError {
+                        message: format!("cannot import `{}`: {}", imported.display(), e),
+                        file: Some(file_name.clone()),
+                        span,
+                    });
+                    continue;
+                }
+            };
+
+            if let Some(start) = self.stack.iter().position(|(k, _)| *k == imported_key) {
+                let cycle: Vec<String> = self.stack[start..]
+                    .iter()
+                    .map(|(_, p)| format!("`{}`", p.display()))
+                    .chain(std::iter::once(format!("`{}`", imported.display())))
+                    .collect();
+                self.errors.push(CompilationError {
+                    message: format!("import cycle: {}", cycle.join(" imports ")),
+                    file: Some(file_name.clone()),
+                    span,
+                });
+                continue;
+            }
+            if self.loaded.contains(&imported_key) {
+                continue;
+            }
+            self.load(imported_key, imported, imported_source);
+        }
+        self.stack.pop();
+
+        self.loaded.insert(key);
+        self.files.push(LoadedFile {
+            path,
+            source,
+            ast: parse_result.ast,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory holding `files`, as names and sources.
+    fn project_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("haira-project-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, source) in files {
+            std::fs::write(dir.join(file), source).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_import_function_from_another_file() {
+        let dir = project_dir(
+            "import",
+            &[
+                (
+                    "main.haira",
+                    "import \"util\"\n\nmain() {\n    print(double(21))\n}\n",
+                ),
+                ("util.haira", "public double(n) {\n    return n * 2\n}\n"),
+            ],
+        );
+        let project = compile_project(&dir.join("main.haira")).unwrap();
+
+        assert!(project.result.success, "{:?}", project.result.errors);
+        let names: Vec<_> = project
+            .files
+            .iter()
+            .map(|file| file.path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["util.haira", "main.haira"]);
+        assert!(matches!(
+            &project.ast.items[0].node,
+            ItemKind::FunctionDef(func) if func.name.node == "double"
+        ));
+        assert_eq!(
+            project.file_of_item(1).unwrap().path,
+            dir.join("main.haira")
+        );
+    }
+
+    #[test]
+    fn test_private_function_is_not_importable() {
+        let dir = project_dir(
+            "private",
+            &[
+                ("main.haira", "import \"util\"\n\nprint(double(21))\n"),
+                (
+                    "util.haira",
+                    "double(n) {\n    return n * 2\n}\n\npublic triple(n) {\n    return double(n) + n\n}\n",
+                ),
+            ],
+        );
+        let project = compile_project(&dir.join("main.haira")).unwrap();
+
+        assert_eq!(project.result.errors.len(), 1);
+        let error = &project.result.errors[0];
+        assert_eq!(
+            error.message,
+            "function `double` is private to another module; mark it `public` to use it here"
+        );
+        assert_eq!(
+            error.file,
+            Some(dir.join("main.haira").display().to_string())
+        );
+        assert_eq!(
+            &project.files[1].source[error.span.clone().unwrap()],
+            "double"
+        );
+    }
+
+    #[test]
+    fn test_import_cycle_is_an_error() {
+        let dir = project_dir(
+            "cycle",
+            &[
+                ("a.haira", "import \"b\"\n\nf() {\n    return 1\n}\n"),
+                ("b.haira", "import \"a.haira\"\n\ng() {\n    return 2\n}\n"),
+            ],
+        );
+        let project = compile_project(&dir.join("a.haira")).unwrap();
+
+        assert!(!project.result.success);
+        assert_eq!(project.result.errors.len(), 1);
+        let error = &project.result.errors[0];
+        assert_eq!(
+            error.message,
+            format!(
+                "import cycle: `{}` imports `{}` imports `{}`",
+                dir.join("a.haira").display(),
+                dir.join("b.haira").display(),
+                dir.join("a.haira").display()
+            )
+        );
+        assert_eq!(error.file, Some(dir.join("b.haira").display().to_string()));
+        // Each file is still merged once
+        assert_eq!(project.files.len(), 2);
+    }
+
+    #[test]
+    fn test_non_exhaustive_match_over_imported_enum() {
+        let dir = project_dir(
+            "match",
+            &[
+                (
+                    "main.haira",
+                    "import \"shape\"\n\narea(s) {\n    match s {\n        Circle(r) => r * r\n    }\n}\n",
+                ),
+                (
+                    "shape.haira",
+                    "public Shape { Circle(int), Square(int) }\n",
+                ),
+            ],
+        );
+        let project = compile_project(&dir.join("main.haira")).unwrap();
+
+        assert_eq!(project.result.errors.len(), 1);
+        let error = &project.result.errors[0];
+        assert_eq!(
+            error.message,
+            haira_types::TypeError::NonExhaustiveMatch {
+                missing: vec!["Square".into()]
+            }
+            .to_string()
+        );
+        assert_eq!(
+            error.file,
+            Some(dir.join("main.haira").display().to_string())
+        );
+    }
+
+    #[test]
+    fn test_definitions_clashing_across_files() {
+        let dir = project_dir(
+            "clash",
+            &[
+                ("main.haira", "import \"other\"\n\nf() {\n    return y\n}\n"),
+                ("other.haira", "f() {\n    return 1\n}\n"),
+            ],
+        );
+        let project = compile_project(&dir.join("main.haira")).unwrap();
+
+        let messages: Vec<_> = project
+            .result
+            .errors
+            .iter()
+            .map(|err| (err.message.clone(), err.file.clone()))
+            .collect();
+        let main = dir.join("main.haira").display().to_string();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    format!(
+                        "`f` is defined in both `{}` and `{}`",
+                        dir.join("other.haira").display(),
+                        main
+                    ),
+                    Some(main.clone())
+                ),
+                ("undefined variable `y`".to_string(), Some(main.clone())),
+            ]
+        );
+        let span = project.result.errors[1].span.clone().unwrap();
+        assert_eq!(&project.files[1].source[span], "y");
+    }
+}
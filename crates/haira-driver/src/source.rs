@@ -0,0 +1,314 @@
+//! Rendering generated functions as Haira source.
+//!
+//! Covers the statements and expressions that CIR converts to, so users can
+//! read what the AI generated as if they had written it. Anything else is
+//! rendered as a `/* ... */` placeholder.
+
+use haira_ast::{
+    AssignPath, BinaryOp, Block, ElseBranch, Expr, ExprKind, FunctionDef, IfStatement, Literal,
+    StatementKind, UnaryOp,
+};
+
+use crate::interpret::type_to_string;
+
+/// Render `func` as Haira source.
+pub fn function_to_source(func: &FunctionDef) -> String {
+    let params: Vec<String> = func
+        .params
+        .iter()
+        .map(|p| match &p.ty {
+            Some(ty) => format!("{}: {}", p.name.node, type_to_string(&ty.node)),
+            None => p.name.node.to_string(),
+        })
+        .collect();
+    let visibility = if func.is_public { "public " } else { "" };
+    let mut out = format!("{}{}({})", visibility, func.name.node, params.join(", "));
+    if let Some(ty) = &func.return_ty {
+        out.push_str(&format!(" -> {}", type_to_string(&ty.node)));
+    }
+    out.push(' ');
+    write_block(&mut out, &func.body, 0);
+    out.push('\n');
+    out
+}
+
+/// Write `{`, the statements of `block` one level deeper than `indent`, and
+/// the closing `}`.
+fn write_block(out: &mut String, block: &Block, indent: usize) {
+    out.push_str("{\n");
+    for stmt in &block.statements {
+        out.push_str(&"    ".repeat(indent + 1));
+        write_statement(out, &stmt.node, indent + 1);
+        out.push('\n');
+    }
+    out.push_str(&"    ".repeat(indent));
+    out.push('}');
+}
+
+fn write_statement(out: &mut String, stmt: &StatementKind, indent: usize) {
+    match stmt {
+        StatementKind::Expr(expr) => write_expr(out, expr, indent),
+        StatementKind::Assignment(assign) => {
+            let targets: Vec<String> = assign
+                .targets
+                .iter()
+                .map(|target| assign_path(&target.path, indent))
+                .collect();
+            out.push_str(&targets.join(", "));
+            match &assign.op {
+                Some(op) => out.push_str(&format!(" {}= ", binary_op(&op.node))),
+                None => out.push_str(" = "),
+            }
+            write_expr(out, &assign.value, indent);
+        }
+        StatementKind::Return(ret) => {
+            out.push_str("return");
+            for (i, value) in ret.values.iter().enumerate() {
+                out.push_str(if i == 0 { " " } else { ", " });
+                write_expr(out, value, indent);
+            }
+        }
+        StatementKind::If(if_stmt) => write_if(out, if_stmt, indent),
+        _ => out.push_str("/* ... */"),
+    }
+}
+
+fn write_if(out: &mut String, if_stmt: &IfStatement, indent: usize) {
+    out.push_str("if ");
+    write_expr(out, &if_stmt.condition, indent);
+    out.push(' ');
+    write_block(out, &if_stmt.then_branch, indent);
+    match &if_stmt.else_branch {
+        Some(ElseBranch::Block(block)) => {
+            out.push_str(" else ");
+            write_block(out, block, indent);
+        }
+        Some(ElseBranch::ElseIf(else_if)) => {
+            out.push_str(" else ");
+            write_if(out, &else_if.node, indent);
+        }
+        None => {}
+    }
+}
+
+fn write_expr(out: &mut String, expr: &Expr, indent: usize) {
+    match &expr.node {
+        ExprKind::Literal(literal) => write_literal(out, literal),
+        ExprKind::Identifier(name) => out.push_str(name),
+        ExprKind::None => out.push_str("none"),
+        ExprKind::Paren(inner) => write_operand(out, inner, true, indent),
+        ExprKind::Binary(bin) => {
+            // Operands that bind looser than `bin` need parentheses, and so
+            // do equal ones on the right since operators associate left
+            let prec = precedence(&bin.op.node);
+            let binds_looser = |operand: &Expr, right: bool| match &operand.node {
+                ExprKind::Binary(inner) => {
+                    let inner = precedence(&inner.op.node);
+                    inner < prec || (right && inner == prec)
+                }
+                _ => false,
+            };
+            write_operand(out, &bin.left, binds_looser(&bin.left, false), indent);
+            out.push_str(&format!(" {} ", binary_op(&bin.op.node)));
+            write_operand(out, &bin.right, binds_looser(&bin.right, true), indent);
+        }
+        ExprKind::Unary(unary) => {
+            out.push_str(match unary.op.node {
+                UnaryOp::Neg => "-",
+                UnaryOp::Not => "not ",
+            });
+            let parenthesize = matches!(unary.operand.node, ExprKind::Binary(_));
+            write_operand(out, &unary.operand, parenthesize, indent);
+        }
+        ExprKind::Call(call) => {
+            write_expr(out, &call.callee, indent);
+            write_args(out, &call.args, indent);
+        }
+        ExprKind::MethodCall(call) => {
+            write_expr(out, &call.receiver, indent);
+            out.push_str(&format!(".{}", call.method.node));
+            write_args(out, &call.args, indent);
+        }
+        ExprKind::Field(field) => {
+            write_expr(out, &field.object, indent);
+            out.push_str(&format!(".{}", field.field.node));
+        }
+        ExprKind::Instance(instance) => {
+            out.push_str(&format!("{} {{ ", instance.type_name.node));
+            for (i, field) in instance.fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                if let Some(name) = &field.name {
+                    out.push_str(&format!("{} = ", name.node));
+                }
+                write_expr(out, &field.value, indent);
+            }
+            out.push_str(" }");
+        }
+        ExprKind::List(elements) => {
+            out.push('[');
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_expr(out, element, indent);
+            }
+            out.push(']');
+        }
+        ExprKind::If(if_stmt) => write_if(out, if_stmt, indent),
+        _ => out.push_str("/* ... */"),
+    }
+}
+
+fn write_operand(out: &mut String, expr: &Expr, parenthesize: bool, indent: usize) {
+    if parenthesize {
+        out.push('(');
+        write_expr(out, expr, indent);
+        out.push(')');
+    } else {
+        write_expr(out, expr, indent);
+    }
+}
+
+fn write_args(out: &mut String, args: &[haira_ast::Argument], indent: usize) {
+    out.push('(');
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        if let Some(name) = &arg.name {
+            out.push_str(&format!("{} = ", name.node));
+        }
+        write_expr(out, &arg.value, indent);
+    }
+    out.push(')');
+}
+
+fn write_literal(out: &mut String, literal: &Literal) {
+    match literal {
+        Literal::Int(n) => out.push_str(&n.to_string()),
+        // `{:?}` keeps the `.0` of whole floats
+        Literal::Float(n) => out.push_str(&format!("{:?}", n)),
+        Literal::Char(c) => out.push_str(&format!("{:?}", c)),
+        Literal::String(s) => {
+            out.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    '\r' => out.push_str("\\r"),
+                    '{' => out.push_str("\\{"),
+                    '}' => out.push_str("\\}"),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+        }
+        Literal::InterpolatedString(_) => out.push_str("/* ... */"),
+        Literal::Bool(b) => out.push_str(&b.to_string()),
+    }
+}
+
+fn assign_path(path: &AssignPath, indent: usize) -> String {
+    match path {
+        AssignPath::Identifier(name) => name.node.to_string(),
+        AssignPath::Field { object, field } => {
+            format!("{}.{}", assign_path(object, indent), field.node)
+        }
+        AssignPath::Index { object, index } => {
+            let mut index_source = String::new();
+            write_expr(&mut index_source, index, indent);
+            format!("{}[{}]", assign_path(object, indent), index_source)
+        }
+    }
+}
+
+/// How tightly an operator binds, following the parser's precedence levels.
+fn precedence(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::BitOr => 1,
+        BinaryOp::Coalesce => 2,
+        BinaryOp::Or => 3,
+        BinaryOp::And => 4,
+        BinaryOp::Eq | BinaryOp::Ne => 5,
+        BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge => 6,
+        BinaryOp::BitXor => 7,
+        BinaryOp::BitAnd => 8,
+        BinaryOp::Shl | BinaryOp::Shr => 9,
+        BinaryOp::Add | BinaryOp::Sub => 10,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 11,
+    }
+}
+
+fn binary_op(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Eq => "==",
+        BinaryOp::Ne => "!=",
+        BinaryOp::Lt => "<",
+        BinaryOp::Gt => ">",
+        BinaryOp::Le => "<=",
+        BinaryOp::Ge => ">=",
+        BinaryOp::And => "and",
+        BinaryOp::Or => "or",
+        BinaryOp::BitAnd => "&",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::Shl => "<<",
+        BinaryOp::Shr => ">>",
+        BinaryOp::Coalesce => "??",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse `source` and render its only function back.
+    fn round_trip(source: &str) -> String {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        match &result.ast.items[0].node {
+            haira_ast::ItemKind::FunctionDef(func) => function_to_source(func),
+            _ => panic!("expected a function"),
+        }
+    }
+
+    #[test]
+    fn test_function_to_source() {
+        let source = "greet(user: User, times: int) -> string {\n    n = user.name.trim()\n    total = (times + 1) * 2 - 3 - 4 * 5\n    small = not (total > 1.0)\n    if small {\n        return \"a \\\"\\{quoted\\}\\\" name\"\n    } else if total == 0 {\n        p = Point { x = -1, y = 2 }\n    } else {\n        items = [1, 2]\n    }\n    return n\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    /// Drop the parentheses in a binary tree, as CIR-built trees have none.
+    fn strip_parens(expr: &mut Expr) {
+        if let ExprKind::Paren(inner) = &expr.node {
+            *expr = (**inner).clone();
+            strip_parens(expr);
+        } else if let ExprKind::Binary(bin) = &mut expr.node {
+            strip_parens(&mut bin.left);
+            strip_parens(&mut bin.right);
+        }
+    }
+
+    #[test]
+    fn test_parentheses_follow_precedence() {
+        let source = "f(a: int, b: int) {\n    return a - (b - 1) * 2 + (a or b)\n}\n";
+        let mut result = haira_parser::parse(source);
+        let haira_ast::ItemKind::FunctionDef(func) = &mut result.ast.items[0].node else {
+            panic!("expected a function");
+        };
+        let StatementKind::Return(ret) = &mut func.body.statements[0].node else {
+            panic!("expected a return");
+        };
+        strip_parens(&mut ret.values[0]);
+        assert_eq!(function_to_source(func), source);
+    }
+}
@@ -3,6 +3,11 @@
 //! HIR is a desugared, type-annotated version of the AST.
 //! It includes resolved types, lowered constructs, and AI-generated implementations.
 
+mod lower;
+
+pub use haira_types::lower_type;
+pub use lower::lower;
+
 use haira_ast::Span;
 use haira_types::Type;
 use la_arena::{Arena, Idx};
@@ -97,8 +102,12 @@ pub enum HirExprKind {
         then_branch: Idx<HirExpr>,
         else_branch: Option<Idx<HirExpr>>,
     },
-    /// Block expression.
-    Block(Vec<Idx<HirExpr>>),
+    /// Block expression: statements run in order, then the trailing
+    /// expression giving the block its value, if it has one.
+    Block {
+        statements: Vec<Idx<HirExpr>>,
+        value: Option<Idx<HirExpr>>,
+    },
     /// Let binding.
     Let {
         name: SmolStr,
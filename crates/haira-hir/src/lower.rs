@@ -0,0 +1,457 @@
+//! Lowering from the AST to HIR.
+
+use crate::{
+    BinaryOp, FunctionId, HirBody, HirEnumVariant, HirExpr, HirExprKind, HirFunction, HirModule,
+    HirParam, HirTypeDef, HirTypeDefKind, TypeId, UnaryOp,
+};
+use haira_ast::{
+    AssignPath, Block, ElseBranch, ExprKind, IfStatement, ItemKind, LambdaBody, Literal, Param,
+    SourceFile, Span, Spanned, Statement, StatementKind,
+};
+use haira_types::{lower_type, Type, TypeVar};
+use la_arena::{Arena, Idx};
+use smol_str::SmolStr;
+use std::collections::HashMap;
+
+/// Lower a parsed source file into a HIR module.
+///
+/// Type definitions, enums, aliases, and functions and methods are lowered.
+/// Constructs HIR has no form for yet, such as loops and `match`, are lowered
+/// to [`HirExprKind::Error`] placeholders. Unannotated types become fresh
+/// type variables for inference.
+pub fn lower(ast: &SourceFile) -> HirModule {
+    let mut module = HirModule::new();
+    // Bodies are lowered once every function has an id, for calls to refer to
+    let mut bodies: Vec<(FunctionId, &Block)> = Vec::new();
+
+    for item in &ast.items {
+        match &item.node {
+            ItemKind::TypeDef(type_def) => {
+                let fields = type_def
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.node.clone(), lower_annotation(&field.ty)))
+                    .collect();
+                module.types.alloc(HirTypeDef {
+                    name: type_def.name.node.clone(),
+                    kind: HirTypeDefKind::Struct { fields },
+                    span: item.span,
+                });
+            }
+            ItemKind::EnumDef(enum_def) => {
+                let variants = enum_def
+                    .variants
+                    .iter()
+                    .map(|variant| HirEnumVariant {
+                        name: variant.name.node.clone(),
+                        fields: variant
+                            .fields
+                            .iter()
+                            .map(|ty| lower_type(&ty.node))
+                            .collect(),
+                    })
+                    .collect();
+                module.types.alloc(HirTypeDef {
+                    name: enum_def.name.node.clone(),
+                    kind: HirTypeDefKind::Enum { variants },
+                    span: item.span,
+                });
+            }
+            ItemKind::TypeAlias(alias) => {
+                module.types.alloc(HirTypeDef {
+                    name: alias.name.node.clone(),
+                    kind: HirTypeDefKind::Alias(lower_type(&alias.ty.node)),
+                    span: item.span,
+                });
+            }
+            ItemKind::FunctionDef(func) => {
+                let id = module.functions.alloc(HirFunction {
+                    name: func.name.node.clone(),
+                    params: lower_params(&func.params),
+                    return_type: lower_annotation(&func.return_ty),
+                    body: HirBody::empty(),
+                    ai_generated: false,
+                    span: item.span,
+                });
+                bodies.push((id, &func.body));
+            }
+            ItemKind::MethodDef(method) => {
+                let mut params = vec![HirParam {
+                    name: SmolStr::new_inline("self"),
+                    ty: Type::Named(method.type_name.node.clone()),
+                    span: method.type_name.span,
+                }];
+                params.extend(lower_params(&method.params));
+                let id = module.functions.alloc(HirFunction {
+                    name: format!("{}.{}", method.type_name.node, method.name.node).into(),
+                    params,
+                    return_type: lower_annotation(&method.return_ty),
+                    body: HirBody::empty(),
+                    ai_generated: false,
+                    span: item.span,
+                });
+                bodies.push((id, &method.body));
+            }
+            ItemKind::AiFunctionDef(ai_block) => {
+                // Anonymous AI blocks only appear as expressions
+                let Some(name) = &ai_block.name else {
+                    continue;
+                };
+                module.functions.alloc(HirFunction {
+                    name: name.node.clone(),
+                    params: lower_params(&ai_block.params),
+                    return_type: lower_annotation(&ai_block.return_ty),
+                    body: HirBody::empty(),
+                    ai_generated: true,
+                    span: item.span,
+                });
+            }
+            ItemKind::Statement(_) | ItemKind::Import(_) | ItemKind::Const(_) => {}
+        }
+    }
+
+    let functions = module
+        .functions
+        .iter()
+        .map(|(id, func)| (func.name.clone(), id))
+        .collect();
+    let types = module
+        .types
+        .iter()
+        .map(|(id, ty)| (ty.name.clone(), id))
+        .collect();
+    for (id, block) in bodies {
+        let mut lowerer = BodyLowerer {
+            exprs: Arena::new(),
+            functions: &functions,
+            types: &types,
+        };
+        let root = lowerer.block(block);
+        module.functions[id].body = HirBody {
+            exprs: lowerer.exprs,
+            root: Some(root),
+        };
+    }
+
+    module
+}
+
+/// Lowers the statements and expressions of one body into its arena.
+struct BodyLowerer<'a> {
+    exprs: Arena<HirExpr>,
+    /// The module's functions, by name, for calls.
+    functions: &'a HashMap<SmolStr, FunctionId>,
+    /// The module's types, by name, for struct instances.
+    types: &'a HashMap<SmolStr, TypeId>,
+}
+
+impl BodyLowerer<'_> {
+    fn alloc(&mut self, kind: HirExprKind, ty: Type, span: Span) -> Idx<HirExpr> {
+        self.exprs.alloc(HirExpr { kind, ty, span })
+    }
+
+    /// Allocate an expression whose type is left to inference.
+    fn alloc_unknown(&mut self, kind: HirExprKind, span: Span) -> Idx<HirExpr> {
+        self.alloc(kind, Type::Unknown(TypeVar::fresh()), span)
+    }
+
+    /// Lower a block, its last statement becoming the block's value when it
+    /// has one (see [`Block::value`]).
+    fn block(&mut self, block: &Block) -> Idx<HirExpr> {
+        let (statements, value) = match block.value() {
+            Some(_) => {
+                let (last, rest) = block.statements.split_last().unwrap();
+                (rest, Some(self.stmt(last)))
+            }
+            None => (&block.statements[..], None),
+        };
+        let statements = statements.iter().map(|stmt| self.stmt(stmt)).collect();
+        let ty = match value {
+            Some(value) => self.exprs[value].ty.clone(),
+            None => Type::Unit,
+        };
+        self.alloc(HirExprKind::Block { statements, value }, ty, block.span)
+    }
+
+    fn stmt(&mut self, stmt: &Statement) -> Idx<HirExpr> {
+        match &stmt.node {
+            StatementKind::Assignment(assign) => match &assign.targets[..] {
+                [target] if assign.op.is_none() => match &target.path {
+                    AssignPath::Identifier(name) => {
+                        let value = self.expr(&assign.value);
+                        let ty = match &target.ty {
+                            Some(ty) => lower_type(&ty.node),
+                            None => self.exprs[value].ty.clone(),
+                        };
+                        let kind = HirExprKind::Let {
+                            name: name.node.clone(),
+                            ty,
+                            value,
+                        };
+                        self.alloc(kind, Type::Unit, stmt.span)
+                    }
+                    _ => self.alloc(HirExprKind::Error, Type::Error, stmt.span),
+                },
+                _ => self.alloc(HirExprKind::Error, Type::Error, stmt.span),
+            },
+            StatementKind::If(if_stmt) => self.if_expr(if_stmt, stmt.span),
+            StatementKind::Return(ret) => {
+                let value = match &ret.values[..] {
+                    [] => None,
+                    [value] => Some(self.expr(value)),
+                    _ => Some(self.alloc(HirExprKind::Error, Type::Error, stmt.span)),
+                };
+                self.alloc(HirExprKind::Return(value), Type::Unit, stmt.span)
+            }
+            StatementKind::Expr(expr) => self.expr(expr),
+            StatementKind::For(_)
+            | StatementKind::While(_)
+            | StatementKind::Match(_)
+            | StatementKind::Try(_)
+            | StatementKind::Break
+            | StatementKind::Continue => self.alloc(HirExprKind::Error, Type::Error, stmt.span),
+        }
+    }
+
+    fn if_expr(&mut self, if_stmt: &IfStatement, span: Span) -> Idx<HirExpr> {
+        let condition = self.expr(&if_stmt.condition);
+        let then_branch = self.block(&if_stmt.then_branch);
+        let else_branch = if_stmt.else_branch.as_ref().map(|branch| match branch {
+            ElseBranch::Block(block) => self.block(block),
+            ElseBranch::ElseIf(else_if) => self.if_expr(&else_if.node, else_if.span),
+        });
+        let kind = HirExprKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        };
+        if if_stmt.has_else() {
+            self.alloc_unknown(kind, span)
+        } else {
+            self.alloc(kind, Type::Unit, span)
+        }
+    }
+
+    fn expr(&mut self, expr: &haira_ast::Expr) -> Idx<HirExpr> {
+        let span = expr.span;
+        match &expr.node {
+            ExprKind::Literal(Literal::Int(n)) => {
+                self.alloc(HirExprKind::IntLit(*n), Type::Int, span)
+            }
+            ExprKind::Literal(Literal::Float(f)) => {
+                self.alloc(HirExprKind::FloatLit(*f), Type::Float, span)
+            }
+            ExprKind::Literal(Literal::String(s)) => {
+                self.alloc(HirExprKind::StringLit(s.clone()), Type::String, span)
+            }
+            ExprKind::Literal(Literal::Bool(b)) => {
+                self.alloc(HirExprKind::BoolLit(*b), Type::Bool, span)
+            }
+            ExprKind::Identifier(name) => {
+                self.alloc_unknown(HirExprKind::Local(name.clone()), span)
+            }
+            ExprKind::Binary(bin) => {
+                let Some(op) = binary_op(bin.op.node) else {
+                    return self.alloc(HirExprKind::Error, Type::Error, span);
+                };
+                let lhs = self.expr(&bin.left);
+                let rhs = self.expr(&bin.right);
+                self.alloc_unknown(HirExprKind::Binary { op, lhs, rhs }, span)
+            }
+            ExprKind::Unary(unary) => {
+                let op = match unary.op.node {
+                    haira_ast::UnaryOp::Neg => UnaryOp::Neg,
+                    haira_ast::UnaryOp::Not => UnaryOp::Not,
+                };
+                let operand = self.expr(&unary.operand);
+                self.alloc_unknown(HirExprKind::Unary { op, operand }, span)
+            }
+            ExprKind::Call(call) => {
+                let func = match &call.callee.node {
+                    ExprKind::Identifier(name) => self.functions.get(name).copied(),
+                    _ => None,
+                };
+                let Some(func) = func else {
+                    return self.alloc(HirExprKind::Error, Type::Error, span);
+                };
+                let args = call.args.iter().map(|arg| self.expr(&arg.value)).collect();
+                self.alloc_unknown(HirExprKind::Call { func, args }, span)
+            }
+            ExprKind::MethodCall(call) => {
+                let receiver = self.expr(&call.receiver);
+                let args = call.args.iter().map(|arg| self.expr(&arg.value)).collect();
+                let kind = HirExprKind::MethodCall {
+                    receiver,
+                    method: call.method.node.clone(),
+                    args,
+                };
+                self.alloc_unknown(kind, span)
+            }
+            ExprKind::Field(field) => {
+                let base = self.expr(&field.object);
+                let kind = HirExprKind::Field {
+                    base,
+                    field: field.field.node.clone(),
+                };
+                self.alloc_unknown(kind, span)
+            }
+            ExprKind::Index(index) => {
+                let base = self.expr(&index.object);
+                let index = self.expr(&index.index);
+                self.alloc_unknown(HirExprKind::Index { base, index }, span)
+            }
+            ExprKind::If(if_stmt) => self.if_expr(if_stmt, span),
+            ExprKind::Block(block) => self.block(block),
+            ExprKind::Instance(instance) => {
+                let Some(&ty) = self.types.get(&instance.type_name.node) else {
+                    return self.alloc(HirExprKind::Error, Type::Error, span);
+                };
+                let mut fields = Vec::new();
+                for field in &instance.fields {
+                    let Some(name) = &field.name else {
+                        return self.alloc(HirExprKind::Error, Type::Error, span);
+                    };
+                    fields.push((name.node.clone(), self.expr(&field.value)));
+                }
+                let kind = HirExprKind::Struct { ty, fields };
+                self.alloc(kind, Type::Named(instance.type_name.node.clone()), span)
+            }
+            ExprKind::Lambda(lambda) => {
+                let body = match &lambda.body {
+                    LambdaBody::Expr(body) => self.expr(body),
+                    LambdaBody::Block(block) => self.block(block),
+                };
+                let kind = HirExprKind::Lambda {
+                    params: lower_params(&lambda.params),
+                    body,
+                };
+                self.alloc_unknown(kind, span)
+            }
+            ExprKind::Paren(inner) => self.expr(inner),
+            _ => self.alloc(HirExprKind::Error, Type::Error, span),
+        }
+    }
+}
+
+/// The HIR operator for an AST one; `??` has no HIR form yet.
+fn binary_op(op: haira_ast::BinaryOp) -> Option<BinaryOp> {
+    use haira_ast::BinaryOp as Ast;
+    Some(match op {
+        Ast::Add => BinaryOp::Add,
+        Ast::Sub => BinaryOp::Sub,
+        Ast::Mul => BinaryOp::Mul,
+        Ast::Div => BinaryOp::Div,
+        Ast::Mod => BinaryOp::Mod,
+        Ast::Eq => BinaryOp::Eq,
+        Ast::Ne => BinaryOp::Ne,
+        Ast::Lt => BinaryOp::Lt,
+        Ast::Le => BinaryOp::Le,
+        Ast::Gt => BinaryOp::Gt,
+        Ast::Ge => BinaryOp::Ge,
+        Ast::And => BinaryOp::And,
+        Ast::Or => BinaryOp::Or,
+        Ast::BitAnd => BinaryOp::BitAnd,
+        Ast::BitOr => BinaryOp::BitOr,
+        Ast::BitXor => BinaryOp::BitXor,
+        Ast::Shl => BinaryOp::Shl,
+        Ast::Shr => BinaryOp::Shr,
+        Ast::Coalesce => return None,
+    })
+}
+
+fn lower_params(params: &[Param]) -> Vec<HirParam> {
+    params
+        .iter()
+        .map(|param| HirParam {
+            name: param.name.node.clone(),
+            ty: lower_annotation(&param.ty),
+            span: param.span,
+        })
+        .collect()
+}
+
+fn lower_annotation(ty: &Option<Spanned<haira_ast::Type>>) -> Type {
+    match ty {
+        Some(ty) => lower_type(&ty.node),
+        None => Type::Unknown(TypeVar::fresh()),
+    }
+}
+
+impl HirBody {
+    /// A body with no expressions.
+    pub fn empty() -> Self {
+        Self {
+            exprs: Arena::new(),
+            root: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The kind of the root block of function `name`'s body.
+    fn body_root<'a>(module: &'a HirModule, name: &str) -> (&'a HirBody, &'a HirExprKind) {
+        let (_, func) = module
+            .functions
+            .iter()
+            .find(|(_, func)| func.name == name)
+            .unwrap();
+        (&func.body, &func.body.exprs[func.body.root.unwrap()].kind)
+    }
+
+    #[test]
+    fn test_block_value_is_its_trailing_expression() {
+        let ast = haira_parser::parse(
+            "scale(x) {\n    y = x + 1\n    y * 2\n}\n\nlog(x) {\n    print(x)\n    z = x\n}\n\npick(x) {\n    if x > 0 {\n        1\n    } else {\n        2\n    }\n}\n",
+        )
+        .ast;
+        let module = lower(&ast);
+
+        let (body, root) = body_root(&module, "scale");
+        let HirExprKind::Block { statements, value } = root else {
+            panic!("expected a block");
+        };
+        assert_eq!(statements.len(), 1);
+        assert!(matches!(
+            &body.exprs[statements[0]].kind,
+            HirExprKind::Let { name, .. } if name == "y"
+        ));
+        assert!(matches!(
+            body.exprs[value.unwrap()].kind,
+            HirExprKind::Binary {
+                op: BinaryOp::Mul,
+                ..
+            }
+        ));
+
+        // A block ending in an assignment has no value
+        let (_, root) = body_root(&module, "log");
+        let HirExprKind::Block { statements, value } = root else {
+            panic!("expected a block");
+        };
+        assert_eq!(statements.len(), 2);
+        assert!(value.is_none());
+
+        // An `if` with an `else` is the value, each branch a valued block
+        let (body, root) = body_root(&module, "pick");
+        let HirExprKind::Block { value, .. } = root else {
+            panic!("expected a block");
+        };
+        let HirExprKind::If {
+            then_branch,
+            else_branch,
+            ..
+        } = &body.exprs[value.unwrap()].kind
+        else {
+            panic!("expected an if");
+        };
+        for branch in [*then_branch, else_branch.unwrap()] {
+            assert!(matches!(
+                body.exprs[branch].kind,
+                HirExprKind::Block { value: Some(_), .. }
+            ));
+        }
+    }
+}
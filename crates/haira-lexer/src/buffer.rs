@@ -0,0 +1,196 @@
+//! Incrementally re-lexed token buffer.
+
+use crate::error::LexError;
+use crate::lexer::Lexer;
+use crate::token::Token;
+use std::ops::Range;
+
+/// Tokens re-lexed before an edit, since a token's extent can depend on the
+/// text after it (`1` followed by `.5`).
+const RESYNC_WINDOW: usize = 2;
+
+/// The lexed tokens and errors of a document, kept up to date across edits
+/// by re-lexing only around the edited region.
+///
+/// After every edit the buffer holds exactly what a full lex of the new
+/// source would produce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenBuffer {
+    entries: Vec<Result<Token, LexError>>,
+}
+
+impl TokenBuffer {
+    /// Lex the whole of `source`.
+    pub fn new(source: &str) -> Self {
+        Self {
+            entries: Lexer::new(source).collect(),
+        }
+    }
+
+    /// The tokens and errors, in source order.
+    pub fn entries(&self) -> &[Result<Token, LexError>] {
+        &self.entries
+    }
+
+    /// The tokens, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = &Token> {
+        self.entries.iter().filter_map(|entry| entry.as_ref().ok())
+    }
+
+    /// The lexer errors, in source order.
+    pub fn errors(&self) -> impl Iterator<Item = &LexError> {
+        self.entries.iter().filter_map(|entry| entry.as_ref().err())
+    }
+
+    /// Update the buffer for an edit that replaced the bytes `edited` of the
+    /// previous source with `inserted_len` bytes, `source` being the new text.
+    ///
+    /// Lexing restarts a few tokens before the edit and stops as soon as a
+    /// token after it starts where a token of the previous source did; every
+    /// later entry is kept, shifted by the change in length.
+    pub fn edit(&mut self, source: &str, edited: Range<usize>, inserted_len: usize) {
+        let inserted_end = edited.start + inserted_len;
+
+        // Restart at the start of a token well before the edit
+        let first_touched = self
+            .entries
+            .iter()
+            .position(|entry| entry_span(entry).end >= edited.start)
+            .unwrap_or(self.entries.len());
+        let (restart_index, restart) = self.entries[..first_touched]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| Some((i, entry.as_ref().ok()?.span.start)))
+            .rev()
+            .nth(RESYNC_WINDOW - 1)
+            .unwrap_or((0, 0));
+
+        let mut relexed = Vec::new();
+        let mut old = first_touched;
+        for entry in Lexer::new(&source[restart..]) {
+            let entry = offset_entry(entry, |pos| pos + restart);
+            if let Ok(token) = &entry {
+                if token.span.start >= inserted_end {
+                    // Past the edit, the old entries resume once a token
+                    // starts where one did before
+                    let old_start = token.span.start - inserted_end + edited.end;
+                    while self
+                        .entries
+                        .get(old)
+                        .is_some_and(|entry| entry_span(entry).start < old_start)
+                    {
+                        old += 1;
+                    }
+                    if let Some(Ok(resumed)) = self.entries.get(old) {
+                        if resumed.span.start == old_start {
+                            let rest: Vec<_> = self
+                                .entries
+                                .drain(old..)
+                                .map(|entry| {
+                                    offset_entry(entry, |pos| pos - edited.end + inserted_end)
+                                })
+                                .collect();
+                            self.entries.truncate(restart_index);
+                            self.entries.extend(relexed);
+                            self.entries.extend(rest);
+                            return;
+                        }
+                    }
+                }
+            }
+            relexed.push(entry);
+        }
+
+        // Nothing after the edit lined up, so the re-lex ran to the end
+        self.entries.truncate(restart_index);
+        self.entries.extend(relexed);
+    }
+}
+
+/// The span of a token or error.
+fn entry_span(entry: &Result<Token, LexError>) -> Range<usize> {
+    match entry {
+        Ok(token) => token.span.clone(),
+        Err(err) => err.span(),
+    }
+}
+
+/// Move the span of a token or error by mapping its positions.
+fn offset_entry(
+    entry: Result<Token, LexError>,
+    map: impl Fn(usize) -> usize,
+) -> Result<Token, LexError> {
+    match entry {
+        Ok(token) => Ok(Token::new(
+            token.kind,
+            map(token.span.start)..map(token.span.end),
+        )),
+        Err(err) => {
+            let span = err.span();
+            Err(err.with_span(map(span.start)..map(span.end)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply `edited` → `text` to `source` both incrementally and from
+    /// scratch, and check the two agree.
+    fn check_edit(source: &str, edited: Range<usize>, text: &str) {
+        let mut buffer = TokenBuffer::new(source);
+        let mut new_source = source.to_string();
+        new_source.replace_range(edited.clone(), text);
+        buffer.edit(&new_source, edited, text.len());
+        assert_eq!(buffer, TokenBuffer::new(&new_source), "{:?}", new_source);
+    }
+
+    #[test]
+    fn test_edit_one_token_in_the_middle() {
+        let source = "x = 1\ny = foo(2, 3)\nz = \"done\"\n";
+        let start = source.find("foo").unwrap();
+        check_edit(source, start..start + 3, "barbaz");
+        check_edit(source, start..start + 3, "f");
+        check_edit(source, start..start, "1.5 + ");
+    }
+
+    #[test]
+    fn test_edit_that_merges_or_splits_tokens() {
+        let source = "a = 1 .5\nb = x y\n";
+        // `1 .5` becomes the float `1.5`
+        check_edit(source, 5..6, "");
+        // `x y` becomes one identifier, or gains an operator
+        check_edit(source, 14..15, "");
+        check_edit(source, 14..14, " + ");
+    }
+
+    #[test]
+    fn test_every_single_character_edit() {
+        let source = "f(a) {\n    /* note */ a + 1.5 // done\n}\ns = \"x{y}\"\n";
+        for start in 0..source.len() {
+            check_edit(source, start..start + 1, "");
+            for text in ["\"", "/", "*", " ", "9", "."] {
+                check_edit(source, start..start, text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_edit_that_reaches_the_end() {
+        let source = "a = 1\nb = 2\nc = 3\n";
+        // An unterminated string swallows the rest of the line
+        check_edit(source, 10..10, "\"");
+        // So does a line comment
+        check_edit(source, 6..6, "// ");
+        check_edit(source, 0..source.len(), "");
+    }
+
+    #[test]
+    fn test_edit_with_errors() {
+        let source = "a = $\nb = \"x\\q\"\nc = 3\n";
+        check_edit(source, 4..5, "2");
+        check_edit(source, 0..0, "@ ");
+        check_edit(source, 18..19, "4");
+    }
+}
@@ -19,6 +19,9 @@ pub enum LexError {
 
     #[error("invalid escape sequence")]
     InvalidEscape { span: std::ops::Range<usize> },
+
+    #[error("character literal must contain exactly one character")]
+    InvalidChar { span: std::ops::Range<usize> },
 }
 
 impl LexError {
@@ -30,6 +33,19 @@ impl LexError {
             LexError::UnterminatedComment { span } => span.clone(),
             LexError::InvalidNumber { span } => span.clone(),
             LexError::InvalidEscape { span } => span.clone(),
+            LexError::InvalidChar { span } => span.clone(),
+        }
+    }
+
+    /// The same error at another span.
+    pub(crate) fn with_span(self, span: std::ops::Range<usize>) -> Self {
+        match self {
+            LexError::UnexpectedChar { .. } => LexError::UnexpectedChar { span },
+            LexError::UnterminatedString { .. } => LexError::UnterminatedString { span },
+            LexError::UnterminatedComment { .. } => LexError::UnterminatedComment { span },
+            LexError::InvalidNumber { .. } => LexError::InvalidNumber { span },
+            LexError::InvalidEscape { .. } => LexError::InvalidEscape { span },
+            LexError::InvalidChar { .. } => LexError::InvalidChar { span },
         }
     }
 }
@@ -0,0 +1,96 @@
+//! Escape sequence decoding for string literals.
+
+use std::ops::Range;
+
+/// Decode the escape sequences in the body of a string literal.
+///
+/// Supports `\n`, `\t`, `\r`, `\\`, `\"`, `\'`, `\0`, `\{`, `\}` and `\u{XXXX}`.
+/// On an unknown or malformed escape, returns the byte range of the escape
+/// within `s`.
+pub fn unescape(s: &str) -> Result<String, Range<usize>> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let decoded = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, '\\')) => '\\',
+            Some((_, '"')) => '"',
+            Some((_, '\'')) => '\'',
+            Some((_, '0')) => '\0',
+            Some((_, '{')) => '{',
+            Some((_, '}')) => '}',
+            Some((_, 'u')) => {
+                // `\u{` hex digits `}`
+                let mut end = start + 2;
+                let mut code = None;
+                match chars.next() {
+                    Some((_, '{')) => {
+                        end += 1;
+                        let mut hex = String::new();
+                        for (i, c) in chars.by_ref() {
+                            end = i + c.len_utf8();
+                            if c == '}' {
+                                code = u32::from_str_radix(&hex, 16).ok();
+                                break;
+                            }
+                            hex.push(c);
+                        }
+                        if hex.is_empty() || hex.len() > 6 {
+                            code = None;
+                        }
+                    }
+                    Some((i, c)) => end = i + c.len_utf8(),
+                    None => {}
+                }
+                match code.and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => return Err(start..end),
+                }
+            }
+            Some((i, other)) => return Err(start..i + other.len_utf8()),
+            None => return Err(start..s.len()),
+        };
+        result.push(decoded);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_escapes() {
+        assert_eq!(
+            unescape(r#"a\nb\tc\rd\\e\"f\'g\0h\{i\}"#).unwrap(),
+            "a\nb\tc\rd\\e\"f'g\0h{i}"
+        );
+    }
+
+    #[test]
+    fn test_unicode_escapes() {
+        assert_eq!(unescape(r"\u{41}").unwrap(), "A");
+        assert_eq!(unescape(r"\u{e9}t\u{E9}").unwrap(), "été");
+        assert_eq!(unescape(r"\u{1F600}").unwrap(), "😀");
+    }
+
+    #[test]
+    fn test_invalid_escapes() {
+        assert_eq!(unescape(r"ab\q"), Err(2..4));
+        assert_eq!(unescape(r"\u{}"), Err(0..4));
+        assert_eq!(unescape(r"\u{D800}"), Err(0..8));
+        assert_eq!(unescape(r"\u{1234567}"), Err(0..11));
+        assert_eq!(unescape(r"\u41"), Err(0..3));
+        assert_eq!(unescape(r"x\u{41"), Err(1..6));
+        assert_eq!(unescape("\\"), Err(0..1));
+    }
+}
@@ -1,6 +1,7 @@
 //! High-level lexer interface.
 
 use crate::error::LexError;
+use crate::escape::unescape;
 use crate::token::{Token, TokenKind};
 use logos::Logos;
 
@@ -12,6 +13,8 @@ pub struct Lexer<'source> {
     peeked: Option<Result<Token, LexError>>,
     /// Track if we've emitted EOF
     done: bool,
+    /// Emit comment tokens instead of skipping them
+    keep_comments: bool,
 }
 
 impl<'source> Lexer<'source> {
@@ -21,9 +24,17 @@ impl<'source> Lexer<'source> {
             inner: TokenKind::lexer(source),
             peeked: None,
             done: false,
+            keep_comments: false,
         }
     }
 
+    /// Emit `LineComment` and `BlockComment` tokens instead of skipping
+    /// them, e.g. to collect doc comments.
+    pub fn with_comments(mut self) -> Self {
+        self.keep_comments = true;
+        self
+    }
+
     /// Peek at the next token without consuming it.
     pub fn peek(&mut self) -> Option<&Result<Token, LexError>> {
         if self.peeked.is_none() {
@@ -49,7 +60,7 @@ impl<'source> Lexer<'source> {
                     let span = self.inner.span();
 
                     // Skip trivia (comments)
-                    if kind.is_trivia() {
+                    if kind.is_trivia() && !self.keep_comments {
                         continue;
                     }
 
@@ -57,6 +68,41 @@ impl<'source> Lexer<'source> {
                 }
                 Some(Err(())) => {
                     let span = self.inner.span();
+                    let slice = self.inner.slice();
+                    // Only number callbacks reject a slice starting with a digit
+                    if slice.starts_with(|c: char| c.is_ascii_digit()) {
+                        return Some(Err(LexError::InvalidNumber { span }));
+                    }
+                    // Only the block comment callback rejects a slice starting with `/*`
+                    if slice.starts_with("/*") {
+                        return Some(Err(LexError::UnterminatedComment { span }));
+                    }
+                    // Only the raw string callback rejects a slice starting with `r`
+                    if slice.starts_with("r\"") || slice.starts_with("r#") {
+                        return Some(Err(LexError::UnterminatedString { span }));
+                    }
+                    // A complete string literal is only rejected for a bad escape
+                    if slice.len() >= 2 && slice.starts_with('"') && slice.ends_with('"') {
+                        if let Err(escape) = unescape(&slice[1..slice.len() - 1]) {
+                            let start = span.start + 1;
+                            return Some(Err(LexError::InvalidEscape {
+                                span: start + escape.start..start + escape.end,
+                            }));
+                        }
+                    }
+                    if slice == "\"" {
+                        return Some(Err(LexError::UnterminatedString { span }));
+                    }
+                    // Likewise a complete char literal with a valid escape is empty or too long
+                    if slice.len() >= 2 && slice.starts_with('\'') && slice.ends_with('\'') {
+                        if let Err(escape) = unescape(&slice[1..slice.len() - 1]) {
+                            let start = span.start + 1;
+                            return Some(Err(LexError::InvalidEscape {
+                                span: start + escape.start..start + escape.end,
+                            }));
+                        }
+                        return Some(Err(LexError::InvalidChar { span }));
+                    }
                     return Some(Err(LexError::UnexpectedChar { span }));
                 }
                 None => {
@@ -163,6 +209,64 @@ mod tests {
         assert_eq!(tokens[4].kind, TokenKind::Int(2));
     }
 
+    #[test]
+    fn test_comments_kept_on_request() {
+        let source = "/// doc\nx = 1 // note\n";
+        let comments: Vec<_> = Lexer::new(source)
+            .with_comments()
+            .filter_map(|r| r.ok())
+            .filter(|t| t.kind == TokenKind::LineComment)
+            .map(|t| &source[t.span])
+            .collect();
+        assert_eq!(comments, ["/// doc", "// note"]);
+    }
+
+    /// The block comments of `source`, as text.
+    fn block_comments(source: &str) -> Vec<&str> {
+        Lexer::new(source)
+            .with_comments()
+            .filter_map(|r| r.ok())
+            .filter(|t| t.kind == TokenKind::BlockComment)
+            .map(|t| &source[t.span])
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_block_comments() {
+        assert_eq!(
+            block_comments("x /* a /* b */ c */ y"),
+            ["/* a /* b */ c */"]
+        );
+        assert_eq!(
+            block_comments("/* a /* b /* c */ d */ e */\n/**/"),
+            ["/* a /* b /* c */ d */ e */", "/**/"]
+        );
+
+        let tokens: Vec<_> = Lexer::new("x /* a /* b */ c */ y")
+            .map(|r| r.unwrap().kind)
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                TokenKind::Ident(SmolStr::from("x")),
+                TokenKind::Ident(SmolStr::from("y")),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_block_comment() {
+        let source = "x = 1\n/* a /* b */ c\ny = 2";
+        let errors: Vec<_> = Lexer::new(source).filter_map(|r| r.err()).collect();
+        assert_eq!(
+            errors,
+            [LexError::UnterminatedComment {
+                span: 6..source.len()
+            }]
+        );
+    }
+
     #[test]
     fn test_comments_skipped() {
         let source = r#"
@@ -200,6 +304,126 @@ mod tests {
         assert_eq!(tokens[4].kind, TokenKind::Question);
     }
 
+    #[test]
+    fn test_integer_overflow() {
+        let source = "x = 9223372036854775808";
+        let errors: Vec<_> = Lexer::new(source).filter_map(|r| r.err()).collect();
+
+        assert_eq!(errors, vec![LexError::InvalidNumber { span: 4..23 }]);
+    }
+
+    #[test]
+    fn test_malformed_integers() {
+        let source = "0x + 0b + 0o";
+        let results: Vec<_> = Lexer::new(source).collect();
+
+        assert_eq!(results[0], Err(LexError::InvalidNumber { span: 0..2 }));
+        assert_eq!(results[2], Err(LexError::InvalidNumber { span: 5..7 }));
+        assert_eq!(results[4], Err(LexError::InvalidNumber { span: 10..12 }));
+    }
+
+    #[test]
+    fn test_malformed_floats() {
+        let source = "1e + 2.5e- + 1.2.3";
+        let results: Vec<_> = Lexer::new(source).collect();
+
+        assert_eq!(results[0], Err(LexError::InvalidNumber { span: 0..2 }));
+        assert_eq!(results[2], Err(LexError::InvalidNumber { span: 5..10 }));
+        assert_eq!(results[4], Err(LexError::InvalidNumber { span: 13..18 }));
+    }
+
+    #[test]
+    fn test_integer_spans() {
+        let source = "0xFF 1_000 0b10";
+        let tokens: Vec<_> = Lexer::new(source).filter_map(|r| r.ok()).collect();
+
+        assert_eq!(tokens[0], Token::new(TokenKind::Int(255), 0..4));
+        assert_eq!(tokens[1], Token::new(TokenKind::Int(1000), 5..10));
+        assert_eq!(tokens[2], Token::new(TokenKind::Int(2), 11..15));
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let source = r#""tab\there" "quote \" and \\" "nul\0" "\u{1F600}""#;
+        let tokens: Vec<_> = Lexer::new(source).filter_map(|r| r.ok()).collect();
+
+        assert_eq!(
+            tokens[0].kind,
+            TokenKind::String(SmolStr::from("tab\there"))
+        );
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::String(SmolStr::from("quote \" and \\"))
+        );
+        assert_eq!(tokens[2].kind, TokenKind::String(SmolStr::from("nul\0")));
+        assert_eq!(tokens[3].kind, TokenKind::String(SmolStr::from("😀")));
+    }
+
+    #[test]
+    fn test_invalid_escape() {
+        let source = r#"x = "ab\q" + 1"#;
+        let results: Vec<_> = Lexer::new(source).collect();
+
+        assert_eq!(results[2], Err(LexError::InvalidEscape { span: 7..9 }));
+        assert_eq!(results[3].as_ref().unwrap().kind, TokenKind::Plus);
+    }
+
+    #[test]
+    fn test_interpolated_string_escapes() {
+        let source = r#""\t{name}\n" "{a} \q""#;
+        let results: Vec<_> = Lexer::new(source).collect();
+
+        // Interpolated strings keep their raw body for the parser
+        assert_eq!(
+            results[0],
+            Ok(Token::new(
+                TokenKind::InterpolatedString(SmolStr::from(r"\t{name}\n")),
+                0..12
+            ))
+        );
+        assert_eq!(results[1], Err(LexError::InvalidEscape { span: 18..20 }));
+    }
+
+    #[test]
+    fn test_unterminated_raw_string() {
+        let source = r##"x = r#"open" "##;
+        let results: Vec<_> = Lexer::new(source).collect();
+
+        assert_eq!(
+            results[2],
+            Err(LexError::UnterminatedString {
+                span: 4..source.len()
+            })
+        );
+        assert_eq!(results[3].as_ref().unwrap().kind, TokenKind::Eof);
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let source = r"'a' '\n' '\u{1F600}' '\''";
+        let tokens: Vec<_> = Lexer::new(source).filter_map(|r| r.ok()).collect();
+
+        assert_eq!(tokens[0], Token::new(TokenKind::Char('a'), 0..3));
+        assert_eq!(tokens[1], Token::new(TokenKind::Char('\n'), 4..8));
+        assert_eq!(tokens[2], Token::new(TokenKind::Char('😀'), 9..20));
+        assert_eq!(tokens[3], Token::new(TokenKind::Char('\''), 21..25));
+    }
+
+    #[test]
+    fn test_invalid_char_literals() {
+        let source = r"'' 'ab' '\q'";
+        let errors: Vec<_> = Lexer::new(source).filter_map(|r| r.err()).collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                LexError::InvalidChar { span: 0..2 },
+                LexError::InvalidChar { span: 3..7 },
+                LexError::InvalidEscape { span: 9..11 },
+            ]
+        );
+    }
+
     #[test]
     fn test_range_operators() {
         let source = "0..10 0..=10";
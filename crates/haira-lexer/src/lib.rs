@@ -17,17 +17,24 @@
 //! }
 //! ```
 
+mod buffer;
 mod error;
+mod escape;
 mod lexer;
 mod token;
 
+pub use buffer::TokenBuffer;
 pub use error::LexError;
+pub use escape::unescape;
 pub use lexer::Lexer;
 pub use token::{Token, TokenKind};
 
 /// Tokenize source code into a vector of tokens.
+///
+/// Comments are kept as `LineComment` and `BlockComment` tokens, for tools
+/// working with the source as written; see [`Token::is_trivia`].
 pub fn tokenize(source: &str) -> (Vec<Token>, Vec<LexError>) {
-    let lexer = Lexer::new(source);
+    let lexer = Lexer::new(source).with_comments();
     let mut tokens = Vec::new();
     let mut errors = Vec::new();
 
@@ -40,3 +47,38 @@ pub fn tokenize(source: &str) -> (Vec<Token>, Vec<LexError>) {
 
     (tokens, errors)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_keeps_comments() {
+        let source = "/// Doubles x\ndouble(x) /* inline */ {\n    x * 2 // twice\n}\n/* outer /* nested */ */";
+        let (tokens, errors) = tokenize(source);
+        assert!(errors.is_empty());
+
+        let comments: Vec<_> = tokens
+            .iter()
+            .filter(|t| matches!(t.kind, TokenKind::LineComment | TokenKind::BlockComment))
+            .map(|t| (t.kind.clone(), t.span.clone(), &source[t.span.clone()]))
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                (TokenKind::LineComment, 0..13, "/// Doubles x"),
+                (TokenKind::BlockComment, 24..36, "/* inline */"),
+                (TokenKind::LineComment, 49..57, "// twice"),
+                (TokenKind::BlockComment, 60..84, "/* outer /* nested */ */"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trivia() {
+        let (tokens, _) = tokenize("x = 1 // one\n/* two */");
+        let trivia: Vec<_> = tokens.iter().map(Token::is_trivia).collect();
+        // x, =, 1, comment, newline, comment, Eof
+        assert_eq!(trivia, [false, false, false, true, true, true, false]);
+    }
+}
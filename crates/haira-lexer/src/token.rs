@@ -1,10 +1,12 @@
 //! Token definitions for Haira.
 
+use crate::escape::unescape;
 use logos::Logos;
 use smol_str::SmolStr;
 
 /// A token with its kind and span.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token {
     pub kind: TokenKind,
     pub span: std::ops::Range<usize>,
@@ -14,10 +16,17 @@ impl Token {
     pub fn new(kind: TokenKind, span: std::ops::Range<usize>) -> Self {
         Self { kind, span }
     }
+
+    /// Check if this token is trivia: a newline or a comment. Spaces and
+    /// tabs never become tokens.
+    pub fn is_trivia(&self) -> bool {
+        self.kind == TokenKind::Newline || self.kind.is_trivia()
+    }
 }
 
 /// Token kinds for Haira.
 #[derive(Logos, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[logos(skip r"[ \t]+")] // Skip spaces and tabs
 pub enum TokenKind {
     // ========================================================================
@@ -77,6 +86,10 @@ pub enum TokenKind {
     Default,
     #[token("ai")]
     Ai,
+    #[token("import")]
+    Import,
+    #[token("const")]
+    Const,
 
     // ========================================================================
     // Operators
@@ -91,6 +104,14 @@ pub enum TokenKind {
     Slash,
     #[token("%")]
     Percent,
+    #[token("&")]
+    Amp,
+    #[token("^")]
+    Caret,
+    #[token("<<")]
+    Shl,
+    #[token(">>")]
+    Shr,
 
     #[token("==")]
     EqEq,
@@ -107,10 +128,32 @@ pub enum TokenKind {
 
     #[token("=")]
     Eq,
+    #[token("+=")]
+    PlusEq,
+    #[token("-=")]
+    MinusEq,
+    #[token("*=")]
+    StarEq,
+    #[token("/=")]
+    SlashEq,
+    #[token("%=")]
+    PercentEq,
+    #[token("&=")]
+    AmpEq,
+    #[token("|=")]
+    PipeEq,
+    #[token("^=")]
+    CaretEq,
+    #[token("<<=")]
+    ShlEq,
+    #[token(">>=")]
+    ShrEq,
     #[token("|")]
     Pipe,
     #[token("?")]
     Question,
+    #[token("??")]
+    QuestionQuestion,
     #[token("=>")]
     FatArrow,
     #[token("->")]
@@ -152,14 +195,26 @@ pub enum TokenKind {
     #[regex(r"0x[0-9a-fA-F][0-9a-fA-F_]*", |lex| parse_hex(lex.slice()))]
     #[regex(r"0b[01][01_]*", |lex| parse_binary(lex.slice()))]
     #[regex(r"0o[0-7][0-7_]*", |lex| parse_octal(lex.slice()))]
+    #[regex(r"0[xbo]", |_| malformed_int())]
     Int(i64),
 
-    /// Float literal
-    #[regex(r"[0-9][0-9_]*\.[0-9][0-9_]*", |lex| parse_float(lex.slice()))]
+    /// Float literal, with an optional exponent: `3.14`, `1e10`, `2.5e-3`
+    ///
+    /// A leading dot (`.5`) is not a float, so `.0` stays a field access.
+    #[regex(r"[0-9][0-9_]*\.[0-9][0-9_]*([eE][+-]?[0-9][0-9_]*)?", |lex| parse_float(lex.slice()))]
+    #[regex(r"[0-9][0-9_]*[eE][+-]?[0-9][0-9_]*", |lex| parse_float(lex.slice()))]
+    #[regex(r"[0-9][0-9_]*(\.[0-9][0-9_]*)?[eE][+-]?", |_| malformed_float())]
+    #[regex(r"[0-9][0-9_]*\.[0-9][0-9_]*(\.[0-9][0-9_]*)+", |_| malformed_float())]
     Float(f64),
 
-    /// String literal (simple strings without interpolation)
-    #[regex(r#""([^"\\{]|\\.)*""#, |lex| parse_string(lex.slice()))]
+    /// Character literal: `'a'`, `'\n'`, `'\u{1F600}'`
+    #[regex(r"'([^'\\\n]|\\u\{[0-9a-fA-F]*\}|\\.)*'", |lex| parse_char(lex.slice()))]
+    Char(char),
+
+    /// String literal (simple strings without interpolation), or a raw
+    /// string `r"..."` / `r#"..."#` taken verbatim
+    #[regex(r#""([^"\\{]|\\u\{[0-9a-fA-F]*\}|\\.)*""#, |lex| parse_string(lex.slice()))]
+    #[regex(r##"r#*""##, lex_raw_string)]
     String(SmolStr),
 
     /// Interpolated string literal (contains `{...}` expressions)
@@ -182,8 +237,8 @@ pub enum TokenKind {
     #[regex(r"//[^\n]*")]
     LineComment,
 
-    /// Multi-line comment (handled specially)
-    #[token("/*", |lex| skip_block_comment(lex))]
+    /// Multi-line comment, which may nest (handled specially)
+    #[token("/*", |lex| lex_block_comment(lex))]
     BlockComment,
 
     /// End of file
@@ -225,6 +280,8 @@ impl TokenKind {
                 | TokenKind::From
                 | TokenKind::Default
                 | TokenKind::Ai
+                | TokenKind::Import
+                | TokenKind::Const
         )
     }
 
@@ -234,6 +291,7 @@ impl TokenKind {
             self,
             TokenKind::Int(_)
                 | TokenKind::Float(_)
+                | TokenKind::Char(_)
                 | TokenKind::String(_)
                 | TokenKind::InterpolatedString(_)
                 | TokenKind::True
@@ -272,41 +330,37 @@ fn parse_octal(s: &str) -> Option<i64> {
     i64::from_str_radix(&s, 8).ok()
 }
 
+/// A radix prefix with no digits (`0x`, `0b`, `0o`); always an error.
+fn malformed_int() -> Option<i64> {
+    None
+}
+
 fn parse_float(s: &str) -> Option<f64> {
     let s = s.replace('_', "");
     s.parse().ok()
 }
 
-fn parse_string(s: &str) -> Option<SmolStr> {
-    // Remove quotes
-    let s = s.strip_prefix('"')?.strip_suffix('"')?;
-
-    // Handle escape sequences
-    let mut result = String::with_capacity(s.len());
-    let mut chars = s.chars().peekable();
+/// An exponent with no digits (`1e`, `2.5e-`) or a second decimal point
+/// (`1.2.3`); always an error.
+fn malformed_float() -> Option<f64> {
+    None
+}
 
-    while let Some(c) = chars.next() {
-        if c == '\\' {
-            match chars.next() {
-                Some('n') => result.push('\n'),
-                Some('t') => result.push('\t'),
-                Some('r') => result.push('\r'),
-                Some('\\') => result.push('\\'),
-                Some('"') => result.push('"'),
-                Some('{') => result.push('{'),
-                Some('}') => result.push('}'),
-                Some(c) => {
-                    result.push('\\');
-                    result.push(c);
-                }
-                None => result.push('\\'),
-            }
-        } else {
-            result.push(c);
-        }
+/// Parse a character literal; empty and multi-character literals are rejected.
+fn parse_char(s: &str) -> Option<char> {
+    let s = s.strip_prefix('\'')?.strip_suffix('\'')?;
+    let decoded = unescape(s).ok()?;
+    let mut chars = decoded.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
     }
+}
 
-    Some(SmolStr::from(result))
+fn parse_string(s: &str) -> Option<SmolStr> {
+    // Remove quotes
+    let s = s.strip_prefix('"')?.strip_suffix('"')?;
+    unescape(s).ok().map(SmolStr::from)
 }
 
 /// Parse an interpolated string, keeping the raw content for the parser to process.
@@ -315,12 +369,17 @@ fn parse_interpolated_string(s: &str) -> Option<SmolStr> {
     // Check if string contains unescaped `{`
     let inner = s.strip_prefix('"')?.strip_suffix('"')?;
 
+    // Escapes are decoded by the parser, but rejected here like plain strings
+    unescape(inner).ok()?;
+
     let mut has_interpolation = false;
     let mut chars = inner.chars().peekable();
     while let Some(c) = chars.next() {
         if c == '\\' {
-            // Skip escaped character
-            chars.next();
+            // Skip escaped character, including the braces of `\u{...}`
+            if chars.next() == Some('u') && chars.peek() == Some(&'{') {
+                chars.by_ref().find(|&c| c == '}');
+            }
         } else if c == '{' {
             has_interpolation = true;
             break;
@@ -336,7 +395,30 @@ fn parse_interpolated_string(s: &str) -> Option<SmolStr> {
     }
 }
 
-fn skip_block_comment(lex: &mut logos::Lexer<TokenKind>) -> logos::Skip {
+/// Lex the rest of a raw string after its opening `r#..."`.
+///
+/// The string ends at the first `"` followed by as many `#` as opened it;
+/// nothing in between is unescaped or interpolated. An unterminated raw
+/// string consumes the rest of the source and is an error.
+fn lex_raw_string(lex: &mut logos::Lexer<TokenKind>) -> Option<SmolStr> {
+    let hashes = lex.slice().len() - 2;
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let remainder = lex.remainder();
+    match remainder.find(&closing) {
+        Some(end) => {
+            lex.bump(end + closing.len());
+            Some(SmolStr::from(&remainder[..end]))
+        }
+        None => {
+            lex.bump(remainder.len());
+            None
+        }
+    }
+}
+
+/// Extend a `/*` token to the `*/` balancing it, counting nested comments.
+/// An unbalanced comment runs to the end of the source and is an error.
+fn lex_block_comment(lex: &mut logos::Lexer<TokenKind>) -> bool {
     let remainder = lex.remainder();
     let mut depth = 1;
     let mut chars = remainder.char_indices();
@@ -349,7 +431,7 @@ fn skip_block_comment(lex: &mut logos::Lexer<TokenKind>) -> logos::Skip {
                     depth -= 1;
                     if depth == 0 {
                         lex.bump(i + 2);
-                        return logos::Skip;
+                        return true;
                     }
                 }
             }
@@ -365,7 +447,7 @@ fn skip_block_comment(lex: &mut logos::Lexer<TokenKind>) -> logos::Skip {
 
     // Unclosed comment - bump to end
     lex.bump(remainder.len());
-    logos::Skip
+    false
 }
 
 #[cfg(test)]
@@ -393,6 +475,31 @@ mod tests {
         assert_eq!(lex.next(), Some(Ok(TokenKind::Int(493))));
     }
 
+    #[test]
+    fn test_integer_bases() {
+        let mut lex = TokenKind::lexer("0xff 0XFF 0xDEAD_BEEF 0b1111_0000 0o777 0");
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(255))));
+        // Prefixes are lowercase only: `0XFF` is `0` followed by `XFF`
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(0))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Ident(SmolStr::from("XFF")))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(0xDEAD_BEEF))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(0b1111_0000))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(0o777))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(0))));
+    }
+
+    #[test]
+    fn test_integer_limits() {
+        let mut lex = TokenKind::lexer("9_223_372_036_854_775_807 0x7FFF_FFFF_FFFF_FFFF");
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(i64::MAX))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(i64::MAX))));
+
+        let mut lex = TokenKind::lexer("9223372036854775808");
+        assert_eq!(lex.next(), Some(Err(())));
+        let mut lex = TokenKind::lexer("0x1_0000_0000_0000_0000");
+        assert_eq!(lex.next(), Some(Err(())));
+    }
+
     #[test]
     fn test_floats() {
         let mut lex = TokenKind::lexer("3.15 1_000.5");
@@ -400,6 +507,23 @@ mod tests {
         assert_eq!(lex.next(), Some(Ok(TokenKind::Float(1000.5))));
     }
 
+    #[test]
+    fn test_scientific_floats() {
+        let mut lex = TokenKind::lexer("1e10 2.5e-3 6.02E+23 1_000e1_0 3E0");
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Float(1e10))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Float(2.5e-3))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Float(6.02e23))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Float(1000e10))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Float(3.0))));
+    }
+
+    #[test]
+    fn test_leading_dot_is_not_a_float() {
+        let mut lex = TokenKind::lexer(".5");
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Dot)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Int(5))));
+    }
+
     #[test]
     fn test_strings() {
         let mut lex = TokenKind::lexer(r#""hello" "world\n""#);
@@ -413,6 +537,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_raw_strings() {
+        let mut lex = TokenKind::lexer(r###"r"\d+" r#"a"b"# r##"say "#hi"#"## raw"###);
+        assert_eq!(
+            lex.next(),
+            Some(Ok(TokenKind::String(SmolStr::from(r"\d+"))))
+        );
+        assert_eq!(lex.span(), 0..6);
+        assert_eq!(
+            lex.next(),
+            Some(Ok(TokenKind::String(SmolStr::from(r#"a"b"#))))
+        );
+        assert_eq!(lex.span(), 7..15);
+        assert_eq!(
+            lex.next(),
+            Some(Ok(TokenKind::String(SmolStr::from(r##"say "#hi"#"##))))
+        );
+        // `r` on its own is still an identifier
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Ident(SmolStr::from("raw")))));
+    }
+
     #[test]
     fn test_identifiers() {
         let mut lex = TokenKind::lexer("foo bar_baz _private");
@@ -429,7 +574,7 @@ mod tests {
 
     #[test]
     fn test_operators() {
-        let mut lex = TokenKind::lexer("+ - * / == != <= >= = | ? => -> .. ..=");
+        let mut lex = TokenKind::lexer("+ - * / == != <= >= = | ? => -> .. ..= & ^ << >>");
         assert_eq!(lex.next(), Some(Ok(TokenKind::Plus)));
         assert_eq!(lex.next(), Some(Ok(TokenKind::Minus)));
         assert_eq!(lex.next(), Some(Ok(TokenKind::Star)));
@@ -445,5 +590,33 @@ mod tests {
         assert_eq!(lex.next(), Some(Ok(TokenKind::Arrow)));
         assert_eq!(lex.next(), Some(Ok(TokenKind::DotDot)));
         assert_eq!(lex.next(), Some(Ok(TokenKind::DotDotEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Amp)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Caret)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Shl)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Shr)));
+    }
+
+    #[test]
+    fn test_compound_assignment_operators() {
+        let mut lex = TokenKind::lexer("+= -= *= /= %= &= |= ^= <<= >>=");
+        assert_eq!(lex.next(), Some(Ok(TokenKind::PlusEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::MinusEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::StarEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::SlashEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::PercentEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::AmpEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::PipeEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::CaretEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::ShlEq)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::ShrEq)));
+    }
+
+    #[test]
+    fn test_default_operator() {
+        let mut lex = TokenKind::lexer("x ?? y?");
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Ident(SmolStr::from("x")))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::QuestionQuestion)));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Ident(SmolStr::from("y")))));
+        assert_eq!(lex.next(), Some(Ok(TokenKind::Question)));
     }
 }
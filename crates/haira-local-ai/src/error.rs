@@ -1,5 +1,6 @@
 //! Error types for local AI operations.
 
+use std::time::Duration;
 use thiserror::Error;
 
 /// Errors that can occur during local AI operations.
@@ -41,6 +42,10 @@ pub enum LocalAIError {
     #[error("Model checksum mismatch. Expected: {expected}, got: {actual}")]
     ChecksumMismatch { expected: String, actual: String },
 
+    /// Model file has the wrong size (usually a truncated download).
+    #[error("Model size mismatch. Expected: {expected} bytes, got: {actual} bytes")]
+    SizeMismatch { expected: u64, actual: u64 },
+
     /// llama-server binary not found.
     #[error("llama-server binary not found at {0}")]
     ServerBinaryNotFound(String),
@@ -53,7 +58,7 @@ pub enum LocalAIError {
     #[error("Failed to create data directory: {0}")]
     DataDirCreationFailed(String),
 
-    /// Timeout waiting for server to start.
-    #[error("Timeout waiting for server to become ready")]
-    ServerStartTimeout,
+    /// Server did not pass its health check in time.
+    #[error("llama-server on port {port} not ready after {timeout:?}")]
+    ServerNotReady { port: u16, timeout: Duration },
 }
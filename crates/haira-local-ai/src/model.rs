@@ -4,8 +4,8 @@ use futures_util::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
 use sha2::{Digest, Sha256};
 use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
 use crate::error::LocalAIError;
@@ -52,7 +52,10 @@ impl ModelManager {
                 "https://github.com/haira-lang/haira/releases/download/models-v1/{}",
                 DEFAULT_MODEL_FILENAME
             ),
-            sha256: None, // Add checksum when model is released
+            // Not published until the model is released. Until then a
+            // download is checked against its Content-Length, and later
+            // loads against the record written after that download.
+            sha256: None,
             size_bytes: None,
         }
     }
@@ -123,7 +126,8 @@ impl ModelManager {
             )));
         }
 
-        let total_size = response.content_length().or(model.size_bytes);
+        let content_length = response.content_length();
+        let total_size = content_length.or(model.size_bytes);
 
         // Create progress bar
         let pb = if let Some(size) = total_size {
@@ -160,20 +164,21 @@ impl ModelManager {
         }
 
         pb.finish_with_message("Download complete");
-
-        // Verify checksum if provided
-        if let Some(expected) = &model.sha256 {
-            let actual = hex::encode(hasher.finalize());
-            if actual != *expected {
-                // Remove corrupted file
-                let _ = fs::remove_file(&dest_path);
-                return Err(LocalAIError::ChecksumMismatch {
-                    expected: expected.clone(),
-                    actual,
-                });
-            }
-            debug!("Checksum verified: {}", actual);
+        drop(file);
+
+        // Verify size and checksum, falling back to the advertised length
+        // so a truncated download is caught even without a registry size
+        let sha256 = hex::encode(hasher.finalize());
+        let expected = ModelInfo {
+            size_bytes: model.size_bytes.or(content_length),
+            ..model.clone()
+        };
+        if let Err(e) = check_integrity(&expected, downloaded, || Ok(sha256.clone())) {
+            // Remove corrupted file
+            let _ = fs::remove_file(&dest_path);
+            return Err(e);
         }
+        write_record(&dest_path, &sha256, downloaded)?;
 
         info!("Model '{}' downloaded successfully", model.name);
         Ok(dest_path)
@@ -185,6 +190,18 @@ impl ModelManager {
         self.download(&model).await
     }
 
+    /// Verify an installed model against its expected size and checksum.
+    ///
+    /// Returns the model path, so a previously corrupted file is caught
+    /// before it is handed to llama-server.
+    pub fn verify(&self, model: &ModelInfo) -> Result<PathBuf, LocalAIError> {
+        let path = self
+            .get_model_path(&model.filename)
+            .ok_or_else(|| LocalAIError::ModelNotFound(model.name.clone()))?;
+        verify_file(&path, model)?;
+        Ok(path)
+    }
+
     /// Install a model from a local file path.
     pub fn install_from_path(&self, source: &PathBuf) -> Result<PathBuf, LocalAIError> {
         ensure_dirs()?;
@@ -203,6 +220,8 @@ impl ModelManager {
 
         info!("Installing model from {:?} to {:?}", source, dest_path);
         fs::copy(source, &dest_path)?;
+        // A record from an earlier download describes a different file
+        let _ = fs::remove_file(record_path(&dest_path));
 
         Ok(dest_path)
     }
@@ -214,6 +233,10 @@ impl ModelManager {
             fs::remove_file(&path)?;
             info!("Removed model: {}", filename);
         }
+        let record = record_path(&path);
+        if record.exists() {
+            fs::remove_file(&record)?;
+        }
         Ok(())
     }
 }
@@ -224,6 +247,87 @@ impl Default for ModelManager {
     }
 }
 
+/// Verify a model file on disk against the expected size and checksum.
+///
+/// Whatever the registry entry leaves out is taken from the record written
+/// when the file was downloaded.
+pub(crate) fn verify_file(path: &Path, model: &ModelInfo) -> Result<(), LocalAIError> {
+    let size = fs::metadata(path)?.len();
+
+    let mut expected = model.clone();
+    if let Some((sha256, size_bytes)) = read_record(path) {
+        expected.sha256.get_or_insert(sha256);
+        expected.size_bytes.get_or_insert(size_bytes);
+    }
+
+    check_integrity(&expected, size, || {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    })
+}
+
+/// Path of the `<sha256> <size>` record kept next to a downloaded model.
+fn record_path(path: &Path) -> PathBuf {
+    let mut record = path.as_os_str().to_owned();
+    record.push(".sha256");
+    PathBuf::from(record)
+}
+
+/// Record a verified download's checksum and size for later loads.
+fn write_record(path: &Path, sha256: &str, size: u64) -> Result<(), LocalAIError> {
+    fs::write(record_path(path), format!("{} {}\n", sha256, size))?;
+    Ok(())
+}
+
+/// Read a download record, ignoring one that is missing or malformed.
+fn read_record(path: &Path) -> Option<(String, u64)> {
+    let record = fs::read_to_string(record_path(path)).ok()?;
+    let mut parts = record.split_whitespace();
+    let sha256 = parts.next()?.to_string();
+    let size = parts.next()?.parse().ok()?;
+    Some((sha256, size))
+}
+
+/// Compare a file's size and checksum with the registry entry.
+///
+/// The hash is computed lazily since it means reading the whole model.
+fn check_integrity(
+    model: &ModelInfo,
+    size: u64,
+    sha256: impl FnOnce() -> Result<String, LocalAIError>,
+) -> Result<(), LocalAIError> {
+    if let Some(expected) = model.size_bytes {
+        if size != expected {
+            return Err(LocalAIError::SizeMismatch {
+                expected,
+                actual: size,
+            });
+        }
+    }
+
+    if let Some(expected) = &model.sha256 {
+        let actual = sha256()?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(LocalAIError::ChecksumMismatch {
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        debug!("Checksum verified: {}", actual);
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,4 +338,82 @@ mod tests {
         assert_eq!(model.name, DEFAULT_MODEL_NAME);
         assert_eq!(model.filename, DEFAULT_MODEL_FILENAME);
     }
+
+    /// SHA-256 of `hello world`.
+    const FIXTURE_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    fn fixture(name: &str) -> (PathBuf, ModelInfo) {
+        let path = std::env::temp_dir().join(format!("haira-{}-{}.gguf", name, std::process::id()));
+        fs::write(&path, b"hello world").unwrap();
+
+        let model = ModelInfo {
+            name: name.to_string(),
+            filename: name.to_string(),
+            url: String::new(),
+            sha256: Some(FIXTURE_SHA256.to_string()),
+            size_bytes: Some(11),
+        };
+        (path, model)
+    }
+
+    #[test]
+    fn test_verify_file_passes() {
+        let (path, model) = fixture("verify-ok");
+        let result = verify_file(&path, &model);
+        fs::remove_file(&path).ok();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_verify_file_checksum_mismatch() {
+        let (path, mut model) = fixture("verify-hash");
+        model.sha256 = Some("0".repeat(64));
+        let result = verify_file(&path, &model);
+        fs::remove_file(&path).ok();
+
+        match result {
+            Err(LocalAIError::ChecksumMismatch { actual, .. }) => {
+                assert_eq!(actual, FIXTURE_SHA256)
+            }
+            other => panic!("expected checksum mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_file_truncated() {
+        let (path, mut model) = fixture("verify-size");
+        model.size_bytes = Some(1024);
+        let result = verify_file(&path, &model);
+        fs::remove_file(&path).ok();
+
+        assert!(matches!(
+            result,
+            Err(LocalAIError::SizeMismatch {
+                expected: 1024,
+                actual: 11
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_file_truncated_against_record() {
+        // Like the default model: no checksum or size in the registry
+        let (path, mut model) = fixture("verify-record");
+        model.sha256 = None;
+        model.size_bytes = None;
+        write_record(&path, FIXTURE_SHA256, 11).unwrap();
+        fs::write(&path, b"hello").unwrap();
+
+        let result = verify_file(&path, &model);
+        fs::remove_file(&path).ok();
+        fs::remove_file(record_path(&path)).ok();
+
+        assert!(matches!(
+            result,
+            Err(LocalAIError::SizeMismatch {
+                expected: 11,
+                actual: 5
+            })
+        ));
+    }
 }
@@ -1,20 +1,29 @@
 //! Process manager for llama-server.
 
 use std::process::{Child, Command, Stdio};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, info};
 
 use crate::client::LlamaCppClient;
 use crate::error::LocalAIError;
+use crate::model::{verify_file, ModelManager};
 use crate::paths::{llama_server_path, model_path};
 use crate::DEFAULT_PORT;
 
+/// Delay between health checks while waiting for the server.
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Manager for the llama-server process.
 pub struct LlamaCppServer {
     port: u16,
     model_filename: String,
-    process: Option<Child>,
+    /// Behind a mutex so readiness checks can kill the process through `&self`.
+    process: Mutex<Option<Child>>,
+    /// Set once the health endpoint has answered.
+    ready: AtomicBool,
 }
 
 impl LlamaCppServer {
@@ -23,7 +32,8 @@ impl LlamaCppServer {
         Self {
             port: DEFAULT_PORT,
             model_filename: model_filename.into(),
-            process: None,
+            process: Mutex::new(None),
+            ready: AtomicBool::new(false),
         }
     }
 
@@ -64,6 +74,12 @@ impl LlamaCppServer {
             return Err(LocalAIError::ModelNotFound(self.model_filename.clone()));
         }
 
+        // Catch a corrupted download here rather than as a cryptic llama-server failure
+        let known = ModelManager::default_model();
+        if known.filename == self.model_filename {
+            verify_file(&model, &known)?;
+        }
+
         info!(
             "Starting llama-server on port {} with model {}",
             self.port, self.model_filename
@@ -86,43 +102,62 @@ impl LlamaCppServer {
             .map_err(|e| LocalAIError::ServerStartFailed(e.to_string()))?;
 
         debug!("llama-server process started with PID: {}", child.id());
-        self.process = Some(child);
+        *self.process_mut() = Some(child);
+        self.ready.store(false, Ordering::SeqCst);
 
         Ok(())
     }
 
-    /// Wait for the server to become ready.
-    pub async fn wait_ready(&self, timeout: Duration) -> Result<(), LocalAIError> {
-        let client = LlamaCppClient::with_port(self.port);
-        let start = std::time::Instant::now();
-        let check_interval = Duration::from_millis(500);
+    /// Poll the health endpoint until the server responds.
+    ///
+    /// Returns immediately once the server has been seen healthy. On timeout
+    /// the server process is killed and `ServerNotReady` is returned; if the
+    /// process exits while loading, `ServerDied` is returned instead.
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<(), LocalAIError> {
+        if self.ready.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let client = self.client();
+        let start = Instant::now();
 
         info!("Waiting for llama-server to become ready...");
 
-        while start.elapsed() < timeout {
-            match client.check_health().await {
-                Ok(()) => {
-                    info!("llama-server is ready");
-                    return Ok(());
-                }
-                Err(_) => {
-                    // Check if process died
-                    if let Some(ref process) = self.process {
-                        // Try to check if process is still running
-                        // Note: We can't use try_wait without &mut self
-                        debug!("Server not ready yet, PID: {}", process.id());
-                    }
-                    sleep(check_interval).await;
+        loop {
+            if client.check_health().await.is_ok() {
+                info!("llama-server is ready");
+                self.ready.store(true, Ordering::SeqCst);
+                return Ok(());
+            }
+
+            if let Some(child) = self.process_mut().as_mut() {
+                if let Ok(Some(status)) = child.try_wait() {
+                    return Err(LocalAIError::ServerDied(status.to_string()));
                 }
+                debug!("Server not ready yet, PID: {}", child.id());
+            }
+
+            if start.elapsed() >= timeout {
+                break;
             }
+            sleep(READY_POLL_INTERVAL).await;
         }
 
-        Err(LocalAIError::ServerStartTimeout)
+        if let Some(mut child) = self.process_mut().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        Err(LocalAIError::ServerNotReady {
+            port: self.port,
+            timeout,
+        })
     }
 
     /// Stop the server process.
     pub fn stop(&mut self) -> Result<(), LocalAIError> {
-        if let Some(mut child) = self.process.take() {
+        self.ready.store(false, Ordering::SeqCst);
+        if let Some(mut child) = self.process_mut().take() {
             info!("Stopping llama-server (PID: {})", child.id());
 
             // Kill the process
@@ -142,11 +177,12 @@ impl LlamaCppServer {
 
     /// Check if the server process is running.
     pub fn is_running(&mut self) -> bool {
-        if let Some(ref mut child) = self.process {
+        let mut process = self.process_mut();
+        if let Some(ref mut child) = *process {
             match child.try_wait() {
                 Ok(Some(_)) => {
                     // Process has exited
-                    self.process = None;
+                    *process = None;
                     false
                 }
                 Ok(None) => true, // Still running
@@ -161,11 +197,17 @@ impl LlamaCppServer {
     pub fn client(&self) -> LlamaCppClient {
         LlamaCppClient::with_port(self.port)
     }
+
+    fn process_mut(&self) -> std::sync::MutexGuard<'_, Option<Child>> {
+        self.process
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 }
 
 impl Drop for LlamaCppServer {
     fn drop(&mut self) {
-        if self.process.is_some() {
+        if self.process_mut().is_some() {
             let _ = self.stop();
         }
     }
@@ -174,10 +216,62 @@ impl Drop for LlamaCppServer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
 
     #[test]
     fn test_server_config() {
         let server = LlamaCppServer::new("test-model.gguf").with_port(9999);
         assert_eq!(server.port(), 9999);
     }
+
+    /// Serve `/health` with 503 until `healthy_after` has passed, then 200.
+    async fn mock_health_server(healthy_after: Duration) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let start = Instant::now();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let status = if start.elapsed() >= healthy_after {
+                    "200 OK"
+                } else {
+                    "503 Service Unavailable"
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_ready_after_delay() {
+        let port = mock_health_server(Duration::from_millis(600)).await;
+        let server = LlamaCppServer::new("test-model.gguf").with_port(port);
+
+        server
+            .wait_until_ready(Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(server.ready.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_ready_timeout() {
+        let port = mock_health_server(Duration::from_secs(3600)).await;
+        let server = LlamaCppServer::new("test-model.gguf").with_port(port);
+
+        let result = server.wait_until_ready(Duration::from_millis(300)).await;
+        assert!(matches!(
+            result,
+            Err(LocalAIError::ServerNotReady { port: p, .. }) if p == port
+        ));
+    }
 }
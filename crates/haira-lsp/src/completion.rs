@@ -22,6 +22,8 @@ const KEYWORDS: &[(&str, &str)] = &[
     ("none", "Option none value"),
     ("some", "Option some value"),
     ("public", "Public visibility modifier"),
+    ("import", "Import another file"),
+    ("const", "Compile-time constant"),
     ("and", "Logical and"),
     ("or", "Logical or"),
     ("not", "Logical not"),
@@ -1,18 +1,24 @@
 //! Diagnostics collection for Haira.
 
+use haira_driver::analyze;
 use haira_lexer::Lexer;
-use haira_parser::parse;
+use ropey::Rope;
+use std::collections::HashSet;
+use std::ops::Range as ByteRange;
 use tower_lsp::lsp_types::*;
 
+use crate::offsets::range_from_bytes;
+
 /// Collect diagnostics from source code.
 pub fn collect_diagnostics(source: &str) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
+    let rope = Rope::from_str(source);
 
     // Lex the source and collect errors
     let lexer = Lexer::new(source);
     for result in lexer {
         if let Err(error) = result {
-            let range = span_to_range(source, error.span().start, error.span().end);
+            let range = range_from_bytes(&rope, error.span());
             diagnostics.push(Diagnostic {
                 range,
                 severity: Some(DiagnosticSeverity::ERROR),
@@ -27,48 +33,74 @@ pub fn collect_diagnostics(source: &str) -> Vec<Diagnostic> {
         }
     }
 
-    // Parse the source and collect errors
-    let result = parse(source);
+    // Run the front end and collect its errors and warnings
+    let result = analyze(source);
     for error in result.errors {
-        let span = error.span();
-        let range = span_to_range(source, span.start, span.end);
-        diagnostics.push(Diagnostic {
-            range,
-            severity: Some(DiagnosticSeverity::ERROR),
-            code: None,
-            code_description: None,
-            source: Some("haira".to_string()),
-            message: error.to_string(),
-            related_information: None,
-            tags: None,
-            data: None,
-        });
+        diagnostics.push(diagnostic(
+            &rope,
+            error.span,
+            DiagnosticSeverity::ERROR,
+            error.message,
+        ));
+    }
+    for warning in result.warnings {
+        diagnostics.push(diagnostic(
+            &rope,
+            warning.span,
+            DiagnosticSeverity::WARNING,
+            warning.message,
+        ));
     }
 
+    // Lexer errors resurface as parse errors, so keep one diagnostic per range
+    let mut seen = HashSet::new();
+    diagnostics.retain(|d| {
+        let Range { start, end } = d.range;
+        seen.insert((start.line, start.character, end.line, end.character))
+    });
+
     diagnostics
 }
 
-/// Convert byte offsets to an LSP range.
-fn span_to_range(source: &str, start: usize, end: usize) -> Range {
-    let start_pos = offset_to_position(source, start);
-    let end_pos = offset_to_position(source, end);
-    Range::new(start_pos, end_pos)
+fn diagnostic(
+    rope: &Rope,
+    span: Option<ByteRange<usize>>,
+    severity: DiagnosticSeverity,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range: range_from_bytes(rope, span.unwrap_or(0..0)),
+        severity: Some(severity),
+        code: None,
+        code_description: None,
+        source: Some("haira".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        data: None,
+    }
 }
 
-/// Convert a byte offset to an LSP position.
-fn offset_to_position(source: &str, offset: usize) -> Position {
-    let mut line = 0;
-    let mut col = 0;
-    for (i, c) in source.char_indices() {
-        if i >= offset {
-            break;
-        }
-        if c == '\n' {
-            line += 1;
-            col = 0;
-        } else {
-            col += 1;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undefined_variable_diagnostic() {
+        let diagnostics = collect_diagnostics("f(a) {\n    return a + missing\n}\n");
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostic.message, "undefined variable `missing`");
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(1, 15), Position::new(1, 22))
+        );
+    }
+
+    #[test]
+    fn test_valid_program_has_no_diagnostics() {
+        assert!(collect_diagnostics("x = 1\nprint(x)\n").is_empty());
     }
-    Position::new(line, col)
 }
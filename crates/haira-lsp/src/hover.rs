@@ -1,5 +1,7 @@
 //! Hover information for Haira.
 
+use haira_ast::ItemKind;
+use haira_parser::parse;
 use tower_lsp::lsp_types::*;
 
 /// Get hover information at the given position.
@@ -27,6 +29,8 @@ pub fn get_hover(source: &str, position: Position) -> Option<Hover> {
         "none" => Some(("constant", "Represents absence of a value (Option type)")),
         "some" => Some(("function", "Wraps a value in an Option\n\n```haira\nsome(42)  // Option containing 42\n```")),
         "public" => Some(("keyword", "Makes a function or type publicly accessible")),
+        "const" => Some(("keyword", "Declares a constant, substituted at every use\n\n```haira\nconst MAX_USERS = 100\n```")),
+        "import" => Some(("keyword", "Import the definitions of another file, relative to this one\n\n```haira\nimport \"shapes\"\n```")),
         "and" => Some(("operator", "Logical AND operator")),
         "or" => Some(("operator", "Logical OR operator")),
         "not" => Some(("operator", "Logical NOT operator")),
@@ -90,7 +94,37 @@ pub fn get_hover(source: &str, position: Position) -> Option<Hover> {
         });
     }
 
-    None
+    // Check for definitions in this file, with their doc comments
+    let (kind, doc) = find_item_doc(source, &word)?;
+    let value = match doc {
+        Some(doc) => format!("**{}** _{}_\n\n{}", word, kind, doc),
+        None => format!("**{}** _{}_", word, kind),
+    };
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: None,
+    })
+}
+
+/// Find a function, type or method named `name` and return its kind and doc comment.
+fn find_item_doc(source: &str, name: &str) -> Option<(&'static str, Option<String>)> {
+    let result = parse(source);
+
+    result
+        .ast
+        .items
+        .into_iter()
+        .find_map(|item| match item.node {
+            ItemKind::FunctionDef(func) if func.name.node == name => Some(("function", func.doc)),
+            ItemKind::TypeDef(type_def) if type_def.name.node == name => {
+                Some(("type", type_def.doc))
+            }
+            ItemKind::MethodDef(method) if method.name.node == name => Some(("method", method.doc)),
+            _ => None,
+        })
 }
 
 /// Convert an LSP position to a byte offset.
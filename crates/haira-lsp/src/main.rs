@@ -12,6 +12,7 @@ mod analysis;
 mod completion;
 mod diagnostics;
 mod hover;
+mod offsets;
 mod symbols;
 
 use diagnostics::collect_diagnostics;
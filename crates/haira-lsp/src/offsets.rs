@@ -0,0 +1,101 @@
+//! Conversions between byte offsets and LSP positions.
+//!
+//! The AST records byte offsets, while LSP positions count UTF-16 code units
+//! within a line, so characters outside the Basic Multilingual Plane take
+//! two columns.
+
+use haira_ast::Span;
+use ropey::Rope;
+use tower_lsp::lsp_types::{Position, Range};
+
+/// Convert a byte offset to an LSP position.
+///
+/// Offsets past the end of the document map to the end of the document.
+pub fn byte_to_position(rope: &Rope, offset: usize) -> Position {
+    let offset = offset.min(rope.len_bytes());
+    let line = rope.byte_to_line(offset);
+    let line_start = rope.char_to_utf16_cu(rope.line_to_char(line));
+    let column = rope.char_to_utf16_cu(rope.byte_to_char(offset)) - line_start;
+    Position::new(line as u32, column as u32)
+}
+
+/// Convert an LSP position to a byte offset.
+///
+/// Columns past the end of a line map to the end of that line, before its
+/// line break.
+pub fn position_to_byte(rope: &Rope, position: Position) -> usize {
+    let line = position.line as usize;
+    if line >= rope.len_lines() {
+        return rope.len_bytes();
+    }
+    let line_start = rope.line_to_char(line);
+    let mut line_end = line_start + rope.line(line).len_chars();
+    while line_end > line_start && matches!(rope.char(line_end - 1), '\n' | '\r') {
+        line_end -= 1;
+    }
+    let start_cu = rope.char_to_utf16_cu(line_start);
+    let end_cu = rope.char_to_utf16_cu(line_end);
+    let target = (start_cu + position.character as usize).min(end_cu);
+    rope.char_to_byte(rope.utf16_cu_to_char(target))
+}
+
+/// Convert a byte range to an LSP range.
+pub fn range_from_bytes(rope: &Rope, range: std::ops::Range<usize>) -> Range {
+    Range::new(
+        byte_to_position(rope, range.start),
+        byte_to_position(rope, range.end),
+    )
+}
+
+/// Convert a source span to an LSP range.
+pub fn range_from_span(rope: &Rope, span: Span) -> Range {
+    range_from_bytes(rope, span.start as usize..span.end as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "😀" is four bytes in UTF-8 and two code units in UTF-16
+    const SOURCE: &str = "x = \"😀\" + y\nz = \"é😀\"\n";
+
+    #[test]
+    fn test_byte_to_position_counts_utf16_units() {
+        let rope = Rope::from_str(SOURCE);
+        let y = SOURCE.find('y').unwrap();
+        assert_eq!(byte_to_position(&rope, y), Position::new(0, 11));
+
+        let second_line = SOURCE.find('z').unwrap();
+        assert_eq!(byte_to_position(&rope, second_line), Position::new(1, 0));
+        let emoji = SOURCE.rfind('😀').unwrap();
+        assert_eq!(byte_to_position(&rope, emoji), Position::new(1, 6));
+        assert_eq!(byte_to_position(&rope, emoji + 4), Position::new(1, 8));
+    }
+
+    #[test]
+    fn test_position_to_byte_round_trips() {
+        let rope = Rope::from_str(SOURCE);
+        for (offset, _) in SOURCE.char_indices() {
+            let position = byte_to_position(&rope, offset);
+            assert_eq!(position_to_byte(&rope, position), offset);
+        }
+    }
+
+    #[test]
+    fn test_positions_past_line_end_clamp() {
+        let rope = Rope::from_str(SOURCE);
+        let newline = SOURCE.find('\n').unwrap();
+        assert_eq!(position_to_byte(&rope, Position::new(0, 100)), newline);
+        assert_eq!(position_to_byte(&rope, Position::new(9, 0)), SOURCE.len());
+    }
+
+    #[test]
+    fn test_range_from_span() {
+        let rope = Rope::from_str(SOURCE);
+        let start = SOURCE.find('y').unwrap() as u32;
+        assert_eq!(
+            range_from_span(&rope, Span::new(start, start + 1)),
+            Range::new(Position::new(0, 11), Position::new(0, 12))
+        );
+    }
+}
@@ -2,11 +2,15 @@
 
 use haira_ast::{AssignPath, ItemKind, StatementKind};
 use haira_parser::parse;
+use ropey::Rope;
 use tower_lsp::lsp_types::*;
 
+use crate::offsets::range_from_span;
+
 /// Get document symbols from source code.
 pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
     let mut symbols = Vec::new();
+    let rope = Rope::from_str(source);
 
     // Parse the source
     let result = parse(source);
@@ -15,11 +19,7 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
     for item in &result.ast.items {
         match &item.node {
             ItemKind::FunctionDef(func) => {
-                let range = span_to_range(
-                    source,
-                    func.name.span.start as usize,
-                    func.name.span.end as usize,
-                );
+                let range = range_from_span(&rope, func.name.span);
                 #[allow(deprecated)]
                 symbols.push(SymbolInformation {
                     name: func.name.node.to_string(),
@@ -34,11 +34,7 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
                 });
             }
             ItemKind::TypeDef(type_def) => {
-                let range = span_to_range(
-                    source,
-                    type_def.name.span.start as usize,
-                    type_def.name.span.end as usize,
-                );
+                let range = range_from_span(&rope, type_def.name.span);
                 #[allow(deprecated)]
                 symbols.push(SymbolInformation {
                     name: type_def.name.node.to_string(),
@@ -54,11 +50,7 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
 
                 // Add fields as children
                 for field in &type_def.fields {
-                    let field_range = span_to_range(
-                        source,
-                        field.name.span.start as usize,
-                        field.name.span.end as usize,
-                    );
+                    let field_range = range_from_span(&rope, field.name.span);
                     #[allow(deprecated)]
                     symbols.push(SymbolInformation {
                         name: field.name.node.to_string(),
@@ -74,11 +66,7 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
                 }
             }
             ItemKind::MethodDef(method) => {
-                let range = span_to_range(
-                    source,
-                    method.name.span.start as usize,
-                    method.name.span.end as usize,
-                );
+                let range = range_from_span(&rope, method.name.span);
                 #[allow(deprecated)]
                 symbols.push(SymbolInformation {
                     name: format!("{}.{}", method.type_name.node, method.name.node),
@@ -92,12 +80,39 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
                     container_name: Some(method.type_name.node.to_string()),
                 });
             }
+            ItemKind::EnumDef(enum_def) => {
+                let range = range_from_span(&rope, enum_def.name.span);
+                #[allow(deprecated)]
+                symbols.push(SymbolInformation {
+                    name: enum_def.name.node.to_string(),
+                    kind: SymbolKind::ENUM,
+                    tags: None,
+                    deprecated: None,
+                    location: Location {
+                        uri: Url::parse("file:///").unwrap(),
+                        range,
+                    },
+                    container_name: None,
+                });
+
+                for variant in &enum_def.variants {
+                    let variant_range = range_from_span(&rope, variant.name.span);
+                    #[allow(deprecated)]
+                    symbols.push(SymbolInformation {
+                        name: variant.name.node.to_string(),
+                        kind: SymbolKind::ENUM_MEMBER,
+                        tags: None,
+                        deprecated: None,
+                        location: Location {
+                            uri: Url::parse("file:///").unwrap(),
+                            range: variant_range,
+                        },
+                        container_name: Some(enum_def.name.node.to_string()),
+                    });
+                }
+            }
             ItemKind::TypeAlias(alias) => {
-                let range = span_to_range(
-                    source,
-                    alias.name.span.start as usize,
-                    alias.name.span.end as usize,
-                );
+                let range = range_from_span(&rope, alias.name.span);
                 #[allow(deprecated)]
                 symbols.push(SymbolInformation {
                     name: alias.name.node.to_string(),
@@ -114,8 +129,7 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
             ItemKind::AiFunctionDef(ai_block) => {
                 // Only add named AI functions
                 if let Some(name) = &ai_block.name {
-                    let range =
-                        span_to_range(source, name.span.start as usize, name.span.end as usize);
+                    let range = range_from_span(&rope, name.span);
                     #[allow(deprecated)]
                     symbols.push(SymbolInformation {
                         name: name.node.to_string(),
@@ -136,11 +150,7 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
                     for target in &assign.targets {
                         // Only extract simple identifier assignments as symbols
                         if let AssignPath::Identifier(name) = &target.path {
-                            let range = span_to_range(
-                                source,
-                                name.span.start as usize,
-                                name.span.end as usize,
-                            );
+                            let range = range_from_span(&rope, name.span);
                             #[allow(deprecated)]
                             symbols.push(SymbolInformation {
                                 name: name.node.to_string(),
@@ -157,33 +167,24 @@ pub fn get_document_symbols(source: &str) -> Vec<SymbolInformation> {
                     }
                 }
             }
+            ItemKind::Const(const_def) => {
+                let range = range_from_span(&rope, const_def.name.span);
+                #[allow(deprecated)]
+                symbols.push(SymbolInformation {
+                    name: const_def.name.node.to_string(),
+                    kind: SymbolKind::CONSTANT,
+                    tags: None,
+                    deprecated: None,
+                    location: Location {
+                        uri: Url::parse("file:///").unwrap(),
+                        range,
+                    },
+                    container_name: None,
+                });
+            }
+            ItemKind::Import(_) => {}
         }
     }
 
     symbols
 }
-
-/// Convert byte offsets to an LSP range.
-fn span_to_range(source: &str, start: usize, end: usize) -> Range {
-    let start_pos = offset_to_position(source, start);
-    let end_pos = offset_to_position(source, end);
-    Range::new(start_pos, end_pos)
-}
-
-/// Convert a byte offset to an LSP position.
-fn offset_to_position(source: &str, offset: usize) -> Position {
-    let mut line = 0;
-    let mut col = 0;
-    for (i, c) in source.char_indices() {
-        if i >= offset {
-            break;
-        }
-        if c == '\n' {
-            line += 1;
-            col = 0;
-        } else {
-            col += 1;
-        }
-    }
-    Position::new(line, col)
-}
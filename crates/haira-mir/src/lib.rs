@@ -8,6 +8,7 @@
 use haira_ast::Span;
 use haira_types::Type;
 use smol_str::SmolStr;
+use thiserror::Error;
 
 /// A MIR function.
 pub struct MirFunction {
@@ -55,6 +56,8 @@ pub enum Statement {
 }
 
 /// Local variable ID.
+///
+/// Parameters are numbered first, followed by the function's other locals.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LocalId(pub u32);
 
@@ -127,6 +130,28 @@ pub enum UnOp {
     Not,
 }
 
+/// MIR validation errors.
+#[derive(Debug, Error)]
+pub enum MirError {
+    #[error("type mismatch in {block:?}: expected {expected:?}, found {found:?}")]
+    TypeMismatch {
+        expected: Type,
+        found: Type,
+        block: BlockId,
+        span: Span,
+    },
+
+    #[error("condition in {block:?} must be Bool, found {found:?}")]
+    NonBoolCondition {
+        found: Type,
+        block: BlockId,
+        span: Span,
+    },
+
+    #[error("unknown local {0:?}")]
+    UnknownLocal(LocalId),
+}
+
 /// Block terminator.
 pub enum Terminator {
     /// Go to another block.
@@ -161,4 +186,212 @@ impl MirFunction {
             span,
         }
     }
+
+    /// Look up a parameter or local by ID.
+    pub fn local(&self, id: LocalId) -> Option<&MirLocal> {
+        let index = id.0 as usize;
+        match self.params.get(index) {
+            Some(param) => Some(param),
+            None => self.locals.get(index - self.params.len()),
+        }
+    }
+
+    /// The type of a place.
+    ///
+    /// Fields can't be resolved without struct definitions, so they are
+    /// `Type::Error`, as are places rooted at an unknown local.
+    pub fn place_type(&self, place: &Place) -> Type {
+        match place {
+            Place::Local(id) => self
+                .local(*id)
+                .map_or(Type::Error, |local| local.ty.clone()),
+            Place::Field { .. } => Type::Error,
+            Place::Index { base, .. } => match self.place_type(base) {
+                Type::Array(elem) => *elem,
+                _ => Type::Error,
+            },
+        }
+    }
+
+    /// The type of an operand.
+    pub fn operand_type(&self, op: &Operand) -> Type {
+        match op {
+            Operand::Copy(place) | Operand::Move(place) => self.place_type(place),
+            Operand::Constant(constant) => match constant {
+                Constant::Int(_) => Type::Int,
+                Constant::Float(_) => Type::Float,
+                Constant::Bool(_) => Type::Bool,
+                Constant::String(_) => Type::String,
+                Constant::Unit => Type::Unit,
+            },
+        }
+    }
+
+    /// The type an rvalue evaluates to.
+    pub fn rvalue_type(&self, rvalue: &Rvalue) -> Type {
+        match rvalue {
+            Rvalue::Use(op) => self.operand_type(op),
+            Rvalue::BinaryOp(op, left, _) => match op {
+                BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => Type::Bool,
+                _ => self.operand_type(left),
+            },
+            Rvalue::UnaryOp(UnOp::Neg, op) => self.operand_type(op),
+            Rvalue::UnaryOp(UnOp::Not, _) => Type::Bool,
+            Rvalue::Aggregate { ty, .. } => ty.clone(),
+            Rvalue::Ref(place) => self.place_type(place),
+        }
+    }
+
+    /// Check that assignments and branch conditions are well-typed.
+    ///
+    /// `Type::Error` stands for a type that couldn't be determined and
+    /// matches anything.
+    pub fn validate(&self) -> Result<(), MirError> {
+        for block in &self.blocks {
+            for stmt in &block.statements {
+                match stmt {
+                    Statement::Assign { place, rvalue } => {
+                        let expected = self.place_type(place);
+                        let found = self.rvalue_type(rvalue);
+                        if !types_match(&expected, &found) {
+                            return Err(MirError::TypeMismatch {
+                                expected,
+                                found,
+                                block: block.id,
+                                span: block.span,
+                            });
+                        }
+                    }
+                    Statement::StorageLive(id) | Statement::StorageDead(id) => {
+                        if self.local(*id).is_none() {
+                            return Err(MirError::UnknownLocal(*id));
+                        }
+                    }
+                    Statement::Nop => {}
+                }
+            }
+
+            if let Terminator::If { condition, .. } = &block.terminator {
+                let found = self.operand_type(condition);
+                if !types_match(&Type::Bool, &found) {
+                    return Err(MirError::NonBoolCondition {
+                        found,
+                        block: block.id,
+                        span: block.span,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn types_match(expected: &Type, found: &Type) -> bool {
+    matches!(expected, Type::Error) || matches!(found, Type::Error) || expected == found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span() -> Span {
+        Span::new(0, 0)
+    }
+
+    fn local(name: &str, ty: Type) -> MirLocal {
+        MirLocal {
+            name: name.into(),
+            ty,
+            span: span(),
+        }
+    }
+
+    fn block(statements: Vec<Statement>, terminator: Terminator) -> BasicBlock {
+        BasicBlock {
+            id: BlockId(0),
+            statements,
+            terminator,
+            span: span(),
+        }
+    }
+
+    fn copy(id: u32) -> Operand {
+        Operand::Copy(Box::new(Place::Local(LocalId(id))))
+    }
+
+    /// `f(n: Int) -> Unit` with a `Bool` local `flag`.
+    fn function(blocks: Vec<BasicBlock>) -> MirFunction {
+        let mut func = MirFunction::new("f".into(), Type::Unit, span());
+        func.params.push(local("n", Type::Int));
+        func.locals.push(local("flag", Type::Bool));
+        func.blocks = blocks;
+        func
+    }
+
+    #[test]
+    fn test_operand_types() {
+        let func = function(Vec::new());
+        assert_eq!(func.operand_type(&copy(0)), Type::Int);
+        assert_eq!(func.operand_type(&copy(1)), Type::Bool);
+        assert_eq!(
+            func.operand_type(&Operand::Constant(Constant::Float(1.5))),
+            Type::Float
+        );
+        assert_eq!(func.operand_type(&copy(2)), Type::Error);
+    }
+
+    #[test]
+    fn test_well_typed_function_validates() {
+        let func = function(vec![block(
+            vec![Statement::Assign {
+                place: Place::Local(LocalId(1)),
+                rvalue: Rvalue::BinaryOp(BinOp::Lt, copy(0), Operand::Constant(Constant::Int(3))),
+            }],
+            Terminator::If {
+                condition: copy(1),
+                then_block: BlockId(0),
+                else_block: BlockId(0),
+            },
+        )]);
+        assert!(func.validate().is_ok());
+    }
+
+    #[test]
+    fn test_assignment_type_mismatch() {
+        let func = function(vec![block(
+            vec![Statement::Assign {
+                place: Place::Local(LocalId(0)),
+                rvalue: Rvalue::Use(Operand::Constant(Constant::String("x".into()))),
+            }],
+            Terminator::Return,
+        )]);
+        match func.validate() {
+            Err(MirError::TypeMismatch {
+                expected, found, ..
+            }) => {
+                assert_eq!(expected, Type::Int);
+                assert_eq!(found, Type::String);
+            }
+            other => panic!("expected type mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_if_condition_must_be_bool() {
+        let func = function(vec![block(
+            Vec::new(),
+            Terminator::If {
+                condition: copy(0),
+                then_block: BlockId(0),
+                else_block: BlockId(0),
+            },
+        )]);
+        assert!(matches!(
+            func.validate(),
+            Err(MirError::NonBoolCondition {
+                found: Type::Int,
+                ..
+            })
+        ));
+    }
 }
@@ -22,9 +22,11 @@
 //! ```
 
 mod error;
+mod optimize;
 mod parser;
 
 pub use error::ParseError;
+pub use optimize::fold_constants;
 pub use parser::Parser;
 
 use haira_ast::SourceFile;
@@ -0,0 +1,327 @@
+//! AST-level optimizations run between parsing and code generation.
+
+use haira_ast::{
+    fold_binary, fold_unary, AssignPath, Block, ElseBranch, Expr, ExprKind, IfStatement, ItemKind,
+    LambdaBody, Literal, MatchArmBody, Param, SourceFile, StatementKind, StringPart,
+};
+
+/// Fold operations over literal operands into single literals.
+///
+/// Integer and float arithmetic, negation, `not`, and string concatenation
+/// are evaluated at compile time. Integer operations that would overflow or
+/// divide by zero are left alone so they keep their runtime behavior.
+/// Comparisons are not folded, since codegen produces them as integers.
+pub fn fold_constants(ast: &mut SourceFile) {
+    for item in &mut ast.items {
+        match &mut item.node {
+            ItemKind::FunctionDef(func) => {
+                fold_params(&mut func.params);
+                fold_block(&mut func.body);
+            }
+            ItemKind::MethodDef(method) => {
+                fold_params(&mut method.params);
+                fold_block(&mut method.body);
+            }
+            ItemKind::TypeDef(type_def) => {
+                for field in &mut type_def.fields {
+                    if let Some(default) = &mut field.default {
+                        fold_expr(default);
+                    }
+                }
+            }
+            ItemKind::AiFunctionDef(ai_block) => fold_params(&mut ai_block.params),
+            ItemKind::Statement(stmt) => fold_statement(&mut stmt.node),
+            ItemKind::Const(const_def) => fold_expr(&mut const_def.value),
+            ItemKind::EnumDef(_) | ItemKind::TypeAlias(_) | ItemKind::Import(_) => {}
+        }
+    }
+}
+
+fn fold_params(params: &mut [Param]) {
+    for param in params {
+        if let Some(default) = &mut param.default {
+            fold_expr(default);
+        }
+    }
+}
+
+fn fold_block(block: &mut Block) {
+    for stmt in &mut block.statements {
+        fold_statement(&mut stmt.node);
+    }
+}
+
+fn fold_statement(stmt: &mut StatementKind) {
+    match stmt {
+        StatementKind::Assignment(assign) => {
+            for target in &mut assign.targets {
+                fold_assign_path(&mut target.path);
+            }
+            fold_expr(&mut assign.value);
+        }
+        StatementKind::If(if_stmt) => fold_if(if_stmt),
+        StatementKind::For(for_stmt) => {
+            fold_expr(&mut for_stmt.iterator);
+            fold_block(&mut for_stmt.body);
+        }
+        StatementKind::While(while_stmt) => {
+            fold_expr(&mut while_stmt.condition);
+            fold_block(&mut while_stmt.body);
+        }
+        StatementKind::Match(match_expr) => {
+            fold_expr(&mut match_expr.subject);
+            for arm in &mut match_expr.arms {
+                if let Some(guard) = &mut arm.guard {
+                    fold_expr(guard);
+                }
+                fold_arm_body(&mut arm.body);
+            }
+        }
+        StatementKind::Return(ret) => {
+            for value in &mut ret.values {
+                fold_expr(value);
+            }
+        }
+        StatementKind::Try(try_stmt) => {
+            fold_block(&mut try_stmt.body);
+            fold_block(&mut try_stmt.catch_body);
+        }
+        StatementKind::Expr(expr) => fold_expr(expr),
+        StatementKind::Break | StatementKind::Continue => {}
+    }
+}
+
+fn fold_assign_path(path: &mut AssignPath) {
+    match path {
+        AssignPath::Identifier(_) => {}
+        AssignPath::Field { object, .. } => fold_assign_path(object),
+        AssignPath::Index { object, index } => {
+            fold_assign_path(object);
+            fold_expr(index);
+        }
+    }
+}
+
+fn fold_if(if_stmt: &mut IfStatement) {
+    fold_expr(&mut if_stmt.condition);
+    fold_block(&mut if_stmt.then_branch);
+    match &mut if_stmt.else_branch {
+        Some(ElseBranch::Block(block)) => fold_block(block),
+        Some(ElseBranch::ElseIf(else_if)) => fold_if(&mut else_if.node),
+        None => {}
+    }
+}
+
+fn fold_arm_body(body: &mut MatchArmBody) {
+    match body {
+        MatchArmBody::Expr(expr) => fold_expr(expr),
+        MatchArmBody::Block(block) => fold_block(block),
+    }
+}
+
+fn fold_expr(expr: &mut Expr) {
+    // Fold children first so nested constants collapse bottom-up
+    match &mut expr.node {
+        ExprKind::Literal(Literal::InterpolatedString(parts)) => {
+            for part in parts {
+                if let StringPart::Expr(inner) = part {
+                    fold_expr(inner);
+                }
+            }
+        }
+        ExprKind::Literal(_) | ExprKind::Identifier(_) | ExprKind::None | ExprKind::Ai(_) => {}
+        ExprKind::Binary(bin) => {
+            fold_expr(&mut bin.left);
+            fold_expr(&mut bin.right);
+        }
+        ExprKind::Unary(unary) => fold_expr(&mut unary.operand),
+        ExprKind::Call(call) => {
+            fold_expr(&mut call.callee);
+            for arg in &mut call.args {
+                fold_expr(&mut arg.value);
+            }
+        }
+        ExprKind::MethodCall(call) => {
+            fold_expr(&mut call.receiver);
+            for arg in &mut call.args {
+                fold_expr(&mut arg.value);
+            }
+        }
+        ExprKind::Field(field) => fold_expr(&mut field.object),
+        ExprKind::Index(index) => {
+            fold_expr(&mut index.object);
+            fold_expr(&mut index.index);
+        }
+        ExprKind::Pipe(pipe) => {
+            fold_expr(&mut pipe.left);
+            fold_expr(&mut pipe.right);
+        }
+        ExprKind::Lambda(lambda) => {
+            fold_params(&mut lambda.params);
+            match &mut lambda.body {
+                LambdaBody::Expr(body) => fold_expr(body),
+                LambdaBody::Block(block) => fold_block(block),
+            }
+        }
+        ExprKind::Match(match_expr) => {
+            fold_expr(&mut match_expr.subject);
+            for arm in &mut match_expr.arms {
+                if let Some(guard) = &mut arm.guard {
+                    fold_expr(guard);
+                }
+                fold_arm_body(&mut arm.body);
+            }
+        }
+        ExprKind::If(if_stmt) => fold_if(if_stmt),
+        ExprKind::Block(block) | ExprKind::Async(block) | ExprKind::Spawn(block) => {
+            fold_block(block)
+        }
+        ExprKind::List(elements) | ExprKind::Tuple(elements) => {
+            for element in elements {
+                fold_expr(element);
+            }
+        }
+        ExprKind::Map(entries) => {
+            for (key, value) in entries {
+                fold_expr(key);
+                fold_expr(value);
+            }
+        }
+        ExprKind::Instance(instance) => {
+            for field in &mut instance.fields {
+                fold_expr(&mut field.value);
+            }
+        }
+        ExprKind::Range(range) => {
+            fold_expr(&mut range.start);
+            fold_expr(&mut range.end);
+        }
+        ExprKind::Propagate(inner) | ExprKind::Some(inner) | ExprKind::Paren(inner) => {
+            fold_expr(inner)
+        }
+        ExprKind::Select(select) => {
+            for arm in &mut select.arms {
+                fold_expr(&mut arm.channel);
+                fold_arm_body(&mut arm.body);
+            }
+            if let Some(default) = &mut select.default {
+                fold_block(default);
+            }
+        }
+    }
+
+    let folded = match &expr.node {
+        ExprKind::Binary(bin) => match (literal(&bin.left), literal(&bin.right)) {
+            (Some(left), Some(right)) => fold_binary(bin.op.node, left, right),
+            _ => None,
+        },
+        ExprKind::Unary(unary) => {
+            literal(&unary.operand).and_then(|operand| fold_unary(unary.op.node, operand))
+        }
+        ExprKind::Paren(inner) => literal(inner).cloned(),
+        _ => None,
+    };
+    if let Some(lit) = folded {
+        expr.node = ExprKind::Literal(lit);
+    }
+}
+
+/// The literal an expression evaluates to, looking through parentheses.
+fn literal(expr: &Expr) -> Option<&Literal> {
+    match &expr.node {
+        ExprKind::Literal(lit) => Some(lit),
+        ExprKind::Paren(inner) => literal(inner),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    /// Parse `source`, fold it, and return the value of the first assignment.
+    fn folded_value(source: &str) -> ExprKind {
+        let mut ast = parse(source).ast;
+        fold_constants(&mut ast);
+        match &ast.items[0].node {
+            ItemKind::Statement(stmt) => match &stmt.node {
+                StatementKind::Assignment(assign) => assign.value.node.clone(),
+                other => panic!("expected assignment, got {:?}", other),
+            },
+            other => panic!("expected statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fold_nested_arithmetic() {
+        assert_eq!(
+            folded_value("x = 2 + 3 * 4\n"),
+            ExprKind::Literal(Literal::Int(14))
+        );
+        assert_eq!(
+            folded_value("x = (2 + 3) * -4\n"),
+            ExprKind::Literal(Literal::Int(-20))
+        );
+        assert_eq!(
+            folded_value("x = 1 + 0.5\n"),
+            ExprKind::Literal(Literal::Float(1.5))
+        );
+    }
+
+    #[test]
+    fn test_fold_negative_scientific_float() {
+        assert_eq!(
+            folded_value("x = -2.5e-3\n"),
+            ExprKind::Literal(Literal::Float(-2.5e-3))
+        );
+    }
+
+    #[test]
+    fn test_fold_string_concatenation() {
+        assert_eq!(
+            folded_value("x = \"a\" + \"b\"\n"),
+            ExprKind::Literal(Literal::String("ab".into()))
+        );
+    }
+
+    #[test]
+    fn test_fold_bitwise_operators() {
+        assert_eq!(
+            folded_value("x = 6 & 3 | 1 << 4\n"),
+            ExprKind::Literal(Literal::Int(18))
+        );
+        assert_eq!(
+            folded_value("x = -1 >> 60\n"),
+            ExprKind::Literal(Literal::Int(15))
+        );
+        assert!(matches!(folded_value("x = 1 << 64\n"), ExprKind::Binary(_)));
+    }
+
+    #[test]
+    fn test_non_constant_operands_are_untouched() {
+        assert!(matches!(folded_value("x = y + 1\n"), ExprKind::Binary(_)));
+    }
+
+    #[test]
+    fn test_division_by_zero_and_overflow_are_not_folded() {
+        assert!(matches!(folded_value("x = 1 / 0\n"), ExprKind::Binary(_)));
+        assert!(matches!(
+            folded_value("x = 9223372036854775807 + 1\n"),
+            ExprKind::Binary(_)
+        ));
+    }
+
+    #[test]
+    fn test_fold_inside_function_body() {
+        let mut ast = parse("f() {\n    return 6 / 3\n}\n").ast;
+        fold_constants(&mut ast);
+        let ItemKind::FunctionDef(func) = &ast.items[0].node else {
+            panic!("expected function");
+        };
+        let StatementKind::Return(ret) = &func.body.statements[0].node else {
+            panic!("expected return");
+        };
+        assert_eq!(ret.values[0].node, ExprKind::Literal(Literal::Int(2)));
+    }
+}
@@ -1,7 +1,7 @@
 //! Recursive descent parser for Haira.
 
 use haira_ast::*;
-use haira_lexer::{Lexer, Token, TokenKind};
+use haira_lexer::{unescape, Lexer, Token, TokenKind};
 use smol_str::SmolStr;
 
 use crate::error::ParseError;
@@ -10,17 +10,39 @@ use crate::error::ParseError;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Precedence {
     None,
-    Pipe,       // |
+    Pipe,       // | (also bitwise or)
+    Coalesce,   // ??
     Or,         // or
     And,        // and
     Equality,   // == !=
     Comparison, // < > <= >=
+    BitXor,     // ^
+    BitAnd,     // &
+    Shift,      // << >>
     Term,       // + -
     Factor,     // * / %
     Unary,      // - not
     Call,       // () [] .
 }
 
+/// The operator a compound assignment token applies: `Add` for `+=`.
+fn compound_assign_op(kind: &TokenKind) -> Option<BinaryOp> {
+    let op = match kind {
+        TokenKind::PlusEq => BinaryOp::Add,
+        TokenKind::MinusEq => BinaryOp::Sub,
+        TokenKind::StarEq => BinaryOp::Mul,
+        TokenKind::SlashEq => BinaryOp::Div,
+        TokenKind::PercentEq => BinaryOp::Mod,
+        TokenKind::AmpEq => BinaryOp::BitAnd,
+        TokenKind::PipeEq => BinaryOp::BitOr,
+        TokenKind::CaretEq => BinaryOp::BitXor,
+        TokenKind::ShlEq => BinaryOp::Shl,
+        TokenKind::ShrEq => BinaryOp::Shr,
+        _ => return None,
+    };
+    Some(op)
+}
+
 impl Precedence {
     fn of(kind: &TokenKind) -> Self {
         match kind {
@@ -28,10 +50,14 @@ impl Precedence {
             // assignment is handled at the statement level, not as an expression.
             // Including it here would cause an infinite loop in parse_expr_precedence.
             TokenKind::Pipe => Precedence::Pipe,
+            TokenKind::QuestionQuestion => Precedence::Coalesce,
             TokenKind::Or => Precedence::Or,
             TokenKind::And => Precedence::And,
             TokenKind::EqEq | TokenKind::Ne => Precedence::Equality,
             TokenKind::Lt | TokenKind::Gt | TokenKind::Le | TokenKind::Ge => Precedence::Comparison,
+            TokenKind::Caret => Precedence::BitXor,
+            TokenKind::Amp => Precedence::BitAnd,
+            TokenKind::Shl | TokenKind::Shr => Precedence::Shift,
             TokenKind::Plus | TokenKind::Minus => Precedence::Term,
             TokenKind::Star | TokenKind::Slash | TokenKind::Percent => Precedence::Factor,
             TokenKind::LParen | TokenKind::LBracket | TokenKind::Dot | TokenKind::Question => {
@@ -45,26 +71,37 @@ impl Precedence {
 
 /// Parser for Haira source code.
 pub struct Parser<'source> {
+    source: &'source str,
     lexer: Lexer<'source>,
     current: Token,
     previous: Token,
+    /// `///` comment run directly above `current`, if any.
+    doc: Option<String>,
+    /// Second half of a `>>` split by [`Parser::split_shr`], next after `current`.
+    split_gt: Option<Token>,
+    /// Whether `Name { ... }` parses as a struct instance. Off in the headers
+    /// of `if`, `for`, `while` and `match`, where the `{` opens the body.
+    instances: bool,
     errors: Vec<ParseError>,
 }
 
 impl<'source> Parser<'source> {
     /// Create a new parser for the given source.
     pub fn new(source: &'source str) -> Self {
-        let mut lexer = Lexer::new(source);
-
-        // Get the first non-newline token
-        let current = Self::next_significant_token(&mut lexer);
-
-        Self {
-            lexer,
-            current,
+        let mut parser = Self {
+            source,
+            lexer: Lexer::new(source).with_comments(),
+            current: Token::new(TokenKind::Eof, 0..0),
             previous: Token::new(TokenKind::Eof, 0..0),
+            doc: None,
+            split_gt: None,
+            instances: true,
             errors: Vec::new(),
-        }
+        };
+
+        // Get the first non-newline token
+        parser.advance();
+        parser
     }
 
     /// Get the collected errors.
@@ -72,34 +109,81 @@ impl<'source> Parser<'source> {
         self.errors
     }
 
-    fn next_significant_token(lexer: &mut Lexer) -> Token {
+    /// Run `parse` with struct instances allowed or not, restoring the
+    /// previous setting afterwards.
+    fn with_instances<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.instances, allowed);
+        let result = parse(self);
+        self.instances = outer;
+        result
+    }
+
+    /// Next token that isn't a newline or comment. Leading `///` comments
+    /// on the way are collected into `self.doc`.
+    fn next_significant_token(&mut self) -> Token {
+        let mut doc_lines = Vec::new();
         loop {
-            match lexer.next() {
-                Some(Ok(token)) => {
+            match self.lexer.next() {
+                Some(Ok(token)) => match token.kind {
                     // Skip whitespace, newlines, and comments
-                    if !matches!(
-                        token.kind,
-                        TokenKind::Newline | TokenKind::LineComment | TokenKind::BlockComment
-                    ) {
+                    TokenKind::Newline | TokenKind::BlockComment => {}
+                    TokenKind::LineComment => {
+                        if let Some(line) = self.leading_doc_line(&token) {
+                            doc_lines.push(line);
+                        }
+                    }
+                    _ => {
+                        self.doc = (!doc_lines.is_empty()).then(|| doc_lines.join("\n"));
                         return token;
                     }
-                }
+                },
                 Some(Err(_)) => {
                     // Skip errors, they'll be reported elsewhere
                     continue;
                 }
                 None => {
+                    self.doc = None;
                     return Token::new(TokenKind::Eof, 0..0);
                 }
             }
         }
     }
 
+    /// The text of a `///` comment that starts its own line. Trailing doc
+    /// comments after code are not documentation for the next item.
+    fn leading_doc_line(&self, comment: &Token) -> Option<String> {
+        let text = self.source[comment.span.clone()].strip_prefix("///")?;
+        let line_prefix = self.source[..comment.span.start]
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+        if !line_prefix.trim().is_empty() {
+            return None;
+        }
+        Some(
+            text.strip_prefix(' ')
+                .unwrap_or(text)
+                .trim_end()
+                .to_string(),
+        )
+    }
+
     fn advance(&mut self) {
-        self.previous = std::mem::replace(
-            &mut self.current,
-            Self::next_significant_token(&mut self.lexer),
-        );
+        let next = match self.split_gt.take() {
+            Some(gt) => gt,
+            None => self.next_significant_token(),
+        };
+        self.previous = std::mem::replace(&mut self.current, next);
+    }
+
+    /// Split a current `>>` token into two `>`, so nested generics like
+    /// `Option<List<int>>` close both argument lists.
+    fn split_shr(&mut self) {
+        if self.check(&TokenKind::Shr) {
+            let span = self.current.span.clone();
+            self.current = Token::new(TokenKind::Gt, span.start..span.start + 1);
+            self.split_gt = Some(Token::new(TokenKind::Gt, span.start + 1..span.end));
+        }
     }
 
     fn skip_newlines(&mut self) {
@@ -134,6 +218,50 @@ impl<'source> Parser<'source> {
         self.errors.push(err);
     }
 
+    /// Whether the current token is the first on its line.
+    fn at_line_start(&self) -> bool {
+        self.source
+            .get(self.previous.span.end..self.current.span.start)
+            .is_some_and(|gap| gap.contains('\n'))
+    }
+
+    /// Whether the current token plausibly begins a new item or statement.
+    fn at_statement_start(&self) -> bool {
+        match self.current.kind {
+            TokenKind::If
+            | TokenKind::For
+            | TokenKind::While
+            | TokenKind::Return
+            | TokenKind::Match
+            | TokenKind::Try
+            | TokenKind::Break
+            | TokenKind::Continue
+            | TokenKind::Public
+            | TokenKind::Ai => true,
+            TokenKind::Ident(_) => self.at_line_start(),
+            _ => false,
+        }
+    }
+
+    /// Recover from a parse error by skipping tokens until the start of the
+    /// next item or statement, or the `}` closing the enclosing block.
+    ///
+    /// Braced groups are skipped whole, so a broken function header doesn't
+    /// resynchronize inside its own body.
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        while !self.at_end() {
+            match self.current.kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace if depth == 0 => return,
+                TokenKind::RBrace => depth -= 1,
+                _ if depth == 0 && self.at_statement_start() => return,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
     fn span(&self, start: usize) -> Span {
         Span::new(start as u32, self.previous.span.end as u32)
     }
@@ -157,11 +285,19 @@ impl<'source> Parser<'source> {
                 break;
             }
 
+            let item_start = self.current.span.start;
             if let Some(item) = self.parse_item() {
                 items.push(item);
             } else {
-                // Error recovery: skip to next line
-                self.advance();
+                // Error recovery: skip to the next item
+                if self.current.span.start == item_start {
+                    self.advance();
+                }
+                self.synchronize();
+                // Stray closing braces at the top level belong to the broken item
+                while self.check(&TokenKind::RBrace) {
+                    self.advance();
+                }
             }
         }
 
@@ -173,6 +309,7 @@ impl<'source> Parser<'source> {
 
     fn parse_item(&mut self) -> Option<Item> {
         let start = self.current.span.start;
+        let doc = self.doc.take();
 
         // Check for `public` modifier
         let is_public = if matches!(self.current.kind, TokenKind::Public) {
@@ -188,79 +325,70 @@ impl<'source> Parser<'source> {
                 let name = self.parse_identifier()?;
 
                 match &self.current.kind {
+                    // Enum definition: `Color { Red, Green, Blue }`
+                    TokenKind::LBrace if self.at_enum_body() => {
+                        let mut enum_def = self.parse_enum_def_body(is_public, name)?;
+                        enum_def.doc = doc;
+                        Some(Spanned::new(ItemKind::EnumDef(enum_def), self.span(start)))
+                    }
                     // Type definition: `User { ... }`
                     TokenKind::LBrace => {
-                        let type_def = self.parse_type_def_body(is_public, name)?;
+                        let mut type_def = self.parse_type_def_body(is_public, name)?;
+                        type_def.doc = doc;
                         Some(Spanned::new(ItemKind::TypeDef(type_def), self.span(start)))
                     }
                     // Function definition: `foo(...) { ... }`
                     // or expression statement: `foo(...)`
                     TokenKind::LParen => {
-                        // We need to look ahead to determine if this is a function definition or a call.
-                        // Function definitions have a block after the params: `foo(x, y) { ... }`
-                        // Function calls are just expressions: `foo(arg1, arg2)`
-                        //
-                        // The key difference: function definitions require a `{` after `)`,
-                        // while function calls end with `)`.
-                        //
-                        // We'll parse the parens and then check what follows.
-                        let expr = Spanned::new(ExprKind::Identifier(name.node.clone()), name.span);
-
-                        // Try to parse it as a call expression
-                        let call_expr = self.parse_infix(expr, Precedence::None)?;
-
-                        // Check if there's a block following (which would indicate a function def)
-                        if self.check(&TokenKind::LBrace) || self.check(&TokenKind::Arrow) {
-                            // This is a function definition
-                            // Extract parameters from the call expr
-                            if let ExprKind::Call(call) = &call_expr.node {
-                                // Convert arguments back to parameters
-                                let params = self.args_to_params(&call.args)?;
-
-                                let return_ty = if self.check(&TokenKind::Arrow) {
-                                    self.advance();
-                                    Some(self.parse_type()?)
-                                } else {
-                                    None
-                                };
-
-                                let body = self.parse_block()?;
-
-                                Some(Spanned::new(
-                                    ItemKind::FunctionDef(FunctionDef {
-                                        is_public,
-                                        name,
-                                        params,
-                                        return_ty,
-                                        body,
-                                    }),
-                                    self.span(start),
-                                ))
+                        // Function definitions have a block (or a return type)
+                        // after the params: `foo(x, y) { ... }`, while calls
+                        // end at the `)`. Look past the parens to tell them apart.
+                        if self.at_function_params() {
+                            let params = self.parse_params()?;
+
+                            let return_ty = if self.check(&TokenKind::Arrow) {
+                                self.advance();
+                                Some(self.parse_type()?)
                             } else {
-                                // Not a call expression, can't be a function def
-                                self.error(ParseError::ExpectedStatement {
-                                    span: call_expr.span.start as usize
-                                        ..call_expr.span.end as usize,
-                                });
                                 None
-                            }
+                            };
+
+                            let body = self.parse_block()?;
+
+                            Some(Spanned::new(
+                                ItemKind::FunctionDef(FunctionDef {
+                                    is_public,
+                                    name,
+                                    params,
+                                    return_ty,
+                                    body,
+                                    doc,
+                                }),
+                                self.span(start),
+                            ))
                         } else {
                             // This is an expression statement (function call)
+                            let expr =
+                                Spanned::new(ExprKind::Identifier(name.node.clone()), name.span);
+                            let call_expr = self.parse_infix(expr, Precedence::None)?;
                             let stmt =
                                 Spanned::new(StatementKind::Expr(call_expr), self.span(start));
                             Some(Spanned::new(ItemKind::Statement(stmt), self.span(start)))
                         }
                     }
-                    // Method definition: `Type.method(...) { ... }` OR field access/assignment
+                    // Method definition: `Type.method(...) { ... }`, OR a static
+                    // call `Type.method(...)` or field access/assignment
                     TokenKind::Dot => {
                         // Method definitions have uppercase type name (e.g., User.greet)
+                        // and a block after the params, like function definitions.
                         // Field access/assignment has lowercase variable name (e.g., user.age)
                         let first_char = name.node.chars().next().unwrap_or('a');
-                        if first_char.is_uppercase() {
+                        if first_char.is_uppercase() && self.at_method_def() {
                             // Method definition
                             self.advance(); // consume .
                             let method_name = self.parse_identifier()?;
-                            let method = self.parse_method_def_body(name, method_name)?;
+                            let mut method = self.parse_method_def_body(name, method_name)?;
+                            method.doc = doc;
                             Some(Spanned::new(ItemKind::MethodDef(method), self.span(start)))
                         } else {
                             // Field access expression - parse as statement
@@ -292,10 +420,12 @@ impl<'source> Parser<'source> {
                             Some(Spanned::new(ItemKind::Statement(stmt), self.span(start)))
                         }
                     }
-                    // Otherwise it's a statement starting with an identifier
+                    // Otherwise it's a statement starting with an identifier,
+                    // such as `arr[i] = 2` or `x += 1`
                     _ => {
                         // Put the name back as an expression
                         let expr = Spanned::new(ExprKind::Identifier(name.node.clone()), name.span);
+                        let expr = self.parse_expr_rest(expr)?;
                         let stmt = self.parse_statement_rest(expr)?;
                         Some(Spanned::new(ItemKind::Statement(stmt), self.span(start)))
                     }
@@ -310,6 +440,42 @@ impl<'source> Parser<'source> {
                     self.span(start),
                 ))
             }
+            // Constant: `const MAX_USERS = 100`
+            TokenKind::Const => {
+                self.advance();
+                let name = self.parse_identifier()?;
+                self.consume(TokenKind::Eq, "=");
+                let value = self.parse_expr()?;
+                Some(Spanned::new(
+                    ItemKind::Const(ConstDef {
+                        is_public,
+                        name,
+                        value,
+                        doc,
+                    }),
+                    self.span(start),
+                ))
+            }
+            // Import of another file: `import "shapes"`
+            TokenKind::Import => {
+                self.advance();
+                let path = match &self.current.kind {
+                    TokenKind::String(path) => Spanned::new(path.clone(), self.current_span()),
+                    found => {
+                        self.error(ParseError::UnexpectedToken {
+                            expected: "import path string".to_string(),
+                            found: found.clone(),
+                            span: self.current.span.clone(),
+                        });
+                        return None;
+                    }
+                };
+                self.advance();
+                Some(Spanned::new(
+                    ItemKind::Import(Import { path }),
+                    self.span(start),
+                ))
+            }
             // Keywords that start statements
             TokenKind::If
             | TokenKind::For
@@ -320,7 +486,9 @@ impl<'source> Parser<'source> {
             | TokenKind::Break
             | TokenKind::Continue
             | TokenKind::Spawn
-            | TokenKind::Async => {
+            | TokenKind::Async
+            // Tuple destructuring: `(a, b) = pair`
+            | TokenKind::LParen => {
                 let stmt = self.parse_statement()?;
                 let span = stmt.span;
                 Some(Spanned::new(ItemKind::Statement(stmt), span))
@@ -362,6 +530,97 @@ impl<'source> Parser<'source> {
             is_public,
             name,
             fields,
+            doc: None,
+        })
+    }
+
+    /// Whether the braced body starting at the current `{` lists enum
+    /// variants rather than fields.
+    ///
+    /// Variants are capitalized names with an optional parenthesized
+    /// payload. Any entry that is lowercase or has a `:` type or an `=`
+    /// default makes the body a struct's, even if its first field is
+    /// capitalized.
+    fn at_enum_body(&self) -> bool {
+        let rest = &self.source[self.current.span.start..];
+        let mut tokens = Lexer::new(rest)
+            .filter_map(Result::ok)
+            .map(|token| token.kind)
+            .skip(1)
+            .peekable();
+
+        let mut depth = 0;
+        let mut at_entry = true;
+        let mut variants = 0;
+        while let Some(kind) = tokens.next() {
+            match kind {
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace if depth == 0 => break,
+                TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => depth -= 1,
+                TokenKind::Newline | TokenKind::Comma if depth == 0 => at_entry = true,
+                TokenKind::Ident(name) if depth == 0 && at_entry => {
+                    let capitalized = name.starts_with(|c: char| c.is_uppercase());
+                    let typed = matches!(tokens.peek(), Some(TokenKind::Colon | TokenKind::Eq));
+                    if !capitalized || typed {
+                        return false;
+                    }
+                    variants += 1;
+                    at_entry = false;
+                }
+                _ => {}
+            }
+        }
+        variants > 0
+    }
+
+    fn parse_enum_def_body(&mut self, is_public: bool, name: Spanned<SmolStr>) -> Option<EnumDef> {
+        self.consume(TokenKind::LBrace, "{");
+        self.skip_newlines();
+
+        let mut variants = Vec::new();
+
+        while !self.check(&TokenKind::RBrace) && !self.at_end() {
+            if let Some(variant) = self.parse_variant() {
+                variants.push(variant);
+            }
+
+            // Expect comma or newline between variants
+            if self.check(&TokenKind::Comma) {
+                self.advance();
+            }
+            self.skip_newlines();
+        }
+
+        self.consume(TokenKind::RBrace, "}");
+
+        Some(EnumDef {
+            is_public,
+            name,
+            variants,
+            doc: None,
+        })
+    }
+
+    fn parse_variant(&mut self) -> Option<Variant> {
+        let start = self.current.span.start;
+        let name = self.parse_identifier()?;
+
+        let mut fields = Vec::new();
+        if self.check(&TokenKind::LParen) {
+            self.advance();
+            while !self.check(&TokenKind::RParen) && !self.at_end() {
+                fields.push(self.parse_type()?);
+                if !self.check(&TokenKind::RParen) {
+                    self.consume(TokenKind::Comma, ",");
+                }
+            }
+            self.consume(TokenKind::RParen, ")");
+        }
+
+        Some(Variant {
+            name,
+            fields,
+            span: self.span(start),
         })
     }
 
@@ -417,6 +676,7 @@ impl<'source> Parser<'source> {
             params,
             return_ty,
             body,
+            doc: None,
         })
     }
 
@@ -440,45 +700,57 @@ impl<'source> Parser<'source> {
         Some(params)
     }
 
-    /// Convert call arguments back to function parameters.
-    /// Used when we realize a "call" was actually a function definition.
-    fn args_to_params(&mut self, args: &[Argument]) -> Option<Vec<Param>> {
-        let mut params = Vec::new();
+    /// Whether the parenthesized list starting at the current `(` is
+    /// followed by `{` or `->`, making it a function's parameter list.
+    fn at_function_params(&self) -> bool {
+        self.function_params_at(self.current.span.start)
+    }
 
-        for arg in args {
-            // Each argument should be a simple identifier (parameter name)
-            // or a named argument with default value
-            match &arg.value.node {
-                ExprKind::Identifier(name) => {
-                    params.push(Param {
-                        name: Spanned::new(name.clone(), arg.value.span),
-                        ty: None,
-                        default: None,
-                        is_rest: false,
-                        span: arg.span,
-                    });
-                }
-                _ => {
-                    // If arg has a name, it's `name = default_value`
-                    if let Some(param_name) = &arg.name {
-                        params.push(Param {
-                            name: param_name.clone(),
-                            ty: None,
-                            default: Some(arg.value.clone()),
-                            is_rest: false,
-                            span: arg.span,
-                        });
-                    } else {
-                        self.error(ParseError::ExpectedIdent {
-                            span: arg.value.span.start as usize..arg.value.span.end as usize,
-                        });
-                        return None;
+    /// Whether the `.method(...)` starting at the current `.` is followed
+    /// by a body, making it a method definition rather than a static call.
+    fn at_method_def(&self) -> bool {
+        let start = self.current.span.start;
+        let mut tokens = Lexer::new(&self.source[start..])
+            .filter_map(Result::ok)
+            .filter(|token| !matches!(token.kind, TokenKind::Newline));
+        match (tokens.nth(1), tokens.next()) {
+            (Some(method), Some(paren))
+                if matches!(method.kind, TokenKind::Ident(_))
+                    && matches!(paren.kind, TokenKind::LParen) =>
+            {
+                self.function_params_at(start + paren.span.start)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the parenthesized list at byte `start` is followed by a
+    /// block or return type.
+    fn function_params_at(&self, start: usize) -> bool {
+        let rest = &self.source[start..];
+        let mut tokens = Lexer::new(rest)
+            .filter_map(Result::ok)
+            .filter(|token| !matches!(token.kind, TokenKind::Newline));
+
+        let mut depth = 0usize;
+        for token in tokens.by_ref() {
+            match token.kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
                     }
                 }
+                TokenKind::Eof => return false,
+                _ => {}
             }
         }
 
-        Some(params)
+        matches!(
+            tokens.next().map(|token| token.kind),
+            Some(TokenKind::LBrace | TokenKind::Arrow)
+        )
     }
 
     fn parse_param(&mut self) -> Option<Param> {
@@ -519,8 +791,48 @@ impl<'source> Parser<'source> {
     // Types
     // ========================================================================
 
+    /// Parse a type annotation.
+    ///
+    /// `?` binds tightest, then `|`: `int? | string` is a union of an
+    /// option and a string. A function's return type extends as far right
+    /// as possible, so `(int) -> int | string` returns a union; parenthesize
+    /// to group, as in `((int) -> int) | string`.
     fn parse_type(&mut self) -> Option<Spanned<Type>> {
         let start = self.current.span.start;
+        let first = self.parse_optional_type()?;
+        if !self.check(&TokenKind::Pipe) {
+            return Some(first);
+        }
+
+        // Union: `Type | Other`
+        let mut variants = vec![first];
+        while self.check(&TokenKind::Pipe) {
+            self.advance();
+            variants.push(self.parse_optional_type()?);
+        }
+        Some(Spanned::new(Type::Union(variants), self.span(start)))
+    }
+
+    /// Parse a type followed by any number of `?` option markers.
+    fn parse_optional_type(&mut self) -> Option<Spanned<Type>> {
+        let start = self.current.span.start;
+        let mut ty = self.parse_primary_type()?;
+        loop {
+            let markers = match self.current.kind {
+                TokenKind::Question => 1,
+                TokenKind::QuestionQuestion => 2,
+                _ => break,
+            };
+            self.advance();
+            for _ in 0..markers {
+                ty = Spanned::new(Type::Option(Box::new(ty)), self.span(start));
+            }
+        }
+        Some(ty)
+    }
+
+    fn parse_primary_type(&mut self) -> Option<Spanned<Type>> {
+        let start = self.current.span.start;
 
         let ty = match &self.current.kind {
             // Simple named type
@@ -534,16 +846,22 @@ impl<'source> Parser<'source> {
                     let mut args = Vec::new();
                     while !self.check(&TokenKind::Gt) && !self.at_end() {
                         args.push(self.parse_type()?);
+                        self.split_shr();
                         if !self.check(&TokenKind::Gt) {
                             self.consume(TokenKind::Comma, ",");
                         }
                     }
                     self.consume(TokenKind::Gt, ">");
-                    Type::Generic { name, args }
+                    generic_type(name, args)
                 } else {
                     Type::Named(name)
                 }
             }
+            // The unit type, spelled like the value
+            TokenKind::None => {
+                self.advance();
+                Type::Named(SmolStr::new_inline("none"))
+            }
             // List type: `[int]`
             TokenKind::LBracket => {
                 self.advance();
@@ -563,22 +881,33 @@ impl<'source> Parser<'source> {
                     value: Box::new(value),
                 }
             }
-            // Function type: `(int, int) -> int`
+            // Function type `(int, int) -> int`, tuple `(int, int)`, or a
+            // parenthesized type `(int)`
             TokenKind::LParen => {
                 self.advance();
-                let mut params = Vec::new();
+                let mut elements = Vec::new();
+                let mut trailing_comma = false;
                 while !self.check(&TokenKind::RParen) && !self.at_end() {
-                    params.push(self.parse_type()?);
+                    elements.push(self.parse_type()?);
+                    trailing_comma = false;
                     if !self.check(&TokenKind::RParen) {
                         self.consume(TokenKind::Comma, ",");
+                        trailing_comma = true;
                     }
                 }
                 self.consume(TokenKind::RParen, ")");
-                self.consume(TokenKind::Arrow, "->");
-                let ret = self.parse_type()?;
-                Type::Function {
-                    params,
-                    ret: Box::new(ret),
+
+                if self.check(&TokenKind::Arrow) {
+                    self.advance();
+                    let ret = self.parse_type()?;
+                    Type::Function {
+                        params: elements,
+                        ret: Box::new(ret),
+                    }
+                } else if elements.len() == 1 && !trailing_comma {
+                    elements.pop().unwrap().node
+                } else {
+                    Type::Tuple(elements)
                 }
             }
             _ => {
@@ -589,16 +918,6 @@ impl<'source> Parser<'source> {
             }
         };
 
-        // Check for union: `Type | Other`
-        if self.check(&TokenKind::Pipe) {
-            let mut variants = vec![Spanned::new(ty, self.span(start))];
-            while self.check(&TokenKind::Pipe) {
-                self.advance();
-                variants.push(self.parse_type()?);
-            }
-            return Some(Spanned::new(Type::Union(variants), self.span(start)));
-        }
-
         Some(Spanned::new(ty, self.span(start)))
     }
 
@@ -659,17 +978,64 @@ impl<'source> Parser<'source> {
     fn parse_statement_rest(&mut self, first_expr: Expr) -> Option<Statement> {
         let start = first_expr.span.start as usize;
 
+        // Check for typed assignment: `count: int = 0`
+        if self.check(&TokenKind::Colon) && matches!(first_expr.node, ExprKind::Identifier(_)) {
+            self.advance();
+            let ty = self.parse_type()?;
+            self.consume(TokenKind::Eq, "=");
+            let value = self.parse_expr()?;
+
+            let mut target = self.expr_to_assign_target(first_expr)?;
+            target.ty = Some(ty);
+
+            return Some(Spanned::new(
+                StatementKind::Assignment(Assignment {
+                    targets: vec![target],
+                    value,
+                    op: None,
+                }),
+                self.span(start),
+            ));
+        }
+
+        // Check for compound assignment: `x += 1`
+        if let Some(op) = compound_assign_op(&self.current.kind) {
+            let op = Spanned::new(op, self.current_span());
+            self.advance();
+            let value = self.parse_expr()?;
+            let target = self.expr_to_assign_target(first_expr)?;
+
+            return Some(Spanned::new(
+                StatementKind::Assignment(Assignment {
+                    targets: vec![target],
+                    value,
+                    op: Some(op),
+                }),
+                self.span(start),
+            ));
+        }
+
         // Check for assignment
         if self.check(&TokenKind::Eq) {
             self.advance();
             let value = self.parse_expr()?;
 
-            // Convert expression to assignment target
-            let target = self.expr_to_assign_target(first_expr)?;
-            let targets = vec![target];
+            // Convert expression to assignment target(s); `(a, b) = pair`
+            // destructures like `a, b = pair`
+            let targets = match first_expr.node {
+                ExprKind::Tuple(elements) => elements
+                    .into_iter()
+                    .map(|element| self.expr_to_assign_target(element))
+                    .collect::<Option<Vec<_>>>()?,
+                _ => vec![self.expr_to_assign_target(first_expr)?],
+            };
 
             return Some(Spanned::new(
-                StatementKind::Assignment(Assignment { targets, value }),
+                StatementKind::Assignment(Assignment {
+                    targets,
+                    value,
+                    op: None,
+                }),
                 self.span(start),
             ));
         }
@@ -688,7 +1054,11 @@ impl<'source> Parser<'source> {
             let value = self.parse_expr()?;
 
             return Some(Spanned::new(
-                StatementKind::Assignment(Assignment { targets, value }),
+                StatementKind::Assignment(Assignment {
+                    targets,
+                    value,
+                    op: None,
+                }),
                 self.span(start),
             ));
         }
@@ -735,7 +1105,7 @@ impl<'source> Parser<'source> {
     }
 
     fn parse_if_statement(&mut self) -> Option<IfStatement> {
-        let condition = self.parse_expr()?;
+        let condition = self.with_instances(false, Self::parse_expr)?;
         let then_branch = self.parse_block()?;
 
         let else_branch = if self.check(&TokenKind::Else) {
@@ -765,7 +1135,7 @@ impl<'source> Parser<'source> {
     fn parse_for_statement(&mut self) -> Option<ForStatement> {
         let pattern = self.parse_for_pattern()?;
         self.consume(TokenKind::In, "in");
-        let iterator = self.parse_expr()?;
+        let iterator = self.with_instances(false, Self::parse_expr)?;
         let body = self.parse_block()?;
 
         Some(ForStatement {
@@ -788,7 +1158,7 @@ impl<'source> Parser<'source> {
     }
 
     fn parse_while_statement(&mut self) -> Option<WhileStatement> {
-        let condition = self.parse_expr()?;
+        let condition = self.with_instances(false, Self::parse_expr)?;
         let body = self.parse_block()?;
 
         Some(WhileStatement { condition, body })
@@ -836,11 +1206,15 @@ impl<'source> Parser<'source> {
         let mut statements = Vec::new();
 
         while !self.check(&TokenKind::RBrace) && !self.at_end() {
+            let stmt_start = self.current.span.start;
             if let Some(stmt) = self.parse_statement() {
                 statements.push(stmt);
             } else {
-                // Error recovery
-                self.advance();
+                // Error recovery: skip to the next statement
+                if self.current.span.start == stmt_start {
+                    self.advance();
+                }
+                self.synchronize();
             }
             self.skip_newlines();
         }
@@ -873,6 +1247,10 @@ impl<'source> Parser<'source> {
             if prec <= min_prec {
                 break;
             }
+            // A `(` starting a line begins a tuple, not a call
+            if self.check(&TokenKind::LParen) && self.at_line_start() {
+                break;
+            }
 
             left = self.parse_infix(left, prec)?;
         }
@@ -906,6 +1284,14 @@ impl<'source> Parser<'source> {
                     self.span(start),
                 ))
             }
+            TokenKind::Char(c) => {
+                let c = *c;
+                self.advance();
+                Some(Spanned::new(
+                    ExprKind::Literal(Literal::Char(c)),
+                    self.span(start),
+                ))
+            }
             TokenKind::String(s) => {
                 let s = s.clone();
                 self.advance();
@@ -977,9 +1363,10 @@ impl<'source> Parser<'source> {
                 }
 
                 // Check for type instantiation: `User { ... }`
-                // Only treat as instance if the name starts with uppercase (type name convention)
+                // Only treat as instance if the name starts with uppercase (type name convention),
+                // and not in a header like `for i in 0..SIZE { ... }`
                 let first_char = name.chars().next().unwrap_or('a');
-                if first_char.is_uppercase() && self.check(&TokenKind::LBrace) {
+                if first_char.is_uppercase() && self.instances && self.check(&TokenKind::LBrace) {
                     return self.parse_instance(name, start);
                 }
 
@@ -1011,13 +1398,13 @@ impl<'source> Parser<'source> {
             }
 
             // Grouping or lambda: `(...)` or `(x, y) { ... }` or `(x, y) => ...`
-            TokenKind::LParen => self.parse_paren_or_lambda(start),
+            TokenKind::LParen => self.with_instances(true, |p| p.parse_paren_or_lambda(start)),
 
             // List: `[1, 2, 3]`
-            TokenKind::LBracket => self.parse_list(start),
+            TokenKind::LBracket => self.with_instances(true, |p| p.parse_list(start)),
 
             // Map: `{ "a": 1, "b": 2 }`
-            TokenKind::LBrace => self.parse_map_or_block(start),
+            TokenKind::LBrace => self.with_instances(true, |p| p.parse_map_or_block(start)),
 
             // If expression
             TokenKind::If => {
@@ -1139,7 +1526,12 @@ impl<'source> Parser<'source> {
             | TokenKind::Le
             | TokenKind::Ge
             | TokenKind::And
-            | TokenKind::Or => {
+            | TokenKind::Or
+            | TokenKind::QuestionQuestion
+            | TokenKind::Amp
+            | TokenKind::Caret
+            | TokenKind::Shl
+            | TokenKind::Shr => {
                 let op = self.parse_binary_op()?;
                 let right = self.parse_expr_precedence(prec)?;
                 Some(Spanned::new(
@@ -1152,17 +1544,23 @@ impl<'source> Parser<'source> {
                 ))
             }
 
-            // Pipe
+            // Pipe when the right side could name a function, which codegen
+            // settles from the function's signature; otherwise a bitwise or
             TokenKind::Pipe => {
                 self.advance();
                 let right = self.parse_expr_precedence(prec)?;
-                Some(Spanned::new(
-                    ExprKind::Pipe(PipeExpr {
+                let kind = match &right.node {
+                    ExprKind::Identifier(_) | ExprKind::Call(_) => ExprKind::Pipe(PipeExpr {
                         left: Box::new(left),
                         right: Box::new(right),
                     }),
-                    self.span(start),
-                ))
+                    _ => ExprKind::Binary(BinaryExpr {
+                        left: Box::new(left),
+                        op: Spanned::new(BinaryOp::BitOr, op_span),
+                        right: Box::new(right),
+                    }),
+                };
+                Some(Spanned::new(kind, self.span(start)))
             }
 
             // Range
@@ -1193,7 +1591,7 @@ impl<'source> Parser<'source> {
 
             // Call
             TokenKind::LParen => {
-                let args = self.parse_call_args()?;
+                let args = self.with_instances(true, Self::parse_call_args)?;
                 Some(Spanned::new(
                     ExprKind::Call(CallExpr {
                         callee: Box::new(left),
@@ -1206,7 +1604,7 @@ impl<'source> Parser<'source> {
             // Index
             TokenKind::LBracket => {
                 self.advance();
-                let index = self.parse_expr()?;
+                let index = self.with_instances(true, Self::parse_expr)?;
                 self.consume(TokenKind::RBracket, "]");
                 Some(Spanned::new(
                     ExprKind::Index(IndexExpr {
@@ -1220,11 +1618,14 @@ impl<'source> Parser<'source> {
             // Field access or method call
             TokenKind::Dot => {
                 self.advance();
+                if matches!(self.current.kind, TokenKind::Int(_) | TokenKind::Float(_)) {
+                    return self.parse_tuple_index(left, start);
+                }
                 let field = self.parse_identifier()?;
 
                 // Check for method call
                 if self.check(&TokenKind::LParen) {
-                    let args = self.parse_call_args()?;
+                    let args = self.with_instances(true, Self::parse_call_args)?;
                     Some(Spanned::new(
                         ExprKind::MethodCall(MethodCallExpr {
                             receiver: Box::new(left),
@@ -1272,6 +1673,11 @@ impl<'source> Parser<'source> {
             TokenKind::Ge => BinaryOp::Ge,
             TokenKind::And => BinaryOp::And,
             TokenKind::Or => BinaryOp::Or,
+            TokenKind::QuestionQuestion => BinaryOp::Coalesce,
+            TokenKind::Amp => BinaryOp::BitAnd,
+            TokenKind::Caret => BinaryOp::BitXor,
+            TokenKind::Shl => BinaryOp::Shl,
+            TokenKind::Shr => BinaryOp::Shr,
             _ => return None,
         };
         self.advance();
@@ -1296,7 +1702,7 @@ impl<'source> Parser<'source> {
                 } else {
                     // Not a named argument, put it back
                     // We need to re-parse this as an expression
-                    let value = Spanned::new(ExprKind::Identifier(ident.node), ident.span);
+                    let value = ident.map(ExprKind::Identifier);
                     let value = self.parse_expr_rest(value)?;
                     args.push(Argument {
                         name: None,
@@ -1366,8 +1772,40 @@ impl<'source> Parser<'source> {
         // Parse first expression
         let first = self.parse_expr()?;
 
-        // Check if this looks like a parameter list (has comma or type annotation)
-        if self.check(&TokenKind::Comma) || self.check(&TokenKind::Colon) {
+        // A comma makes a tuple, unless a lambda body follows
+        if self.check(&TokenKind::Comma) {
+            let mut elements = vec![first];
+            while self.check(&TokenKind::Comma) {
+                self.advance();
+                // A trailing comma, as in the one-element `(x,)`
+                if self.check(&TokenKind::RParen) {
+                    break;
+                }
+                elements.push(self.parse_expr()?);
+            }
+            self.consume(TokenKind::RParen, ")");
+
+            if !self.check(&TokenKind::FatArrow) && !self.check(&TokenKind::LBrace) {
+                return Some(Spanned::new(ExprKind::Tuple(elements), self.span(start)));
+            }
+            let params = elements
+                .into_iter()
+                .map(|element| self.expr_to_param(element))
+                .collect::<Option<Vec<_>>>()?;
+            let body = if self.check(&TokenKind::FatArrow) {
+                self.advance();
+                LambdaBody::Expr(Box::new(self.parse_expr()?))
+            } else {
+                LambdaBody::Block(self.parse_block()?)
+            };
+            return Some(Spanned::new(
+                ExprKind::Lambda(LambdaExpr { params, body }),
+                self.span(start),
+            ));
+        }
+
+        // A type annotation makes a lambda parameter list
+        if self.check(&TokenKind::Colon) {
             // This is a lambda parameter list
             let mut params = vec![self.expr_to_param(first)?];
 
@@ -1437,6 +1875,31 @@ impl<'source> Parser<'source> {
         ))
     }
 
+    /// Parse the element indices after the `.` of `pair.0`, with `object`
+    /// as `pair`. In `nested.0.1` both indices lex as the float `0.1`.
+    fn parse_tuple_index(&mut self, mut object: Expr, start: usize) -> Option<Expr> {
+        let span = self.current.span.clone();
+        let mut offset = span.start;
+        for index in self.source[span.clone()].split('.') {
+            if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                self.error(ParseError::ExpectedIdent { span });
+                return None;
+            }
+            let end = offset + index.len();
+            let field = Spanned::new(SmolStr::new(index), Span::new(offset as u32, end as u32));
+            object = Spanned::new(
+                ExprKind::Field(FieldExpr {
+                    object: Box::new(object),
+                    field,
+                }),
+                Span::new(start as u32, end as u32),
+            );
+            offset = end + 1;
+        }
+        self.advance();
+        Some(object)
+    }
+
     fn expr_to_param(&mut self, expr: Expr) -> Option<Param> {
         match expr.node {
             ExprKind::Identifier(name) => Some(Param {
@@ -1510,15 +1973,21 @@ impl<'source> Parser<'source> {
             return Some(Spanned::new(ExprKind::Map(entries), self.span(start)));
         }
 
-        // It's a block - convert first_expr to a statement
-        let first_stmt = Spanned::new(StatementKind::Expr(first_expr), self.span(start));
-        let mut statements = vec![first_stmt];
+        // It's a block - finish the statement first_expr starts, such as `a = 1`
+        let mut statements = vec![self.parse_statement_rest(first_expr)?];
 
         self.skip_newlines();
 
         while !self.check(&TokenKind::RBrace) && !self.at_end() {
+            let stmt_start = self.current.span.start;
             if let Some(stmt) = self.parse_statement() {
                 statements.push(stmt);
+            } else {
+                // Error recovery: skip to the next statement
+                if self.current.span.start == stmt_start {
+                    self.advance();
+                }
+                self.synchronize();
             }
             self.skip_newlines();
         }
@@ -1535,6 +2004,8 @@ impl<'source> Parser<'source> {
     }
 
     fn parse_instance(&mut self, type_name: SmolStr, start: usize) -> Option<Expr> {
+        // The type name was the last token
+        let type_name = Spanned::new(type_name, self.span(start));
         self.advance(); // consume {
         self.skip_newlines();
 
@@ -1552,7 +2023,7 @@ impl<'source> Parser<'source> {
                     Some(ident)
                 } else {
                     // Positional field - the identifier itself is the value
-                    let value = Spanned::new(ExprKind::Identifier(ident.node), ident.span);
+                    let value = ident.map(ExprKind::Identifier);
                     fields.push(InstanceField {
                         name: None,
                         value,
@@ -1589,16 +2060,13 @@ impl<'source> Parser<'source> {
         self.consume(TokenKind::RBrace, "}");
 
         Some(Spanned::new(
-            ExprKind::Instance(InstanceExpr {
-                type_name: Spanned::new(type_name, self.span(start)),
-                fields,
-            }),
+            ExprKind::Instance(InstanceExpr { type_name, fields }),
             self.span(start),
         ))
     }
 
     fn parse_match_expr(&mut self) -> Option<MatchExpr> {
-        let subject = self.parse_expr()?;
+        let subject = self.with_instances(false, Self::parse_expr)?;
         self.consume(TokenKind::LBrace, "{");
         self.skip_newlines();
 
@@ -1675,16 +2143,50 @@ impl<'source> Parser<'source> {
 
                     self.consume(TokenKind::RBrace, "}");
                     Pattern::Constructor { name, fields }
+                } else if self.check(&TokenKind::LParen) {
+                    // Enum variant pattern: `Circle(radius)`
+                    self.advance();
+                    let mut fields = Vec::new();
+
+                    while !self.check(&TokenKind::RParen) && !self.at_end() {
+                        fields.push(self.parse_identifier()?);
+                        if !self.check(&TokenKind::RParen) {
+                            self.consume(TokenKind::Comma, ",");
+                        }
+                    }
+
+                    self.consume(TokenKind::RParen, ")");
+                    Pattern::Constructor { name, fields }
                 } else {
                     Pattern::Identifier(name)
                 }
             }
+            // Tuple pattern: `(x, 0, _)`
+            TokenKind::LParen => {
+                self.advance();
+                let mut elements = Vec::new();
+
+                while !self.check(&TokenKind::RParen) && !self.at_end() {
+                    elements.push(self.parse_pattern()?);
+                    if !self.check(&TokenKind::RParen) {
+                        self.consume(TokenKind::Comma, ",");
+                    }
+                }
+
+                self.consume(TokenKind::RParen, ")");
+                Pattern::Tuple(elements)
+            }
             // Literal patterns
             TokenKind::Int(n) => {
                 let n = *n;
                 self.advance();
                 Pattern::Literal(Literal::Int(n))
             }
+            TokenKind::Char(c) => {
+                let c = *c;
+                self.advance();
+                Pattern::Literal(Literal::Char(c))
+            }
             TokenKind::String(s) => {
                 let s = s.clone();
                 self.advance();
@@ -2051,10 +2553,30 @@ impl<'source> Parser<'source> {
                     intent_parts.push("|".to_string());
                     self.advance();
                 }
+                TokenKind::Amp => {
+                    intent_parts.push("&".to_string());
+                    self.advance();
+                }
+                TokenKind::Caret => {
+                    intent_parts.push("^".to_string());
+                    self.advance();
+                }
+                TokenKind::Shl => {
+                    intent_parts.push("<<".to_string());
+                    self.advance();
+                }
+                TokenKind::Shr => {
+                    intent_parts.push(">>".to_string());
+                    self.advance();
+                }
                 TokenKind::Question => {
                     intent_parts.push("?".to_string());
                     self.advance();
                 }
+                TokenKind::QuestionQuestion => {
+                    intent_parts.push("??".to_string());
+                    self.advance();
+                }
                 TokenKind::Percent => {
                     intent_parts.push("%".to_string());
                     self.advance();
@@ -2119,25 +2641,23 @@ impl<'source> Parser<'source> {
 
         while let Some(c) = chars.next() {
             if c == '\\' {
-                // Handle escape sequences
-                match chars.next() {
-                    Some('n') => current_literal.push('\n'),
-                    Some('t') => current_literal.push('\t'),
-                    Some('r') => current_literal.push('\r'),
-                    Some('\\') => current_literal.push('\\'),
-                    Some('"') => current_literal.push('"'),
-                    Some('{') => current_literal.push('{'),
-                    Some('}') => current_literal.push('}'),
-                    Some(other) => {
-                        current_literal.push('\\');
-                        current_literal.push(other);
+                // Keep escapes raw (including `\u{...}`); they are decoded per literal part
+                current_literal.push(c);
+                if let Some(escaped) = chars.next() {
+                    current_literal.push(escaped);
+                    if escaped == 'u' && chars.peek() == Some(&'{') {
+                        for uc in chars.by_ref() {
+                            current_literal.push(uc);
+                            if uc == '}' {
+                                break;
+                            }
+                        }
                     }
-                    None => current_literal.push('\\'),
                 }
             } else if c == '{' {
                 // Start of interpolation - save current literal if not empty
                 if !current_literal.is_empty() {
-                    parts.push(StringPart::Literal(SmolStr::from(&current_literal)));
+                    parts.push(Self::string_literal_part(&current_literal));
                     current_literal.clear();
                 }
 
@@ -2181,11 +2701,36 @@ impl<'source> Parser<'source> {
 
         // Add any remaining literal
         if !current_literal.is_empty() {
-            parts.push(StringPart::Literal(SmolStr::from(&current_literal)));
+            parts.push(Self::string_literal_part(&current_literal));
         }
 
         Some(parts)
     }
+
+    /// Decode the escapes in a literal part of an interpolated string.
+    /// The lexer has already rejected invalid escapes.
+    fn string_literal_part(raw: &str) -> StringPart {
+        let decoded = unescape(raw).unwrap_or_else(|_| raw.to_string());
+        StringPart::Literal(SmolStr::from(decoded))
+    }
+}
+
+/// The type for `name<args>`, spelling the built-in generics `List<T>`,
+/// `Map<K, V>`, and `Option<T>` as their dedicated forms.
+fn generic_type(name: SmolStr, mut args: Vec<Spanned<Type>>) -> Type {
+    match (name.as_str(), args.len()) {
+        ("List", 1) => Type::List(Box::new(args.remove(0))),
+        ("Option", 1) => Type::Option(Box::new(args.remove(0))),
+        ("Map", 2) => {
+            let value = args.pop().unwrap();
+            let key = args.pop().unwrap();
+            Type::Map {
+                key: Box::new(key),
+                value: Box::new(value),
+            }
+        }
+        _ => Type::Generic { name, args },
+    }
 }
 
 #[cfg(test)]
@@ -2223,6 +2768,273 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_function_typed_and_rest_params() {
+        let ast = parse("label(prefix: string, values: int...) -> string {\n    prefix\n}\nlabel(\"a\", 1, 2)\n");
+        assert_eq!(ast.items.len(), 2);
+        match &ast.items[0].node {
+            ItemKind::FunctionDef(def) => {
+                assert_eq!(def.params.len(), 2);
+                assert!(def.params[0].ty.is_some());
+                assert!(!def.params[0].is_rest);
+                assert!(def.params[1].is_rest);
+            }
+            _ => panic!("expected function def"),
+        }
+        assert!(matches!(ast.items[1].node, ItemKind::Statement(_)));
+    }
+
+    #[test]
+    fn test_const() {
+        let ast = parse("/// Most users\npublic const MAX_USERS = 10 * 10\n");
+        match &ast.items[0].node {
+            ItemKind::Const(const_def) => {
+                assert!(const_def.is_public);
+                assert_eq!(const_def.name.node.as_str(), "MAX_USERS");
+                assert!(matches!(const_def.value.node, ExprKind::Binary(_)));
+                assert_eq!(const_def.doc.as_deref(), Some("Most users"));
+            }
+            _ => panic!("expected const"),
+        }
+
+        // A constant before a block is not a struct instance
+        let (ast, errors) = parse_with_errors("for i in 0..MAX_USERS {\n    print(i)\n}\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(ast.items[0].node, ItemKind::Statement(_)));
+
+        let (_, errors) = parse_with_errors("while count < LIMIT {\n    count = count + 1\n}\n");
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_all_caps_struct_instance() {
+        let source = "RGB { r, g, b }\n\nc = RGB { r = 1, g = 2, b = 3 }\nfor x in [RGB { r = 0, g = 0, b = 0 }] {\n    print(x)\n}\n";
+        let (ast, errors) = parse_with_errors(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        match &ast.items[1].node {
+            ItemKind::Statement(stmt) => match &stmt.node {
+                StatementKind::Assignment(assign) => {
+                    assert!(matches!(assign.value.node, ExprKind::Instance(_)));
+                }
+                _ => panic!("expected assignment"),
+            },
+            _ => panic!("expected statement"),
+        }
+    }
+
+    #[test]
+    fn test_import() {
+        let source = "import \"lib/shapes\"\n\narea(r) {\n    r * r\n}\n";
+        let ast = parse(source);
+        assert_eq!(ast.items.len(), 2);
+        match &ast.items[0].node {
+            ItemKind::Import(import) => {
+                assert_eq!(import.path.node.as_str(), "lib/shapes");
+                let span = import.path.span;
+                assert_eq!(
+                    &source[span.start as usize..span.end as usize],
+                    "\"lib/shapes\""
+                );
+            }
+            _ => panic!("expected import"),
+        }
+
+        let (_, errors) = parse_with_errors("import shapes\n");
+        assert!(matches!(errors[0], ParseError::UnexpectedToken { .. }));
+    }
+
+    #[test]
+    fn test_list_literals() {
+        let ast = parse("a = []\nb = [1,]\nc = [1, 2, 3,]\nd = [\n    1,\n    2,\n]\n");
+        let lengths: Vec<_> = ast
+            .items
+            .iter()
+            .map(|item| match &item.node {
+                ItemKind::Statement(stmt) => match &stmt.node {
+                    StatementKind::Assignment(assign) => match &assign.value.node {
+                        ExprKind::List(elements) => elements.len(),
+                        other => panic!("expected list, got {:?}", other),
+                    },
+                    _ => panic!("expected assignment"),
+                },
+                _ => panic!("expected statement"),
+            })
+            .collect();
+        assert_eq!(lengths, [0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_negative_float_is_unary_neg() {
+        let ast = parse("x = -2.5e-3\ny = 1e10\n");
+        let values: Vec<_> = ast
+            .items
+            .iter()
+            .map(|item| match &item.node {
+                ItemKind::Statement(stmt) => match &stmt.node {
+                    StatementKind::Assignment(assign) => assign.value.node.clone(),
+                    _ => panic!("expected assignment"),
+                },
+                _ => panic!("expected statement"),
+            })
+            .collect();
+        match &values[0] {
+            ExprKind::Unary(unary) => {
+                assert_eq!(unary.op.node, UnaryOp::Neg);
+                assert_eq!(
+                    unary.operand.node,
+                    ExprKind::Literal(Literal::Float(2.5e-3))
+                );
+            }
+            other => panic!("expected unary negation, got {:?}", other),
+        }
+        assert_eq!(values[1], ExprKind::Literal(Literal::Float(1e10)));
+    }
+
+    /// Parse `annotation` as a parameter type and render its structure.
+    fn annotation(annotation: &str) -> String {
+        fn render(ty: &Type) -> String {
+            let list = |types: &[Spanned<Type>]| {
+                types
+                    .iter()
+                    .map(|ty| render(&ty.node))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            match ty {
+                Type::Named(name) => name.to_string(),
+                Type::List(inner) => format!("List({})", render(&inner.node)),
+                Type::Map { key, value } => {
+                    format!("Map({}, {})", render(&key.node), render(&value.node))
+                }
+                Type::Option(inner) => format!("Option({})", render(&inner.node)),
+                Type::Tuple(elements) => format!("Tuple({})", list(elements)),
+                Type::Function { params, ret } => {
+                    format!("Fn({}) -> {}", list(params), render(&ret.node))
+                }
+                Type::Union(variants) => format!("Union({})", list(variants)),
+                Type::Generic { name, args } => format!("{}<{}>", name, list(args)),
+            }
+        }
+
+        let source = format!("f(x: {}) {{\n    x\n}}\n", annotation);
+        let mut parser = Parser::new(&source);
+        let ast = parser.parse_source_file();
+        assert!(parser.errors.is_empty(), "{:?}", parser.errors);
+        match &ast.items[0].node {
+            ItemKind::FunctionDef(def) => render(&def.params[0].ty.as_ref().unwrap().node),
+            _ => panic!("expected function def"),
+        }
+    }
+
+    #[test]
+    fn test_generic_types() {
+        assert_eq!(annotation("List<int>"), "List(int)");
+        assert_eq!(annotation("Map<string, int>"), "Map(string, int)");
+        assert_eq!(annotation("Option<User>"), "Option(User)");
+        assert_eq!(annotation("Box<T>"), "Box<T>");
+        assert_eq!(
+            annotation("Map<string, List<int>>"),
+            "Map(string, List(int))"
+        );
+        assert_eq!(
+            annotation("List<List<Option<int>>>"),
+            "List(List(Option(int)))"
+        );
+    }
+
+    #[test]
+    fn test_function_and_tuple_types() {
+        assert_eq!(annotation("(int, int) -> bool"), "Fn(int, int) -> bool");
+        assert_eq!(annotation("() -> int"), "Fn() -> int");
+        assert_eq!(annotation("(a, b)"), "Tuple(a, b)");
+        assert_eq!(annotation("(int,)"), "Tuple(int)");
+        assert_eq!(annotation("(int)"), "int");
+        assert_eq!(
+            annotation("(int) -> (int) -> int"),
+            "Fn(int) -> Fn(int) -> int"
+        );
+    }
+
+    #[test]
+    fn test_union_and_option_types() {
+        assert_eq!(annotation("int?"), "Option(int)");
+        assert_eq!(annotation("List<int>?"), "Option(List(int))");
+        assert_eq!(annotation("[int?]"), "List(Option(int))");
+        assert_eq!(annotation("int | string"), "Union(int, string)");
+        assert_eq!(
+            annotation("int? | string | none"),
+            "Union(Option(int), string, none)"
+        );
+        assert_eq!(annotation("(int | string)?"), "Option(Union(int, string))");
+        assert_eq!(
+            annotation("(int) -> int | string"),
+            "Fn(int) -> Union(int, string)"
+        );
+        assert_eq!(
+            annotation("((int) -> int) | string"),
+            "Union(Fn(int) -> int, string)"
+        );
+        assert_eq!(annotation("(int) -> int?"), "Fn(int) -> Option(int)");
+    }
+
+    #[test]
+    fn test_method_definition_and_static_call() {
+        let ast =
+            parse("User.create(name) {\n    name\n}\nUser.create(\"a\")\nu = User.create(\"b\")\n");
+        assert_eq!(ast.items.len(), 3);
+        assert!(matches!(ast.items[0].node, ItemKind::MethodDef(_)));
+        for item in &ast.items[1..] {
+            let ItemKind::Statement(stmt) = &item.node else {
+                panic!("expected statement");
+            };
+            let value = match &stmt.node {
+                StatementKind::Expr(expr) => expr,
+                StatementKind::Assignment(assign) => &assign.value,
+                other => panic!("expected call, got {:?}", other),
+            };
+            match &value.node {
+                ExprKind::MethodCall(call) => {
+                    assert_eq!(call.receiver.node, ExprKind::Identifier("User".into()));
+                    assert_eq!(call.method.node.as_str(), "create");
+                }
+                other => panic!("expected method call, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_enum_definition() {
+        let ast = parse(
+            "Shape {\n    Circle(float)\n    Rect(float, float)\n    Empty\n}\nUser { name }\n",
+        );
+        assert_eq!(ast.items.len(), 2);
+        match &ast.items[0].node {
+            ItemKind::EnumDef(def) => {
+                assert_eq!(def.name.node.as_str(), "Shape");
+                let arity: Vec<_> = def.variants.iter().map(|v| v.fields.len()).collect();
+                assert_eq!(arity, [1, 2, 0]);
+            }
+            _ => panic!("expected enum def"),
+        }
+        assert!(matches!(ast.items[1].node, ItemKind::TypeDef(_)));
+    }
+
+    #[test]
+    fn test_struct_with_capitalized_first_field() {
+        let ast =
+            parse("Header {\n    ID: int\n    name\n}\nRow { Key, value }\nPair { A(int), B }\n");
+        assert_eq!(ast.items.len(), 3);
+        match &ast.items[0].node {
+            ItemKind::TypeDef(def) => {
+                let names: Vec<_> = def.fields.iter().map(|f| f.name.node.as_str()).collect();
+                assert_eq!(names, ["ID", "name"]);
+            }
+            _ => panic!("expected type def"),
+        }
+        assert!(matches!(ast.items[1].node, ItemKind::TypeDef(_)));
+        assert!(matches!(ast.items[2].node, ItemKind::EnumDef(_)));
+    }
+
     #[test]
     fn test_assignment() {
         let ast = parse("x = 42");
@@ -2244,6 +3056,126 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_doc_comment_on_function() {
+        let ast =
+            parse("/// Adds two numbers.\n/// Returns their sum.\nadd(a, b) {\n    a + b\n}\n");
+        match &ast.items[0].node {
+            ItemKind::FunctionDef(func) => assert_eq!(
+                func.doc.as_deref(),
+                Some("Adds two numbers.\nReturns their sum.")
+            ),
+            _ => panic!("expected function"),
+        }
+    }
+
+    #[test]
+    fn test_doc_comment_on_type_and_method() {
+        let ast = parse("/// A user.\nUser { name }\n\n/// Say hello.\nUser.greet() {\n    1\n}\n");
+        match &ast.items[0].node {
+            ItemKind::TypeDef(def) => assert_eq!(def.doc.as_deref(), Some("A user.")),
+            _ => panic!("expected type"),
+        }
+        match &ast.items[1].node {
+            ItemKind::MethodDef(method) => assert_eq!(method.doc.as_deref(), Some("Say hello.")),
+            _ => panic!("expected method"),
+        }
+    }
+
+    #[test]
+    fn test_plain_and_trailing_comments_are_not_docs() {
+        let ast = parse("// Not a doc comment.\nfirst() {\n    1\n}\nx = 1 /// trailing\nsecond() {\n    2\n}\n");
+        for item in &ast.items {
+            if let ItemKind::FunctionDef(func) = &item.node {
+                assert_eq!(func.doc, None, "{}", func.name.node);
+            }
+        }
+    }
+
+    fn parse_with_errors(source: &str) -> (SourceFile, Vec<ParseError>) {
+        let mut parser = Parser::new(source);
+        let ast = parser.parse_source_file();
+        (ast, parser.into_errors())
+    }
+
+    #[test]
+    fn test_recovery_at_top_level() {
+        let (ast, errors) = parse_with_errors("a = 1\nb = (2 + ) * [3, 4\nc = 3\nprint(c)\n");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        // `a`, `c` and `print(c)` survive the broken statement
+        assert_eq!(ast.items.len(), 3);
+    }
+
+    #[test]
+    fn test_recovery_in_block() {
+        let source = "main() {\n    x = 1\n    y = (x + ]\n    if x > 0 {\n        print(x)\n    }\n}\nafter() {\n    1\n}\n";
+        let (ast, errors) = parse_with_errors(source);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(ast.items.len(), 2);
+        match &ast.items[0].node {
+            ItemKind::FunctionDef(func) => {
+                assert_eq!(func.body.statements.len(), 2);
+                assert!(matches!(func.body.statements[1].node, StatementKind::If(_)));
+            }
+            _ => panic!("expected function"),
+        }
+        match &ast.items[1].node {
+            ItemKind::FunctionDef(func) => assert_eq!(func.name.node.as_str(), "after"),
+            _ => panic!("expected function"),
+        }
+    }
+
+    fn typed_assignment(source: &str) -> (SmolStr, Type, ExprKind) {
+        let ast = parse(source);
+        assert_eq!(ast.items.len(), 1);
+        match &ast.items[0].node {
+            ItemKind::Statement(stmt) => match &stmt.node {
+                StatementKind::Assignment(assign) => {
+                    let target = &assign.targets[0];
+                    let AssignPath::Identifier(name) = &target.path else {
+                        panic!("expected identifier");
+                    };
+                    let ty = target.ty.as_ref().expect("expected annotation");
+                    (
+                        name.node.clone(),
+                        ty.node.clone(),
+                        assign.value.node.clone(),
+                    )
+                }
+                _ => panic!("expected assignment"),
+            },
+            _ => panic!("expected statement"),
+        }
+    }
+
+    #[test]
+    fn test_typed_assignment() {
+        let (name, ty, value) = typed_assignment("count: int = 0");
+        assert_eq!(name.as_str(), "count");
+        assert_eq!(ty, Type::Named("int".into()));
+        assert!(matches!(value, ExprKind::Literal(Literal::Int(0))));
+
+        let (name, ty, value) = typed_assignment("name: string = \"x\"");
+        assert_eq!(name.as_str(), "name");
+        assert_eq!(ty, Type::Named("string".into()));
+        assert!(matches!(value, ExprKind::Literal(Literal::String(ref s)) if s == "x"));
+    }
+
+    #[test]
+    fn test_typed_assignment_in_function() {
+        let ast = parse("f() {\n    ratio: float = 1\n    ratio\n}");
+        match &ast.items[0].node {
+            ItemKind::FunctionDef(func) => match &func.body.statements[0].node {
+                StatementKind::Assignment(assign) => {
+                    let ty = assign.targets[0].ty.as_ref().expect("expected annotation");
+                    assert_eq!(ty.node, Type::Named("float".into()));
+                }
+                _ => panic!("expected assignment"),
+            },
+            _ => panic!("expected function"),
+        }
+    }
+
     #[test]
     fn test_pipe_expression() {
         let ast = parse("x = users | filter_active | sort_by_name");
@@ -2357,6 +3289,205 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compound_assignment() {
+        let ast = parse("x += 1\nuser.count -= 2\narr[i] <<= 3\nf() {\n    x *= 2\n}\n");
+        let assignments: Vec<(String, BinaryOp)> = ast
+            .items
+            .iter()
+            .flat_map(|item| match &item.node {
+                ItemKind::Statement(stmt) => vec![stmt.node.clone()],
+                ItemKind::FunctionDef(def) => def
+                    .body
+                    .statements
+                    .iter()
+                    .map(|stmt| stmt.node.clone())
+                    .collect(),
+                other => panic!("unexpected item {:?}", other),
+            })
+            .map(|stmt| match stmt {
+                StatementKind::Assignment(assign) => {
+                    let target = match &assign.targets[..] {
+                        [target] => format!("{:?}", target.path),
+                        targets => panic!("expected one target, got {:?}", targets),
+                    };
+                    (target, assign.op.expect("compound operator").node)
+                }
+                other => panic!("expected assignment, got {:?}", other),
+            })
+            .collect();
+
+        let ops: Vec<BinaryOp> = assignments.iter().map(|(_, op)| *op).collect();
+        assert_eq!(
+            ops,
+            [BinaryOp::Add, BinaryOp::Sub, BinaryOp::Shl, BinaryOp::Mul]
+        );
+        assert!(assignments[1].0.starts_with("Field"));
+        assert!(assignments[2].0.starts_with("Index"));
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let ast = parse("a = 1 | 2 ^ 3 & 4 << 5\nb = x | f\nc = x | f()\nd = x | (y)\n");
+        let values: Vec<&ExprKind> = ast
+            .items
+            .iter()
+            .map(|item| match &item.node {
+                ItemKind::Statement(Spanned {
+                    node: StatementKind::Assignment(assign),
+                    ..
+                }) => &assign.value.node,
+                other => panic!("expected assignment, got {:?}", other),
+            })
+            .collect();
+
+        // `|` binds loosest, then `^`, `&` and the shifts
+        let op = |expr: &ExprKind| match expr {
+            ExprKind::Binary(binary) => (binary.op.node, binary.right.node.clone()),
+            other => panic!("expected binary, got {:?}", other),
+        };
+        let (or, rest) = op(values[0]);
+        assert_eq!(or, BinaryOp::BitOr);
+        let (xor, rest) = op(&rest);
+        assert_eq!(xor, BinaryOp::BitXor);
+        let (and, rest) = op(&rest);
+        assert_eq!(and, BinaryOp::BitAnd);
+        assert_eq!(op(&rest).0, BinaryOp::Shl);
+
+        // A name or call on the right of `|` is left for codegen to decide
+        assert!(matches!(values[1], ExprKind::Pipe(_)));
+        assert!(matches!(values[2], ExprKind::Pipe(_)));
+        assert!(matches!(values[3], ExprKind::Binary(_)));
+    }
+
+    #[test]
+    fn test_default_operator() {
+        let ast = parse("a = x ?? y or z\nf(n: int??) {\n}\n");
+
+        // `??` binds looser than `or`
+        let ItemKind::Statement(Spanned {
+            node: StatementKind::Assignment(assign),
+            ..
+        }) = &ast.items[0].node
+        else {
+            panic!("expected assignment, got {:?}", ast.items[0].node);
+        };
+        let ExprKind::Binary(binary) = &assign.value.node else {
+            panic!("expected binary, got {:?}", assign.value.node);
+        };
+        assert_eq!(binary.op.node, BinaryOp::Coalesce);
+        assert!(matches!(
+            &binary.right.node,
+            ExprKind::Binary(or) if or.op.node == BinaryOp::Or
+        ));
+
+        // `??` in a type is two option markers
+        let ItemKind::FunctionDef(func) = &ast.items[1].node else {
+            panic!("expected function, got {:?}", ast.items[1].node);
+        };
+        let ty = &func.params[0].ty.as_ref().unwrap().node;
+        assert!(matches!(ty, Type::Option(inner) if matches!(inner.node, Type::Option(_))));
+    }
+
+    #[test]
+    fn test_block_values() {
+        let ast = parse("z = {\n    a = 2\n    a * 3\n}\nf() {\n    if x {\n        1\n    } else {\n        2\n    }\n}\ng() {\n    y = 1\n}\n");
+
+        // The block's first statement is a whole assignment, and its value
+        // the trailing expression
+        let ItemKind::Statement(Spanned {
+            node: StatementKind::Assignment(assign),
+            ..
+        }) = &ast.items[0].node
+        else {
+            panic!("expected assignment, got {:?}", ast.items[0].node);
+        };
+        let ExprKind::Block(block) = &assign.value.node else {
+            panic!("expected block, got {:?}", assign.value.node);
+        };
+        assert!(matches!(
+            block.statements[0].node,
+            StatementKind::Assignment(_)
+        ));
+        assert!(matches!(
+            &block.value().unwrap().node,
+            StatementKind::Expr(Spanned {
+                node: ExprKind::Binary(_),
+                ..
+            })
+        ));
+
+        // An `if` with an `else` is a value; an assignment isn't
+        let body = |index: usize| match &ast.items[index].node {
+            ItemKind::FunctionDef(func) => &func.body,
+            other => panic!("expected function, got {:?}", other),
+        };
+        assert!(matches!(
+            &body(1).value().unwrap().node,
+            StatementKind::If(_)
+        ));
+        assert!(body(2).value().is_none());
+    }
+
+    #[test]
+    fn test_tuples() {
+        let ast = parse(
+            "pair = (1, \"x\")\n(a, b) = pair\nc = nested.0.1\nadd = (a, b) => a + b\nmatch pair {\n    (0, _) => 0\n    (n, s) => n\n}\n",
+        );
+        let statements: Vec<&StatementKind> = ast
+            .items
+            .iter()
+            .map(|item| match &item.node {
+                ItemKind::Statement(stmt) => &stmt.node,
+                other => panic!("expected statement, got {:?}", other),
+            })
+            .collect();
+
+        let StatementKind::Assignment(assign) = statements[0] else {
+            panic!("expected assignment");
+        };
+        assert!(matches!(&assign.value.node, ExprKind::Tuple(elements) if elements.len() == 2));
+
+        let StatementKind::Assignment(assign) = statements[1] else {
+            panic!("expected assignment");
+        };
+        let targets: Vec<_> = assign
+            .targets
+            .iter()
+            .map(|target| match &target.path {
+                AssignPath::Identifier(name) => name.node.as_str(),
+                other => panic!("expected identifier, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(targets, ["a", "b"]);
+
+        let StatementKind::Assignment(assign) = statements[2] else {
+            panic!("expected assignment");
+        };
+        let ExprKind::Field(outer) = &assign.value.node else {
+            panic!("expected field access");
+        };
+        assert_eq!(outer.field.node, "1");
+        assert!(matches!(&outer.object.node, ExprKind::Field(inner) if inner.field.node == "0"));
+
+        let StatementKind::Assignment(assign) = statements[3] else {
+            panic!("expected assignment");
+        };
+        assert!(matches!(&assign.value.node, ExprKind::Lambda(lambda) if lambda.params.len() == 2));
+
+        let StatementKind::Match(match_expr) = statements[4] else {
+            panic!("expected match");
+        };
+        assert!(matches!(
+            &match_expr.arms[0].pattern.node,
+            Pattern::Tuple(elements)
+                if matches!(elements[..], [
+                    Spanned { node: Pattern::Literal(Literal::Int(0)), .. },
+                    Spanned { node: Pattern::Wildcard, .. },
+                ])
+        ));
+    }
+
     #[test]
     fn test_match_expression() {
         let ast = parse(
@@ -2420,4 +3551,47 @@ mod tests {
             _ => panic!("expected statement"),
         }
     }
+
+    #[test]
+    fn test_interpolated_string_escapes() {
+        let ast = parse(r#"msg = "\t{name}\u{21}\n""#);
+        match &ast.items[0].node {
+            ItemKind::Statement(stmt) => match &stmt.node {
+                StatementKind::Assignment(assign) => match &assign.value.node {
+                    ExprKind::Literal(Literal::InterpolatedString(parts)) => {
+                        assert_eq!(parts.len(), 3);
+                        assert!(matches!(&parts[0], StringPart::Literal(s) if s == "\t"));
+                        assert!(matches!(&parts[1], StringPart::Expr(_)));
+                        assert!(matches!(&parts[2], StringPart::Literal(s) if s == "!\n"));
+                    }
+                    _ => panic!("expected interpolated string"),
+                },
+                _ => panic!("expected assignment"),
+            },
+            _ => panic!("expected statement"),
+        }
+    }
+
+    #[test]
+    fn test_char_literal() {
+        let ast = parse("c = '\\n'\nmatch c {\n    'a' => 1\n    _ => 0\n}\n");
+        match &ast.items[0].node {
+            ItemKind::Statement(stmt) => match &stmt.node {
+                StatementKind::Assignment(assign) => {
+                    assert_eq!(assign.value.node, ExprKind::Literal(Literal::Char('\n')));
+                }
+                _ => panic!("expected assignment"),
+            },
+            _ => panic!("expected statement"),
+        }
+        match &ast.items[1].node {
+            ItemKind::Statement(stmt) => match &stmt.node {
+                StatementKind::Match(m) => {
+                    assert_eq!(m.arms[0].pattern.node, Pattern::Literal(Literal::Char('a')));
+                }
+                _ => panic!("expected match"),
+            },
+            _ => panic!("expected statement"),
+        }
+    }
 }
@@ -4,11 +4,17 @@
 //! - Resolving identifiers to their definitions
 //! - Building scope trees
 //! - Detecting undefined references
+//! - Warning about unused local variables
 //! - Collecting unresolved function calls for AI interpretation
 
-use haira_ast::SourceFile;
-use rustc_hash::FxHashMap;
+use haira_ast::{
+    AssignPath, Block, ElseBranch, Expr, ExprKind, ForPattern, IfStatement, ItemKind, LambdaBody,
+    Literal, MatchArm, MatchArmBody, Param, Pattern, SourceFile, Span, Spanned, StatementKind,
+    StringPart, Type,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
 use smol_str::SmolStr;
+use std::ops::Range;
 
 /// Result of name resolution.
 pub struct ResolvedModule {
@@ -18,6 +24,8 @@ pub struct ResolvedModule {
     pub unresolved_calls: Vec<UnresolvedCall>,
     /// Resolution errors.
     pub errors: Vec<ResolutionError>,
+    /// Resolution warnings.
+    pub warnings: Vec<ResolutionWarning>,
 }
 
 /// A resolved definition.
@@ -56,14 +64,647 @@ pub struct UnresolvedCall {
 pub struct ResolutionError {
     pub message: String,
     pub span: std::ops::Range<usize>,
+    /// Index of the item the error is in, within the resolved file's items.
+    pub item: usize,
+}
+
+/// Resolution warning.
+#[derive(Debug, Clone)]
+pub struct ResolutionWarning {
+    pub message: String,
+    pub span: std::ops::Range<usize>,
+    /// Index of the item the warning is in, within the resolved file's items.
+    pub item: usize,
 }
 
 /// Resolve names in a source file.
-pub fn resolve(_ast: &SourceFile) -> ResolvedModule {
-    // TODO: Implement name resolution
+///
+/// Scopes are currently flow-insensitive: a name bound anywhere in a
+/// function body (or at module level) counts as defined throughout it.
+/// Calls to unknown functions are not errors, since they may be generated
+/// by AI interpretation.
+///
+/// Parameters and locals of a function that are never read are reported
+/// as warnings, unless their name starts with `_`.
+pub fn resolve(ast: &SourceFile) -> ResolvedModule {
+    resolve_modules(ast, std::slice::from_ref(&(0..ast.items.len())))
+}
+
+/// Resolve names in a source file merged from several modules, each given
+/// as the range of its items, like [`resolve`].
+///
+/// A module can only refer to the functions, types and constants of another
+/// module that are `public`; within a module, visibility doesn't matter.
+pub fn resolve_modules(ast: &SourceFile, modules: &[Range<usize>]) -> ResolvedModule {
+    let module_of = |item: usize| modules.iter().position(|module| module.contains(&item));
+    let mut globals = FxHashSet::default();
+    // The kind and module of every non-public function and type
+    let mut private = FxHashMap::default();
+    let mut module = Collector::default();
+
+    for (index, item) in ast.items.iter().enumerate() {
+        match &item.node {
+            ItemKind::TypeDef(type_def) => {
+                globals.insert(type_def.name.node.clone());
+                if !type_def.is_public {
+                    private.insert(type_def.name.node.clone(), ("type", module_of(index)));
+                }
+            }
+            ItemKind::FunctionDef(func) => {
+                globals.insert(func.name.node.clone());
+                if !func.is_public {
+                    private.insert(func.name.node.clone(), ("function", module_of(index)));
+                }
+            }
+            ItemKind::EnumDef(enum_def) => {
+                globals.insert(enum_def.name.node.clone());
+                if !enum_def.is_public {
+                    private.insert(enum_def.name.node.clone(), ("type", module_of(index)));
+                }
+                for variant in &enum_def.variants {
+                    globals.insert(variant.name.node.clone());
+                }
+            }
+            ItemKind::TypeAlias(alias) => {
+                globals.insert(alias.name.node.clone());
+            }
+            ItemKind::Const(const_def) => {
+                globals.insert(const_def.name.node.clone());
+                if !const_def.is_public {
+                    private.insert(const_def.name.node.clone(), ("constant", module_of(index)));
+                }
+            }
+            ItemKind::AiFunctionDef(ai_block) => {
+                if let Some(name) = &ai_block.name {
+                    globals.insert(name.node.clone());
+                }
+            }
+            ItemKind::Statement(stmt) => {
+                module.item = index;
+                module.statement(&stmt.node);
+            }
+            ItemKind::MethodDef(_) | ItemKind::Import(_) => {}
+        }
+    }
+    globals.extend(module.bound.iter().cloned());
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    module.report(&globals, &mut errors);
+    module.report_private(&private, module_of, &mut errors);
+
+    for (index, item) in ast.items.iter().enumerate() {
+        let mut body = Collector {
+            item: index,
+            ..Collector::default()
+        };
+        match &item.node {
+            ItemKind::TypeDef(type_def) => {
+                for field in &type_def.fields {
+                    if let Some(ty) = &field.ty {
+                        body.type_refs(ty);
+                    }
+                    if let Some(default) = &field.default {
+                        body.expr(default);
+                    }
+                }
+            }
+            ItemKind::FunctionDef(func) => {
+                body.params(&func.params);
+                if let Some(ty) = &func.return_ty {
+                    body.type_refs(ty);
+                }
+                body.block(&func.body);
+            }
+            ItemKind::MethodDef(method) => {
+                body.bound.insert(SmolStr::new_inline("self"));
+                body.params(&method.params);
+                if let Some(ty) = &method.return_ty {
+                    body.type_refs(ty);
+                }
+                body.block(&method.body);
+            }
+            ItemKind::AiFunctionDef(ai_block) => body.params(&ai_block.params),
+            ItemKind::Const(const_def) => body.expr(&const_def.value),
+            ItemKind::EnumDef(_)
+            | ItemKind::TypeAlias(_)
+            | ItemKind::Statement(_)
+            | ItemKind::Import(_) => continue,
+        }
+        body.report(&globals, &mut errors);
+        body.report_private(&private, module_of, &mut errors);
+        body.report_unused(&globals, &mut warnings);
+    }
+
     ResolvedModule {
         definitions: FxHashMap::default(),
         unresolved_calls: Vec::new(),
-        errors: Vec::new(),
+        errors,
+        warnings,
+    }
+}
+
+/// Collects the names bound in a scope and the identifiers it reads.
+#[derive(Default)]
+struct Collector {
+    bound: FxHashSet<SmolStr>,
+    /// Where each bound name is first defined, in definition order.
+    definitions: Vec<(SmolStr, Span)>,
+    /// Each identifier read, with the item it is read in.
+    uses: Vec<(SmolStr, Span, usize)>,
+    /// Each name called or used as a type, with the item it is used in.
+    references: Vec<(SmolStr, Span, usize)>,
+    /// The item being walked.
+    item: usize,
+}
+
+impl Collector {
+    /// Report every use that is neither bound here nor global.
+    fn report(&self, globals: &FxHashSet<SmolStr>, errors: &mut Vec<ResolutionError>) {
+        for (name, span, item) in &self.uses {
+            if !self.bound.contains(name) && !globals.contains(name) {
+                errors.push(ResolutionError {
+                    message: format!("undefined variable `{}`", name),
+                    span: span.start as usize..span.end as usize,
+                    item: *item,
+                });
+            }
+        }
+    }
+
+    /// Report every use or reference, not bound here, of a private function
+    /// or type of another module.
+    fn report_private(
+        &self,
+        private: &FxHashMap<SmolStr, (&str, Option<usize>)>,
+        module_of: impl Fn(usize) -> Option<usize>,
+        errors: &mut Vec<ResolutionError>,
+    ) {
+        for (name, span, item) in self.uses.iter().chain(&self.references) {
+            if self.bound.contains(name) {
+                continue;
+            }
+            if let Some((kind, owner)) = private.get(name) {
+                if module_of(*item) != *owner {
+                    errors.push(ResolutionError {
+                        message: format!(
+                            "{} `{}` is private to another module; mark it `public` to use it here",
+                            kind, name
+                        ),
+                        span: span.start as usize..span.end as usize,
+                        item: *item,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Warn about every name defined here that is never read. Globals are
+    /// skipped: assigning one updates it, and a pattern naming one (such
+    /// as an enum variant) matches rather than binds.
+    fn report_unused(&self, globals: &FxHashSet<SmolStr>, warnings: &mut Vec<ResolutionWarning>) {
+        let used: FxHashSet<&SmolStr> = self.uses.iter().map(|(name, ..)| name).collect();
+        for (name, span) in &self.definitions {
+            if !used.contains(name) && !name.starts_with('_') && !globals.contains(name) {
+                warnings.push(ResolutionWarning {
+                    message: format!("unused variable `{}`", name),
+                    span: span.start as usize..span.end as usize,
+                    item: self.item,
+                });
+            }
+        }
+    }
+
+    /// Bind `name`, defined at `span`.
+    fn bind(&mut self, name: &SmolStr, span: Span) {
+        if self.bound.insert(name.clone()) {
+            self.definitions.push((name.clone(), span));
+        }
+    }
+
+    fn params(&mut self, params: &[Param]) {
+        for param in params {
+            self.bind(&param.name.node, param.name.span);
+            if let Some(ty) = &param.ty {
+                self.type_refs(ty);
+            }
+            if let Some(default) = &param.default {
+                self.expr(default);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.statements {
+            self.statement(&stmt.node);
+        }
+    }
+
+    fn statement(&mut self, stmt: &StatementKind) {
+        match stmt {
+            StatementKind::Assignment(assign) => {
+                // A compound assignment updates an existing variable
+                for target in &assign.targets {
+                    self.assign_path(&target.path, assign.op.is_none());
+                }
+                self.expr(&assign.value);
+            }
+            StatementKind::If(if_stmt) => self.if_statement(if_stmt),
+            StatementKind::For(for_stmt) => {
+                match &for_stmt.pattern {
+                    ForPattern::Single(name) => self.bind(&name.node, name.span),
+                    ForPattern::Pair(first, second) => {
+                        self.bind(&first.node, first.span);
+                        self.bind(&second.node, second.span);
+                    }
+                }
+                self.expr(&for_stmt.iterator);
+                self.block(&for_stmt.body);
+            }
+            StatementKind::While(while_stmt) => {
+                self.expr(&while_stmt.condition);
+                self.block(&while_stmt.body);
+            }
+            StatementKind::Match(match_expr) => {
+                self.expr(&match_expr.subject);
+                self.arms(&match_expr.arms);
+            }
+            StatementKind::Return(ret) => {
+                for value in &ret.values {
+                    self.expr(value);
+                }
+            }
+            StatementKind::Try(try_stmt) => {
+                self.block(&try_stmt.body);
+                self.bind(&try_stmt.error_name.node, try_stmt.error_name.span);
+                self.block(&try_stmt.catch_body);
+            }
+            StatementKind::Expr(expr) => self.expr(expr),
+            StatementKind::Break | StatementKind::Continue => {}
+        }
+    }
+
+    /// Walk an assignment target. Only the root of a plain `x = ...`
+    /// binds a name; `x.field = ...` and `x[i] = ...` read `x`.
+    fn assign_path(&mut self, path: &AssignPath, binds: bool) {
+        match path {
+            AssignPath::Identifier(name) => {
+                if binds {
+                    self.bind(&name.node, name.span);
+                } else {
+                    self.uses.push((name.node.clone(), name.span, self.item));
+                }
+            }
+            AssignPath::Field { object, .. } => self.assign_path(object, false),
+            AssignPath::Index { object, index } => {
+                self.assign_path(object, false);
+                self.expr(index);
+            }
+        }
+    }
+
+    fn if_statement(&mut self, if_stmt: &IfStatement) {
+        self.expr(&if_stmt.condition);
+        self.block(&if_stmt.then_branch);
+        match &if_stmt.else_branch {
+            Some(ElseBranch::Block(block)) => self.block(block),
+            Some(ElseBranch::ElseIf(else_if)) => self.if_statement(&else_if.node),
+            None => {}
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Spanned<Pattern>) {
+        match &pattern.node {
+            Pattern::Identifier(name) => self.bind(name, pattern.span),
+            Pattern::Constructor { fields, .. } => {
+                for field in fields {
+                    self.bind(&field.node, field.span);
+                }
+            }
+            Pattern::Tuple(elements) => {
+                for element in elements {
+                    self.pattern(element);
+                }
+            }
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+        }
+    }
+
+    fn arms(&mut self, arms: &[MatchArm]) {
+        for arm in arms {
+            self.pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                self.expr(guard);
+            }
+            self.arm_body(&arm.body);
+        }
+    }
+
+    fn arm_body(&mut self, body: &MatchArmBody) {
+        match body {
+            MatchArmBody::Expr(expr) => self.expr(expr),
+            MatchArmBody::Block(block) => self.block(block),
+        }
+    }
+
+    /// Walk an expression in call position, where an unknown name is an
+    /// unresolved call rather than an undefined variable.
+    fn callee(&mut self, expr: &Expr) {
+        match &expr.node {
+            ExprKind::Identifier(name) => {
+                self.references.push((name.clone(), expr.span, self.item))
+            }
+            _ => self.expr(expr),
+        }
+    }
+
+    /// Record the named types a type annotation refers to.
+    fn type_refs(&mut self, ty: &Spanned<Type>) {
+        match &ty.node {
+            Type::Named(name) => self.references.push((name.clone(), ty.span, self.item)),
+            Type::Generic { name, args } => {
+                self.references.push((name.clone(), ty.span, self.item));
+                for arg in args {
+                    self.type_refs(arg);
+                }
+            }
+            Type::List(inner) | Type::Option(inner) => self.type_refs(inner),
+            Type::Map { key, value } => {
+                self.type_refs(key);
+                self.type_refs(value);
+            }
+            Type::Tuple(types) | Type::Union(types) => {
+                for ty in types {
+                    self.type_refs(ty);
+                }
+            }
+            Type::Function { params, ret } => {
+                for param in params {
+                    self.type_refs(param);
+                }
+                self.type_refs(ret);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.node {
+            ExprKind::Identifier(name) => self.uses.push((name.clone(), expr.span, self.item)),
+            ExprKind::Literal(Literal::InterpolatedString(parts)) => {
+                for part in parts {
+                    if let StringPart::Expr(inner) = part {
+                        self.expr(inner);
+                    }
+                }
+            }
+            ExprKind::Literal(_) | ExprKind::None => {}
+            ExprKind::Binary(bin) => {
+                self.expr(&bin.left);
+                self.expr(&bin.right);
+            }
+            ExprKind::Unary(unary) => self.expr(&unary.operand),
+            ExprKind::Call(call) => {
+                self.callee(&call.callee);
+                for arg in &call.args {
+                    self.expr(&arg.value);
+                }
+            }
+            ExprKind::MethodCall(call) => {
+                self.expr(&call.receiver);
+                for arg in &call.args {
+                    self.expr(&arg.value);
+                }
+            }
+            ExprKind::Field(field) => self.expr(&field.object),
+            ExprKind::Index(index) => {
+                self.expr(&index.object);
+                self.expr(&index.index);
+            }
+            ExprKind::Pipe(pipe) => {
+                self.expr(&pipe.left);
+                self.callee(&pipe.right);
+            }
+            ExprKind::Lambda(lambda) => {
+                self.params(&lambda.params);
+                match &lambda.body {
+                    LambdaBody::Expr(body) => self.expr(body),
+                    LambdaBody::Block(block) => self.block(block),
+                }
+            }
+            ExprKind::Match(match_expr) => {
+                self.expr(&match_expr.subject);
+                self.arms(&match_expr.arms);
+            }
+            ExprKind::If(if_stmt) => self.if_statement(if_stmt),
+            ExprKind::Block(block) | ExprKind::Async(block) | ExprKind::Spawn(block) => {
+                self.block(block)
+            }
+            ExprKind::List(elements) | ExprKind::Tuple(elements) => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            ExprKind::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            ExprKind::Instance(instance) => {
+                self.references.push((
+                    instance.type_name.node.clone(),
+                    instance.type_name.span,
+                    self.item,
+                ));
+                for field in &instance.fields {
+                    self.expr(&field.value);
+                }
+            }
+            ExprKind::Range(range) => {
+                self.expr(&range.start);
+                self.expr(&range.end);
+            }
+            ExprKind::Propagate(inner) | ExprKind::Some(inner) | ExprKind::Paren(inner) => {
+                self.expr(inner)
+            }
+            ExprKind::Select(select) => {
+                for arm in &select.arms {
+                    self.bind(&arm.binding.node, arm.binding.span);
+                    self.expr(&arm.channel);
+                    self.arm_body(&arm.body);
+                }
+                if let Some(default) = &select.default {
+                    self.block(default);
+                }
+            }
+            ExprKind::Ai(ai_block) => self.params(&ai_block.params),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(source: &str) -> Vec<String> {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        resolve(&result.ast)
+            .errors
+            .into_iter()
+            .map(|err| format!("{} at {}", err.message, &source[err.span]))
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_defined_names_resolve() {
+        let source = "\
+Point { x, y }
+
+Point.sum() {
+    return self.x + self.y
+}
+
+double(n) {
+    return n * 2
+}
+
+const COUNT = 3
+total = 0
+for i in 0..COUNT {
+    total = total + double(i)
+}
+p = Point { x = total, y = 1 }
+print(p.sum())
+";
+        assert!(errors(source).is_empty(), "{:?}", errors(source));
+    }
+
+    #[test]
+    fn test_undefined_variable_is_an_error() {
+        let source = "f(a) {\n    return a + b\n}\n";
+        assert_eq!(errors(source), vec!["undefined variable `b` at b"]);
+    }
+
+    #[test]
+    fn test_unknown_call_is_not_an_error() {
+        assert!(errors("x = get_users_by_age(30)\n").is_empty());
+    }
+
+    /// The errors resolving `source` as two modules, the first holding its
+    /// first `split` items.
+    fn module_errors(source: &str, split: usize) -> Vec<String> {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let modules = [0..split, split..result.ast.items.len()];
+        resolve_modules(&result.ast, &modules)
+            .errors
+            .into_iter()
+            .map(|err| format!("{} at {}", err.message, &source[err.span]))
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_public_items_of_another_module_resolve() {
+        let source = "\
+public Point { x, y }
+
+public norm(p: Point) -> int {
+    return p.x + p.y
+}
+
+main() {
+    p = Point { x = 1, y = 2 }
+    print(norm(p))
+}
+";
+        assert!(
+            module_errors(source, 2).is_empty(),
+            "{:?}",
+            module_errors(source, 2)
+        );
+    }
+
+    #[test]
+    fn test_private_items_of_another_module_are_errors() {
+        let source = "\
+Point { x, y }
+
+norm(p) {
+    return p.x + p.y
+}
+
+main(q: Point) {
+    p = Point { x = 1, y = 2 }
+    print(norm(p))
+}
+";
+        assert_eq!(
+            module_errors(source, 2),
+            vec![
+                "type `Point` is private to another module; mark it `public` to use it here at Point",
+                "type `Point` is private to another module; mark it `public` to use it here at Point",
+                "function `norm` is private to another module; mark it `public` to use it here at norm",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_private_items_within_a_module_resolve() {
+        let source = "\
+helper(n) {
+    return n + 1
+}
+
+main() {
+    print(helper(1))
+}
+
+public api() {
+    return helper(2)
+}
+";
+        assert!(errors(source).is_empty(), "{:?}", errors(source));
+        assert!(module_errors(source, 0).is_empty());
+    }
+
+    fn warnings(source: &str) -> Vec<String> {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        resolve(&result.ast)
+            .warnings
+            .into_iter()
+            .map(|warning| format!("{} at {}", warning.message, &source[warning.span]))
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_unused_local_is_a_warning() {
+        let source = "f(a, unused_param) {\n    x = 1\n    x = 2\n    return a\n}\n";
+        assert_eq!(
+            warnings(source),
+            vec![
+                "unused variable `unused_param` at unused_param",
+                "unused variable `x` at x"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_used_local_is_not_a_warning() {
+        let source = "f(a) {\n    x = a * 2\n    for i in 0..x {\n        print(i)\n    }\n}\n";
+        assert!(warnings(source).is_empty(), "{:?}", warnings(source));
+    }
+
+    #[test]
+    fn test_underscore_local_is_not_a_warning() {
+        let source = "f(_a) {\n    _ignored = 1\n    for _ in 0..3 {\n        print(1)\n    }\n}\n";
+        assert!(warnings(source).is_empty(), "{:?}", warnings(source));
+    }
+
+    #[test]
+    fn test_variant_pattern_is_not_a_warning() {
+        let source = "Color { Red, Green }\n\nf(c) {\n    return match c {\n        Red => 1\n        other => 2\n    }\n}\n";
+        assert_eq!(warnings(source), vec!["unused variable `other` at other"]);
     }
 }
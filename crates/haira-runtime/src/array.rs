@@ -0,0 +1,142 @@
+//! Growable arrays
+//!
+//! Lists are `HairaArray` headers pointing at a buffer of 8-byte slots.
+//! Each slot holds one element as a machine word: ints and bools directly,
+//! floats as their bit pattern, and strings, structs and lists as pointers.
+
+use crate::memory::{haira_alloc, haira_realloc};
+use std::ptr;
+
+/// Slots allocated on the first push.
+const INITIAL_CAPACITY: i64 = 4;
+
+/// The runtime list representation.
+///
+/// Compiled code reads `len` at offset 0 and `data` at offset 16 directly.
+#[repr(C)]
+pub struct HairaArray {
+    /// Number of elements.
+    pub len: i64,
+    /// Number of slots allocated in `data`.
+    pub cap: i64,
+    /// Element slots, or NULL when nothing has been allocated.
+    pub data: *mut i64,
+}
+
+/// Create an empty array
+#[no_mangle]
+pub extern "C" fn haira_array_new() -> *mut HairaArray {
+    let arr = haira_alloc(std::mem::size_of::<HairaArray>() as i64) as *mut HairaArray;
+    if !arr.is_null() {
+        unsafe {
+            arr.write(HairaArray {
+                len: 0,
+                cap: 0,
+                data: ptr::null_mut(),
+            });
+        }
+    }
+    arr
+}
+
+/// Append a value, doubling the buffer when it is full
+#[no_mangle]
+pub extern "C" fn haira_array_push(arr: *mut HairaArray, value: i64) {
+    let Some(arr) = (unsafe { arr.as_mut() }) else {
+        return;
+    };
+
+    if arr.len == arr.cap {
+        let cap = if arr.cap == 0 {
+            INITIAL_CAPACITY
+        } else {
+            arr.cap * 2
+        };
+        let data = haira_realloc(arr.data as *mut u8, cap * 8) as *mut i64;
+        if data.is_null() {
+            return;
+        }
+        arr.data = data;
+        arr.cap = cap;
+    }
+
+    unsafe { arr.data.add(arr.len as usize).write(value) };
+    arr.len += 1;
+}
+
+/// Remove and return the last value, or 0 if the array is empty
+#[no_mangle]
+pub extern "C" fn haira_array_pop(arr: *mut HairaArray) -> i64 {
+    let Some(arr) = (unsafe { arr.as_mut() }) else {
+        return 0;
+    };
+    if arr.len == 0 {
+        return 0;
+    }
+    arr.len -= 1;
+    unsafe { arr.data.add(arr.len as usize).read() }
+}
+
+/// Get the value at an index, or 0 if it is out of range
+#[no_mangle]
+pub extern "C" fn haira_array_get(arr: *const HairaArray, index: i64) -> i64 {
+    let Some(arr) = (unsafe { arr.as_ref() }) else {
+        return 0;
+    };
+    if index < 0 || index >= arr.len {
+        return 0;
+    }
+    unsafe { arr.data.add(index as usize).read() }
+}
+
+/// Get the number of elements
+#[no_mangle]
+pub extern "C" fn haira_array_len(arr: *const HairaArray) -> i64 {
+    unsafe { arr.as_ref() }.map_or(0, |arr| arr.len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_grows_past_initial_capacity() {
+        let arr = haira_array_new();
+        for i in 0..10 {
+            haira_array_push(arr, i * 10);
+        }
+
+        assert_eq!(haira_array_len(arr), 10);
+        assert!(unsafe { (*arr).cap } >= 10);
+        for i in 0..10 {
+            assert_eq!(haira_array_get(arr, i), i * 10);
+        }
+    }
+
+    #[test]
+    fn test_pop_returns_last_value() {
+        let arr = haira_array_new();
+        haira_array_push(arr, 1);
+        haira_array_push(arr, 2);
+
+        assert_eq!(haira_array_pop(arr), 2);
+        assert_eq!(haira_array_pop(arr), 1);
+        assert_eq!(haira_array_pop(arr), 0);
+        assert_eq!(haira_array_len(arr), 0);
+    }
+
+    #[test]
+    fn test_out_of_range_get_is_zero() {
+        let arr = haira_array_new();
+        haira_array_push(arr, 7);
+
+        assert_eq!(haira_array_get(arr, 1), 0);
+        assert_eq!(haira_array_get(arr, -1), 0);
+    }
+
+    #[test]
+    fn test_layout_matches_codegen_offsets() {
+        assert_eq!(std::mem::offset_of!(HairaArray, len), 0);
+        assert_eq!(std::mem::offset_of!(HairaArray, data), 16);
+    }
+}
@@ -1,5 +1,6 @@
 //! Concurrency primitives - threads and channels
 
+use crate::memory::{alloc_value, take_value};
 use std::collections::VecDeque;
 use std::sync::{Condvar, Mutex};
 use std::thread;
@@ -27,7 +28,7 @@ struct ChannelBuffer {
 pub extern "C" fn haira_channel_new(capacity: i64) -> *mut HairaChannel {
     let cap = if capacity <= 0 { 1 } else { capacity as usize };
 
-    let inner = Box::new(ChannelInner {
+    let inner = alloc_value(ChannelInner {
         buffer: Mutex::new(ChannelBuffer {
             queue: VecDeque::with_capacity(cap),
             capacity: cap,
@@ -37,11 +38,11 @@ pub extern "C" fn haira_channel_new(capacity: i64) -> *mut HairaChannel {
         not_full: Condvar::new(),
     });
 
-    let channel = Box::new(HairaChannel {
-        inner: Box::into_raw(inner),
-    });
+    if inner.is_null() {
+        return std::ptr::null_mut();
+    }
 
-    Box::into_raw(channel)
+    alloc_value(HairaChannel { inner })
 }
 
 /// Send a value to the channel (blocks if full)
@@ -160,8 +161,7 @@ pub extern "C" fn haira_spawn_joinable(func: extern "C" fn()) -> i64 {
     });
 
     // Store the handle
-    let boxed = Box::new(handle);
-    Box::into_raw(boxed) as i64
+    alloc_value(handle) as i64
 }
 
 /// Wait for a joinable thread to complete
@@ -171,8 +171,7 @@ pub extern "C" fn haira_thread_join(handle: i64) {
         return;
     }
 
-    let boxed: Box<thread::JoinHandle<()>> =
-        unsafe { Box::from_raw(handle as *mut thread::JoinHandle<()>) };
+    let handle = unsafe { take_value(handle as *mut thread::JoinHandle<()>) };
 
-    let _ = boxed.join();
+    let _ = handle.join();
 }
@@ -23,7 +23,7 @@ pub extern "C" fn haira_env_get(name: *const u8, name_len: i64) -> *mut HairaStr
             let cap = len + 1;
 
             let data = unsafe {
-                let ptr = libc::malloc(cap as usize) as *mut u8;
+                let ptr = crate::memory::haira_alloc(cap);
                 if !ptr.is_null() {
                     ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
                     *ptr.add(bytes.len()) = 0;
@@ -31,8 +31,7 @@ pub extern "C" fn haira_env_get(name: *const u8, name_len: i64) -> *mut HairaStr
                 ptr
             };
 
-            let result = Box::new(HairaString { data, len, cap });
-            Box::into_raw(result)
+            crate::memory::alloc_value(HairaString { data, len, cap })
         }
         Err(_) => ptr::null_mut(),
     }
@@ -73,7 +72,7 @@ pub extern "C" fn haira_file_read(path: *const u8, path_len: i64) -> *mut HairaS
             let cap = len + 1;
 
             let data = unsafe {
-                let ptr = libc::malloc(cap as usize) as *mut u8;
+                let ptr = crate::memory::haira_alloc(cap);
                 if !ptr.is_null() {
                     ptr::copy_nonoverlapping(contents.as_ptr(), ptr, contents.len());
                     *ptr.add(contents.len()) = 0;
@@ -81,8 +80,7 @@ pub extern "C" fn haira_file_read(path: *const u8, path_len: i64) -> *mut HairaS
                 ptr
             };
 
-            let result = Box::new(HairaString { data, len, cap });
-            Box::into_raw(result)
+            crate::memory::alloc_value(HairaString { data, len, cap })
         }
         Err(_) => {
             haira_set_error(1);
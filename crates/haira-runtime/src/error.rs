@@ -1,18 +1,35 @@
 //! Error handling with thread-local state
+//!
+//! Each thread, including every spawned one, has its own error code and
+//! message, so concurrent functions can't clobber each other's errors.
 
+use crate::strings::HairaString;
 use std::cell::Cell;
+use std::ptr;
 
 thread_local! {
     static CURRENT_ERROR: Cell<i64> = const { Cell::new(0) };
+    static ERROR_MESSAGE: Cell<*mut HairaString> = const { Cell::new(ptr::null_mut()) };
 }
 
 /// Set current error
 #[no_mangle]
 pub extern "C" fn haira_set_error(error: i64) {
     CURRENT_ERROR.with(|e| e.set(error));
+    ERROR_MESSAGE.with(|m| m.set(ptr::null_mut()));
+}
+
+/// Set current error with a message, for `err("message")`
+#[no_mangle]
+pub extern "C" fn haira_set_error_message(message: *mut HairaString) {
+    CURRENT_ERROR.with(|e| e.set(1));
+    ERROR_MESSAGE.with(|m| m.set(message));
 }
 
 /// Get and clear current error
+///
+/// The message stays readable until the next error is set or cleared, so a
+/// `catch` block can still ask for it.
 #[no_mangle]
 pub extern "C" fn haira_get_error() -> i64 {
     CURRENT_ERROR.with(|e| {
@@ -22,6 +39,17 @@ pub extern "C" fn haira_get_error() -> i64 {
     })
 }
 
+/// Get the current error's message, or an empty string if it has none
+#[no_mangle]
+pub extern "C" fn haira_get_error_message() -> *mut HairaString {
+    let message = ERROR_MESSAGE.with(|m| m.get());
+    if message.is_null() {
+        HairaString::new(b"")
+    } else {
+        message
+    }
+}
+
 /// Check if there's an error
 #[no_mangle]
 pub extern "C" fn haira_has_error() -> i64 {
@@ -32,4 +60,51 @@ pub extern "C" fn haira_has_error() -> i64 {
 #[no_mangle]
 pub extern "C" fn haira_clear_error() {
     CURRENT_ERROR.with(|e| e.set(0));
+    ERROR_MESSAGE.with(|m| m.set(ptr::null_mut()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    fn message() -> String {
+        let message = unsafe { &*haira_get_error_message() };
+        let bytes = unsafe { std::slice::from_raw_parts(message.data, message.len as usize) };
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+
+    #[test]
+    fn test_message_outlives_get_error() {
+        haira_set_error_message(HairaString::new(b"boom"));
+        assert_eq!(haira_get_error(), 1);
+        assert_eq!(haira_has_error(), 0);
+        assert_eq!(message(), "boom");
+
+        haira_clear_error();
+        assert_eq!(message(), "");
+    }
+
+    #[test]
+    fn test_threads_have_their_own_errors() {
+        // Both threads set their error before either reads one back
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = ["first", "second"]
+            .into_iter()
+            .map(|text| {
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    haira_set_error_message(HairaString::new(text.as_bytes()));
+                    barrier.wait();
+                    assert_eq!(haira_has_error(), 1);
+                    message()
+                })
+            })
+            .collect();
+
+        let messages: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(messages, ["first", "second"]);
+        assert_eq!(haira_has_error(), 0);
+    }
 }
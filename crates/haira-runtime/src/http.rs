@@ -0,0 +1,86 @@
+//! HTTP client functions
+//!
+//! Requests are blocking. Without the `http` feature the functions are
+//! still exported but always fail, so compiled programs link either way.
+
+use crate::error::haira_set_error;
+use crate::strings::HairaString;
+use std::ptr;
+
+/// Fetch a URL and return the response body (returns NULL and sets the error flag on failure)
+#[no_mangle]
+pub extern "C" fn haira_http_get(url_ptr: *const u8, url_len: i64) -> *mut HairaString {
+    if url_ptr.is_null() || url_len <= 0 {
+        haira_set_error(1);
+        return ptr::null_mut();
+    }
+    let url = unsafe { std::slice::from_raw_parts(url_ptr, url_len as usize) };
+
+    match std::str::from_utf8(url).ok().and_then(get) {
+        Some(body) => HairaString::new(body.as_bytes()),
+        None => {
+            haira_set_error(1);
+            ptr::null_mut()
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+fn get(url: &str) -> Option<String> {
+    ureq::get(url).call().ok()?.into_string().ok()
+}
+
+#[cfg(not(feature = "http"))]
+fn get(_url: &str) -> Option<String> {
+    None
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+    use crate::error::{haira_clear_error, haira_get_error};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve a single request with a fixed body and return the server's URL.
+    fn mock_server(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+        format!("http://{}/", addr)
+    }
+
+    #[test]
+    fn test_get_returns_body() {
+        let url = mock_server("hello from haira");
+        let body = haira_http_get(url.as_ptr(), url.len() as i64);
+
+        assert!(!body.is_null());
+        let body = unsafe { &*body };
+        let bytes = unsafe { std::slice::from_raw_parts(body.data, body.len as usize) };
+        assert_eq!(bytes, b"hello from haira");
+    }
+
+    #[test]
+    fn test_failure_sets_error() {
+        haira_clear_error();
+        let url = "not a url";
+
+        assert!(haira_http_get(url.as_ptr(), url.len() as i64).is_null());
+        assert_eq!(haira_get_error(), 1);
+    }
+}
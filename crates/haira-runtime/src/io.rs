@@ -1,5 +1,6 @@
 //! I/O functions
 
+use std::cell::Cell;
 use std::io::{self, Write};
 
 /// Print a string (pointer + length)
@@ -26,7 +27,7 @@ pub extern "C" fn haira_print_float(value: f64) {
 
 /// Print a boolean
 #[no_mangle]
-pub extern "C" fn haira_print_bool(value: i8) {
+pub extern "C" fn haira_print_bool(value: i64) {
     print!("{}", if value != 0 { "true" } else { "false" });
 }
 
@@ -49,3 +50,68 @@ pub extern "C" fn haira_panic(msg: *const u8, len: i64) {
     eprintln!("panic: {}", message);
     std::process::exit(1);
 }
+
+/// Exit after an integer division or remainder by zero.
+#[no_mangle]
+pub extern "C" fn haira_panic_div_zero() {
+    let _ = io::stdout().flush();
+    eprintln!("panic: division by zero");
+    std::process::exit(1);
+}
+
+thread_local! {
+    /// Address near the top of this thread's stack, taken at its first check.
+    static STACK_BASE: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Abort if this thread's stack has grown more than `limit` bytes since its
+/// first check, instead of crashing on a stack overflow.
+///
+/// Called on function entry when the stack guard is on.
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn haira_check_stack(limit: i64) {
+    let marker = 0u8;
+    let here = std::hint::black_box(&marker) as *const u8 as usize;
+    let base = STACK_BASE.with(|base| {
+        if base.get() == 0 {
+            base.set(here);
+        }
+        base.get()
+    });
+    if base.saturating_sub(here) > limit as usize {
+        let _ = io::stdout().flush();
+        eprintln!(
+            "panic: stack overflow (more than {} bytes of stack used)",
+            limit
+        );
+        std::process::abort();
+    }
+}
+
+/// Abort after a checked integer operation overflowed.
+///
+/// `op` names the operation, e.g. `add`.
+#[no_mangle]
+pub extern "C" fn haira_panic_overflow(op: *const u8, len: i64) {
+    let op = if op.is_null() || len <= 0 {
+        "arithmetic".to_string()
+    } else {
+        let slice = unsafe { std::slice::from_raw_parts(op, len as usize) };
+        String::from_utf8_lossy(slice).to_string()
+    };
+    let _ = io::stdout().flush();
+    eprintln!("panic: attempt to {} with overflow", op);
+    std::process::abort();
+}
+
+/// Abort on a list index outside `0..len`.
+#[no_mangle]
+pub extern "C" fn haira_panic_index(index: i64, len: i64) {
+    let _ = io::stdout().flush();
+    eprintln!(
+        "panic: index {} out of bounds for a list of length {}",
+        index, len
+    );
+    std::process::abort();
+}
@@ -0,0 +1,149 @@
+//! JSON parsing and serialization
+//!
+//! JSON values are opaque handles: pointers to a `serde_json::Value`
+//! that compiled code only passes back into these functions. Every handle
+//! returned here is owned by the caller and independent of the value it
+//! was derived from, so `haira_json_get_field` hands out a copy of the
+//! field rather than a borrow into its parent. A null handle means the
+//! operation failed; the error flag is set whenever one is produced, and
+//! every function accepts null handles, failing in turn.
+
+use crate::error::haira_set_error;
+use crate::memory::{alloc_value, take_value};
+use crate::strings::HairaString;
+use serde_json::Value;
+use std::ptr;
+
+/// The runtime JSON value representation.
+pub type HairaJson = Value;
+
+fn into_handle(value: Value) -> *mut HairaJson {
+    alloc_value(value)
+}
+
+fn fail<T>(value: T) -> T {
+    haira_set_error(1);
+    value
+}
+
+/// Parse a JSON document (returns NULL and sets the error flag if invalid)
+#[no_mangle]
+pub extern "C" fn haira_json_parse(str_ptr: *const u8, str_len: i64) -> *mut HairaJson {
+    if str_ptr.is_null() || str_len <= 0 {
+        return fail(ptr::null_mut());
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(str_ptr, str_len as usize) };
+    match serde_json::from_slice(bytes) {
+        Ok(value) => into_handle(value),
+        Err(_) => fail(ptr::null_mut()),
+    }
+}
+
+/// Get a copy of an object field (returns NULL and sets the error flag if missing)
+#[no_mangle]
+pub extern "C" fn haira_json_get_field(
+    value: *const HairaJson,
+    key_ptr: *const u8,
+    key_len: i64,
+) -> *mut HairaJson {
+    let Some(value) = (unsafe { value.as_ref() }) else {
+        return fail(ptr::null_mut());
+    };
+    let key = if key_ptr.is_null() || key_len <= 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(key_ptr, key_len as usize) }
+    };
+    let field = std::str::from_utf8(key).ok().and_then(|key| value.get(key));
+    match field {
+        Some(field) => into_handle(field.clone()),
+        None => fail(ptr::null_mut()),
+    }
+}
+
+/// Read an integer value (returns 0 and sets the error flag for other values)
+#[no_mangle]
+pub extern "C" fn haira_json_as_int(value: *const HairaJson) -> i64 {
+    match unsafe { value.as_ref() }.and_then(Value::as_i64) {
+        Some(n) => n,
+        None => fail(0),
+    }
+}
+
+/// Read a string value (returns NULL and sets the error flag for other values)
+#[no_mangle]
+pub extern "C" fn haira_json_as_string(value: *const HairaJson) -> *mut HairaString {
+    match unsafe { value.as_ref() }.and_then(Value::as_str) {
+        Some(s) => HairaString::new(s.as_bytes()),
+        None => fail(ptr::null_mut()),
+    }
+}
+
+/// Serialize a value as compact JSON
+#[no_mangle]
+pub extern "C" fn haira_json_stringify(value: *const HairaJson) -> *mut HairaString {
+    match unsafe { value.as_ref() } {
+        Some(value) => HairaString::new(value.to_string().as_bytes()),
+        None => fail(ptr::null_mut()),
+    }
+}
+
+/// Free a JSON value handle
+#[no_mangle]
+pub extern "C" fn haira_json_free(value: *mut HairaJson) {
+    if !value.is_null() {
+        drop(unsafe { take_value(value) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{haira_clear_error, haira_get_error};
+
+    fn parse(s: &str) -> *mut HairaJson {
+        haira_json_parse(s.as_ptr(), s.len() as i64)
+    }
+
+    fn string(s: *mut HairaString) -> String {
+        let s = unsafe { &*s };
+        let bytes = unsafe { std::slice::from_raw_parts(s.data, s.len as usize) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_field_and_stringify() {
+        let value = parse(r#"{"a":1}"#);
+        assert!(!value.is_null());
+
+        let a = haira_json_get_field(value, "a".as_ptr(), 1);
+        assert_eq!(haira_json_as_int(a), 1);
+        assert_eq!(string(haira_json_stringify(value)), r#"{"a":1}"#);
+
+        haira_json_free(a);
+        haira_json_free(value);
+    }
+
+    #[test]
+    fn test_as_string() {
+        let value = parse(r#"{"name":"haira"}"#);
+        let name = haira_json_get_field(value, "name".as_ptr(), 4);
+        assert_eq!(string(haira_json_as_string(name)), "haira");
+    }
+
+    #[test]
+    fn test_failures_set_error() {
+        haira_clear_error();
+        assert!(parse("{").is_null());
+        assert_eq!(haira_get_error(), 1);
+
+        haira_clear_error();
+        let value = parse("[1, 2]");
+        assert!(haira_json_get_field(value, "a".as_ptr(), 1).is_null());
+        assert_eq!(haira_get_error(), 1);
+
+        haira_clear_error();
+        assert_eq!(haira_json_as_int(ptr::null()), 0);
+        assert_eq!(haira_get_error(), 1);
+    }
+}
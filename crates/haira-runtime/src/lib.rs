@@ -5,10 +5,14 @@
 
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
+mod array;
 mod concurrency;
 mod env;
 mod error;
+mod http;
 mod io;
+mod json;
+mod map;
 mod math;
 mod memory;
 mod regex;
@@ -17,10 +21,14 @@ mod testing;
 mod time;
 
 // Re-export all runtime functions
+pub use array::*;
 pub use concurrency::*;
 pub use env::*;
 pub use error::*;
+pub use http::*;
 pub use io::*;
+pub use json::*;
+pub use map::*;
 pub use math::*;
 pub use memory::*;
 pub use regex::*;
@@ -0,0 +1,111 @@
+//! Hash maps with string keys
+//!
+//! Maps are Rust `HashMap`s behind an opaque pointer. Values are 8-byte
+//! words, like list elements.
+
+use crate::error::haira_set_error;
+use std::collections::HashMap;
+
+/// The runtime map representation.
+pub type HairaMap = HashMap<String, i64>;
+
+/// Borrow the key bytes as a string, replacing invalid UTF-8.
+fn key(ptr: *const u8, len: i64) -> String {
+    if ptr.is_null() || len <= 0 {
+        return String::new();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Create an empty map
+#[no_mangle]
+pub extern "C" fn haira_map_new() -> *mut HairaMap {
+    crate::memory::alloc_value(HairaMap::default())
+}
+
+/// Insert or replace the value for a key
+#[no_mangle]
+pub extern "C" fn haira_map_set(map: *mut HairaMap, key_ptr: *const u8, key_len: i64, value: i64) {
+    if let Some(map) = unsafe { map.as_mut() } {
+        map.insert(key(key_ptr, key_len), value);
+    }
+}
+
+/// Get the value for a key, or set the error flag and return 0 if it is missing
+#[no_mangle]
+pub extern "C" fn haira_map_get(map: *const HairaMap, key_ptr: *const u8, key_len: i64) -> i64 {
+    let value = unsafe { map.as_ref() }.and_then(|map| map.get(&key(key_ptr, key_len)).copied());
+    value.unwrap_or_else(|| {
+        haira_set_error(1);
+        0
+    })
+}
+
+/// Check whether a key is present (1) or not (0)
+#[no_mangle]
+pub extern "C" fn haira_map_has(map: *const HairaMap, key_ptr: *const u8, key_len: i64) -> i64 {
+    unsafe { map.as_ref() }.map_or(0, |map| map.contains_key(&key(key_ptr, key_len)) as i64)
+}
+
+/// Remove a key, returning its value, or 0 if it was missing
+#[no_mangle]
+pub extern "C" fn haira_map_remove(map: *mut HairaMap, key_ptr: *const u8, key_len: i64) -> i64 {
+    unsafe { map.as_mut() }
+        .and_then(|map| map.remove(&key(key_ptr, key_len)))
+        .unwrap_or(0)
+}
+
+/// Free a map and its entries
+#[no_mangle]
+pub extern "C" fn haira_map_free(map: *mut HairaMap) {
+    if !map.is_null() {
+        drop(unsafe { crate::memory::take_value(map) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{haira_clear_error, haira_get_error};
+
+    fn set(map: *mut HairaMap, k: &str, value: i64) {
+        haira_map_set(map, k.as_ptr(), k.len() as i64, value);
+    }
+
+    fn get(map: *const HairaMap, k: &str) -> i64 {
+        haira_map_get(map, k.as_ptr(), k.len() as i64)
+    }
+
+    #[test]
+    fn test_set_get_and_overwrite() {
+        let map = haira_map_new();
+        set(map, "a", 1);
+        set(map, "b", 2);
+        set(map, "a", 3);
+
+        assert_eq!(get(map, "a"), 3);
+        assert_eq!(get(map, "b"), 2);
+        assert_eq!(haira_map_has(map, "b".as_ptr(), 1), 1);
+    }
+
+    #[test]
+    fn test_missing_key_sets_error() {
+        haira_clear_error();
+        let map = haira_map_new();
+
+        assert_eq!(get(map, "missing"), 0);
+        assert_eq!(haira_get_error(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let map = haira_map_new();
+        set(map, "k", 9);
+
+        assert_eq!(haira_map_remove(map, "k".as_ptr(), 1), 9);
+        assert_eq!(haira_map_has(map, "k".as_ptr(), 1), 0);
+        assert_eq!(haira_map_remove(map, "k".as_ptr(), 1), 0);
+        haira_map_free(map);
+    }
+}
@@ -1,16 +1,94 @@
 //! Memory management functions
+//!
+//! Memory handed to compiled code comes from `haira_alloc`/`haira_free`:
+//! strings, lists, and the boxes behind map, JSON and channel handles. Each
+//! block comes from the installed hook, or from Rust's global allocator if
+//! there is none, and goes back to the same one: embedders can route them
+//! elsewhere with `haira_set_allocator`.
+//!
+//! What the runtime allocates for its own use stays on Rust's global
+//! allocator: the storage inside a map, the value tree of a JSON handle,
+//! and temporaries such as formatted messages. The runtime is also linked
+//! into the compiler, so it can't install a `#[global_allocator]`.
 
-use std::alloc::{alloc, dealloc, realloc, Layout};
+use std::alloc::{self, Layout};
+use std::ptr;
+use std::sync::RwLock;
+
+/// Allocation callback provided by an embedder.
+pub type HairaAllocFn = extern "C" fn(size: i64) -> *mut u8;
+
+/// Deallocation callback provided by an embedder.
+pub type HairaFreeFn = extern "C" fn(ptr: *mut u8);
+
+#[derive(Clone, Copy)]
+struct Allocator {
+    alloc: HairaAllocFn,
+    free: HairaFreeFn,
+}
+
+/// The installed allocator hook, or `None` for Rust's global allocator.
+static ALLOCATOR: RwLock<Option<Allocator>> = RwLock::new(None);
+
+/// Every block carries an 8-byte header holding the requested size, so
+/// `haira_realloc` can copy and `haira_free` can rebuild the layout without
+/// the caller tracking sizes.
+const HEADER_SIZE: usize = 8;
+
+/// Layout of a block holding `size` bytes after its header.
+fn block_layout(size: usize) -> Option<Layout> {
+    Layout::from_size_align(size.checked_add(HEADER_SIZE)?, HEADER_SIZE).ok()
+}
+
+fn current_allocator() -> Option<Allocator> {
+    match ALLOCATOR.read() {
+        Ok(guard) => *guard,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
+
+/// Install a custom allocator for memory handed to compiled code.
+///
+/// Passing NULL for either callback restores Rust's global allocator. The hook
+/// should be installed before the program allocates anything: memory must be
+/// released by the same allocator that produced it.
+#[no_mangle]
+pub extern "C" fn haira_set_allocator(
+    alloc_fn: Option<HairaAllocFn>,
+    free_fn: Option<HairaFreeFn>,
+) {
+    let allocator = match (alloc_fn, free_fn) {
+        (Some(alloc), Some(free)) => Some(Allocator { alloc, free }),
+        _ => None,
+    };
+
+    match ALLOCATOR.write() {
+        Ok(mut guard) => *guard = allocator,
+        Err(poisoned) => *poisoned.into_inner() = allocator,
+    }
+}
 
 /// Allocate memory
 #[no_mangle]
 pub extern "C" fn haira_alloc(size: i64) -> *mut u8 {
     if size <= 0 {
-        return std::ptr::null_mut();
+        return ptr::null_mut();
+    }
+
+    let Some(layout) = block_layout(size as usize) else {
+        return ptr::null_mut();
+    };
+    let base = match current_allocator() {
+        Some(allocator) => (allocator.alloc)(layout.size() as i64),
+        None => unsafe { alloc::alloc(layout) },
+    };
+    if base.is_null() {
+        return ptr::null_mut();
     }
+
     unsafe {
-        let layout = Layout::from_size_align_unchecked(size as usize, 8);
-        alloc(layout)
+        (base as *mut i64).write_unaligned(size);
+        base.add(HEADER_SIZE)
     }
 }
 
@@ -22,14 +100,19 @@ pub extern "C" fn haira_realloc(ptr: *mut u8, new_size: i64) -> *mut u8 {
     }
     if new_size <= 0 {
         haira_free(ptr);
-        return std::ptr::null_mut();
+        return ptr::null_mut();
+    }
+
+    let new_ptr = haira_alloc(new_size);
+    if new_ptr.is_null() {
+        return ptr::null_mut();
     }
     unsafe {
-        // We don't know the original size, assume worst case
-        let old_layout = Layout::from_size_align_unchecked(1, 8);
-        let new_layout = Layout::from_size_align_unchecked(new_size as usize, 8);
-        realloc(ptr, old_layout, new_layout.size())
+        let old_size = (ptr.sub(HEADER_SIZE) as *const i64).read_unaligned();
+        ptr::copy_nonoverlapping(ptr, new_ptr, old_size.min(new_size) as usize);
     }
+    haira_free(ptr);
+    new_ptr
 }
 
 /// Free memory
@@ -38,9 +121,141 @@ pub extern "C" fn haira_free(ptr: *mut u8) {
     if ptr.is_null() {
         return;
     }
-    unsafe {
-        // We use a minimal layout since we don't track sizes
-        let layout = Layout::from_size_align_unchecked(1, 8);
-        dealloc(ptr, layout);
+
+    let base = unsafe { ptr.sub(HEADER_SIZE) };
+    match current_allocator() {
+        Some(allocator) => (allocator.free)(base),
+        None => {
+            let size = unsafe { (base as *const i64).read_unaligned() };
+            if let Some(layout) = block_layout(size as usize) {
+                unsafe { alloc::dealloc(base, layout) };
+            }
+        }
+    }
+}
+
+/// Move `value` into a block from `haira_alloc`, for runtime objects handed
+/// to compiled code by pointer. Returns NULL if the allocation fails.
+pub(crate) fn alloc_value<T>(value: T) -> *mut T {
+    // Blocks are only aligned past their header
+    debug_assert!(std::mem::align_of::<T>() <= HEADER_SIZE);
+    let ptr = haira_alloc(std::mem::size_of::<T>().max(1) as i64) as *mut T;
+    if !ptr.is_null() {
+        unsafe { ptr.write(value) };
+    }
+    ptr
+}
+
+/// Move the value out of a block from `alloc_value` and free the block.
+///
+/// # Safety
+///
+/// `ptr` must come from `alloc_value` and not have been taken before.
+pub(crate) unsafe fn take_value<T>(ptr: *mut T) -> T {
+    let value = ptr.read();
+    haira_free(ptr as *mut u8);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::sync::Mutex;
+
+    // Counted per thread, so runtime calls made by tests running in
+    // parallel don't show up in another test's counts
+    thread_local! {
+        static ALLOCS: Cell<usize> = const { Cell::new(0) };
+        static FREES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Held by tests installing the hook, which is process-wide.
+    static HOOK: Mutex<()> = Mutex::new(());
+
+    extern "C" fn counting_alloc(size: i64) -> *mut u8 {
+        ALLOCS.with(|n| n.set(n.get() + 1));
+        unsafe { libc::malloc(size as usize) as *mut u8 }
+    }
+
+    extern "C" fn counting_free(ptr: *mut u8) {
+        FREES.with(|n| n.set(n.get() + 1));
+        unsafe { libc::free(ptr as *mut libc::c_void) }
+    }
+
+    /// Hook calls on this thread as `(allocations, frees)`.
+    fn counts() -> (usize, usize) {
+        (ALLOCS.with(Cell::get), FREES.with(Cell::get))
+    }
+
+    #[test]
+    fn test_custom_allocator_hook() {
+        let _hook = HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        haira_set_allocator(Some(counting_alloc), Some(counting_free));
+
+        let (allocs_before, frees_before) = counts();
+
+        let ptr = haira_alloc(16);
+        assert!(!ptr.is_null());
+        let grown = haira_realloc(ptr, 64);
+        assert!(!grown.is_null());
+        haira_free(grown);
+
+        // Strings, maps and JSON values are runtime allocations too: the
+        // string takes its header and its buffer
+        let s = crate::strings::haira_string_from_static(b"hello".as_ptr(), 5);
+        assert!(!s.is_null());
+        let map = crate::map::haira_map_new();
+        assert!(!map.is_null());
+        crate::map::haira_map_free(map);
+        let json = crate::json::haira_json_parse(b"[1]".as_ptr(), 3);
+        assert!(!json.is_null());
+        crate::json::haira_json_free(json);
+
+        haira_set_allocator(None, None);
+
+        let (allocs, frees) = counts();
+        assert_eq!(allocs - allocs_before, 6);
+        assert_eq!(frees - frees_before, 4);
+    }
+
+    #[test]
+    fn test_json_parse_and_map_insert_hook_calls() {
+        let _hook = HOOK.lock().unwrap_or_else(|e| e.into_inner());
+        haira_set_allocator(Some(counting_alloc), Some(counting_free));
+
+        // A parsed document is one handle from the hook; its value tree is
+        // the runtime's own
+        let before = counts();
+        let json = crate::json::haira_json_parse(br#"{"a": [1, 2, "x"]}"#.as_ptr(), 18);
+        assert!(!json.is_null());
+        let parsed = counts();
+        crate::json::haira_json_free(json);
+        let freed = counts();
+
+        // Inserting into a map grows storage the runtime owns
+        let map = crate::map::haira_map_new();
+        let created = counts();
+        crate::map::haira_map_set(map, b"key".as_ptr(), 3, 7);
+        let inserted = counts();
+        crate::map::haira_map_free(map);
+        let map_freed = counts();
+
+        haira_set_allocator(None, None);
+
+        assert_eq!(parsed, (before.0 + 1, before.1));
+        assert_eq!(freed, (parsed.0, parsed.1 + 1));
+        assert_eq!(created, (freed.0 + 1, freed.1));
+        assert_eq!(inserted, created);
+        assert_eq!(map_freed, (inserted.0, inserted.1 + 1));
+    }
+
+    #[test]
+    fn test_realloc_preserves_contents() {
+        let ptr = haira_alloc(8);
+        unsafe { (ptr as *mut i64).write(0x1234_5678) };
+        let grown = haira_realloc(ptr, 32);
+        assert_eq!(unsafe { (grown as *const i64).read() }, 0x1234_5678);
+        haira_free(grown);
     }
 }
@@ -1,5 +1,6 @@
 //! String operations
 
+use crate::array::{haira_array_new, haira_array_push, HairaArray};
 use std::ptr;
 
 /// HairaString - the runtime string representation
@@ -15,7 +16,7 @@ impl HairaString {
         let len = s.len() as i64;
         let cap = len + 1;
         let data = unsafe {
-            let ptr = libc::malloc(cap as usize) as *mut u8;
+            let ptr = crate::memory::haira_alloc(cap);
             if !ptr.is_null() {
                 ptr::copy_nonoverlapping(s.as_ptr(), ptr, s.len());
                 *ptr.add(s.len()) = 0; // null terminator
@@ -23,24 +24,22 @@ impl HairaString {
             ptr
         };
 
-        let result = Box::new(HairaString { data, len, cap });
-        Box::into_raw(result)
+        crate::memory::alloc_value(HairaString { data, len, cap })
     }
 
     pub fn empty() -> *mut HairaString {
         let data = unsafe {
-            let ptr = libc::malloc(1) as *mut u8;
+            let ptr = crate::memory::haira_alloc(1);
             if !ptr.is_null() {
                 *ptr = 0;
             }
             ptr
         };
-        let result = Box::new(HairaString {
+        crate::memory::alloc_value(HairaString {
             data,
             len: 0,
             cap: 1,
-        });
-        Box::into_raw(result)
+        })
     }
 }
 
@@ -213,6 +212,40 @@ pub extern "C" fn haira_string_contains(
     haystack.windows(needle.len()).any(|w| w == needle) as i64
 }
 
+/// Check if two strings have the same bytes
+#[no_mangle]
+pub extern "C" fn haira_string_eq(
+    ptr: *const u8,
+    len: i64,
+    other: *const u8,
+    other_len: i64,
+) -> i64 {
+    (haira_string_cmp(ptr, len, other, other_len) == 0) as i64
+}
+
+/// Compare two strings lexically by bytes: -1, 0 or 1
+#[no_mangle]
+pub extern "C" fn haira_string_cmp(
+    ptr: *const u8,
+    len: i64,
+    other: *const u8,
+    other_len: i64,
+) -> i64 {
+    let bytes = |ptr: *const u8, len: i64| {
+        if ptr.is_null() || len <= 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(ptr, len as usize) }
+        }
+    };
+
+    match bytes(ptr, len).cmp(bytes(other, other_len)) {
+        std::cmp::Ordering::Less => -1,
+        std::cmp::Ordering::Equal => 0,
+        std::cmp::Ordering::Greater => 1,
+    }
+}
+
 /// Check if string starts with prefix
 #[no_mangle]
 pub extern "C" fn haira_string_starts_with(
@@ -370,3 +403,145 @@ pub extern "C" fn haira_string_char_at(ptr: *const u8, len: i64, mut index: i64)
     let s = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
     s[index as usize] as i64
 }
+
+/// Split a string on a separator, returning a `HairaArray` of `HairaString*`
+///
+/// Every separator ends a segment, so consecutive, leading and trailing
+/// separators produce empty strings and `n` separators always yield `n + 1`
+/// segments (`"a,,b,"` splits into `"a"`, `""`, `"b"` and `""`). An empty
+/// separator splits the string into its characters instead.
+#[no_mangle]
+pub extern "C" fn haira_string_split(
+    ptr: *const u8,
+    len: i64,
+    sep: *const u8,
+    sep_len: i64,
+) -> *mut HairaArray {
+    let arr = haira_array_new();
+    let s = if ptr.is_null() || len <= 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len as usize) }
+    };
+
+    if sep.is_null() || sep_len <= 0 {
+        let mut buf = [0u8; 4];
+        for c in String::from_utf8_lossy(s).chars() {
+            let part = HairaString::new(c.encode_utf8(&mut buf).as_bytes());
+            haira_array_push(arr, part as i64);
+        }
+        return arr;
+    }
+
+    let sep = unsafe { std::slice::from_raw_parts(sep, sep_len as usize) };
+    let mut start = 0;
+    let mut i = 0;
+    while i + sep.len() <= s.len() {
+        if &s[i..i + sep.len()] == sep {
+            haira_array_push(arr, HairaString::new(&s[start..i]) as i64);
+            i += sep.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    haira_array_push(arr, HairaString::new(&s[start..]) as i64);
+    arr
+}
+
+/// Join the `HairaString*` elements of a `HairaArray` with a separator
+///
+/// An empty array joins to an empty string and a single element is
+/// returned without any separator.
+#[no_mangle]
+pub extern "C" fn haira_string_join(
+    arr: *const HairaArray,
+    sep: *const u8,
+    sep_len: i64,
+) -> *mut HairaString {
+    let Some(arr) = (unsafe { arr.as_ref() }) else {
+        return HairaString::empty();
+    };
+    if arr.data.is_null() || arr.len <= 0 {
+        return HairaString::empty();
+    }
+    let sep = if sep.is_null() || sep_len <= 0 {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(sep, sep_len as usize) }
+    };
+
+    let parts = unsafe { std::slice::from_raw_parts(arr.data, arr.len as usize) };
+    let mut joined = Vec::new();
+    for (i, &part) in parts.iter().enumerate() {
+        if i > 0 {
+            joined.extend_from_slice(sep);
+        }
+        if let Some(part) = unsafe { (part as *const HairaString).as_ref() } {
+            if !part.data.is_null() && part.len > 0 {
+                joined.extend_from_slice(unsafe {
+                    std::slice::from_raw_parts(part.data, part.len as usize)
+                });
+            }
+        }
+    }
+
+    HairaString::new(&joined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::array::{haira_array_get, haira_array_len};
+
+    fn split(s: &str, sep: &str) -> Vec<String> {
+        let arr = haira_string_split(s.as_ptr(), s.len() as i64, sep.as_ptr(), sep.len() as i64);
+        (0..haira_array_len(arr))
+            .map(|i| as_string(haira_array_get(arr, i) as *mut HairaString))
+            .collect()
+    }
+
+    fn as_string(s: *mut HairaString) -> String {
+        let s = unsafe { &*s };
+        let bytes = unsafe { std::slice::from_raw_parts(s.data, s.len as usize) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn join(parts: &[&str], sep: &str) -> String {
+        let arr = haira_array_new();
+        for part in parts {
+            haira_array_push(arr, HairaString::new(part.as_bytes()) as i64);
+        }
+        as_string(haira_string_join(arr, sep.as_ptr(), sep.len() as i64))
+    }
+
+    fn cmp(a: &str, b: &str) -> i64 {
+        haira_string_cmp(a.as_ptr(), a.len() as i64, b.as_ptr(), b.len() as i64)
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(cmp("apple", "banana"), -1);
+        assert_eq!(cmp("b", "abc"), 1);
+        assert_eq!(cmp("ab", "abc"), -1);
+        assert_eq!(cmp("", ""), 0);
+        assert_eq!(haira_string_eq("hi".as_ptr(), 2, "hi!".as_ptr(), 2), 1);
+        assert_eq!(haira_string_eq("hi".as_ptr(), 2, "ho".as_ptr(), 2), 0);
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("a,b,c", ","), ["a", "b", "c"]);
+        assert_eq!(split("a,,b,", ","), ["a", "", "b", ""]);
+        assert_eq!(split("a::b", "::"), ["a", "b"]);
+        assert_eq!(split("", ","), [""]);
+        assert_eq!(split("hé", ""), ["h", "é"]);
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(join(&["a", "b", "c"], "-"), "a-b-c");
+        assert_eq!(join(&["only"], ", "), "only");
+        assert_eq!(join(&[], ", "), "");
+    }
+}
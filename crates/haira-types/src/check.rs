@@ -0,0 +1,217 @@
+//! Type checking of declarations.
+
+use crate::{lower_type, InferenceContext, Type, TypeError, TypeVar};
+use haira_ast::{BinaryOp, Expr, ExprKind, ItemKind, Literal, SourceFile, UnaryOp};
+use rustc_hash::FxHashMap;
+use smol_str::SmolStr;
+use std::ops::Range;
+
+/// A type error at a location in the source.
+#[derive(Debug, Clone)]
+pub struct TypeDiagnostic {
+    pub error: TypeError,
+    pub span: Range<usize>,
+}
+
+/// Check that struct field defaults match their annotated types.
+///
+/// Only defaults whose type follows from their shape (literals, operators
+/// over them, lists and instances) are checked. Unannotated fields, and
+/// defaults involving calls or variables, are left alone; undefined names
+/// in defaults are reported by name resolution.
+pub fn check_field_defaults(ast: &SourceFile) -> Vec<TypeDiagnostic> {
+    let aliases: FxHashMap<SmolStr, Type> = ast
+        .items
+        .iter()
+        .filter_map(|item| match &item.node {
+            ItemKind::TypeAlias(alias) => {
+                Some((alias.name.node.clone(), lower_type(&alias.ty.node)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    for item in &ast.items {
+        let ItemKind::TypeDef(type_def) = &item.node else {
+            continue;
+        };
+        for field in &type_def.fields {
+            let (Some(ty), Some(default)) = (&field.ty, &field.default) else {
+                continue;
+            };
+            let Some(found) = infer_expr(default) else {
+                continue;
+            };
+            let expected = expand_alias(lower_type(&ty.node), &aliases);
+            if !accepts(&expected, &found) {
+                diagnostics.push(TypeDiagnostic {
+                    error: TypeError::Mismatch { expected, found },
+                    span: default.span.start as usize..default.span.end as usize,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Check that every `const` item has a value computable at compile time.
+pub fn check_consts(ast: &SourceFile) -> Vec<TypeDiagnostic> {
+    let values = haira_ast::const_values(ast);
+    ast.items
+        .iter()
+        .filter_map(|item| match &item.node {
+            ItemKind::Const(const_def) => Some(const_def),
+            _ => None,
+        })
+        .filter(|const_def| !values.iter().any(|(name, _)| *name == const_def.name.node))
+        .map(|const_def| TypeDiagnostic {
+            error: TypeError::NonConstant(const_def.name.node.clone()),
+            span: const_def.value.span.start as usize..const_def.value.span.end as usize,
+        })
+        .collect()
+}
+
+/// Replace a type alias with its target, following chains of aliases.
+fn expand_alias(mut ty: Type, aliases: &FxHashMap<SmolStr, Type>) -> Type {
+    // Bounded so that cyclic aliases terminate
+    for _ in 0..aliases.len() {
+        match &ty {
+            Type::Named(name) if aliases.contains_key(name) => ty = aliases[name].clone(),
+            _ => break,
+        }
+    }
+    ty
+}
+
+/// Whether a value of type `found` can be stored where `expected` is declared.
+fn accepts(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        // Integers widen to floats
+        (Type::Float, Type::Int) => true,
+        (Type::Union(variants), _) => variants.iter().any(|variant| accepts(variant, found)),
+        _ => InferenceContext::new().unify(expected, found).is_ok(),
+    }
+}
+
+/// The type of an expression, if it follows from the expression's shape.
+fn infer_expr(expr: &Expr) -> Option<Type> {
+    match &expr.node {
+        ExprKind::Literal(lit) => match lit {
+            Literal::Int(_) => Some(Type::Int),
+            Literal::Float(_) => Some(Type::Float),
+            Literal::String(_) | Literal::InterpolatedString(_) => Some(Type::String),
+            Literal::Bool(_) => Some(Type::Bool),
+            Literal::Char(_) => None,
+        },
+        ExprKind::Paren(inner) => infer_expr(inner),
+        ExprKind::None => Some(Type::Option(Box::new(Type::Unknown(TypeVar::fresh())))),
+        ExprKind::Some(inner) => Some(Type::Option(Box::new(infer_expr(inner)?))),
+        ExprKind::Unary(unary) => match unary.op.node {
+            UnaryOp::Not => Some(Type::Bool),
+            UnaryOp::Neg => match infer_expr(&unary.operand)? {
+                ty @ (Type::Int | Type::Float) => Some(ty),
+                _ => None,
+            },
+        },
+        ExprKind::Binary(binary) => match binary.op.node {
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Gt
+            | BinaryOp::Le
+            | BinaryOp::Ge
+            | BinaryOp::And
+            | BinaryOp::Or => Some(Type::Bool),
+            BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor
+            | BinaryOp::Shl
+            | BinaryOp::Shr => match (infer_expr(&binary.left)?, infer_expr(&binary.right)?) {
+                (Type::Int, Type::Int) => Some(Type::Int),
+                _ => None,
+            },
+            BinaryOp::Coalesce => match infer_expr(&binary.left)? {
+                Type::Option(inner) if !matches!(*inner, Type::Unknown(_)) => Some(*inner),
+                Type::Option(_) => infer_expr(&binary.right),
+                _ => None,
+            },
+            op => match (infer_expr(&binary.left)?, infer_expr(&binary.right)?) {
+                (Type::Int, Type::Int) => Some(Type::Int),
+                (Type::Int | Type::Float, Type::Int | Type::Float) => Some(Type::Float),
+                (Type::String, Type::String) if op == BinaryOp::Add => Some(Type::String),
+                _ => None,
+            },
+        },
+        ExprKind::List(elements) => {
+            let mut elem = None;
+            for element in elements {
+                elem = match (elem, infer_expr(element)?) {
+                    (Some(Type::Int), Type::Float) => Some(Type::Float),
+                    (Some(Type::Float), Type::Int) => Some(Type::Float),
+                    (None, ty) => Some(ty),
+                    (prev, _) => prev,
+                };
+            }
+            let elem = elem.unwrap_or_else(|| Type::Unknown(TypeVar::fresh()));
+            Some(Type::Array(Box::new(elem)))
+        }
+        ExprKind::Tuple(elements) => elements
+            .iter()
+            .map(infer_expr)
+            .collect::<Option<_>>()
+            .map(Type::Tuple),
+        ExprKind::Instance(instance) => Some(Type::Named(instance.type_name.node.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> Vec<TypeDiagnostic> {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        check_field_defaults(&result.ast)
+    }
+
+    #[test]
+    fn test_well_typed_defaults() {
+        let diagnostics = check(
+            "Id = int\n\nUser {\n    name: string = \"anon\"\n    age: int = 18 + 2\n    score: float = 1\n    tags: [string] = []\n    id: Id = 7\n    note = 3\n}\n",
+        );
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_mismatched_default() {
+        let source = "User {\n    age: int = \"hello\"\n}\n";
+        let diagnostics = check(source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&source[diagnostics[0].span.clone()], "\"hello\"");
+        assert_eq!(
+            diagnostics[0].error.to_string(),
+            "type mismatch: expected `int`, found `string`"
+        );
+    }
+
+    #[test]
+    fn test_non_constant_const_value() {
+        let source = "const SIZE = 4 * 2
+const HALF = SIZE / 2
+const X = random_int(10)
+";
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let diagnostics = check_consts(&result.ast);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&source[diagnostics[0].span.clone()], "random_int(10)");
+        assert_eq!(
+            diagnostics[0].error.to_string(),
+            "constant `X` must be a literal or operators over constants"
+        );
+    }
+}
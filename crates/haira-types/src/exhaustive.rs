@@ -0,0 +1,183 @@
+//! Exhaustiveness checking of match expressions.
+
+use crate::{TypeDiagnostic, TypeError};
+use haira_ast::visit::{walk_expr, walk_source_file, walk_stmt, Visitor};
+use haira_ast::{
+    Expr, ExprKind, ItemKind, Literal, MatchExpr, Pattern, SourceFile, Span, Statement,
+    StatementKind,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use smol_str::SmolStr;
+
+/// Check that every match covers all the values of its subject.
+///
+/// A match whose patterns name enum variants must cover each variant of
+/// that enum. A match over literals must have a catch-all arm, except that
+/// `true` and `false` together cover a boolean. Wildcards and bindings are
+/// catch-alls; arms with a guard cover nothing, since the guard may fail.
+pub fn check_matches(ast: &SourceFile) -> Vec<TypeDiagnostic> {
+    let mut enums = FxHashMap::default();
+    for item in &ast.items {
+        if let ItemKind::EnumDef(enum_def) = &item.node {
+            let variants: Vec<SmolStr> = enum_def
+                .variants
+                .iter()
+                .map(|variant| variant.name.node.clone())
+                .collect();
+            for variant in &variants {
+                enums.insert(variant.clone(), variants.clone());
+            }
+        }
+    }
+
+    let mut checker = MatchChecker {
+        enums,
+        diagnostics: Vec::new(),
+    };
+    walk_source_file(&mut checker, ast);
+    checker.diagnostics
+}
+
+struct MatchChecker {
+    /// The variants of each variant's enum, keyed by variant name.
+    enums: FxHashMap<SmolStr, Vec<SmolStr>>,
+    diagnostics: Vec<TypeDiagnostic>,
+}
+
+impl MatchChecker {
+    /// Check one match; `start` is where the `match` keyword begins.
+    fn check(&mut self, start: u32, match_expr: &MatchExpr) {
+        let mut covered = FxHashSet::default();
+        let mut catch_all = false;
+        let mut variants: Option<&Vec<SmolStr>> = None;
+
+        for arm in &match_expr.arms {
+            let unguarded = arm.guard.is_none();
+            match &arm.pattern.node {
+                Pattern::Identifier(name) | Pattern::Constructor { name, .. }
+                    if self.enums.contains_key(name) =>
+                {
+                    variants.get_or_insert(&self.enums[name]);
+                    if unguarded {
+                        covered.insert(name.clone());
+                    }
+                }
+                Pattern::Wildcard | Pattern::Identifier(_) => catch_all |= unguarded,
+                Pattern::Literal(Literal::Bool(value)) if unguarded => {
+                    covered.insert(SmolStr::new_inline(if *value { "true" } else { "false" }));
+                }
+                Pattern::Tuple(elements) => {
+                    catch_all |= unguarded && elements.iter().all(|e| self.irrefutable(&e.node))
+                }
+                Pattern::Literal(_) | Pattern::Constructor { .. } => {}
+            }
+        }
+        if catch_all {
+            return;
+        }
+
+        let error = match variants {
+            Some(variants) => {
+                let missing: Vec<SmolStr> = variants
+                    .iter()
+                    .filter(|variant| !covered.contains(*variant))
+                    .cloned()
+                    .collect();
+                if missing.is_empty() {
+                    return;
+                }
+                TypeError::NonExhaustiveMatch { missing }
+            }
+            None if covered.contains("true") && covered.contains("false") => return,
+            None => TypeError::MissingCatchAll,
+        };
+        let Span { end, .. } = match_expr.subject.span;
+        self.diagnostics.push(TypeDiagnostic {
+            error,
+            span: start as usize..end as usize,
+        });
+    }
+
+    /// Whether `pattern` matches every value: a binding, a wildcard, or a
+    /// tuple of those.
+    fn irrefutable(&self, pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Wildcard => true,
+            Pattern::Identifier(name) => !self.enums.contains_key(name),
+            Pattern::Tuple(elements) => elements.iter().all(|e| self.irrefutable(&e.node)),
+            Pattern::Literal(_) | Pattern::Constructor { .. } => false,
+        }
+    }
+}
+
+impl Visitor for MatchChecker {
+    fn visit_stmt(&mut self, stmt: &Statement) {
+        if let StatementKind::Match(match_expr) = &stmt.node {
+            self.check(stmt.span.start, match_expr);
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Match(match_expr) = &expr.node {
+            self.check(expr.span.start, match_expr);
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(source: &str) -> Vec<TypeDiagnostic> {
+        let result = haira_parser::parse(source);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        check_matches(&result.ast)
+    }
+
+    const COLOR: &str = "Color { Red, Green, Blue }\nc = Green\n";
+
+    #[test]
+    fn test_complete_enum_match() {
+        let source =
+            format!("{COLOR}x = match c {{\n    Red => 1\n    Green => 2\n    Blue => 3\n}}\n");
+        assert!(check(&source).is_empty());
+    }
+
+    #[test]
+    fn test_missing_variant() {
+        let source = format!(
+            "{COLOR}x = match c {{\n    Red => 1\n    Green if x > 0 => 2\n    Blue => 3\n}}\n"
+        );
+        let diagnostics = check(&source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(&source[diagnostics[0].span.clone()], "match c");
+        assert_eq!(
+            diagnostics[0].error.to_string(),
+            "non-exhaustive match: missing `Green`"
+        );
+    }
+
+    #[test]
+    fn test_wildcard_covers_remaining_variants() {
+        let source = format!("{COLOR}x = match c {{\n    Red => 1\n    _ => 0\n}}\n");
+        assert!(check(&source).is_empty());
+    }
+
+    #[test]
+    fn test_literal_match_requires_catch_all() {
+        let diagnostics = check("f(n) {\n    match n {\n        0 => print(\"zero\")\n    }\n}\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(diagnostics[0].error, TypeError::MissingCatchAll));
+
+        assert!(
+            check("f(n) {\n    match n {\n        0 => 1\n        m => m\n    }\n}\n").is_empty()
+        );
+        assert!(
+            check("f(b) {\n    match b {\n        true => 1\n        false => 0\n    }\n}\n")
+                .is_empty()
+        );
+    }
+}
@@ -6,7 +6,14 @@
 //! - Type checking
 //! - Constraint generation and solving
 
+mod check;
+mod exhaustive;
+
+pub use check::{check_consts, check_field_defaults, TypeDiagnostic};
+pub use exhaustive::check_matches;
+
 use smol_str::SmolStr;
+use std::fmt;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 /// Unique type variable ID.
@@ -16,6 +23,8 @@ pub struct TypeVar(u32);
 static NEXT_TYPE_VAR: AtomicU32 = AtomicU32::new(0);
 
 impl TypeVar {
+    /// A new type variable, distinct from every other in the process, so
+    /// files checked on separate threads never share one.
     pub fn fresh() -> Self {
         TypeVar(NEXT_TYPE_VAR.fetch_add(1, Ordering::SeqCst))
     }
@@ -70,6 +79,89 @@ impl Type {
     }
 }
 
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list(f: &mut fmt::Formatter<'_>, types: &[Type], sep: &str) -> fmt::Result {
+            for (i, ty) in types.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(sep)?;
+                }
+                write!(f, "{}", ty)?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Type::Unknown(_) => write!(f, "_"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Generic(name, args) => {
+                write!(f, "{}<", name)?;
+                list(f, args, ", ")?;
+                write!(f, ">")
+            }
+            Type::Option(inner) => write!(f, "Option<{}>", inner),
+            Type::Array(elem) => write!(f, "[{}]", elem),
+            Type::Tuple(types) => {
+                write!(f, "(")?;
+                list(f, types, ", ")?;
+                write!(f, ")")
+            }
+            Type::Function { params, returns } => {
+                write!(f, "(")?;
+                list(f, params, ", ")?;
+                write!(f, ") -> {}", returns)
+            }
+            Type::Union(types) => list(f, types, " | "),
+            Type::Unit => write!(f, "none"),
+            Type::Error => write!(f, "{{error}}"),
+        }
+    }
+}
+
+/// Convert a type annotation into a semantic type.
+pub fn lower_type(ty: &haira_ast::Type) -> Type {
+    match ty {
+        haira_ast::Type::Named(name) => match name.as_str() {
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "string" => Type::String,
+            "bool" => Type::Bool,
+            "none" => Type::Unit,
+            _ => Type::Named(name.clone()),
+        },
+        haira_ast::Type::List(elem) => Type::Array(Box::new(lower_type(&elem.node))),
+        haira_ast::Type::Map { key, value } => Type::Generic(
+            SmolStr::new_inline("Map"),
+            vec![lower_type(&key.node), lower_type(&value.node)],
+        ),
+        haira_ast::Type::Option(inner) => Type::Option(Box::new(lower_type(&inner.node))),
+        haira_ast::Type::Tuple(elements) => Type::Tuple(
+            elements
+                .iter()
+                .map(|element| lower_type(&element.node))
+                .collect(),
+        ),
+        haira_ast::Type::Function { params, ret } => Type::Function {
+            params: params.iter().map(|param| lower_type(&param.node)).collect(),
+            returns: Box::new(lower_type(&ret.node)),
+        },
+        haira_ast::Type::Union(variants) => Type::Union(
+            variants
+                .iter()
+                .map(|variant| lower_type(&variant.node))
+                .collect(),
+        ),
+        haira_ast::Type::Generic { name, args } => Type::Generic(
+            name.clone(),
+            args.iter().map(|arg| lower_type(&arg.node)).collect(),
+        ),
+    }
+}
+
 /// Type inference context.
 pub struct InferenceContext {
     /// Substitution map from type variables to types.
@@ -164,9 +256,27 @@ impl Default for InferenceContext {
 }
 
 /// Type error.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum TypeError {
+    #[error("type mismatch: expected `{expected}`, found `{found}`")]
     Mismatch { expected: Type, found: Type },
+    #[error("unresolved type `{0}`")]
     UnresolvedType(SmolStr),
+    #[error("infinite type involving {0:?}")]
     InfiniteType(TypeVar),
+    #[error("non-exhaustive match: missing {}", quoted_list(.missing))]
+    NonExhaustiveMatch { missing: Vec<SmolStr> },
+    #[error("non-exhaustive match: add a `_` arm to cover the remaining values")]
+    MissingCatchAll,
+    #[error("constant `{0}` must be a literal or operators over constants")]
+    NonConstant(SmolStr),
+}
+
+/// Format names as a comma-separated list of quoted names.
+fn quoted_list(names: &[SmolStr]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(", ")
 }